@@ -1,22 +1,333 @@
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use zeevonk::showfile::Showfile;
+use zeevonk::client::Client;
+use zeevonk::dmx::{Address, Multiverse, UniverseId};
+use zeevonk::showfile::{self, Showfile};
+
+/// Loads the showfile, printing its validation issues as warnings, so the
+/// information can be shown for a showfile that can't be run.
+fn load_showfile(showfile_path: &Path) -> anyhow::Result<Showfile> {
+    let showfile = Showfile::load(showfile_path)?;
+    match showfile.check_valid() {
+        Ok(()) => {}
+        Err(showfile::Error::Invalid(issues)) => {
+            for issue in &issues {
+                log::warn!("{issue}");
+            }
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(showfile)
+}
 
 pub fn dump_patch(showfile_path: PathBuf) -> anyhow::Result<()> {
-    let showfile = Showfile::load_from_folder(&showfile_path)?;
+    let showfile = load_showfile(&showfile_path)?;
 
     let server = zeevonk::server::Server::new(&showfile)?;
     let show_data = server.show_data();
 
-    for (_, fixture) in show_data.patch().fixtures() {
+    let patch = show_data.patch();
+    for (_, fixture) in patch.fixtures() {
         dump::dump_fixture(fixture);
     }
 
+    let universes = patch
+        .fixtures()
+        .values()
+        .flat_map(|fixture| fixture.physical_addresses().map(|address| address.universe))
+        .collect::<BTreeSet<_>>();
+    for universe in universes {
+        dump::dump_occupancy(universe, &patch.universe_occupancy(universe));
+    }
+
+    Ok(())
+}
+
+/// Prints one row per root fixture, with its address and footprint in the
+/// `universe.channel` notation.
+pub fn export_patch_csv(showfile_path: PathBuf) -> anyhow::Result<()> {
+    let showfile = load_showfile(&showfile_path)?;
+
+    let server = zeevonk::server::Server::new(&showfile)?;
+    let show_data = server.show_data();
+    let patch = show_data.patch();
+
+    println!("id,label,universe,channel,footprint,gdtf_type,mode");
+    for fixture in patch.root_fixtures() {
+        let root_id = fixture.path().root();
+        let address = fixture.base_address();
+        let footprint = patch
+            .fixture_footprint(root_id)
+            .filter(|footprint| !footprint.is_empty())
+            .and_then(|footprint| {
+                let first = Address::from_absolute(footprint.start).ok()?;
+                let last = Address::from_absolute(footprint.end - 1).ok()?;
+                Some(format!("{first}-{last}"))
+            })
+            .unwrap_or_default();
+        let gdtf_type = match show_data.fixture_type_of(fixture) {
+            Some(fixture_type) => fixture_type.name().to_string(),
+            None => fixture.gdtf_fixture_type_id().to_string(),
+        };
+
+        let row = [
+            root_id.to_string(),
+            fixture.name().to_string(),
+            address.universe.to_string(),
+            address.channel.to_string(),
+            footprint,
+            gdtf_type,
+            fixture.gdtf_dmx_mode().to_string(),
+        ];
+        println!("{}", row.map(|field| csv_field(&field)).join(","));
+    }
+
+    Ok(())
+}
+
+/// Prints the full patch as JSON.
+pub fn export_patch_json(showfile_path: PathBuf) -> anyhow::Result<()> {
+    let showfile = load_showfile(&showfile_path)?;
+
+    let server = zeevonk::server::Server::new(&showfile)?;
+    let show_data = server.show_data();
+
+    serde_json::to_writer_pretty(std::io::stdout().lock(), show_data.patch())?;
+    println!();
+
     Ok(())
 }
 
+/// Quotes the field if it contains characters that have a meaning in CSV.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn dump_output(showfile_path: PathBuf, universe: Option<UniverseId>) -> anyhow::Result<()> {
+    let showfile = load_showfile(&showfile_path)?;
+
+    let server = zeevonk::server::Server::new(&showfile)?;
+    let multiverse = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(server.dmx_output());
+
+    let universes = multiverse
+        .sorted_universes()
+        .filter(|(id, _)| universe.is_none_or(|universe| **id == universe));
+
+    let mut printed_any = false;
+    for (id, universe) in universes {
+        for (ix, value) in universe.values().iter().enumerate() {
+            if value.0 != 0 {
+                println!("{id}.{} = {value}", ix + 1);
+                printed_any = true;
+            }
+        }
+    }
+
+    if !printed_any {
+        println!("all channels are zero");
+    }
+
+    Ok(())
+}
+
+pub fn dump_universes(showfile_path: PathBuf) -> anyhow::Result<()> {
+    let showfile = load_showfile(&showfile_path)?;
+
+    let server = zeevonk::server::Server::new(&showfile)?;
+    let show_data = server.show_data();
+    let universes = show_data
+        .patch()
+        .fixtures()
+        .values()
+        .flat_map(|fixture| fixture.physical_addresses().map(|address| address.universe))
+        .collect::<BTreeSet<_>>();
+
+    let mapping = showfile.protocols().sacn().universes();
+    for universe in universes {
+        match mapping.sacn_universe(universe) {
+            Some(sacn) => println!("{universe} -> sACN {sacn}"),
+            None => println!("{universe} -> not sent"),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn dump_parked(server: String) -> anyhow::Result<()> {
+    let parked =
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(
+            async {
+                let client = Client::connect(&server).await?;
+                client.request_parked_addresses().await
+            },
+        )?;
+
+    if parked.is_empty() {
+        println!("no parked channels");
+    }
+    for (address, value) in parked {
+        println!("{address} = {value}");
+    }
+
+    Ok(())
+}
+
+/// How often `info dmx --watch` requests the output of the server.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Prints the DMX output of a running server as a grid of channel values,
+/// for the given universe or all universes. With `watch`, the grid is redrawn
+/// every second and channels that changed since the previous frame are
+/// highlighted.
+pub fn dump_dmx(
+    server: String,
+    universe: Option<UniverseId>,
+    watch: bool,
+    radix: grid::Radix,
+) -> anyhow::Result<()> {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+        let client = Client::connect(&server).await?;
+
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        let mut previous: Option<Multiverse> = None;
+        loop {
+            interval.tick().await;
+            let multiverse = client.request_dmx_output().await?;
+
+            if watch {
+                print!("{}", grid::CLEAR_SCREEN);
+            }
+            print!("{}", grid::format_multiverse(&multiverse, universe, previous.as_ref(), radix));
+
+            if !watch {
+                return anyhow::Ok(());
+            }
+            previous = Some(multiverse);
+        }
+    })
+}
+
+pub mod grid {
+    use std::fmt::Write as _;
+
+    use zeevonk::dmx::{Multiverse, Universe, UniverseId};
+
+    const RESET: &str = "\x1b[0m";
+    const BOLD: &str = "\x1b[1m";
+    const DIM: &str = "\x1b[2m";
+    const YELLOW: &str = "\x1b[33m";
+
+    /// Clears the terminal and moves the cursor to the top left.
+    pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+    /// Number of channels on each row of the grid.
+    const COLUMNS: usize = 10;
+
+    /// How channel values are printed in the grid. Both take three
+    /// characters, so the grid is equally wide.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+    pub enum Radix {
+        /// `0` to `255`.
+        Decimal,
+        /// `00` to `FF`.
+        Hex,
+    }
+
+    impl Radix {
+        fn format(self, value: u8) -> String {
+            match self {
+                Self::Decimal => format!("{value:>3}"),
+                Self::Hex => format!(" {value:02X}"),
+            }
+        }
+    }
+
+    /// Formats the given universe, or all universes in the multiverse if
+    /// `universe` is `None`. See [format_universe].
+    pub fn format_multiverse(
+        multiverse: &Multiverse,
+        universe: Option<UniverseId>,
+        previous: Option<&Multiverse>,
+        radix: Radix,
+    ) -> String {
+        let ids = match universe {
+            Some(id) => vec![id],
+            None => multiverse.universe_ids(),
+        };
+        if ids.is_empty() {
+            return "no universes present\n".to_string();
+        }
+
+        ids.iter()
+            .map(|id| {
+                let previous = previous.and_then(|previous| previous.universe(id));
+                format_universe(*id, multiverse.universe(id), previous, radix)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Formats the values of a universe as a grid of ten channels per row,
+    /// with the channel offsets as column headers and the first channel of
+    /// each row as row header. Channels that differ from `previous` are
+    /// highlighted, and zeros are dimmed.
+    ///
+    /// A universe that is not in the output prints as `not present`, instead
+    /// of a grid of zeros.
+    pub fn format_universe(
+        id: UniverseId,
+        universe: Option<&Universe>,
+        previous: Option<&Universe>,
+        radix: Radix,
+    ) -> String {
+        let mut out = String::new();
+        let Some(universe) = universe else {
+            writeln!(out, "{BOLD}universe {id}{RESET}: not present").unwrap();
+            return out;
+        };
+
+        writeln!(out, "{BOLD}universe {id}{RESET}").unwrap();
+        write!(out, "{DIM}    ").unwrap();
+        for column in 0..COLUMNS {
+            write!(out, " {:>3}", format!("+{column}")).unwrap();
+        }
+        writeln!(out, "{RESET}").unwrap();
+
+        for (row, values) in universe.values().chunks(COLUMNS).enumerate() {
+            let first_channel = row * COLUMNS + 1;
+            write!(out, "{DIM}{first_channel:>3}:{RESET}").unwrap();
+            for (column, value) in values.iter().enumerate() {
+                let ix = row * COLUMNS + column;
+                let cell = radix.format(value.0);
+                let changed = previous.is_some_and(|previous| previous.values()[ix] != *value);
+                if changed {
+                    write!(out, " {BOLD}{YELLOW}{cell}{RESET}").unwrap();
+                } else if value.0 == 0 {
+                    write!(out, " {DIM}{cell}{RESET}").unwrap();
+                } else {
+                    write!(out, " {cell}").unwrap();
+                }
+            }
+            writeln!(out).unwrap();
+        }
+
+        out
+    }
+}
+
 mod dump {
+    use zeevonk::dmx::UniverseId;
     use zeevonk::show::fixture::{Fixture, FixtureChannelFunctionKind};
+    use zeevonk::show::patch::AddressSpan;
 
     const RESET: &str = "\x1b[0m";
     const BOLD: &str = "\x1b[1m";
@@ -24,6 +335,18 @@ mod dump {
     const YELLOW: &str = "\x1b[33m";
     const MAGENTA: &str = "\x1b[35m";
 
+    pub fn dump_occupancy(universe: UniverseId, spans: &[AddressSpan]) {
+        println!("{BOLD}universe {universe}{RESET}");
+        for span in spans {
+            let (start, end) = (*span.start(), *span.end());
+            let fixture = span.fixture();
+            let label = span.label();
+            println!(
+                "  {YELLOW}{start:>3}{RESET}{DIM}..{RESET}{YELLOW}{end:>3}{RESET} {MAGENTA}{label}{RESET} {DIM}({RESET}path{DIM}={RESET}{YELLOW}{fixture}{RESET}{DIM}){RESET}"
+            );
+        }
+    }
+
     pub fn dump_fixture(fixture: &Fixture) {
         dump_fixture_with_depth(fixture, 0);
     }
@@ -91,3 +414,81 @@ mod dump {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zeevonk::dmx::{Channel, Universe, UniverseId, Value};
+
+    use super::grid::{Radix, format_universe};
+
+    /// Removes the ANSI escape codes, leaving the text as it is displayed.
+    fn strip_ansi(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn universe_with(values: &[(u16, u8)]) -> Universe {
+        let mut universe = Universe::new();
+        for (channel, value) in values {
+            universe.set_value(&Channel::new(*channel).unwrap(), Value(*value));
+        }
+        universe
+    }
+
+    fn id(id: u16) -> UniverseId {
+        UniverseId::new(id).unwrap()
+    }
+
+    #[test]
+    fn grid_layout() {
+        let universe = universe_with(&[(1, 255), (12, 7), (512, 42)]);
+        let grid = strip_ansi(&format_universe(id(2), Some(&universe), None, Radix::Decimal));
+        let lines = grid.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2 + 52);
+        assert_eq!(lines[0], "universe 2");
+        assert_eq!(lines[1], "      +0  +1  +2  +3  +4  +5  +6  +7  +8  +9");
+        assert_eq!(lines[2], "  1: 255   0   0   0   0   0   0   0   0   0");
+        assert_eq!(lines[3], " 11:   0   7   0   0   0   0   0   0   0   0");
+        assert_eq!(lines[53], "511:   0  42");
+        assert!(lines[1..53].iter().all(|line| line.len() == lines[1].len()));
+    }
+
+    #[test]
+    fn grid_in_hex() {
+        let universe = universe_with(&[(1, 255), (2, 10)]);
+        let grid = strip_ansi(&format_universe(id(1), Some(&universe), None, Radix::Hex));
+        assert_eq!(grid.lines().nth(2).unwrap(), "  1:  FF  0A  00  00  00  00  00  00  00  00");
+    }
+
+    #[test]
+    fn changed_channels_are_highlighted() {
+        let previous = universe_with(&[(1, 10), (2, 20)]);
+        let current = universe_with(&[(1, 10), (2, 21), (3, 30)]);
+        let grid = format_universe(id(1), Some(&current), Some(&previous), Radix::Decimal);
+
+        let highlight = "\x1b[1m\x1b[33m";
+        let first_row = grid.lines().nth(2).unwrap();
+        assert_eq!(first_row.matches(highlight).count(), 2);
+        assert!(first_row.contains(&format!("{highlight} 21")));
+        assert!(first_row.contains(&format!("{highlight} 30")));
+        assert!(!first_row.contains(&format!("{highlight} 10")));
+
+        let unchanged = format_universe(id(1), Some(&current), Some(&current), Radix::Decimal);
+        assert!(!unchanged.contains(highlight));
+    }
+
+    #[test]
+    fn missing_universe_is_not_present() {
+        let grid = strip_ansi(&format_universe(id(3), None, None, Radix::Decimal));
+        assert_eq!(grid, "universe 3: not present\n");
+    }
+}
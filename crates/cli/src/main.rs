@@ -1,10 +1,16 @@
+use std::net::IpAddr;
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use zeevonk::dmx::{Address, UniverseId};
+use zeevonk::show::fixture::FixtureId;
+use zeevonk::show::selection::FixtureSelection;
 
 mod info;
 mod init;
+mod patch;
 mod run;
+mod validate;
 
 #[derive(Parser)]
 #[command(name = "zeevonk")]
@@ -23,7 +29,23 @@ enum Commands {
     },
     /// Run the showfile.
     Run {
-        /// Path to the showfile.
+        /// Path to the showfile folder or `.zvk` archive.
+        showfile_path: PathBuf,
+        /// Record the output in memory instead of sending it over the
+        /// network, e.g. to run in CI.
+        #[arg(long)]
+        dummy_output: bool,
+        /// IP address to listen on, instead of the one in the showfile.
+        #[arg(long)]
+        address: Option<IpAddr>,
+        /// Port to listen on, instead of the one in the showfile. Use `0` to
+        /// let the operating system pick a free port.
+        #[arg(long, env = "ZEEVONK_PORT")]
+        port: Option<u16>,
+    },
+    /// Check the showfile for problems without running it.
+    Validate {
+        /// Path to the showfile folder or `.zvk` archive.
         showfile_path: PathBuf,
     },
     /// Get info about a showfile.
@@ -31,14 +53,103 @@ enum Commands {
         #[command(subcommand)]
         command: InfoSubcommand,
     },
+    /// Edit the patch of a showfile.
+    Patch {
+        #[command(subcommand)]
+        command: PatchSubcommand,
+    },
+}
+
+/// Formats the patch can be exported in.
+#[derive(Clone, Copy, ValueEnum)]
+enum PatchFormat {
+    /// One row per fixture, as a patch sheet.
+    Csv,
+    /// The full patch, including sub-fixtures and channel functions.
+    Json,
 }
 
 #[derive(Subcommand)]
 enum InfoSubcommand {
-    /// Dump the patch tree.
+    /// Dump the patch tree, or export the patch.
     Patch {
+        /// Path to the showfile folder or `.zvk` archive.
+        showfile_path: PathBuf,
+        /// Export the patch in this format instead of dumping the tree.
+        #[arg(long, value_enum)]
+        format: Option<PatchFormat>,
+    },
+    /// Check the showfile for problems.
+    Validate {
+        /// Path to the showfile folder or `.zvk` archive.
+        showfile_path: PathBuf,
+    },
+    /// Dump the resolved DMX output with all attributes at their default values.
+    Output {
+        /// Path to the showfile folder or `.zvk` archive.
+        showfile_path: PathBuf,
+        /// Only show the output of this universe.
+        #[arg(long)]
+        universe: Option<UniverseId>,
+    },
+    /// List the patched universes and the sACN universes they are sent as.
+    Universes {
+        /// Path to the showfile folder or `.zvk` archive.
+        showfile_path: PathBuf,
+    },
+    /// Print the DMX output of a running server as a grid of channel values.
+    Dmx {
+        /// Address of the running server (e.g. `127.0.0.1:7334`).
+        server: String,
+        /// Only show this universe.
+        #[arg(long)]
+        universe: Option<UniverseId>,
+        /// Refresh once a second, highlighting the channels that changed.
+        #[arg(long)]
+        watch: bool,
+        /// How to print the channel values.
+        #[arg(long, value_enum, default_value_t = info::grid::Radix::Decimal)]
+        radix: info::grid::Radix,
+    },
+    /// List the parked DMX channels of a running server.
+    Parked {
+        /// Address of the running server (e.g. `127.0.0.1:7334`).
+        server: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PatchSubcommand {
+    /// Add a fixture to the patch.
+    Add {
+        /// Path to the showfile.
+        showfile_path: PathBuf,
+        /// Unique id of the new fixture.
+        #[arg(long)]
+        id: FixtureId,
+        /// Label of the new fixture.
+        #[arg(long)]
+        label: String,
+        /// DMX address of the new fixture (e.g. `1.101`).
+        #[arg(long)]
+        address: Address,
+        /// GDTF fixture type id or name.
+        #[arg(long)]
+        gdtf: String,
+        /// GDTF DMX mode.
+        #[arg(long)]
+        mode: String,
+        /// Number of identical fixtures to add, with consecutive ids and
+        /// addresses, labeled `<LABEL> 1` to `<LABEL> <COUNT>`.
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+    },
+    /// Remove fixtures from the patch.
+    Remove {
         /// Path to the showfile.
         showfile_path: PathBuf,
+        /// Fixtures to remove (e.g. `1 thru 10 + 21 - 5`).
+        selection: FixtureSelection,
     },
 }
 
@@ -54,11 +165,40 @@ fn main() -> anyhow::Result<()> {
         Commands::Init { showfile_path } => {
             init::init_showfile(showfile_path)?;
         }
-        Commands::Run { showfile_path } => {
-            run::run_showfile(showfile_path)?;
+        Commands::Run { showfile_path, dummy_output, address, port } => {
+            run::run_showfile(showfile_path, dummy_output, address, port)?;
+        }
+        Commands::Validate { showfile_path } => {
+            validate::validate(showfile_path)?;
+        }
+        Commands::Info { command: InfoSubcommand::Patch { showfile_path, format } } => match format
+        {
+            None => info::dump_patch(showfile_path)?,
+            Some(PatchFormat::Csv) => info::export_patch_csv(showfile_path)?,
+            Some(PatchFormat::Json) => info::export_patch_json(showfile_path)?,
+        },
+        Commands::Info { command: InfoSubcommand::Validate { showfile_path } } => {
+            validate::validate(showfile_path)?;
+        }
+        Commands::Info { command: InfoSubcommand::Output { showfile_path, universe } } => {
+            info::dump_output(showfile_path, universe)?;
+        }
+        Commands::Info { command: InfoSubcommand::Universes { showfile_path } } => {
+            info::dump_universes(showfile_path)?;
+        }
+        Commands::Info { command: InfoSubcommand::Dmx { server, universe, watch, radix } } => {
+            info::dump_dmx(server, universe, watch, radix)?;
+        }
+        Commands::Info { command: InfoSubcommand::Parked { server } } => {
+            info::dump_parked(server)?;
+        }
+        Commands::Patch {
+            command: PatchSubcommand::Add { showfile_path, id, label, address, gdtf, mode, count },
+        } => {
+            patch::add_fixture(showfile_path, id, label, address, gdtf, mode, count)?;
         }
-        Commands::Info { command: InfoSubcommand::Patch { showfile_path } } => {
-            info::dump_patch(showfile_path)?;
+        Commands::Patch { command: PatchSubcommand::Remove { showfile_path, selection } } => {
+            patch::remove_fixtures(showfile_path, selection)?;
         }
     }
 
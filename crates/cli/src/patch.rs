@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use zeevonk::dmx::Address;
+use zeevonk::server::GdtfFixtureTypes;
+use zeevonk::show::fixture::{FixtureId, FixtureTypeInfo};
+use zeevonk::show::selection::FixtureSelection;
+use zeevonk::showfile::{Fixture, FixtureKind, Showfile};
+
+/// Appends a fixture to the showfile at the given path.
+///
+/// If `count` is more than one, an array of fixtures is added instead, with
+/// consecutive ids and addresses and numbered labels.
+pub fn add_fixture(
+    showfile_path: PathBuf,
+    id: FixtureId,
+    label: String,
+    address: Address,
+    gdtf: String,
+    mode: String,
+    count: u32,
+) -> anyhow::Result<()> {
+    let showfile = load_showfile_folder(&showfile_path)?;
+
+    if count == 0 {
+        bail!("count should be at least 1");
+    }
+
+    if let Some(existing) = showfile.patch().fixture(id) {
+        bail!("fixture id {id} is already used by '{}'", existing.label());
+    }
+
+    // Read the GDTF files once, they are needed for every step below.
+    let gdtf_fixture_types = GdtfFixtureTypes::load(&showfile)?;
+    let fixture_types = gdtf_fixture_types.infos();
+    let fixture_type = find_fixture_type(&fixture_types, &gdtf)?;
+
+    if !fixture_type.dmx_modes().iter().any(|m| m == &mode) {
+        bail!(
+            "dmx mode '{mode}' not found for fixture type '{}', available modes: {}",
+            fixture_type.name(),
+            fixture_type.dmx_modes().join(", ")
+        );
+    }
+
+    let kind = FixtureKind::new(fixture_type.id(), mode.as_str());
+
+    let mut new_showfile = showfile.clone();
+    let ids = if count == 1 {
+        new_showfile.patch_mut().add_fixture(Fixture::new(id, label, address, kind));
+        vec![id]
+    } else {
+        let channel_count = gdtf_fixture_types.dmx_mode_channel_count(fixture_type.id(), &mode)?;
+        new_showfile.patch_mut().add_fixture_array(
+            id,
+            &label,
+            address,
+            count,
+            kind,
+            channel_count,
+            1,
+        )?
+    };
+
+    check_address_collisions(&gdtf_fixture_types, &new_showfile, &ids)?;
+
+    new_showfile.save_to_folder(&showfile_path)?;
+
+    for id in ids {
+        let address = new_showfile.patch().fixture(id).map(|f| f.address()).unwrap_or(address);
+        println!("added fixture {id} at {address}");
+    }
+
+    Ok(())
+}
+
+/// Removes the selected fixtures from the showfile at the given path.
+///
+/// Selected ids that are not in the patch are skipped with a warning.
+pub fn remove_fixtures(showfile_path: PathBuf, selection: FixtureSelection) -> anyhow::Result<()> {
+    let mut showfile = load_showfile_folder(&showfile_path)?;
+
+    let mut removed = Vec::new();
+    for id in selection.ids() {
+        match showfile.patch_mut().remove_fixture(*id) {
+            Some(fixture) => removed.push(fixture),
+            None => log::warn!("fixture {id} is not in the patch, skipping"),
+        }
+    }
+
+    if removed.is_empty() {
+        bail!("none of the selected fixtures are in the patch");
+    }
+
+    showfile.save_to_folder(&showfile_path)?;
+
+    for fixture in removed {
+        println!("removed fixture {} '{}'", fixture.id(), fixture.label());
+    }
+
+    Ok(())
+}
+
+/// Loads the showfile folder at the given path. Archives are rejected, as
+/// showfiles can only be saved to folders.
+fn load_showfile_folder(showfile_path: &Path) -> anyhow::Result<Showfile> {
+    if showfile_path.is_file() {
+        bail!(
+            "patch editing only supports showfile folders, '{}' is an archive",
+            showfile_path.display()
+        );
+    }
+    Ok(Showfile::load_from_folder(showfile_path)?)
+}
+
+fn find_fixture_type<'a>(
+    fixture_types: &'a [FixtureTypeInfo],
+    gdtf: &str,
+) -> anyhow::Result<&'a FixtureTypeInfo> {
+    if let Some(fixture_type) = fixture_types
+        .iter()
+        .find(|fixture_type| fixture_type.id().to_string().eq_ignore_ascii_case(gdtf))
+    {
+        return Ok(fixture_type);
+    }
+
+    let matches = fixture_types
+        .iter()
+        .filter(|fixture_type| fixture_type.name().eq_ignore_ascii_case(gdtf))
+        .collect::<Vec<_>>();
+
+    match matches.as_slice() {
+        [fixture_type] => Ok(fixture_type),
+        [] => bail!("no GDTF fixture type found with name '{gdtf}'"),
+        _ => bail!(
+            "multiple GDTF fixture types found with name '{gdtf}', use one of their ids instead: {}",
+            matches
+                .iter()
+                .map(|fixture_type| fixture_type.id().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Checks that the fixtures with the given ids in `new_showfile` don't
+/// occupy any address of the other fixtures in its patch.
+fn check_address_collisions(
+    gdtf_fixture_types: &GdtfFixtureTypes,
+    new_showfile: &Showfile,
+    ids: &[FixtureId],
+) -> anyhow::Result<()> {
+    let show_data = gdtf_fixture_types.build_show_data(new_showfile)?;
+    let new_patch = show_data.patch();
+
+    // The patch as it was before the fixtures were added.
+    let mut patch = new_patch.clone();
+    for id in ids {
+        patch.remove_fixture(*id)?;
+    }
+
+    for id in ids {
+        let Some(fixture) = new_showfile.patch().fixture(*id) else { continue };
+        let footprint = new_patch.fixture_footprint(*id).map_or(0, |range| range.len() as u32);
+        let addresses = new_patch
+            .fixtures()
+            .values()
+            .filter(|fixture| fixture.path().root() == *id)
+            .flat_map(|fixture| fixture.physical_addresses().copied().collect::<Vec<_>>());
+        patch.check_address_collision(fixture.address(), footprint, addresses, None)?;
+    }
+
+    Ok(())
+}
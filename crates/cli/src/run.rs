@@ -1,15 +1,72 @@
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 
-use anyhow::Ok;
-use zeevonk::server::Server;
-use zeevonk::showfile::Showfile;
+use anyhow::bail;
+use zeevonk::server::{self, Server};
+use zeevonk::showfile::{self, Showfile};
+
+/// Runs the showfile at the given path. With `dummy_output`, the output is
+/// recorded in memory instead of sent over the network. `ip` and `port`
+/// override the address in the config of the showfile.
+///
+/// Once bound, the address is printed as `listening on <address>`, so scripts
+/// that use port `0` can find the assigned port. On Ctrl-C, the outputs are
+/// stopped, so they send their final packets before the process exits.
+pub fn run_showfile(
+    showfile_path: PathBuf,
+    dummy_output: bool,
+    ip: Option<IpAddr>,
+    port: Option<u16>,
+) -> anyhow::Result<()> {
+    tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap().block_on(async {
+        let mut showfile = Showfile::load(&showfile_path)?;
+        match showfile.check_valid() {
+            Ok(()) => {}
+            Err(showfile::Error::Invalid(issues)) => {
+                for issue in &issues {
+                    log::error!("{issue}");
+                }
+                bail!("showfile has {} validation issue(s)", issues.len());
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if showfile.ensure_sacn_cid() {
+            if showfile_path.is_dir() {
+                showfile.save_to_folder(&showfile_path)?;
+                log::info!("saved new sACN component identifier to the showfile");
+            } else {
+                log::warn!(
+                    "showfile archive has no sACN component identifier, using a new one for this run"
+                );
+            }
+        }
 
-/// Runs the showfile at the given path.
-pub fn run_showfile(showfile_path: PathBuf) -> anyhow::Result<()> {
-    tokio::runtime::Builder::new_multi_thread().enable_io().build().unwrap().block_on(async {
-        let showfile = Showfile::load_from_folder(&showfile_path)?;
         let mut server = Server::new(&showfile)?;
-        server.start().await?;
+        server.set_dummy_output(dummy_output);
+        server.set_showfile_path(&showfile_path);
+        if ip.is_some() || port.is_some() {
+            let config = showfile.config();
+            let configured = match ip {
+                Some(ip) => SocketAddr::new(ip, config.address().port()),
+                None => server::resolve_address(config.address(), config.prefer_ipv4()).await?,
+            };
+            server.set_address(SocketAddr::new(
+                configured.ip(),
+                port.unwrap_or(configured.port()),
+            ));
+        }
+
+        let address = server.bind().await?;
+        println!("listening on {address}");
+        tokio::select! {
+            result = server.start() => result?,
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                log::info!("shutting down...");
+            }
+        }
+        server.shutdown();
 
         anyhow::Result::<()>::Ok(())
     })?;
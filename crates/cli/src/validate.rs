@@ -0,0 +1,33 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use zeevonk::showfile::{self, Showfile};
+
+/// Checks the showfile for problems without starting the server,
+/// failing if any problem is found.
+pub fn validate(showfile_path: PathBuf) -> anyhow::Result<()> {
+    let showfile = Showfile::load(&showfile_path)?;
+    let issues = match showfile.check_valid() {
+        Ok(()) => {
+            println!(
+                "showfile is valid ({} fixtures, {} sACN outputs)",
+                showfile.patch().fixtures().len(),
+                showfile.protocols().sacn().outputs().len()
+            );
+            return Ok(());
+        }
+        Err(showfile::Error::Invalid(issues)) => issues,
+        Err(err) => return Err(err.into()),
+    };
+
+    for issue in &issues {
+        println!("error: {issue}");
+    }
+
+    let fixture_count =
+        issues.iter().filter_map(|issue| issue.fixture_id()).collect::<BTreeSet<_>>().len();
+    println!();
+    println!("found {} issue(s) affecting {} fixture(s)", issues.len(), fixture_count);
+
+    anyhow::bail!("showfile is invalid");
+}
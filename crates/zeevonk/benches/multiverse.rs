@@ -0,0 +1,68 @@
+//! Compares ways to copy 16 universes out of the output [Multiverse] every
+//! frame: cloning it, copying it into a multiverse that is reused every frame,
+//! and copying each universe into a reused buffer.
+//!
+//! Run with `cargo bench -p zeevonk --bench multiverse`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use zeevonk::dmx::{Multiverse, Universe, UniverseId};
+
+const UNIVERSE_COUNT: u16 = 16;
+const FRAMES: u32 = 100_000;
+
+fn main() {
+    let mut multiverse = Multiverse::new();
+    for id in 1..=UNIVERSE_COUNT {
+        let values = (0..512).map(|channel| (channel + id) as u8).collect::<Vec<_>>();
+        multiverse
+            .create_universe(UniverseId::new(id).unwrap(), Universe::from_slice(&values).unwrap());
+    }
+
+    let clone = bench("clone per frame", || {
+        black_box(black_box(&multiverse).clone());
+    });
+
+    let mut target = Multiverse::new();
+    let copy_from = bench("copy_from into a reused multiverse", || {
+        target.copy_from(black_box(&multiverse));
+        black_box(&target);
+    });
+
+    let ids = multiverse.universe_ids();
+    let mut buf = [0; 512];
+    let copy_universe_into = bench("copy_universe_into a reused buffer", || {
+        for id in &ids {
+            black_box(&multiverse).copy_universe_into(id, &mut buf);
+            black_box(&buf);
+        }
+    });
+
+    println!();
+    println!("copy_from takes {:.2}x the time of cloning", ratio(copy_from, clone));
+    println!(
+        "copy_universe_into takes {:.2}x the time of cloning",
+        ratio(copy_universe_into, clone)
+    );
+}
+
+/// Runs `frame` [FRAMES] times after warming up, and prints and returns the
+/// mean time per frame.
+fn bench(name: &str, mut frame: impl FnMut()) -> Duration {
+    for _ in 0..FRAMES / 10 {
+        frame();
+    }
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        frame();
+    }
+    let per_frame = start.elapsed() / FRAMES;
+    println!("{name:<40}{per_frame:>12.2?} per frame");
+    per_frame
+}
+
+fn ratio(time: Duration, baseline: Duration) -> f64 {
+    time.as_secs_f64() / baseline.as_secs_f64()
+}
@@ -0,0 +1,64 @@
+//! A sine-wave chase over all dimmers, run against a [TestHarness] so it needs
+//! no running server.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use zeevonk::attr::Attribute;
+use zeevonk::client::{AttributeRequirement, Processor, ProcessorContext, TickContext};
+use zeevonk::dmx::UniverseId;
+use zeevonk::testing::TestHarness;
+
+/// The time it takes for the wave to pass over all fixtures once.
+const PERIOD: Duration = Duration::from_secs(2);
+
+struct SineChase;
+
+impl Processor for SineChase {
+    fn required_attributes(&self) -> Vec<AttributeRequirement> {
+        vec![AttributeRequirement::required(Attribute::Dimmer)]
+    }
+
+    fn process(&mut self, tick: TickContext, mut cx: ProcessorContext<'_, '_>) {
+        let phase = tick.elapsed().as_secs_f32() / PERIOD.as_secs_f32();
+        let count = cx.matching_fixtures().len() as f32;
+
+        let values = cx
+            .matching_fixtures()
+            .iter()
+            .enumerate()
+            .map(|(ix, fixture)| {
+                let offset = ix as f32 / count;
+                let value = 0.5 + 0.5 * ((phase - offset) * TAU).sin();
+                (fixture.path(), value)
+            })
+            .collect::<Vec<_>>();
+
+        for (path, value) in values {
+            cx.set_attribute(path, Attribute::Dimmer, value, false);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = TestHarness::new(8)?;
+    let client = harness.connect().await?;
+    let universe = UniverseId::new(1)?;
+    let print_output = async {
+        for _ in 0..10 {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let output = harness.server().dmx_output().await;
+            let values = output
+                .universe(&universe)
+                .map(|u| u.values()[..8].iter().map(|v| v.0).collect::<Vec<_>>());
+            println!("{values:?}");
+        }
+    };
+
+    tokio::select! {
+        result = client.run_at_rate(SineChase, 30.0) => result?,
+        _ = print_output => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Color conversion helpers.
+//!
+//! All components are represented as [dmx::Value]s, where `0` is the minimum
+//! and `255` the maximum. Hue is mapped from `0..360` degrees onto `0..=255`.
+
+use crate::attr::Attribute;
+use crate::dmx;
+use crate::show::fixture::Fixture;
+
+/// Attribute sets for additive color mixing, in order of preference.
+const RGB_ATTRIBUTES: [[Attribute; 3]; 2] = [
+    [Attribute::ColorAddR, Attribute::ColorAddG, Attribute::ColorAddB],
+    [Attribute::ColorRgbRed, Attribute::ColorRgbGreen, Attribute::ColorRgbBlue],
+];
+
+/// Attribute sets for subtractive color mixing, in order of preference.
+const CMY_ATTRIBUTES: [[Attribute; 3]; 2] = [
+    [Attribute::ColorSubC, Attribute::ColorSubM, Attribute::ColorSubY],
+    [Attribute::ColorRgbCyan, Attribute::ColorRgbMagenta, Attribute::ColorRgbYellow],
+];
+
+/// Attribute set for HSB color mixing.
+const HSB_ATTRIBUTES: [Attribute; 3] =
+    [Attribute::HsbHue, Attribute::HsbSaturation, Attribute::HsbBrightness];
+
+/// Converts an RGB color to CMY.
+pub fn rgb_to_cmy(
+    r: dmx::Value,
+    g: dmx::Value,
+    b: dmx::Value,
+) -> (dmx::Value, dmx::Value, dmx::Value) {
+    (dmx::Value(255 - r.0), dmx::Value(255 - g.0), dmx::Value(255 - b.0))
+}
+
+/// Converts a CMY color to RGB.
+pub fn cmy_to_rgb(
+    c: dmx::Value,
+    m: dmx::Value,
+    y: dmx::Value,
+) -> (dmx::Value, dmx::Value, dmx::Value) {
+    (dmx::Value(255 - c.0), dmx::Value(255 - m.0), dmx::Value(255 - y.0))
+}
+
+/// Converts an HSB color to RGB.
+pub fn hsb_to_rgb(
+    h: dmx::Value,
+    s: dmx::Value,
+    b: dmx::Value,
+) -> (dmx::Value, dmx::Value, dmx::Value) {
+    let hue = h.0 as f32 / 255.0 * 6.0;
+    let saturation = s.0 as f32 / 255.0;
+    let brightness = b.0 as f32 / 255.0;
+
+    let chroma = brightness * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let m = brightness - chroma;
+    (to_value(r + m), to_value(g + m), to_value(b + m))
+}
+
+/// Converts an RGB color to HSB.
+pub fn rgb_to_hsb(
+    r: dmx::Value,
+    g: dmx::Value,
+    b: dmx::Value,
+) -> (dmx::Value, dmx::Value, dmx::Value) {
+    let (r, g, b) = (r.0 as f32 / 255.0, g.0 as f32 / 255.0, b.0 as f32 / 255.0);
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (to_value(hue / 6.0), to_value(saturation), to_value(max))
+}
+
+/// Returns the attribute values needed to show the given RGB color on the
+/// fixture.
+///
+/// Additive RGB attributes are preferred, then subtractive CMY and then HSB.
+/// Other attributes, like a white emitter, are left untouched. Returns `None`
+/// if the fixture has none of these attribute sets.
+pub fn fixture_color_values(
+    fixture: &Fixture,
+    r: dmx::Value,
+    g: dmx::Value,
+    b: dmx::Value,
+) -> Option<Vec<(Attribute, dmx::Value)>> {
+    let has_all = |attributes: &[Attribute; 3]| {
+        attributes.iter().all(|attribute| fixture.channel_function(attribute).is_some())
+    };
+
+    let (attributes, (x, y, z)) =
+        if let Some(attributes) = RGB_ATTRIBUTES.iter().find(|a| has_all(a)) {
+            (attributes, (r, g, b))
+        } else if let Some(attributes) = CMY_ATTRIBUTES.iter().find(|a| has_all(a)) {
+            (attributes, rgb_to_cmy(r, g, b))
+        } else if has_all(&HSB_ATTRIBUTES) {
+            (&HSB_ATTRIBUTES, rgb_to_hsb(r, g, b))
+        } else {
+            return None;
+        };
+
+    Some(attributes.iter().copied().zip([x, y, z]).collect())
+}
+
+fn to_value(value: f32) -> dmx::Value {
+    dmx::Value((value * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::fpath;
+    use crate::show::fixture::{FixtureChannelFunction, FixtureChannelFunctionKind};
+    use crate::value::{ClampedValue, DimmerCurve};
+
+    fn v(r: u8, g: u8, b: u8) -> (dmx::Value, dmx::Value, dmx::Value) {
+        (dmx::Value(r), dmx::Value(g), dmx::Value(b))
+    }
+
+    fn fixture(attributes: &[Attribute]) -> Fixture {
+        let channel_functions = attributes
+            .iter()
+            .map(|attribute| {
+                let channel_function = FixtureChannelFunction {
+                    kind: FixtureChannelFunctionKind::Virtual { relations: Vec::new() },
+                    min: ClampedValue::new(0.0),
+                    max: ClampedValue::new(1.0),
+                    default: ClampedValue::new(0.0),
+                };
+                (*attribute, channel_function)
+            })
+            .collect::<HashMap<_, _>>();
+
+        Fixture {
+            path: fpath![1],
+            root_base_address: dmx::Address::default(),
+            name: "Fixture".to_string(),
+            gdtf_fixture_type_id: uuid::Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions,
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        }
+    }
+
+    #[test]
+    fn rgb_cmy_round_trip() {
+        assert_eq!(rgb_to_cmy(dmx::Value(255), dmx::Value(128), dmx::Value(0)), v(0, 127, 255));
+        assert_eq!(cmy_to_rgb(dmx::Value(0), dmx::Value(127), dmx::Value(255)), v(255, 128, 0));
+    }
+
+    #[test]
+    fn hsb_to_rgb_primaries() {
+        assert_eq!(hsb_to_rgb(dmx::Value(0), dmx::Value(255), dmx::Value(255)), v(255, 0, 0));
+        assert_eq!(hsb_to_rgb(dmx::Value(85), dmx::Value(255), dmx::Value(255)), v(0, 255, 0));
+        assert_eq!(hsb_to_rgb(dmx::Value(170), dmx::Value(255), dmx::Value(255)), v(0, 0, 255));
+        assert_eq!(hsb_to_rgb(dmx::Value(42), dmx::Value(0), dmx::Value(255)), v(255, 255, 255));
+        assert_eq!(hsb_to_rgb(dmx::Value(42), dmx::Value(255), dmx::Value(0)), v(0, 0, 0));
+    }
+
+    #[test]
+    fn rgb_to_hsb_primaries() {
+        assert_eq!(rgb_to_hsb(dmx::Value(255), dmx::Value(0), dmx::Value(0)), v(0, 255, 255));
+        assert_eq!(rgb_to_hsb(dmx::Value(0), dmx::Value(255), dmx::Value(0)), v(85, 255, 255));
+        assert_eq!(rgb_to_hsb(dmx::Value(0), dmx::Value(0), dmx::Value(255)), v(170, 255, 255));
+        assert_eq!(rgb_to_hsb(dmx::Value(128), dmx::Value(128), dmx::Value(128)), v(0, 0, 128));
+    }
+
+    #[test]
+    fn fixture_color_values_prefers_rgb_and_keeps_white() {
+        let fixture = fixture(&[
+            Attribute::ColorAddR,
+            Attribute::ColorAddG,
+            Attribute::ColorAddB,
+            Attribute::ColorAddW,
+            Attribute::ColorSubC,
+            Attribute::ColorSubM,
+            Attribute::ColorSubY,
+        ]);
+        let values =
+            fixture_color_values(&fixture, dmx::Value(255), dmx::Value(128), dmx::Value(0))
+                .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (Attribute::ColorAddR, dmx::Value(255)),
+                (Attribute::ColorAddG, dmx::Value(128)),
+                (Attribute::ColorAddB, dmx::Value(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn fixture_color_values_falls_back_to_cmy_and_hsb() {
+        let cmy = fixture(&[Attribute::ColorSubC, Attribute::ColorSubM, Attribute::ColorSubY]);
+        let values =
+            fixture_color_values(&cmy, dmx::Value(255), dmx::Value(0), dmx::Value(0)).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (Attribute::ColorSubC, dmx::Value(0)),
+                (Attribute::ColorSubM, dmx::Value(255)),
+                (Attribute::ColorSubY, dmx::Value(255)),
+            ]
+        );
+
+        let hsb = fixture(&[Attribute::HsbHue, Attribute::HsbSaturation, Attribute::HsbBrightness]);
+        let values =
+            fixture_color_values(&hsb, dmx::Value(0), dmx::Value(0), dmx::Value(255)).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (Attribute::HsbHue, dmx::Value(170)),
+                (Attribute::HsbSaturation, dmx::Value(255)),
+                (Attribute::HsbBrightness, dmx::Value(255)),
+            ]
+        );
+
+        let dimmer = fixture(&[Attribute::Dimmer]);
+        assert!(
+            fixture_color_values(&dimmer, dmx::Value(0), dmx::Value(0), dmx::Value(0)).is_none()
+        );
+    }
+}
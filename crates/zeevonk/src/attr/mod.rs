@@ -8,6 +8,8 @@ use std::fmt;
 use std::str::FromStr;
 use std::sync::Mutex;
 
+pub mod color;
+
 lazy_static::lazy_static! {
     static ref CUSTOM_NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
 }
@@ -715,7 +717,939 @@ impl ToString for CustomName {
     }
 }
 
+/// All known attributes, with parametric attributes using `1` for each parameter.
+const KNOWN_ATTRIBUTES: &[Attribute] = &[
+    Attribute::Dimmer,
+    Attribute::Pan,
+    Attribute::Tilt,
+    Attribute::PanRotate,
+    Attribute::TiltRotate,
+    Attribute::PositionEffect,
+    Attribute::PositionEffectRate,
+    Attribute::PositionEffectFade,
+    Attribute::XyzX,
+    Attribute::XyzY,
+    Attribute::XyzZ,
+    Attribute::RotX,
+    Attribute::RotY,
+    Attribute::RotZ,
+    Attribute::ScaleX,
+    Attribute::ScaleY,
+    Attribute::ScaleZ,
+    Attribute::ScaleXYZ,
+    Attribute::Gobo(1),
+    Attribute::GoboSelectSpin(1),
+    Attribute::GoboSelectShake(1),
+    Attribute::GoboSelectEffects(1),
+    Attribute::GoboWheelIndex(1),
+    Attribute::GoboWheelSpin(1),
+    Attribute::GoboWheelShake(1),
+    Attribute::GoboWheelRandom(1),
+    Attribute::GoboWheelAudio(1),
+    Attribute::GoboPos(1),
+    Attribute::GoboPosRotate(1),
+    Attribute::GoboPosShake(1),
+    Attribute::AnimationWheel(1),
+    Attribute::AnimationWheelAudio(1),
+    Attribute::AnimationWheelMacro(1),
+    Attribute::AnimationWheelRandom(1),
+    Attribute::AnimationWheelSelectEffects(1),
+    Attribute::AnimationWheelSelectShake(1),
+    Attribute::AnimationWheelSelectSpin(1),
+    Attribute::AnimationWheelPos(1),
+    Attribute::AnimationWheelPosRotate(1),
+    Attribute::AnimationWheelPosShake(1),
+    Attribute::AnimationSystem(1),
+    Attribute::AnimationSystemRamp(1),
+    Attribute::AnimationSystemShake(1),
+    Attribute::AnimationSystemAudio(1),
+    Attribute::AnimationSystemRandom(1),
+    Attribute::AnimationSystemPos(1),
+    Attribute::AnimationSystemPosRotate(1),
+    Attribute::AnimationSystemPosShake(1),
+    Attribute::AnimationSystemPosRandom(1),
+    Attribute::AnimationSystemPosAudio(1),
+    Attribute::AnimationSystemMacro(1),
+    Attribute::MediaFolder(1),
+    Attribute::MediaContent(1),
+    Attribute::ModelFolder(1),
+    Attribute::ModelContent(1),
+    Attribute::PlayMode,
+    Attribute::PlayBegin,
+    Attribute::PlayEnd,
+    Attribute::PlaySpeed,
+    Attribute::ColorEffects(1),
+    Attribute::Color(1),
+    Attribute::ColorWheelIndex(1),
+    Attribute::ColorWheelSpin(1),
+    Attribute::ColorWheelRandom(1),
+    Attribute::ColorWheelAudio(1),
+    Attribute::ColorAddR,
+    Attribute::ColorAddG,
+    Attribute::ColorAddB,
+    Attribute::ColorAddC,
+    Attribute::ColorAddM,
+    Attribute::ColorAddY,
+    Attribute::ColorAddRY,
+    Attribute::ColorAddGY,
+    Attribute::ColorAddGC,
+    Attribute::ColorAddBC,
+    Attribute::ColorAddBM,
+    Attribute::ColorAddRM,
+    Attribute::ColorAddW,
+    Attribute::ColorAddWW,
+    Attribute::ColorAddCW,
+    Attribute::ColorAddUV,
+    Attribute::ColorSubR,
+    Attribute::ColorSubG,
+    Attribute::ColorSubB,
+    Attribute::ColorSubC,
+    Attribute::ColorSubM,
+    Attribute::ColorSubY,
+    Attribute::ColorMacro(1),
+    Attribute::ColorMacroRate(1),
+    Attribute::Cto,
+    Attribute::Ctc,
+    Attribute::Ctb,
+    Attribute::Tint,
+    Attribute::HsbHue,
+    Attribute::HsbSaturation,
+    Attribute::HsbBrightness,
+    Attribute::HsbQuality,
+    Attribute::CieX,
+    Attribute::CieY,
+    Attribute::CieBrightness,
+    Attribute::ColorRgbRed,
+    Attribute::ColorRgbGreen,
+    Attribute::ColorRgbBlue,
+    Attribute::ColorRgbCyan,
+    Attribute::ColorRgbMagenta,
+    Attribute::ColorRgbYellow,
+    Attribute::ColorRgbQuality,
+    Attribute::VideoBoostR,
+    Attribute::VideoBoostG,
+    Attribute::VideoBoostB,
+    Attribute::VideoHueShift,
+    Attribute::VideoSaturation,
+    Attribute::VideoBrightness,
+    Attribute::VideoContrast,
+    Attribute::VideoKeyColorR,
+    Attribute::VideoKeyColorG,
+    Attribute::VideoKeyColorB,
+    Attribute::VideoKeyIntensity,
+    Attribute::VideoKeyTolerance,
+    Attribute::StrobeDuration,
+    Attribute::StrobeRate,
+    Attribute::StrobeFrequency,
+    Attribute::StrobeModeShutter,
+    Attribute::StrobeModeStrobe,
+    Attribute::StrobeModePulse,
+    Attribute::StrobeModePulseOpen,
+    Attribute::StrobeModePulseClose,
+    Attribute::StrobeModeRandom,
+    Attribute::StrobeModeRandomPulse,
+    Attribute::StrobeModeRandomPulseOpen,
+    Attribute::StrobeModeRandomPulseClose,
+    Attribute::StrobeModeEffect,
+    Attribute::Shutter(1),
+    Attribute::ShutterStrobe(1),
+    Attribute::ShutterStrobePulse(1),
+    Attribute::ShutterStrobePulseClose(1),
+    Attribute::ShutterStrobePulseOpen(1),
+    Attribute::ShutterStrobeRandom(1),
+    Attribute::ShutterStrobeRandomPulse(1),
+    Attribute::ShutterStrobeRandomPulseClose(1),
+    Attribute::ShutterStrobeRandomPulseOpen(1),
+    Attribute::ShutterStrobeEffect(1),
+    Attribute::Iris,
+    Attribute::IrisStrobe,
+    Attribute::IrisStrobeRandom,
+    Attribute::IrisPulseClose,
+    Attribute::IrisPulseOpen,
+    Attribute::IrisRandomPulseClose,
+    Attribute::IrisRandomPulseOpen,
+    Attribute::Frost(1),
+    Attribute::FrostPulseOpen(1),
+    Attribute::FrostPulseClose(1),
+    Attribute::FrostRamp(1),
+    Attribute::Prism(1),
+    Attribute::PrismSelectSpin(1),
+    Attribute::PrismMacro(1),
+    Attribute::PrismPos(1),
+    Attribute::PrismPosRotate(1),
+    Attribute::Effects(1),
+    Attribute::EffectsRate(1),
+    Attribute::EffectsFade(1),
+    Attribute::EffectsAdjust(1, 1),
+    Attribute::EffectsPos(1),
+    Attribute::EffectsPosRotate(1),
+    Attribute::EffectsSync,
+    Attribute::BeamShaper,
+    Attribute::BeamShaperMacro,
+    Attribute::BeamShaperPos,
+    Attribute::BeamShaperPosRotate,
+    Attribute::Zoom,
+    Attribute::ZoomModeSpot,
+    Attribute::ZoomModeBeam,
+    Attribute::DigitalZoom,
+    Attribute::Focus(1),
+    Attribute::FocusAdjust(1),
+    Attribute::FocusDistance(1),
+    Attribute::Control(1),
+    Attribute::DimmerMode,
+    Attribute::DimmerCurve,
+    Attribute::BlackoutMode,
+    Attribute::LedFrequency,
+    Attribute::LedZoneMode,
+    Attribute::PixelMode,
+    Attribute::PanMode,
+    Attribute::TiltMode,
+    Attribute::PanTiltMode,
+    Attribute::PositionModes,
+    Attribute::GoboWheelMode(1),
+    Attribute::GoboWheelShortcutMode,
+    Attribute::AnimationWheelMode(1),
+    Attribute::AnimationWheelShortcutMode,
+    Attribute::ColorMode(1),
+    Attribute::ColorWheelShortcutMode,
+    Attribute::CyanMode,
+    Attribute::MagentaMode,
+    Attribute::YellowMode,
+    Attribute::ColorMixMode,
+    Attribute::ChromaticMode,
+    Attribute::ColorCalibrationMode,
+    Attribute::ColorConsistency,
+    Attribute::ColorControl,
+    Attribute::ColorModelMode,
+    Attribute::ColorSettingsReset,
+    Attribute::ColorUniformity,
+    Attribute::CriMode,
+    Attribute::CustomColor,
+    Attribute::UvStability,
+    Attribute::WavelengthCorrection,
+    Attribute::WhiteCount,
+    Attribute::StrobeMode,
+    Attribute::ZoomMode,
+    Attribute::FocusMode,
+    Attribute::IrisMode,
+    Attribute::FanMode(1),
+    Attribute::FollowSpotMode,
+    Attribute::BeamEffectIndexRotateMode,
+    Attribute::IntensityMSpeed,
+    Attribute::PositionMSpeed,
+    Attribute::ColorMixMSpeed,
+    Attribute::ColorWheelSelectMSpeed,
+    Attribute::GoboWheelMSpeed(1),
+    Attribute::IrisMSpeed,
+    Attribute::PrismMSpeed(1),
+    Attribute::FocusMSpeed,
+    Attribute::FrostMSpeed(1),
+    Attribute::ZoomMSpeed,
+    Attribute::FrameMSpeed,
+    Attribute::GlobalMSpeed,
+    Attribute::ReflectorAdjust,
+    Attribute::FixtureGlobalReset,
+    Attribute::DimmerReset,
+    Attribute::ShutterReset,
+    Attribute::BeamReset,
+    Attribute::ColorMixReset,
+    Attribute::ColorWheelReset,
+    Attribute::FocusReset,
+    Attribute::FrameReset,
+    Attribute::GoboWheelReset,
+    Attribute::IntensityReset,
+    Attribute::IrisReset,
+    Attribute::PositionReset,
+    Attribute::PanReset,
+    Attribute::TiltReset,
+    Attribute::ZoomReset,
+    Attribute::CtbReset,
+    Attribute::CtoReset,
+    Attribute::CtcReset,
+    Attribute::AnimationSystemReset,
+    Attribute::FixtureCalibrationReset,
+    Attribute::Function,
+    Attribute::LampControl,
+    Attribute::DisplayIntensity,
+    Attribute::DmxInput,
+    Attribute::NoFeature,
+    Attribute::Blower(1),
+    Attribute::Fan(1),
+    Attribute::Fog(1),
+    Attribute::Haze(1),
+    Attribute::LampPowerMode,
+    Attribute::Fans,
+    Attribute::BladeA(1),
+    Attribute::BladeB(1),
+    Attribute::BladeRot(1),
+    Attribute::ShaperRot,
+    Attribute::ShaperMacros,
+    Attribute::ShaperMacrosSpeed,
+    Attribute::BladeSoftA(1),
+    Attribute::BladeSoftB(1),
+    Attribute::KeyStoneA(1),
+    Attribute::KeyStoneB(1),
+    Attribute::Video,
+    Attribute::VideoEffectType(1),
+    Attribute::VideoEffectParameter(1, 1),
+    Attribute::VideoCamera(1),
+    Attribute::VideoSoundVolume(1),
+    Attribute::VideoBlendMode,
+    Attribute::InputSource,
+    Attribute::FieldOfView,
+];
+
+/// The feature group an [Attribute] belongs to.
+///
+/// Follows the feature groups defined by GDTF and is useful for grouping
+/// fixture controls in a user interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub enum FeatureGroup {
+    Intensity,
+    Position,
+    Color,
+    Gobo,
+    Beam,
+    Focus,
+    Control,
+    Shapers,
+    Video,
+    Uncategorized,
+}
+
+/// The category an [Attribute] belongs to, for asking which kind of
+/// features a fixture has without matching on every attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub enum AttributeCategory {
+    /// Intensity, e.g. [Attribute::Dimmer].
+    Intensity,
+    /// Movement and placement, e.g. [Attribute::Pan] and [Attribute::Tilt].
+    Position,
+    /// Additive color mixing, e.g. [Attribute::ColorAddR].
+    ColorAdditive,
+    /// Subtractive color mixing, e.g. [Attribute::ColorSubC].
+    ColorSubtractive,
+    /// Color wheels and color macros.
+    ColorWheel,
+    /// Gobo and animation wheels.
+    Gobo,
+    /// Beam effects like strobe, iris, frost, prism, zoom and focus.
+    Beam,
+    /// Blades and other beam shapers.
+    Shaper,
+    /// Fixture modes, resets and other control functions.
+    Control,
+    /// Video and media functions.
+    Video,
+    /// Everything else, including color temperature, HSB and CIE color and
+    /// custom attributes.
+    Other,
+}
+
 impl Attribute {
+    /// Iterate over all known attributes.
+    ///
+    /// Parametric attributes are yielded once, with `1` for each parameter
+    /// (e.g. [Attribute::Gobo(1)][Attribute::Gobo]). [Attribute::Custom]
+    /// attributes are not included.
+    pub fn all_known() -> impl Iterator<Item = Attribute> {
+        KNOWN_ATTRIBUTES.iter().copied()
+    }
+
+    /// Get the [FeatureGroup] this attribute belongs to.
+    pub fn feature_group(&self) -> FeatureGroup {
+        match self {
+            Self::Dimmer => FeatureGroup::Intensity,
+
+            Self::Pan
+            | Self::Tilt
+            | Self::PanRotate
+            | Self::TiltRotate
+            | Self::PositionEffect
+            | Self::PositionEffectRate
+            | Self::PositionEffectFade
+            | Self::XyzX
+            | Self::XyzY
+            | Self::XyzZ
+            | Self::RotX
+            | Self::RotY
+            | Self::RotZ
+            | Self::ScaleX
+            | Self::ScaleY
+            | Self::ScaleZ
+            | Self::ScaleXYZ => FeatureGroup::Position,
+
+            Self::Gobo(_)
+            | Self::GoboSelectSpin(_)
+            | Self::GoboSelectShake(_)
+            | Self::GoboSelectEffects(_)
+            | Self::GoboWheelIndex(_)
+            | Self::GoboWheelSpin(_)
+            | Self::GoboWheelShake(_)
+            | Self::GoboWheelRandom(_)
+            | Self::GoboWheelAudio(_)
+            | Self::GoboPos(_)
+            | Self::GoboPosRotate(_)
+            | Self::GoboPosShake(_)
+            | Self::AnimationWheel(_)
+            | Self::AnimationWheelAudio(_)
+            | Self::AnimationWheelMacro(_)
+            | Self::AnimationWheelRandom(_)
+            | Self::AnimationWheelSelectEffects(_)
+            | Self::AnimationWheelSelectShake(_)
+            | Self::AnimationWheelSelectSpin(_)
+            | Self::AnimationWheelPos(_)
+            | Self::AnimationWheelPosRotate(_)
+            | Self::AnimationWheelPosShake(_)
+            | Self::AnimationSystem(_)
+            | Self::AnimationSystemRamp(_)
+            | Self::AnimationSystemShake(_)
+            | Self::AnimationSystemAudio(_)
+            | Self::AnimationSystemRandom(_)
+            | Self::AnimationSystemPos(_)
+            | Self::AnimationSystemPosRotate(_)
+            | Self::AnimationSystemPosShake(_)
+            | Self::AnimationSystemPosRandom(_)
+            | Self::AnimationSystemPosAudio(_)
+            | Self::AnimationSystemMacro(_)
+            | Self::MediaFolder(_)
+            | Self::MediaContent(_)
+            | Self::ModelFolder(_)
+            | Self::ModelContent(_)
+            | Self::PlayMode
+            | Self::PlayBegin
+            | Self::PlayEnd
+            | Self::PlaySpeed => FeatureGroup::Gobo,
+
+            Self::ColorEffects(_)
+            | Self::Color(_)
+            | Self::ColorWheelIndex(_)
+            | Self::ColorWheelSpin(_)
+            | Self::ColorWheelRandom(_)
+            | Self::ColorWheelAudio(_)
+            | Self::ColorAddR
+            | Self::ColorAddG
+            | Self::ColorAddB
+            | Self::ColorAddC
+            | Self::ColorAddM
+            | Self::ColorAddY
+            | Self::ColorAddRY
+            | Self::ColorAddGY
+            | Self::ColorAddGC
+            | Self::ColorAddBC
+            | Self::ColorAddBM
+            | Self::ColorAddRM
+            | Self::ColorAddW
+            | Self::ColorAddWW
+            | Self::ColorAddCW
+            | Self::ColorAddUV
+            | Self::ColorSubR
+            | Self::ColorSubG
+            | Self::ColorSubB
+            | Self::ColorSubC
+            | Self::ColorSubM
+            | Self::ColorSubY
+            | Self::ColorMacro(_)
+            | Self::ColorMacroRate(_)
+            | Self::Cto
+            | Self::Ctc
+            | Self::Ctb
+            | Self::Tint
+            | Self::HsbHue
+            | Self::HsbSaturation
+            | Self::HsbBrightness
+            | Self::HsbQuality
+            | Self::CieX
+            | Self::CieY
+            | Self::CieBrightness
+            | Self::ColorRgbRed
+            | Self::ColorRgbGreen
+            | Self::ColorRgbBlue
+            | Self::ColorRgbCyan
+            | Self::ColorRgbMagenta
+            | Self::ColorRgbYellow
+            | Self::ColorRgbQuality
+            | Self::VideoBoostR
+            | Self::VideoBoostG
+            | Self::VideoBoostB
+            | Self::VideoHueShift
+            | Self::VideoSaturation
+            | Self::VideoBrightness
+            | Self::VideoContrast
+            | Self::VideoKeyColorR
+            | Self::VideoKeyColorG
+            | Self::VideoKeyColorB
+            | Self::VideoKeyIntensity
+            | Self::VideoKeyTolerance => FeatureGroup::Color,
+
+            Self::StrobeDuration
+            | Self::StrobeRate
+            | Self::StrobeFrequency
+            | Self::StrobeModeShutter
+            | Self::StrobeModeStrobe
+            | Self::StrobeModePulse
+            | Self::StrobeModePulseOpen
+            | Self::StrobeModePulseClose
+            | Self::StrobeModeRandom
+            | Self::StrobeModeRandomPulse
+            | Self::StrobeModeRandomPulseOpen
+            | Self::StrobeModeRandomPulseClose
+            | Self::StrobeModeEffect
+            | Self::Shutter(_)
+            | Self::ShutterStrobe(_)
+            | Self::ShutterStrobePulse(_)
+            | Self::ShutterStrobePulseClose(_)
+            | Self::ShutterStrobePulseOpen(_)
+            | Self::ShutterStrobeRandom(_)
+            | Self::ShutterStrobeRandomPulse(_)
+            | Self::ShutterStrobeRandomPulseClose(_)
+            | Self::ShutterStrobeRandomPulseOpen(_)
+            | Self::ShutterStrobeEffect(_)
+            | Self::Iris
+            | Self::IrisStrobe
+            | Self::IrisStrobeRandom
+            | Self::IrisPulseClose
+            | Self::IrisPulseOpen
+            | Self::IrisRandomPulseClose
+            | Self::IrisRandomPulseOpen
+            | Self::Frost(_)
+            | Self::FrostPulseOpen(_)
+            | Self::FrostPulseClose(_)
+            | Self::FrostRamp(_)
+            | Self::Prism(_)
+            | Self::PrismSelectSpin(_)
+            | Self::PrismMacro(_)
+            | Self::PrismPos(_)
+            | Self::PrismPosRotate(_)
+            | Self::Effects(_)
+            | Self::EffectsRate(_)
+            | Self::EffectsFade(_)
+            | Self::EffectsAdjust(_, _)
+            | Self::EffectsPos(_)
+            | Self::EffectsPosRotate(_)
+            | Self::EffectsSync
+            | Self::BeamShaper
+            | Self::BeamShaperMacro
+            | Self::BeamShaperPos
+            | Self::BeamShaperPosRotate => FeatureGroup::Beam,
+
+            Self::Zoom
+            | Self::ZoomModeSpot
+            | Self::ZoomModeBeam
+            | Self::DigitalZoom
+            | Self::Focus(_)
+            | Self::FocusAdjust(_)
+            | Self::FocusDistance(_) => FeatureGroup::Focus,
+
+            Self::Control(_)
+            | Self::DimmerMode
+            | Self::DimmerCurve
+            | Self::BlackoutMode
+            | Self::LedFrequency
+            | Self::LedZoneMode
+            | Self::PixelMode
+            | Self::PanMode
+            | Self::TiltMode
+            | Self::PanTiltMode
+            | Self::PositionModes
+            | Self::GoboWheelMode(_)
+            | Self::GoboWheelShortcutMode
+            | Self::AnimationWheelMode(_)
+            | Self::AnimationWheelShortcutMode
+            | Self::ColorMode(_)
+            | Self::ColorWheelShortcutMode
+            | Self::CyanMode
+            | Self::MagentaMode
+            | Self::YellowMode
+            | Self::ColorMixMode
+            | Self::ChromaticMode
+            | Self::ColorCalibrationMode
+            | Self::ColorConsistency
+            | Self::ColorControl
+            | Self::ColorModelMode
+            | Self::ColorSettingsReset
+            | Self::ColorUniformity
+            | Self::CriMode
+            | Self::CustomColor
+            | Self::UvStability
+            | Self::WavelengthCorrection
+            | Self::WhiteCount
+            | Self::StrobeMode
+            | Self::ZoomMode
+            | Self::FocusMode
+            | Self::IrisMode
+            | Self::FanMode(_)
+            | Self::FollowSpotMode
+            | Self::BeamEffectIndexRotateMode
+            | Self::IntensityMSpeed
+            | Self::PositionMSpeed
+            | Self::ColorMixMSpeed
+            | Self::ColorWheelSelectMSpeed
+            | Self::GoboWheelMSpeed(_)
+            | Self::IrisMSpeed
+            | Self::PrismMSpeed(_)
+            | Self::FocusMSpeed
+            | Self::FrostMSpeed(_)
+            | Self::ZoomMSpeed
+            | Self::FrameMSpeed
+            | Self::GlobalMSpeed
+            | Self::ReflectorAdjust
+            | Self::FixtureGlobalReset
+            | Self::DimmerReset
+            | Self::ShutterReset
+            | Self::BeamReset
+            | Self::ColorMixReset
+            | Self::ColorWheelReset
+            | Self::FocusReset
+            | Self::FrameReset
+            | Self::GoboWheelReset
+            | Self::IntensityReset
+            | Self::IrisReset
+            | Self::PositionReset
+            | Self::PanReset
+            | Self::TiltReset
+            | Self::ZoomReset
+            | Self::CtbReset
+            | Self::CtoReset
+            | Self::CtcReset
+            | Self::AnimationSystemReset
+            | Self::FixtureCalibrationReset
+            | Self::Function
+            | Self::LampControl
+            | Self::DisplayIntensity
+            | Self::DmxInput
+            | Self::NoFeature
+            | Self::Blower(_)
+            | Self::Fan(_)
+            | Self::Fog(_)
+            | Self::Haze(_)
+            | Self::LampPowerMode
+            | Self::Fans => FeatureGroup::Control,
+
+            Self::BladeA(_)
+            | Self::BladeB(_)
+            | Self::BladeRot(_)
+            | Self::ShaperRot
+            | Self::ShaperMacros
+            | Self::ShaperMacrosSpeed
+            | Self::BladeSoftA(_)
+            | Self::BladeSoftB(_)
+            | Self::KeyStoneA(_)
+            | Self::KeyStoneB(_) => FeatureGroup::Shapers,
+
+            Self::Video
+            | Self::VideoEffectType(_)
+            | Self::VideoEffectParameter(_, _)
+            | Self::VideoCamera(_)
+            | Self::VideoSoundVolume(_)
+            | Self::VideoBlendMode
+            | Self::InputSource
+            | Self::FieldOfView => FeatureGroup::Video,
+
+            Self::Custom(_) => FeatureGroup::Uncategorized,
+        }
+    }
+
+    /// Get the [AttributeCategory] this attribute belongs to.
+    ///
+    /// Categories are more specific than [FeatureGroup]s, e.g. they tell
+    /// additive color mixing apart from color wheels.
+    pub fn category(&self) -> AttributeCategory {
+        match self {
+            Self::Dimmer => AttributeCategory::Intensity,
+
+            Self::Pan
+            | Self::Tilt
+            | Self::PanRotate
+            | Self::TiltRotate
+            | Self::PositionEffect
+            | Self::PositionEffectRate
+            | Self::PositionEffectFade
+            | Self::XyzX
+            | Self::XyzY
+            | Self::XyzZ
+            | Self::RotX
+            | Self::RotY
+            | Self::RotZ
+            | Self::ScaleX
+            | Self::ScaleY
+            | Self::ScaleZ
+            | Self::ScaleXYZ => AttributeCategory::Position,
+
+            Self::ColorAddR
+            | Self::ColorAddG
+            | Self::ColorAddB
+            | Self::ColorAddC
+            | Self::ColorAddM
+            | Self::ColorAddY
+            | Self::ColorAddRY
+            | Self::ColorAddGY
+            | Self::ColorAddGC
+            | Self::ColorAddBC
+            | Self::ColorAddBM
+            | Self::ColorAddRM
+            | Self::ColorAddW
+            | Self::ColorAddWW
+            | Self::ColorAddCW
+            | Self::ColorAddUV
+            | Self::ColorRgbRed
+            | Self::ColorRgbGreen
+            | Self::ColorRgbBlue
+            | Self::ColorRgbCyan
+            | Self::ColorRgbMagenta
+            | Self::ColorRgbYellow
+            | Self::ColorRgbQuality => AttributeCategory::ColorAdditive,
+
+            Self::ColorSubR
+            | Self::ColorSubG
+            | Self::ColorSubB
+            | Self::ColorSubC
+            | Self::ColorSubM
+            | Self::ColorSubY => AttributeCategory::ColorSubtractive,
+
+            Self::ColorEffects(_)
+            | Self::Color(_)
+            | Self::ColorWheelIndex(_)
+            | Self::ColorWheelSpin(_)
+            | Self::ColorWheelRandom(_)
+            | Self::ColorWheelAudio(_)
+            | Self::ColorMacro(_)
+            | Self::ColorMacroRate(_) => AttributeCategory::ColorWheel,
+
+            Self::Gobo(_)
+            | Self::GoboSelectSpin(_)
+            | Self::GoboSelectShake(_)
+            | Self::GoboSelectEffects(_)
+            | Self::GoboWheelIndex(_)
+            | Self::GoboWheelSpin(_)
+            | Self::GoboWheelShake(_)
+            | Self::GoboWheelRandom(_)
+            | Self::GoboWheelAudio(_)
+            | Self::GoboPos(_)
+            | Self::GoboPosRotate(_)
+            | Self::GoboPosShake(_)
+            | Self::AnimationWheel(_)
+            | Self::AnimationWheelAudio(_)
+            | Self::AnimationWheelMacro(_)
+            | Self::AnimationWheelRandom(_)
+            | Self::AnimationWheelSelectEffects(_)
+            | Self::AnimationWheelSelectShake(_)
+            | Self::AnimationWheelSelectSpin(_)
+            | Self::AnimationWheelPos(_)
+            | Self::AnimationWheelPosRotate(_)
+            | Self::AnimationWheelPosShake(_)
+            | Self::AnimationSystem(_)
+            | Self::AnimationSystemRamp(_)
+            | Self::AnimationSystemShake(_)
+            | Self::AnimationSystemAudio(_)
+            | Self::AnimationSystemRandom(_)
+            | Self::AnimationSystemPos(_)
+            | Self::AnimationSystemPosRotate(_)
+            | Self::AnimationSystemPosShake(_)
+            | Self::AnimationSystemPosRandom(_)
+            | Self::AnimationSystemPosAudio(_)
+            | Self::AnimationSystemMacro(_) => AttributeCategory::Gobo,
+
+            Self::StrobeDuration
+            | Self::StrobeRate
+            | Self::StrobeFrequency
+            | Self::StrobeModeShutter
+            | Self::StrobeModeStrobe
+            | Self::StrobeModePulse
+            | Self::StrobeModePulseOpen
+            | Self::StrobeModePulseClose
+            | Self::StrobeModeRandom
+            | Self::StrobeModeRandomPulse
+            | Self::StrobeModeRandomPulseOpen
+            | Self::StrobeModeRandomPulseClose
+            | Self::StrobeModeEffect
+            | Self::Shutter(_)
+            | Self::ShutterStrobe(_)
+            | Self::ShutterStrobePulse(_)
+            | Self::ShutterStrobePulseClose(_)
+            | Self::ShutterStrobePulseOpen(_)
+            | Self::ShutterStrobeRandom(_)
+            | Self::ShutterStrobeRandomPulse(_)
+            | Self::ShutterStrobeRandomPulseClose(_)
+            | Self::ShutterStrobeRandomPulseOpen(_)
+            | Self::ShutterStrobeEffect(_)
+            | Self::Iris
+            | Self::IrisStrobe
+            | Self::IrisStrobeRandom
+            | Self::IrisPulseClose
+            | Self::IrisPulseOpen
+            | Self::IrisRandomPulseClose
+            | Self::IrisRandomPulseOpen
+            | Self::Frost(_)
+            | Self::FrostPulseOpen(_)
+            | Self::FrostPulseClose(_)
+            | Self::FrostRamp(_)
+            | Self::Prism(_)
+            | Self::PrismSelectSpin(_)
+            | Self::PrismMacro(_)
+            | Self::PrismPos(_)
+            | Self::PrismPosRotate(_)
+            | Self::Effects(_)
+            | Self::EffectsRate(_)
+            | Self::EffectsFade(_)
+            | Self::EffectsAdjust(_, _)
+            | Self::EffectsPos(_)
+            | Self::EffectsPosRotate(_)
+            | Self::EffectsSync
+            | Self::BeamShaper
+            | Self::BeamShaperMacro
+            | Self::BeamShaperPos
+            | Self::BeamShaperPosRotate
+            | Self::Zoom
+            | Self::ZoomModeSpot
+            | Self::ZoomModeBeam
+            | Self::DigitalZoom
+            | Self::Focus(_)
+            | Self::FocusAdjust(_)
+            | Self::FocusDistance(_) => AttributeCategory::Beam,
+
+            Self::BladeA(_)
+            | Self::BladeB(_)
+            | Self::BladeRot(_)
+            | Self::ShaperRot
+            | Self::ShaperMacros
+            | Self::ShaperMacrosSpeed
+            | Self::BladeSoftA(_)
+            | Self::BladeSoftB(_)
+            | Self::KeyStoneA(_)
+            | Self::KeyStoneB(_) => AttributeCategory::Shaper,
+
+            Self::Control(_)
+            | Self::DimmerMode
+            | Self::DimmerCurve
+            | Self::BlackoutMode
+            | Self::LedFrequency
+            | Self::LedZoneMode
+            | Self::PixelMode
+            | Self::PanMode
+            | Self::TiltMode
+            | Self::PanTiltMode
+            | Self::PositionModes
+            | Self::GoboWheelMode(_)
+            | Self::GoboWheelShortcutMode
+            | Self::AnimationWheelMode(_)
+            | Self::AnimationWheelShortcutMode
+            | Self::ColorMode(_)
+            | Self::ColorWheelShortcutMode
+            | Self::CyanMode
+            | Self::MagentaMode
+            | Self::YellowMode
+            | Self::ColorMixMode
+            | Self::ChromaticMode
+            | Self::ColorCalibrationMode
+            | Self::ColorConsistency
+            | Self::ColorControl
+            | Self::ColorModelMode
+            | Self::ColorSettingsReset
+            | Self::ColorUniformity
+            | Self::CriMode
+            | Self::CustomColor
+            | Self::UvStability
+            | Self::WavelengthCorrection
+            | Self::WhiteCount
+            | Self::StrobeMode
+            | Self::ZoomMode
+            | Self::FocusMode
+            | Self::IrisMode
+            | Self::FanMode(_)
+            | Self::FollowSpotMode
+            | Self::BeamEffectIndexRotateMode
+            | Self::IntensityMSpeed
+            | Self::PositionMSpeed
+            | Self::ColorMixMSpeed
+            | Self::ColorWheelSelectMSpeed
+            | Self::GoboWheelMSpeed(_)
+            | Self::IrisMSpeed
+            | Self::PrismMSpeed(_)
+            | Self::FocusMSpeed
+            | Self::FrostMSpeed(_)
+            | Self::ZoomMSpeed
+            | Self::FrameMSpeed
+            | Self::GlobalMSpeed
+            | Self::ReflectorAdjust
+            | Self::FixtureGlobalReset
+            | Self::DimmerReset
+            | Self::ShutterReset
+            | Self::BeamReset
+            | Self::ColorMixReset
+            | Self::ColorWheelReset
+            | Self::FocusReset
+            | Self::FrameReset
+            | Self::GoboWheelReset
+            | Self::IntensityReset
+            | Self::IrisReset
+            | Self::PositionReset
+            | Self::PanReset
+            | Self::TiltReset
+            | Self::ZoomReset
+            | Self::CtbReset
+            | Self::CtoReset
+            | Self::CtcReset
+            | Self::AnimationSystemReset
+            | Self::FixtureCalibrationReset
+            | Self::Function
+            | Self::LampControl
+            | Self::DisplayIntensity
+            | Self::DmxInput
+            | Self::NoFeature
+            | Self::Blower(_)
+            | Self::Fan(_)
+            | Self::Fog(_)
+            | Self::Haze(_)
+            | Self::LampPowerMode
+            | Self::Fans => AttributeCategory::Control,
+
+            Self::MediaFolder(_)
+            | Self::MediaContent(_)
+            | Self::ModelFolder(_)
+            | Self::ModelContent(_)
+            | Self::PlayMode
+            | Self::PlayBegin
+            | Self::PlayEnd
+            | Self::PlaySpeed
+            | Self::VideoBoostR
+            | Self::VideoBoostG
+            | Self::VideoBoostB
+            | Self::VideoHueShift
+            | Self::VideoSaturation
+            | Self::VideoBrightness
+            | Self::VideoContrast
+            | Self::VideoKeyColorR
+            | Self::VideoKeyColorG
+            | Self::VideoKeyColorB
+            | Self::VideoKeyIntensity
+            | Self::VideoKeyTolerance
+            | Self::Video
+            | Self::VideoEffectType(_)
+            | Self::VideoEffectParameter(_, _)
+            | Self::VideoCamera(_)
+            | Self::VideoSoundVolume(_)
+            | Self::VideoBlendMode
+            | Self::InputSource
+            | Self::FieldOfView => AttributeCategory::Video,
+
+            Self::Cto
+            | Self::Ctc
+            | Self::Ctb
+            | Self::Tint
+            | Self::HsbHue
+            | Self::HsbSaturation
+            | Self::HsbBrightness
+            | Self::HsbQuality
+            | Self::CieX
+            | Self::CieY
+            | Self::CieBrightness
+            | Self::Custom(_) => AttributeCategory::Other,
+        }
+    }
+
     /// Get a pretty name of the attribute.
     pub fn pretty(&self) -> String {
         match self {
@@ -1082,10 +2016,10 @@ impl fmt::Display for Attribute {
 
             Self::ColorEffects(n) => write!(f, "ColorEffects{n}"),
             Self::Color(n) => write!(f, "Color{n}"),
-            Self::ColorWheelIndex(n) => write!(f, "ColorWheel{n}Index"),
-            Self::ColorWheelSpin(n) => write!(f, "ColorWheel{n}Spin"),
-            Self::ColorWheelRandom(n) => write!(f, "ColorWheel{n}Random"),
-            Self::ColorWheelAudio(n) => write!(f, "ColorWheel{n}Audio"),
+            Self::ColorWheelIndex(n) => write!(f, "Color{n}WheelIndex"),
+            Self::ColorWheelSpin(n) => write!(f, "Color{n}WheelSpin"),
+            Self::ColorWheelRandom(n) => write!(f, "Color{n}WheelRandom"),
+            Self::ColorWheelAudio(n) => write!(f, "Color{n}WheelAudio"),
             Self::ColorAddR => write!(f, "ColorAdd_R"),
             Self::ColorAddG => write!(f, "ColorAdd_G"),
             Self::ColorAddB => write!(f, "ColorAdd_B"),
@@ -1109,7 +2043,7 @@ impl fmt::Display for Attribute {
             Self::ColorSubM => write!(f, "ColorSub_M"),
             Self::ColorSubY => write!(f, "ColorSub_Y"),
             Self::ColorMacro(n) => write!(f, "ColorMacro{n}"),
-            Self::ColorMacroRate(n) => write!(f, "ColorMacroRate{n}"),
+            Self::ColorMacroRate(n) => write!(f, "ColorMacro{n}Rate"),
             Self::Cto => write!(f, "CTO"),
             Self::Ctc => write!(f, "CTC"),
             Self::Ctb => write!(f, "CTB"),
@@ -1204,8 +2138,8 @@ impl fmt::Display for Attribute {
             Self::DimmerMode => write!(f, "DimmerMode"),
             Self::DimmerCurve => write!(f, "DimmerCurve"),
             Self::BlackoutMode => write!(f, "BlackoutMode"),
-            Self::LedFrequency => write!(f, "LedFrequency"),
-            Self::LedZoneMode => write!(f, "LedZoneMode"),
+            Self::LedFrequency => write!(f, "LEDFrequency"),
+            Self::LedZoneMode => write!(f, "LEDZoneMode"),
             Self::PixelMode => write!(f, "PixelMode"),
             Self::PanMode => write!(f, "PanMode"),
             Self::TiltMode => write!(f, "TiltMode"),
@@ -1213,7 +2147,7 @@ impl fmt::Display for Attribute {
             Self::PositionModes => write!(f, "PositionModes"),
             Self::GoboWheelMode(n) => write!(f, "Gobo{n}WheelMode"),
             Self::GoboWheelShortcutMode => write!(f, "GoboWheelShortcutMode"),
-            Self::AnimationWheelMode(n) => write!(f, "Animation{n}WheelMode"),
+            Self::AnimationWheelMode(n) => write!(f, "AnimationWheel{n}Mode"),
             Self::AnimationWheelShortcutMode => write!(f, "AnimationWheelShortcutMode"),
             Self::ColorMode(n) => write!(f, "Color{n}Mode"),
             Self::ColorWheelShortcutMode => write!(f, "ColorWheelShortcutMode"),
@@ -1228,9 +2162,9 @@ impl fmt::Display for Attribute {
             Self::ColorModelMode => write!(f, "ColorModelMode"),
             Self::ColorSettingsReset => write!(f, "ColorSettingsReset"),
             Self::ColorUniformity => write!(f, "ColorUniformity"),
-            Self::CriMode => write!(f, "CriMode"),
+            Self::CriMode => write!(f, "CRIMode"),
             Self::CustomColor => write!(f, "CustomColor"),
-            Self::UvStability => write!(f, "UvStability"),
+            Self::UvStability => write!(f, "UVStability"),
             Self::WavelengthCorrection => write!(f, "WavelengthCorrection"),
             Self::WhiteCount => write!(f, "WhiteCount"),
             Self::StrobeMode => write!(f, "StrobeMode"),
@@ -1244,7 +2178,7 @@ impl fmt::Display for Attribute {
             Self::PositionMSpeed => write!(f, "PositionMSpeed"),
             Self::ColorMixMSpeed => write!(f, "ColorMixMSpeed"),
             Self::ColorWheelSelectMSpeed => write!(f, "ColorWheelSelectMSpeed"),
-            Self::GoboWheelMSpeed(n) => write!(f, "Gobo{n}WheelMSpeed"),
+            Self::GoboWheelMSpeed(n) => write!(f, "GoboWheel{n}MSpeed"),
             Self::IrisMSpeed => write!(f, "IrisMSpeed"),
             Self::PrismMSpeed(n) => write!(f, "Prism{n}MSpeed"),
             Self::FocusMSpeed => write!(f, "FocusMSpeed"),
@@ -2007,6 +2941,77 @@ mod tests {
     test_attr!(input_source, "InputSource", Attribute::InputSource);
     test_attr!(field_of_view, "FieldOfView", Attribute::FieldOfView);
 
+    #[test]
+    fn all_known_round_trip() {
+        let attributes = Attribute::all_known().collect::<Vec<_>>();
+        assert!(attributes.contains(&Attribute::Dimmer));
+        assert!(attributes.contains(&Attribute::Gobo(1)));
+        assert!(attributes.contains(&Attribute::EffectsAdjust(1, 1)));
+        assert!(!attributes.iter().any(|attribute| matches!(attribute, Attribute::Custom(_))));
+
+        for attribute in attributes {
+            assert_eq!(Attribute::from_str(&attribute.to_string()).unwrap(), attribute, "{attribute}");
+        }
+    }
+
+    #[test]
+    fn feature_group() {
+        assert_eq!(Attribute::Dimmer.feature_group(), FeatureGroup::Intensity);
+        assert_eq!(Attribute::Pan.feature_group(), FeatureGroup::Position);
+        assert_eq!(Attribute::PanRotate.feature_group(), FeatureGroup::Position);
+        assert_eq!(Attribute::ColorAddR.feature_group(), FeatureGroup::Color);
+        assert_eq!(Attribute::Cto.feature_group(), FeatureGroup::Color);
+        assert_eq!(Attribute::HsbHue.feature_group(), FeatureGroup::Color);
+        assert_eq!(Attribute::Gobo(1).feature_group(), FeatureGroup::Gobo);
+        assert_eq!(Attribute::Shutter(1).feature_group(), FeatureGroup::Beam);
+        assert_eq!(Attribute::Zoom.feature_group(), FeatureGroup::Focus);
+        assert_eq!(Attribute::DimmerMode.feature_group(), FeatureGroup::Control);
+        assert_eq!(Attribute::BladeA(1).feature_group(), FeatureGroup::Shapers);
+        assert_eq!(Attribute::VideoCamera(1).feature_group(), FeatureGroup::Video);
+        assert_eq!(Attribute::from_str("SomeCustomAttribute").unwrap().feature_group(), FeatureGroup::Uncategorized);
+    }
+
+    #[test]
+    fn category() {
+        assert_eq!(Attribute::Dimmer.category(), AttributeCategory::Intensity);
+        assert_eq!(Attribute::Tilt.category(), AttributeCategory::Position);
+        assert_eq!(Attribute::ColorAddR.category(), AttributeCategory::ColorAdditive);
+        assert_eq!(Attribute::ColorRgbRed.category(), AttributeCategory::ColorAdditive);
+        assert_eq!(Attribute::ColorSubC.category(), AttributeCategory::ColorSubtractive);
+        assert_eq!(Attribute::Color(1).category(), AttributeCategory::ColorWheel);
+        assert_eq!(Attribute::Gobo(1).category(), AttributeCategory::Gobo);
+        assert_eq!(Attribute::Zoom.category(), AttributeCategory::Beam);
+        assert_eq!(Attribute::BladeA(1).category(), AttributeCategory::Shaper);
+        assert_eq!(Attribute::DimmerMode.category(), AttributeCategory::Control);
+        assert_eq!(Attribute::MediaContent(1).category(), AttributeCategory::Video);
+        assert_eq!(Attribute::Cto.category(), AttributeCategory::Other);
+        assert_eq!(Attribute::from_str("SomeCustomAttribute").unwrap().category(), AttributeCategory::Other);
+    }
+
+    #[test]
+    fn category_refines_feature_group() {
+        for attribute in Attribute::all_known() {
+            let allowed: &[AttributeCategory] = match attribute.feature_group() {
+                FeatureGroup::Intensity => &[AttributeCategory::Intensity],
+                FeatureGroup::Position => &[AttributeCategory::Position],
+                FeatureGroup::Color => &[
+                    AttributeCategory::ColorAdditive,
+                    AttributeCategory::ColorSubtractive,
+                    AttributeCategory::ColorWheel,
+                    AttributeCategory::Video,
+                    AttributeCategory::Other,
+                ],
+                FeatureGroup::Gobo => &[AttributeCategory::Gobo, AttributeCategory::Video],
+                FeatureGroup::Beam | FeatureGroup::Focus => &[AttributeCategory::Beam],
+                FeatureGroup::Control => &[AttributeCategory::Control],
+                FeatureGroup::Shapers => &[AttributeCategory::Shaper],
+                FeatureGroup::Video => &[AttributeCategory::Video],
+                FeatureGroup::Uncategorized => &[AttributeCategory::Other],
+            };
+            assert!(allowed.contains(&attribute.category()), "{attribute}: {:?}", attribute.category());
+        }
+    }
+
     #[test]
     fn custom() {
         let attribute = Attribute::from_str("CustomAttribute").unwrap();
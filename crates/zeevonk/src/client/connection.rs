@@ -0,0 +1,227 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{SinkExt as _, StreamExt as _};
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::client::{Error, ReconnectPolicy};
+use crate::packet::{
+    self, AttributeValues, ClientPacketPayload, MAX_MISSED_PONGS, Packet, PacketDecoder,
+    PacketEncoder, ServerPacketPayload,
+};
+
+/// Responses to requests, or the error that ended the connection.
+pub(super) type Responses = mpsc::UnboundedReceiver<Result<ClientPacketPayload, packet::Error>>;
+
+/// Channels that notifications from the server are forwarded to.
+#[derive(Clone)]
+pub(super) struct Notifications {
+    pub attribute_changes: broadcast::Sender<AttributeValues>,
+    pub show_data_changes: broadcast::Sender<()>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            attribute_changes: broadcast::channel(super::ATTRIBUTE_CHANGES_CAPACITY).0,
+            show_data_changes: broadcast::channel(super::SHOW_DATA_CHANGES_CAPACITY).0,
+        }
+    }
+}
+
+/// A connection to the server.
+///
+/// Packets are read and written by background tasks, so notifications and
+/// pings from the server are handled even when no request is in flight. The
+/// tasks are stopped when the connection is dropped.
+pub(super) struct Connection {
+    /// Responses that are not handled by the background tasks.
+    pub(super) responses: Responses,
+    outgoing: mpsc::UnboundedSender<Packet<ServerPacketPayload>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Connection {
+    pub async fn open(
+        addrs: &[SocketAddr],
+        policy: ReconnectPolicy,
+        notifications: Notifications,
+        heartbeat: Arc<Mutex<Heartbeat>>,
+    ) -> io::Result<Self> {
+        let (reader, writer) = connect_stream(addrs, policy).await?.into_split();
+        Ok(Self::new(reader, writer, policy, notifications, heartbeat))
+    }
+
+    /// Creates a connection over an already connected stream.
+    pub fn new(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+        policy: ReconnectPolicy,
+        notifications: Notifications,
+        heartbeat: Arc<Mutex<Heartbeat>>,
+    ) -> Self {
+        let decoder = PacketDecoder::<ClientPacketPayload>::default();
+        let encoder = PacketEncoder::<ServerPacketPayload>::default();
+        let mut packet_reader = FramedRead::new(reader, decoder);
+        let mut packet_writer = FramedWrite::new(writer, encoder);
+
+        let (responses_tx, responses) = mpsc::unbounded_channel();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel();
+        heartbeat.lock().unwrap().reset();
+
+        let writer_task = tokio::spawn({
+            let responses_tx = responses_tx.clone();
+            async move {
+                while let Some(packet) = outgoing_rx.recv().await {
+                    if let Err(err) = packet_writer.send(packet).await {
+                        let _ = responses_tx.send(Err(err));
+                        break;
+                    }
+                }
+            }
+        });
+
+        let reader_task = tokio::spawn({
+            let responses_tx = responses_tx.clone();
+            let outgoing = outgoing.clone();
+            let heartbeat = Arc::clone(&heartbeat);
+            async move {
+                while let Some(packet) = packet_reader.next().await {
+                    let payload = match packet {
+                        Ok(packet) => packet.payload,
+                        Err(err) => {
+                            let _ = responses_tx.send(Err(err));
+                            break;
+                        }
+                    };
+
+                    // Any packet shows the server is still there.
+                    heartbeat.lock().unwrap().missed_pongs = 0;
+
+                    match payload {
+                        // Sending only fails if there are no subscribers.
+                        ClientPacketPayload::AttributeValuesChanged(values) => {
+                            let _ = notifications.attribute_changes.send(values);
+                        }
+                        ClientPacketPayload::ShowDataChanged => {
+                            let _ = notifications.show_data_changes.send(());
+                        }
+                        ClientPacketPayload::Ping { nonce } => {
+                            let _ = outgoing.send(Packet::new(ServerPacketPayload::Pong { nonce }));
+                        }
+                        ClientPacketPayload::Pong { nonce } => {
+                            heartbeat.lock().unwrap().pong(nonce);
+                        }
+                        payload => {
+                            if responses_tx.send(Ok(payload)).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut tasks = vec![writer_task, reader_task];
+
+        if let Some(interval) = policy.heartbeat_interval {
+            let outgoing = outgoing.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut interval = tokio::time::interval_at(Instant::now() + interval, interval);
+                loop {
+                    interval.tick().await;
+
+                    let nonce = {
+                        let mut heartbeat = heartbeat.lock().unwrap();
+                        if heartbeat.missed_pongs >= MAX_MISSED_PONGS {
+                            log::warn!("server did not respond to {MAX_MISSED_PONGS} pings");
+                            let _ = responses_tx.send(Err(packet::Error::ConnectionLost));
+                            break;
+                        }
+                        heartbeat.ping()
+                    };
+
+                    if outgoing.send(Packet::new(ServerPacketPayload::Ping { nonce })).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+
+        Self { responses, outgoing, tasks }
+    }
+
+    pub fn send(&self, payload: ServerPacketPayload) -> Result<(), Error> {
+        self.outgoing.send(Packet::new(payload)).map_err(|_| Error::ConnectionClosed)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Keeps track of pings sent to the server.
+#[derive(Debug, Default)]
+pub(super) struct Heartbeat {
+    next_nonce: u64,
+    /// The nonce and send time of the last ping without a response.
+    pending: Option<(u64, Instant)>,
+    /// Number of pings sent since the last packet from the server.
+    missed_pongs: u32,
+    last_latency: Option<Duration>,
+}
+
+impl Heartbeat {
+    pub fn last_latency(&self) -> Option<Duration> {
+        self.last_latency
+    }
+
+    fn reset(&mut self) {
+        self.pending = None;
+        self.missed_pongs = 0;
+    }
+
+    fn ping(&mut self) -> u64 {
+        let nonce = self.next_nonce;
+        self.next_nonce += 1;
+        self.pending = Some((nonce, Instant::now()));
+        self.missed_pongs += 1;
+        nonce
+    }
+
+    fn pong(&mut self, nonce: u64) {
+        if let Some((pending_nonce, sent)) = self.pending
+            && pending_nonce == nonce
+        {
+            self.last_latency = Some(sent.elapsed());
+            self.pending = None;
+        }
+    }
+}
+
+async fn connect_stream(addrs: &[SocketAddr], policy: ReconnectPolicy) -> io::Result<TcpStream> {
+    let mut backoff = policy.initial_backoff;
+    let mut retries = 0;
+    loop {
+        match TcpStream::connect(addrs).await {
+            Ok(stream) => return Ok(stream),
+            Err(err) if retries < policy.max_retries => {
+                log::debug!("failed to connect: {err}, retrying in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+                retries += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
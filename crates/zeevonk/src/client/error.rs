@@ -0,0 +1,110 @@
+use std::ops::RangeInclusive;
+
+use tokio::io;
+
+use crate::packet::{self, PROTOCOL_VERSION};
+
+/// Errors returned by [Client](super::Client) requests.
+///
+/// Use [Error::is_disconnect] to find errors after which the request can be
+/// retried on a new connection, and [Error::is_rejection] to find requests
+/// or connections that the server rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An I/O error occurred.
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The server sent a packet that could not be decoded.
+    #[error("failed to decode packet from server: {message}")]
+    Decode { message: String },
+
+    /// The server responded with a different packet than was requested.
+    #[error("unexpected response from server: {got}")]
+    UnexpectedResponse { got: &'static str },
+
+    /// The connection to the server was closed or lost.
+    #[error("connection to server closed")]
+    ConnectionClosed,
+
+    /// The server does not support the protocol version of the client.
+    #[error(
+        "server supports protocol versions {}..={}, but the client uses version {PROTOCOL_VERSION}",
+        supported.start(),
+        supported.end()
+    )]
+    IncompatibleVersion { supported: RangeInclusive<u16> },
+
+    /// The server refused the request, e.g. because the client is read-only.
+    #[error("permission denied: {reason}")]
+    PermissionDenied { reason: String },
+
+    /// The server did not respond within the configured timeout.
+    #[error("server did not respond in time")]
+    Timeout,
+
+    /// The request is invalid, so it was not sent to the server.
+    #[error("invalid request: {message}")]
+    InvalidInput { message: String },
+}
+
+/// Alias of [Error] that doesn't collide with the crate-level
+/// [Error](crate::Error) when both are imported.
+pub type ClientError = Error;
+
+impl Error {
+    /// Returns `true` if the error means the connection to the server was
+    /// lost, so the request can be retried after reconnecting.
+    pub fn is_disconnect(&self) -> bool {
+        match self {
+            Self::ConnectionClosed => true,
+            Self::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the server refused the request or the connection,
+    /// so retrying it won't help and the error should be shown to the user.
+    pub fn is_rejection(&self) -> bool {
+        matches!(self, Self::PermissionDenied { .. } | Self::IncompatibleVersion { .. })
+    }
+}
+
+impl From<packet::Error> for Error {
+    fn from(err: packet::Error) -> Self {
+        match err {
+            packet::Error::Io(err) => Self::Io(err),
+            packet::Error::ConnectionLost => Self::ConnectionClosed,
+            err @ (packet::Error::FrameTooLarge { .. } | packet::Error::InvalidPayload { .. }) => {
+                Self::Decode { message: err.to_string() }
+            }
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            Error::ConnectionClosed => io::Error::new(io::ErrorKind::ConnectionAborted, err),
+            Error::Timeout => io::Error::new(io::ErrorKind::TimedOut, err),
+            err @ Error::PermissionDenied { .. } => {
+                io::Error::new(io::ErrorKind::PermissionDenied, err)
+            }
+            err @ Error::IncompatibleVersion { .. } => {
+                io::Error::new(io::ErrorKind::Unsupported, err)
+            }
+            err @ Error::InvalidInput { .. } => io::Error::new(io::ErrorKind::InvalidInput, err),
+            err @ (Error::Decode { .. } | Error::UnexpectedResponse { .. }) => {
+                io::Error::new(io::ErrorKind::InvalidData, err)
+            }
+        }
+    }
+}
@@ -1,126 +1,1212 @@
 //! A client that can communicate with a Zeevonk server (e.g. sending and receiving triggers or setting attribute values).
 
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 
-use futures::{SinkExt, StreamExt as _};
-use tokio::io;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::{TcpStream, ToSocketAddrs};
-use tokio::sync::Mutex;
-use tokio_util::codec::{FramedRead, FramedWrite};
+use tokio::io::DuplexStream;
+use tokio::net::{ToSocketAddrs, UdpSocket};
+use tokio::sync::{Mutex, broadcast};
 
-use crate::dmx::Multiverse;
+use crate::attr::Attribute;
+use crate::dmx::{self, Address, Multiverse};
 use crate::packet::{
-    AttributeValues, ClientPacketPayload, Packet, PacketDecoder, PacketEncoder, ServerPacketPayload,
+    AttributeValues, ClientPacketPayload, DISCOVERY_PROBE, FadeCurve, PROTOCOL_VERSION,
+    ServerAnnouncement, ServerPacketPayload, ServerStats, SnapshotInfo,
 };
 use crate::show::ShowData;
+use crate::show::fixture::{FixtureId, FixturePath};
+use crate::showfile::{GroupId, Identifier, Protocols, ValidationIssue};
+use crate::value::ClampedValue;
 
+use connection::{Connection, Heartbeat, Notifications};
+pub use error::*;
 pub use processor::*;
 
+mod connection;
+mod error;
 mod processor;
 
+/// Capacity of the channel used to deliver attribute changes to subscribers.
+const ATTRIBUTE_CHANGES_CAPACITY: usize = 64;
+
+/// Capacity of the channel used to notify subscribers of show data changes.
+const SHOW_DATA_CHANGES_CAPACITY: usize = 8;
+
+/// Capacity of the channel used to notify subscribers of reconnects.
+const RECONNECTS_CAPACITY: usize = 8;
+
+/// The default interval at which the client pings the server.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Determines how often and how fast a [Client] tries to (re)connect to the
+/// server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// The number of connection attempts after the first one fails.
+    pub max_retries: u32,
+    /// The delay before the first retry. It doubles after every failed retry.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two retries.
+    pub max_backoff: Duration,
+    /// The interval at which the client pings the server to detect a lost
+    /// connection, or `None` to never ping. The connection is considered lost
+    /// after [MAX_MISSED_PONGS](crate::packet::MAX_MISSED_PONGS) consecutive pings without a response.
+    pub heartbeat_interval: Option<Duration>,
+}
+
+impl ReconnectPolicy {
+    /// Only try to connect once.
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        heartbeat_interval: Some(DEFAULT_HEARTBEAT_INTERVAL),
+    };
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            heartbeat_interval: Some(DEFAULT_HEARTBEAT_INTERVAL),
+        }
+    }
+}
+
+/// Builder for a [Client], created with [Client::builder].
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    name: String,
+    policy: ReconnectPolicy,
+    timeout: Option<Duration>,
+}
+
+impl ClientBuilder {
+    /// Sets the name the client introduces itself with to the server, which
+    /// is shown in the server logs. Defaults to `zeevonk`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the policy used to (re)connect to the server. Defaults to
+    /// [ReconnectPolicy::NONE].
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the time to wait for the response to a request, after which the
+    /// request fails with [Error::Timeout]. Defaults to waiting forever.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connects to the server and checks that it supports the protocol
+    /// version of the client.
+    pub async fn connect<A: ToSocketAddrs>(self, addr: A) -> Result<Client, Error> {
+        let addrs = tokio::net::lookup_host(addr).await?.collect::<Vec<_>>();
+        let notifications = Notifications::new();
+        let heartbeat = Arc::new(std::sync::Mutex::new(Heartbeat::default()));
+
+        let connection =
+            Connection::open(&addrs, self.policy, notifications.clone(), Arc::clone(&heartbeat))
+                .await?;
+
+        self.finish(addrs, connection, notifications, heartbeat).await
+    }
+
+    /// Connects to a server over an in-memory stream, e.g. one half of a
+    /// [tokio::io::duplex] pipe of which the other half is passed to
+    /// [Server::handle_connection](crate::server::Server::handle_connection).
+    ///
+    /// The client can't reconnect, so requests fail with
+    /// [Error::ConnectionClosed] once the stream is closed.
+    pub async fn connect_duplex(self, io: DuplexStream) -> Result<Client, Error> {
+        let notifications = Notifications::new();
+        let heartbeat = Arc::new(std::sync::Mutex::new(Heartbeat::default()));
+
+        let (reader, writer) = tokio::io::split(io);
+        let connection = Connection::new(
+            reader,
+            writer,
+            self.policy,
+            notifications.clone(),
+            Arc::clone(&heartbeat),
+        );
+
+        self.finish(Vec::new(), connection, notifications, heartbeat).await
+    }
+
+    /// Introduces the client to the server over the new connection.
+    async fn finish(
+        self,
+        addrs: Vec<SocketAddr>,
+        connection: Connection,
+        notifications: Notifications,
+        heartbeat: Arc<std::sync::Mutex<Heartbeat>>,
+    ) -> Result<Client, Error> {
+        let reconnects = broadcast::channel(RECONNECTS_CAPACITY).0;
+
+        let mut inner = Inner {
+            addrs,
+            name: self.name,
+            policy: self.policy,
+            timeout: self.timeout,
+            notifications: notifications.clone(),
+            reconnects: reconnects.clone(),
+            heartbeat: Arc::clone(&heartbeat),
+            connection,
+            stale_responses: 0,
+        };
+        inner.hello().await?;
+        log::info!("client connected");
+
+        let inner = Arc::new(Mutex::new(inner));
+
+        Ok(Client { inner, notifications, reconnects, heartbeat })
+    }
+}
+
+#[derive(Clone)]
 pub struct Client {
     inner: Arc<Mutex<Inner>>,
+    notifications: Notifications,
+    reconnects: broadcast::Sender<()>,
+    heartbeat: Arc<std::sync::Mutex<Heartbeat>>,
 }
 
 impl Client {
-    pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
-        let (reader, writer) = TcpStream::connect(addr).await?.into_split();
-        log::info!("client connected");
+    /// Returns a [ClientBuilder] to configure a client before connecting.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            policy: ReconnectPolicy::NONE,
+            timeout: None,
+        }
+    }
 
-        let decoder = PacketDecoder::<ClientPacketPayload>::default();
-        let encoder = PacketEncoder::<ServerPacketPayload>::default();
-        let packet_reader = FramedRead::new(reader, decoder);
-        let packet_writer = FramedWrite::new(writer, encoder);
+    /// Connects to the server, trying only once.
+    ///
+    /// If the connection is lost, requests still try to reconnect once. Use
+    /// [Client::connect_with_retry] to keep trying for longer.
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, Error> {
+        Self::builder().connect(addr).await
+    }
 
-        let inner = Arc::new(Mutex::new(Inner { packet_reader, packet_writer }));
+    /// Connects to a server over an in-memory stream. See
+    /// [ClientBuilder::connect_duplex].
+    pub async fn connect_duplex(io: DuplexStream) -> Result<Self, Error> {
+        Self::builder().connect_duplex(io).await
+    }
 
-        Ok(Self { inner })
+    /// Finds servers on the local network that have discovery enabled, by
+    /// broadcasting a discovery probe and collecting the answers that arrive
+    /// within `timeout`.
+    pub async fn discover(timeout: Duration) -> Result<Vec<ServerAnnouncement>, Error> {
+        let broadcast = SocketAddr::from((Ipv4Addr::BROADCAST, crate::DEFAULT_PORT));
+        Self::discover_at(broadcast, timeout).await
     }
 
-    pub async fn request_show_data(&self) -> io::Result<ShowData> {
-        let mut guard = self.inner.lock().await;
-        guard.request_show_data().await
+    /// Sends a discovery probe to the given address, e.g. a single server or
+    /// servers with a different discovery port, and collects the answers that
+    /// arrive within `timeout`. See [Client::discover].
+    pub async fn discover_at(
+        addr: SocketAddr,
+        timeout: Duration,
+    ) -> Result<Vec<ServerAnnouncement>, Error> {
+        let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).await?;
+        socket.set_broadcast(true)?;
+        socket.send_to(DISCOVERY_PROBE, addr).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut announcements = Vec::<ServerAnnouncement>::new();
+        let mut buf = [0; 1024];
+        while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await
+        {
+            let (len, peer) = received?;
+            match rmp_serde::from_slice::<ServerAnnouncement>(&buf[..len]) {
+                Ok(mut announcement) => {
+                    announcement.ip = Some(peer.ip());
+                    if !announcements.contains(&announcement) {
+                        announcements.push(announcement);
+                    }
+                }
+                Err(err) => log::debug!("ignoring invalid discovery answer from {peer}: {err}"),
+            }
+        }
+
+        Ok(announcements)
     }
 
-    pub async fn request_dmx_output(&self) -> io::Result<Multiverse> {
-        let mut guard = self.inner.lock().await;
-        guard.request_dmx_output().await
+    /// Connects to the server, retrying according to the given policy.
+    ///
+    /// If a request fails because the connection was lost, the client
+    /// reconnects using the same policy and retries the request once.
+    pub async fn connect_with_retry<A: ToSocketAddrs>(
+        addr: A,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, Error> {
+        Self::builder().reconnect_policy(policy).connect(addr).await
+    }
+
+    /// Subscribes to attribute values set by other clients.
+    ///
+    /// The received values are the values as they were applied by the server.
+    pub fn subscribe_attribute_changes(&self) -> broadcast::Receiver<AttributeValues> {
+        self.notifications.attribute_changes.subscribe()
+    }
+
+    /// Subscribes to changes of the show data on the server, e.g. after the
+    /// server reloaded its showfile. Show data should be requested again
+    /// when notified.
+    pub fn subscribe_show_data_changes(&self) -> broadcast::Receiver<()> {
+        self.notifications.show_data_changes.subscribe()
+    }
+
+    /// Subscribes to reconnects to the server.
+    ///
+    /// The server might have restarted with a different patch, so show data
+    /// should be requested again after a reconnect.
+    pub fn subscribe_reconnects(&self) -> broadcast::Receiver<()> {
+        self.reconnects.subscribe()
+    }
+
+    pub async fn request_show_data(&self) -> Result<ShowData, Error> {
+        self.request(async |inner| inner.request_show_data().await).await
+    }
+
+    pub async fn request_dmx_output(&self) -> Result<Multiverse, Error> {
+        self.request(async |inner| inner.request_dmx_output().await).await
+    }
+
+    /// Requests the output with every fixture at its default values, without
+    /// any attribute values or raw DMX overrides applied.
+    pub async fn request_default_output(&self) -> Result<Multiverse, Error> {
+        self.request(async |inner| inner.request_default_output().await).await
+    }
+
+    pub async fn request_set_attribute_values(&self, values: AttributeValues) -> Result<(), Error> {
+        self.request(async |inner| inner.request_set_attribute_values(values.clone()).await).await
+    }
+
+    /// Fades the attribute values from their current values to the given
+    /// values over the duration, following the curve. The duration is
+    /// rounded down to whole milliseconds.
+    ///
+    /// The server computes the intermediate values, so the fade does not
+    /// have to be streamed by the client.
+    pub async fn request_set_attribute_values_faded(
+        &self,
+        values: AttributeValues,
+        duration: Duration,
+        curve: FadeCurve,
+    ) -> Result<(), Error> {
+        let duration_ms = u32::try_from(duration.as_millis()).map_err(|_| Error::InvalidInput {
+            message: "fade duration is too long".to_string(),
+        })?;
+        self.request(async |inner| {
+            inner.request_set_attribute_values_faded(values.clone(), duration_ms, curve).await
+        })
+        .await
     }
 
-    pub async fn request_set_attribute_values(&self, values: AttributeValues) -> io::Result<()> {
+    /// Sets the value of an attribute on every fixture in the group that has
+    /// that attribute.
+    ///
+    /// Returns `false` if no group with the given id exists.
+    pub async fn request_set_group_attribute_value(
+        &self,
+        group: GroupId,
+        attribute: Attribute,
+        value: impl Into<ClampedValue>,
+    ) -> Result<bool, Error> {
+        let value = value.into();
+        self.request(async |inner| {
+            inner.request_set_group_attribute_value(group, attribute, value).await
+        })
+        .await
+    }
+
+    /// Forces DMX channels to the given values, regardless of the resolved
+    /// attribute values. Overrides stay active until they are cleared.
+    pub async fn request_set_raw_dmx(
+        &self,
+        values: Vec<(Address, dmx::Value)>,
+    ) -> Result<(), Error> {
+        self.request(async |inner| inner.request_set_raw_dmx(values.clone()).await).await
+    }
+
+    /// Clears raw DMX overrides for the given addresses, or all overrides if
+    /// `addresses` is `None`.
+    pub async fn request_clear_raw_dmx(
+        &self,
+        addresses: Option<Vec<Address>>,
+    ) -> Result<(), Error> {
+        self.request(async |inner| inner.request_clear_raw_dmx(addresses.clone()).await).await
+    }
+
+    /// Removes the attribute values of the fixture and its sub-fixtures, so
+    /// they return to their defaults.
+    pub async fn request_clear_attribute_values(
+        &self,
+        fixture_path: FixturePath,
+    ) -> Result<(), Error> {
+        self.request(async |inner| inner.request_clear_attribute_values(Some(fixture_path)).await)
+            .await
+    }
+
+    /// Removes the attribute values of all fixtures, so they return to their
+    /// defaults.
+    pub async fn request_clear_all_attribute_values(&self) -> Result<(), Error> {
+        self.request(async |inner| inner.request_clear_attribute_values(None).await).await
+    }
+
+    /// Sets the grand master, which scales the intensity of all fixtures.
+    pub async fn request_set_grand_master(&self, level: ClampedValue) -> Result<(), Error> {
+        self.request(async |inner| inner.request_set_grand_master(level).await).await
+    }
+
+    /// Sets the master of a universe, which scales the intensity of the
+    /// fixtures in it on top of the grand master.
+    pub async fn request_set_universe_master(
+        &self,
+        universe: dmx::UniverseId,
+        level: ClampedValue,
+    ) -> Result<(), Error> {
+        self.request(async |inner| inner.request_set_universe_master(universe, level).await).await
+    }
+
+    /// Puts the intensity of all fixtures at zero while `blackout` is set,
+    /// without changing their attribute values or the masters.
+    pub async fn request_set_blackout(&self, blackout: bool) -> Result<(), Error> {
+        self.request(async |inner| inner.request_set_blackout(blackout).await).await
+    }
+
+    /// Parks a DMX channel at a value. Parked channels keep their value,
+    /// regardless of attribute values and raw DMX overrides, until unparked.
+    pub async fn request_park_address(
+        &self,
+        address: Address,
+        value: dmx::Value,
+    ) -> Result<(), Error> {
+        self.request(async |inner| inner.request_park_address(address, value).await).await
+    }
+
+    /// Unparks a DMX channel, returning `false` if it was not parked.
+    pub async fn request_unpark_address(&self, address: Address) -> Result<bool, Error> {
+        self.request(async |inner| inner.request_unpark_address(address).await).await
+    }
+
+    /// Requests all parked DMX channels and their values, sorted by address.
+    pub async fn request_parked_addresses(&self) -> Result<Vec<(Address, dmx::Value)>, Error> {
+        self.request(async |inner| inner.request_parked_addresses().await).await
+    }
+
+    /// Fires the trigger with the given identifier, applying its attribute
+    /// values.
+    ///
+    /// Returns `false` if the server has no trigger with that identifier.
+    pub async fn request_fire_trigger(&self, id: Identifier) -> Result<bool, Error> {
+        self.request(async |inner| inner.request_fire_trigger(id.clone()).await).await
+    }
+
+    /// Renames the root fixture with the given id. Labels don't have to be
+    /// unique.
+    ///
+    /// Returns `false` if the server has no root fixture with that id.
+    pub async fn request_rename_fixture(
+        &self,
+        root_id: FixtureId,
+        label: impl Into<String>,
+    ) -> Result<bool, Error> {
+        let label = label.into();
+        self.request(async |inner| inner.request_rename_fixture(root_id, label.clone()).await).await
+    }
+
+    /// Replaces the output protocols of the server, without interrupting sACN
+    /// outputs whose configuration is unchanged.
+    ///
+    /// Returns the issues that prevented the protocols from being reloaded,
+    /// which is empty if they were reloaded.
+    pub async fn request_reload_protocols(
+        &self,
+        protocols: Protocols,
+    ) -> Result<Vec<ValidationIssue>, Error> {
+        self.request(async |inner| inner.request_reload_protocols(protocols.clone()).await).await
+    }
+
+    /// Saves the current attribute values and raw DMX overrides of the server
+    /// as a snapshot under the label, replacing any snapshot with that label.
+    ///
+    /// Returns `true` if a snapshot was replaced, e.g. one saved by another
+    /// client at the same time.
+    pub async fn request_save_snapshot(&self, label: Identifier) -> Result<bool, Error> {
+        self.request(async |inner| inner.request_save_snapshot(label.clone()).await).await
+    }
+
+    /// Replaces the attribute values and raw DMX overrides of the server with
+    /// the ones in the snapshot.
+    ///
+    /// Returns `false` if the server has no snapshot with that label.
+    pub async fn request_restore_snapshot(&self, label: Identifier) -> Result<bool, Error> {
+        self.request(async |inner| inner.request_restore_snapshot(label.clone()).await).await
+    }
+
+    /// Requests all snapshots saved on the server, sorted by label.
+    pub async fn request_list_snapshots(&self) -> Result<Vec<SnapshotInfo>, Error> {
+        self.request(async |inner| inner.request_list_snapshots().await).await
+    }
+
+    /// Deletes the snapshot with the label.
+    ///
+    /// Returns `false` if the server has no snapshot with that label.
+    pub async fn request_delete_snapshot(&self, label: Identifier) -> Result<bool, Error> {
+        self.request(async |inner| inner.request_delete_snapshot(label.clone()).await).await
+    }
+
+    /// Requests statistics about the server, for monitoring.
+    pub async fn request_stats(&self) -> Result<ServerStats, Error> {
+        self.request(async |inner| inner.request_stats().await).await
+    }
+
+    /// Requests the paths of all fixtures that have the given attribute,
+    /// sorted by path.
+    pub async fn request_fixtures_with_attribute(
+        &self,
+        attribute: Attribute,
+    ) -> Result<Vec<FixturePath>, Error> {
+        self.request(async |inner| inner.request_fixtures_with_attribute(attribute).await).await
+    }
+
+    /// Requests the current values of the given attributes, or of every
+    /// attribute of every fixture if `filter` is `None`. Attributes that were
+    /// never set have their default value.
+    pub async fn request_attribute_values(
+        &self,
+        filter: Option<Vec<(FixturePath, Attribute)>>,
+    ) -> Result<AttributeValues, Error> {
+        self.request(async |inner| inner.request_attribute_values(filter.clone()).await).await
+    }
+
+    /// Requests the last frame the server sent to its dummy output, if it
+    /// records its output instead of sending it over the network.
+    pub async fn request_recorded_output(&self) -> Result<Option<Multiverse>, Error> {
+        self.request(async |inner| inner.request_recorded_output().await).await
+    }
+
+    /// Returns the round-trip time of the last heartbeat ping that the
+    /// server responded to.
+    pub fn last_latency(&self) -> Option<Duration> {
+        self.heartbeat.lock().unwrap().last_latency()
+    }
+
+    /// Runs the request, reconnecting and retrying it once if the connection
+    /// to the server was lost.
+    async fn request<T>(
+        &self,
+        mut request: impl AsyncFnMut(&mut Inner) -> Result<T, Error>,
+    ) -> Result<T, Error> {
         let mut guard = self.inner.lock().await;
-        guard.request_set_attribute_values(values).await
+        match request(&mut guard).await {
+            Err(err) if err.is_disconnect() => {
+                log::warn!("connection lost: {err}, reconnecting...");
+                guard.reconnect().await?;
+                request(&mut guard).await
+            }
+            result => result,
+        }
     }
 }
 
 struct Inner {
-    packet_reader: FramedRead<OwnedReadHalf, PacketDecoder<ClientPacketPayload>>,
-    packet_writer: FramedWrite<OwnedWriteHalf, PacketEncoder<ServerPacketPayload>>,
+    /// The addresses of the server, or none if the client is connected over
+    /// an in-memory stream and can't reconnect.
+    addrs: Vec<SocketAddr>,
+    name: String,
+    policy: ReconnectPolicy,
+    timeout: Option<Duration>,
+    notifications: Notifications,
+    reconnects: broadcast::Sender<()>,
+    heartbeat: Arc<std::sync::Mutex<Heartbeat>>,
+    connection: Connection,
+    /// Number of responses to timed out requests that are still on their way.
+    /// The server responds to requests in order, so these are the next
+    /// responses to arrive and are discarded.
+    stale_responses: usize,
 }
 
 impl Inner {
-    pub async fn request_show_data(&mut self) -> io::Result<ShowData> {
-        self.send_packet(ServerPacketPayload::RequestShowData).await?;
-
-        while let Some(packet) = self.packet_reader.next().await {
-            match packet {
-                Ok(packet) => match packet.payload {
-                    ClientPacketPayload::ResponseShowData(show_data) => {
-                        return Ok(show_data);
-                    }
-                    _ => continue,
-                },
-                Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
-            }
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        if self.addrs.is_empty() {
+            return Err(Error::ConnectionClosed);
         }
 
-        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed"))
+        self.connection = Connection::open(
+            &self.addrs,
+            self.policy,
+            self.notifications.clone(),
+            Arc::clone(&self.heartbeat),
+        )
+        .await?;
+        self.stale_responses = 0;
+        self.hello().await?;
+        log::info!("client reconnected");
+
+        // Sending only fails if there are no subscribers.
+        let _ = self.reconnects.send(());
+        Ok(())
     }
 
-    pub async fn request_dmx_output(&mut self) -> io::Result<Multiverse> {
-        self.send_packet(ServerPacketPayload::RequestDmxOutput).await?;
+    /// Introduces the client to the server, failing if the server does not
+    /// support the protocol version of the client.
+    async fn hello(&mut self) -> Result<(), Error> {
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: self.name.clone(),
+        };
+        let server_version = self
+            .request(hello, |payload| match payload {
+                ClientPacketPayload::HelloAck { server_version, .. } => Some(Ok(server_version)),
+                ClientPacketPayload::IncompatibleVersion { supported } => {
+                    Some(Err(Error::IncompatibleVersion { supported }))
+                }
+                _ => None,
+            })
+            .await??;
+        log::debug!("server version: {server_version}");
+        Ok(())
+    }
 
-        while let Some(packet) = self.packet_reader.next().await {
-            match packet {
-                Ok(packet) => match packet.payload {
-                    ClientPacketPayload::ResponseDmxOutput(multiverse) => {
-                        return Ok(multiverse);
-                    }
-                    _ => continue,
-                },
-                Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
-            }
-        }
+    pub async fn request_show_data(&mut self) -> Result<ShowData, Error> {
+        self.request(ServerPacketPayload::RequestShowData, |payload| match payload {
+            ClientPacketPayload::ResponseShowData(show_data) => Some(show_data),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_dmx_output(&mut self) -> Result<Multiverse, Error> {
+        self.request(ServerPacketPayload::RequestDmxOutput, |payload| match payload {
+            ClientPacketPayload::ResponseDmxOutput(multiverse) => Some(multiverse),
+            _ => None,
+        })
+        .await
+    }
 
-        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed"))
+    pub async fn request_default_output(&mut self) -> Result<Multiverse, Error> {
+        self.request(ServerPacketPayload::RequestDefaultOutput, |payload| match payload {
+            ClientPacketPayload::ResponseDefaultOutput(multiverse) => Some(multiverse),
+            _ => None,
+        })
+        .await
     }
 
     pub async fn request_set_attribute_values(
         &mut self,
         values: AttributeValues,
-    ) -> io::Result<()> {
-        self.send_packet(ServerPacketPayload::RequestSetAttributeValues(values)).await?;
-
-        while let Some(packet) = self.packet_reader.next().await {
-            match packet {
-                Ok(packet) => match packet.payload {
-                    ClientPacketPayload::ResponseSetAttributeValues => {
-                        return Ok(());
-                    }
-                    _ => continue,
-                },
-                Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
+    ) -> Result<(), Error> {
+        self.request(
+            ServerPacketPayload::RequestSetAttributeValues(values),
+            |payload| match payload {
+                ClientPacketPayload::ResponseSetAttributeValues => Some(()),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn request_set_attribute_values_faded(
+        &mut self,
+        values: AttributeValues,
+        duration_ms: u32,
+        curve: FadeCurve,
+    ) -> Result<(), Error> {
+        self.request(
+            ServerPacketPayload::SetAttributeValuesFaded { values, duration_ms, curve },
+            |payload| match payload {
+                ClientPacketPayload::ResponseSetAttributeValuesFaded => Some(()),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn request_set_group_attribute_value(
+        &mut self,
+        group: GroupId,
+        attribute: Attribute,
+        value: ClampedValue,
+    ) -> Result<bool, Error> {
+        self.request(
+            ServerPacketPayload::RequestSetGroupAttributeValue { group, attribute, value },
+            |payload| match payload {
+                ClientPacketPayload::ResponseSetGroupAttributeValue { applied } => Some(applied),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn request_set_raw_dmx(
+        &mut self,
+        values: Vec<(Address, dmx::Value)>,
+    ) -> Result<(), Error> {
+        self.request(ServerPacketPayload::RequestSetRawDmx { values }, |payload| match payload {
+            ClientPacketPayload::ResponseSetRawDmx => Some(()),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_clear_raw_dmx(
+        &mut self,
+        addresses: Option<Vec<Address>>,
+    ) -> Result<(), Error> {
+        self.request(ServerPacketPayload::RequestClearRawDmx { addresses }, |payload| match payload
+        {
+            ClientPacketPayload::ResponseClearRawDmx => Some(()),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_clear_attribute_values(
+        &mut self,
+        fixture_path: Option<FixturePath>,
+    ) -> Result<(), Error> {
+        self.request(ServerPacketPayload::RequestClearAttributeValues { fixture_path }, |payload| {
+            match payload {
+                ClientPacketPayload::ResponseClearAttributeValues => Some(()),
+                _ => None,
+            }
+        })
+        .await
+    }
+
+    pub async fn request_set_grand_master(&mut self, level: ClampedValue) -> Result<(), Error> {
+        self.request(
+            ServerPacketPayload::RequestSetGrandMaster { level },
+            |payload| match payload {
+                ClientPacketPayload::ResponseSetGrandMaster => Some(()),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn request_set_universe_master(
+        &mut self,
+        universe: dmx::UniverseId,
+        level: ClampedValue,
+    ) -> Result<(), Error> {
+        let payload = ServerPacketPayload::RequestSetUniverseMaster { universe, level };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseSetUniverseMaster => Some(()),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_set_blackout(&mut self, blackout: bool) -> Result<(), Error> {
+        self.request(
+            ServerPacketPayload::RequestSetBlackout { blackout },
+            |payload| match payload {
+                ClientPacketPayload::ResponseSetBlackout => Some(()),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn request_park_address(
+        &mut self,
+        address: Address,
+        value: dmx::Value,
+    ) -> Result<(), Error> {
+        self.request(ServerPacketPayload::RequestParkAddress { address, value }, |payload| {
+            match payload {
+                ClientPacketPayload::ResponseParkAddress => Some(()),
+                _ => None,
+            }
+        })
+        .await
+    }
+
+    pub async fn request_unpark_address(&mut self, address: Address) -> Result<bool, Error> {
+        self.request(ServerPacketPayload::RequestUnparkAddress { address }, |payload| match payload
+        {
+            ClientPacketPayload::ResponseUnparkAddress { was_parked } => Some(was_parked),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_parked_addresses(&mut self) -> Result<Vec<(Address, dmx::Value)>, Error> {
+        self.request(ServerPacketPayload::RequestParkedAddresses, |payload| match payload {
+            ClientPacketPayload::ResponseParkedAddresses { parked } => Some(parked),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_rename_fixture(
+        &mut self,
+        root_id: FixtureId,
+        label: String,
+    ) -> Result<bool, Error> {
+        let payload = ServerPacketPayload::RequestRenameFixture { root_id, label };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseRenameFixture { renamed } => Some(renamed),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_reload_protocols(
+        &mut self,
+        protocols: Protocols,
+    ) -> Result<Vec<ValidationIssue>, Error> {
+        let payload = ServerPacketPayload::RequestReloadProtocols(protocols);
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseReloadProtocols { issues } => Some(issues),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_fire_trigger(&mut self, id: Identifier) -> Result<bool, Error> {
+        self.request(ServerPacketPayload::FireTrigger(id), |payload| match payload {
+            ClientPacketPayload::ResponseFireTrigger { applied } => Some(applied),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_save_snapshot(&mut self, label: Identifier) -> Result<bool, Error> {
+        self.request(ServerPacketPayload::RequestSaveSnapshot { label }, |payload| match payload {
+            ClientPacketPayload::ResponseSaveSnapshot { overwritten } => Some(overwritten),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_restore_snapshot(&mut self, label: Identifier) -> Result<bool, Error> {
+        let payload = ServerPacketPayload::RequestRestoreSnapshot { label };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseRestoreSnapshot { restored } => Some(restored),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_list_snapshots(&mut self) -> Result<Vec<SnapshotInfo>, Error> {
+        self.request(ServerPacketPayload::RequestListSnapshots, |payload| match payload {
+            ClientPacketPayload::ResponseListSnapshots { snapshots } => Some(snapshots),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_delete_snapshot(&mut self, label: Identifier) -> Result<bool, Error> {
+        let payload = ServerPacketPayload::RequestDeleteSnapshot { label };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseDeleteSnapshot { deleted } => Some(deleted),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_stats(&mut self) -> Result<ServerStats, Error> {
+        self.request(ServerPacketPayload::RequestStats, |payload| match payload {
+            ClientPacketPayload::ResponseStats(stats) => Some(stats),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_attribute_values(
+        &mut self,
+        filter: Option<Vec<(FixturePath, Attribute)>>,
+    ) -> Result<AttributeValues, Error> {
+        let payload = ServerPacketPayload::RequestAttributeValues { filter };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseAttributeValues(values) => Some(values),
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_fixtures_with_attribute(
+        &mut self,
+        attribute: Attribute,
+    ) -> Result<Vec<FixturePath>, Error> {
+        let payload = ServerPacketPayload::RequestFixturesWithAttribute { attribute };
+        self.request(payload, |payload| match payload {
+            ClientPacketPayload::ResponseFixturesWithAttribute { fixture_paths } => {
+                Some(fixture_paths)
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    pub async fn request_recorded_output(&mut self) -> Result<Option<Multiverse>, Error> {
+        self.request(ServerPacketPayload::RequestRecordedOutput, |payload| match payload {
+            ClientPacketPayload::ResponseRecordedOutput { frame } => Some(frame),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Sends the request and waits for its response, which `response`
+    /// converts into the result. `response` returns `None` if the payload is
+    /// not the expected response.
+    async fn request<T>(
+        &mut self,
+        payload: ServerPacketPayload,
+        response: impl FnOnce(ClientPacketPayload) -> Option<T>,
+    ) -> Result<T, Error> {
+        self.connection.send(payload)?;
+
+        let payload = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.recv_response()).await {
+                Ok(payload) => payload?,
+                Err(_) => {
+                    self.stale_responses += 1;
+                    return Err(Error::Timeout);
+                }
+            },
+            None => self.recv_response().await?,
+        };
+
+        if let ClientPacketPayload::PermissionDenied { reason } = payload {
+            return Err(Error::PermissionDenied { reason });
+        }
+
+        let got = payload.name();
+        response(payload).ok_or(Error::UnexpectedResponse { got })
+    }
+
+    /// Receives the next response, skipping responses to timed out requests.
+    async fn recv_response(&mut self) -> Result<ClientPacketPayload, Error> {
+        loop {
+            let payload =
+                self.connection.responses.recv().await.ok_or(Error::ConnectionClosed)??;
+            if self.stale_responses > 0 {
+                self.stale_responses -= 1;
+                log::debug!("discarding late response {}", payload.name());
+                continue;
             }
+            return Ok(payload);
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt as _, StreamExt as _};
+    use tokio::net::TcpListener;
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::task::JoinHandle;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use super::*;
+    use crate::packet::{Packet, PacketDecoder, PacketEncoder};
+
+    const POLICY: ReconnectPolicy = ReconnectPolicy {
+        max_retries: 20,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        heartbeat_interval: Some(Duration::from_millis(20)),
+    };
+
+    /// Serves DMX output and ping requests for a single client.
+    fn serve(listener: TcpListener) -> JoinHandle<()> {
+        serve_with(listener, |payload| match payload {
+            ServerPacketPayload::RequestDmxOutput => {
+                Some(ClientPacketPayload::ResponseDmxOutput(Multiverse::new()))
+            }
+            ServerPacketPayload::Ping { nonce } => Some(ClientPacketPayload::Pong { nonce }),
+            _ => None,
+        })
+    }
+
+    /// Serves a single client, responding to each packet with the response
+    /// returned by `respond`, if any.
+    fn serve_with(
+        listener: TcpListener,
+        mut respond: impl FnMut(ServerPacketPayload) -> Option<ClientPacketPayload> + Send + 'static,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let (mut reader, mut writer) = accept(listener).await;
+            while let Some(Ok(packet)) = reader.next().await {
+                let Some(response) = respond(packet.payload) else { continue };
+                writer.send(Packet::new(response)).await.unwrap();
+            }
+        })
+    }
+
+    /// Accepts a single client and answers its hello, returning the packet
+    /// reader and writer.
+    async fn accept(
+        listener: TcpListener,
+    ) -> (
+        FramedRead<OwnedReadHalf, PacketDecoder<ServerPacketPayload>>,
+        FramedWrite<OwnedWriteHalf, PacketEncoder<ClientPacketPayload>>,
+    ) {
+        let (mut reader, mut writer) = accept_without_hello(listener).await;
+        let packet = reader.next().await.unwrap().unwrap();
+        let ServerPacketPayload::Hello { protocol_version, .. } = packet.payload else {
+            panic!("expected hello, got {:?}", packet.payload);
+        };
+        let response =
+            ClientPacketPayload::HelloAck { protocol_version, server_version: "test".to_string() };
+        writer.send(Packet::new(response)).await.unwrap();
+        (reader, writer)
+    }
+
+    /// Accepts a single client, returning the packet reader and writer.
+    async fn accept_without_hello(
+        listener: TcpListener,
+    ) -> (
+        FramedRead<OwnedReadHalf, PacketDecoder<ServerPacketPayload>>,
+        FramedWrite<OwnedWriteHalf, PacketEncoder<ClientPacketPayload>>,
+    ) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, writer) = stream.into_split();
+        (
+            FramedRead::new(reader, PacketDecoder::default()),
+            FramedWrite::new(writer, PacketEncoder::default()),
+        )
+    }
+
+    /// Accepts a single client and answers its hello, but never responds to
+    /// anything else.
+    fn serve_unresponsive(listener: TcpListener) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let _connection = accept(listener).await;
+            std::future::pending::<()>().await;
+        })
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_server_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve(listener);
+
+        let client = Client::connect_with_retry(addr, POLICY).await.unwrap();
+        let mut reconnects = client.subscribe_reconnects();
+        client.request_dmx_output().await.unwrap();
+
+        // Stop the server, dropping the connection, and restart it on the same port.
+        server.abort();
+        let _ = server.await;
+        let server = serve(TcpListener::bind(addr).await.unwrap());
+
+        client.request_dmx_output().await.unwrap();
+        assert!(reconnects.try_recv().is_ok());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn heartbeat_measures_latency() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve(listener);
+        let client = Client::connect_with_retry(addr, POLICY).await.unwrap();
+
+        assert_eq!(client.last_latency(), None);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(client.last_latency().is_some());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn unresponsive_server_fails_pending_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let policy = ReconnectPolicy { max_retries: 0, ..POLICY };
+        let server = serve_unresponsive(listener);
+        let client = Client::connect_with_retry(addr, policy).await.unwrap();
+
+        let result =
+            tokio::time::timeout(Duration::from_secs(5), client.request_dmx_output()).await;
+        assert!(result.unwrap().is_err());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn connect_retries_until_server_is_up() {
+        let addr = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            serve(TcpListener::bind(addr).await.unwrap()).await.unwrap();
+        });
 
-        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed"))
+        assert!(Client::connect(addr).await.is_err());
+
+        let client = Client::connect_with_retry(addr, POLICY).await.unwrap();
+        client.request_dmx_output().await.unwrap();
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn slow_response_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve_unresponsive(listener);
+        let client =
+            Client::builder().timeout(Duration::from_millis(50)).connect(addr).await.unwrap();
+
+        assert!(matches!(client.request_dmx_output().await, Err(Error::Timeout)));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn late_response_is_discarded() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Respond to the first request too late, with a universe that the
+        // response to the second request does not have.
+        let server = tokio::spawn(async move {
+            let (mut reader, mut writer) = accept(listener).await;
+            let mut late = Multiverse::new();
+            late.create_universe(dmx::UniverseId::new(1).unwrap(), dmx::Universe::new());
+            for (delay, multiverse) in
+                [(Duration::from_millis(300), late), (Duration::ZERO, Multiverse::new())]
+            {
+                reader.next().await.unwrap().unwrap();
+                tokio::time::sleep(delay).await;
+                let response = ClientPacketPayload::ResponseDmxOutput(multiverse);
+                writer.send(Packet::new(response)).await.unwrap();
+            }
+        });
+
+        let client =
+            Client::builder().timeout(Duration::from_millis(200)).connect(addr).await.unwrap();
+        assert!(matches!(client.request_dmx_output().await, Err(Error::Timeout)));
+        assert_eq!(client.request_dmx_output().await.unwrap(), Multiverse::new());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn unexpected_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve_with(listener, |payload| match payload {
+            ServerPacketPayload::RequestDmxOutput => Some(ClientPacketPayload::ResponseSetRawDmx),
+            _ => None,
+        });
+        let client = Client::connect(addr).await.unwrap();
+
+        let err = client.request_dmx_output().await.unwrap_err();
+        assert!(matches!(err, Error::UnexpectedResponse { got: "ResponseSetRawDmx" }), "{err}");
+
+        server.abort();
     }
 
-    async fn send_packet(&mut self, payload: ServerPacketPayload) -> io::Result<()> {
-        self.packet_writer
-            .send(Packet::new(payload))
+    #[tokio::test]
+    async fn permission_denied() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve_with(listener, |payload| match payload {
+            ServerPacketPayload::RequestSetRawDmx { .. } => {
+                Some(ClientPacketPayload::PermissionDenied { reason: "read-only".to_string() })
+            }
+            _ => None,
+        });
+        let client = Client::connect(addr).await.unwrap();
+
+        let err = client.request_set_raw_dmx(Vec::new()).await.unwrap_err();
+        assert!(
+            matches!(&err, Error::PermissionDenied { reason } if reason == "read-only"),
+            "{err}"
+        );
+        assert!(err.is_rejection() && !err.is_disconnect());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn invalid_request_is_not_sent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = serve_with(listener, |_| None);
+        let client = Client::connect(addr).await.unwrap();
+
+        let values = AttributeValues::new();
+        let duration = Duration::from_secs(u64::MAX);
+        let err = client
+            .request_set_attribute_values_faded(values, duration, FadeCurve::Linear)
             .await
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{err}");
+        assert!(!err.is_rejection() && !err.is_disconnect());
+        assert_eq!(std::io::Error::from(err).kind(), std::io::ErrorKind::InvalidInput);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn incompatible_server_fails_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut reader, mut writer) = accept_without_hello(listener).await;
+            reader.next().await.unwrap().unwrap();
+            let response = ClientPacketPayload::IncompatibleVersion { supported: 100..=101 };
+            writer.send(Packet::new(response)).await.unwrap();
+        });
+
+        let err = Client::connect(addr).await.err().unwrap();
+        assert!(
+            matches!(&err, Error::IncompatibleVersion { supported } if *supported == (100..=101))
+        );
+        assert!(err.is_rejection());
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn discovers_servers() {
+        let responder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = responder.local_addr().unwrap();
+        let announcement = ServerAnnouncement {
+            name: "Stage left".to_string(),
+            version: "1.2.3".to_string(),
+            tcp_port: 7334,
+            fixture_count: 12,
+            ip: None,
+        };
+        let server = tokio::spawn({
+            let announcement = announcement.clone();
+            async move {
+                let mut buf = [0; 64];
+                let (len, peer) = responder.recv_from(&mut buf).await.unwrap();
+                assert_eq!(&buf[..len], DISCOVERY_PROBE);
+                responder.send_to(b"garbage", peer).await.unwrap();
+                let bytes = rmp_serde::to_vec(&announcement).unwrap();
+                responder.send_to(&bytes, peer).await.unwrap();
+            }
+        });
+
+        let found = Client::discover_at(addr, Duration::from_millis(200)).await.unwrap();
+        assert_eq!(found, vec![ServerAnnouncement { ip: Some(addr.ip()), ..announcement }]);
+        assert_eq!(found[0].address(), Some(SocketAddr::new(addr.ip(), 7334)));
+
+        server.await.unwrap();
     }
 }
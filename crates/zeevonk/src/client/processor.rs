@@ -1,66 +1,269 @@
-use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::task;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
 
 use crate::attr::Attribute;
-use crate::client::Client;
+use crate::client::{Client, Error};
 use crate::packet::AttributeValues;
 use crate::show::ShowData;
-use crate::show::fixture::FixturePath;
+use crate::show::fixture::{FixtureChannelFunction, FixturePath};
+use crate::show::patch::Patch;
 use crate::value::ClampedValue;
 
+/// The rate at which processors registered with [Client::register_processor] run.
+const DEFAULT_RATE_HZ: f32 = 1000.0 / 33.0;
+
+/// Maximum number of missed ticks that are caught up on after falling behind.
+/// If more ticks are missed, they are skipped instead.
+const MAX_CATCH_UP_FRAMES: u32 = 5;
+
 impl Client {
     pub async fn register_processor<F: Fn(ProcessorContext) + Send + Sync + 'static>(
         &self,
         processor: F,
     ) {
-        let inner = Arc::clone(&self.inner);
-        let processor = Arc::new(processor);
-        task::spawn(async move {
-            let show_data = match inner.lock().await.request_show_data().await {
-                Ok(p) => p,
-                Err(err) => {
-                    log::error!("could not get show data for processor: {err}");
-                    return;
-                }
+        let processor = |_: TickContext, cx: ProcessorContext<'_, '_>| processor(cx);
+        if let Err(err) = self.run_at_rate(processor, DEFAULT_RATE_HZ).await {
+            log::error!("processor stopped: {err}");
+        }
+    }
+
+    /// Runs the processor at a fixed rate, sending the attribute values it
+    /// produces to the server once per tick.
+    ///
+    /// If the processor falls behind, up to a few missed ticks are run
+    /// immediately to catch up. When it falls behind further, missed ticks
+    /// are skipped and the [TickContext::delta] of the next tick covers the
+    /// skipped time.
+    ///
+    /// The show data is requested again after the client reconnects to the
+    /// server or when the server notifies that its show data changed, and the
+    /// fixtures matching [Processor::required_attributes] are matched again.
+    /// Only returns when requesting the show data or sending the
+    /// attribute values fails.
+    pub async fn run_at_rate(
+        &self,
+        mut processor: impl Processor,
+        rate_hz: f32,
+    ) -> Result<(), Error> {
+        if !rate_hz.is_finite() || rate_hz <= 0.0 {
+            return Err(Error::InvalidInput {
+                message: format!("invalid processor rate: {rate_hz} Hz"),
+            });
+        }
+
+        let requirements = processor.required_attributes();
+        let mut reconnects = self.subscribe_reconnects();
+        let mut show_data_changes = self.subscribe_show_data_changes();
+        let mut show_data = self.request_show_data().await?;
+        let mut matching_fixtures = match_fixtures(show_data.patch(), &requirements);
+
+        let mut ticker = Ticker::new(rate_hz);
+        loop {
+            let tick = ticker.tick().await;
+
+            // The patch might have changed if the server restarted or reloaded.
+            if !reconnects.is_empty() || !show_data_changes.is_empty() {
+                reconnects = reconnects.resubscribe();
+                show_data_changes = show_data_changes.resubscribe();
+                show_data = self.request_show_data().await?;
+                matching_fixtures = match_fixtures(show_data.patch(), &requirements);
+            }
+
+            let mut values = AttributeValues::new();
+            let cx = ProcessorContext {
+                frame: tick.frame as usize,
+                show_data: &show_data,
+                matching_fixtures: &matching_fixtures,
+                values: &mut values,
             };
+            processor.process(tick, cx);
+
+            // Await the result to ensure the request is sent and handled.
+            self.request_set_attribute_values(values).await?;
+        }
+    }
+}
+
+/// A processor that produces attribute values at a fixed rate.
+///
+/// Implemented for all closures taking a [TickContext] and a [ProcessorContext].
+pub trait Processor {
+    /// The attributes a fixture needs to be passed to the processor in
+    /// [ProcessorContext::matching_fixtures].
+    ///
+    /// Requires nothing by default, in which case no fixtures match.
+    fn required_attributes(&self) -> Vec<AttributeRequirement> {
+        Vec::new()
+    }
+
+    /// Called once per tick to set the attribute values for that tick.
+    fn process(&mut self, tick: TickContext, cx: ProcessorContext<'_, '_>);
+}
+
+impl<F: FnMut(TickContext, ProcessorContext<'_, '_>)> Processor for F {
+    fn process(&mut self, tick: TickContext, cx: ProcessorContext<'_, '_>) {
+        self(tick, cx)
+    }
+}
+
+/// An attribute a fixture should have to match a [Processor].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttributeRequirement {
+    /// The required attribute.
+    pub attribute: Attribute,
+    /// Whether fixtures without this attribute still match. Optional
+    /// attributes are included in the [MatchingFixture] when present.
+    pub optional: bool,
+}
+
+impl AttributeRequirement {
+    /// A requirement for an attribute every matching fixture must have.
+    pub fn required(attribute: Attribute) -> Self {
+        Self { attribute, optional: false }
+    }
+
+    /// A requirement for an attribute that is used when a fixture has it.
+    pub fn optional(attribute: Attribute) -> Self {
+        Self { attribute, optional: true }
+    }
+}
+
+/// A fixture satisfying the [AttributeRequirement]s of a [Processor], with the
+/// channel functions of the required attributes it has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchingFixture {
+    path: FixturePath,
+    channel_functions: Vec<(Attribute, FixtureChannelFunction)>,
+}
+
+impl MatchingFixture {
+    /// The path of the fixture.
+    pub fn path(&self) -> FixturePath {
+        self.path
+    }
 
-            // Use a fixed interval starting one period from now to get accurate 33ms ticks.
-            let period = Duration::from_millis(33);
-            let start = tokio::time::Instant::now() + period;
-            let mut interval = tokio::time::interval_at(start, period);
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+    /// Returns the channel function for the attribute, if it was required and
+    /// the fixture has it.
+    pub fn channel_function(&self, attribute: &Attribute) -> Option<&FixtureChannelFunction> {
+        self.channel_functions.iter().find(|(a, _)| a == attribute).map(|(_, f)| f)
+    }
 
-            let mut frame = 0;
-            loop {
-                // Wait until the next scheduled tick. Using interval_at fixes the schedule
-                // to the chosen start instant and period, minimizing drift.
-                interval.tick().await;
+    /// Returns whether the fixture has the attribute, if it was required.
+    pub fn has_attribute(&self, attribute: &Attribute) -> bool {
+        self.channel_function(attribute).is_some()
+    }
 
-                let mut values = AttributeValues::new();
-                let cx = ProcessorContext { frame, show_data: &show_data, values: &mut values };
-                (processor.as_ref())(cx);
+    /// The required attributes the fixture has with their channel functions,
+    /// in the order they were required.
+    pub fn channel_functions(&self) -> impl Iterator<Item = (&Attribute, &FixtureChannelFunction)> {
+        self.channel_functions.iter().map(|(a, f)| (a, f))
+    }
+}
 
-                // Await the result to ensure the request is sent and handled.
-                let send_result = inner.lock().await.request_set_attribute_values(values).await;
+/// Returns the fixtures in the patch that have all non-optional attributes of
+/// the requirements, and at least one of the attributes if all are optional.
+fn match_fixtures(patch: &Patch, requirements: &[AttributeRequirement]) -> Vec<MatchingFixture> {
+    if requirements.is_empty() {
+        return Vec::new();
+    }
 
-                if let Err(err) = send_result {
-                    log::error!("failed to send attribute values: {err}");
-                    break;
+    patch
+        .fixtures()
+        .values()
+        .filter_map(|fixture| {
+            let mut channel_functions = Vec::with_capacity(requirements.len());
+            for requirement in requirements {
+                match fixture.channel_function(&requirement.attribute) {
+                    Some(function) => {
+                        channel_functions.push((requirement.attribute, function.clone()))
+                    }
+                    None if requirement.optional => {}
+                    None => return None,
                 }
+            }
 
-                frame += 1;
+            if channel_functions.is_empty() {
+                return None;
             }
+            Some(MatchingFixture { path: fixture.path(), channel_functions })
         })
-        .await
-        .unwrap();
+        .collect()
+}
+
+/// Timing information about a single processor tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickContext {
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+}
+
+impl TickContext {
+    /// Time since the previous tick.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Time since the processor started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The number of this tick, starting at 0.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
+
+/// Produces fixed-rate ticks, catching up on at most [MAX_CATCH_UP_FRAMES]
+/// missed ticks.
+struct Ticker {
+    interval: Interval,
+    period: Duration,
+    start: Instant,
+    last: Instant,
+    frame: u64,
+}
+
+impl Ticker {
+    fn new(rate_hz: f32) -> Self {
+        let period = Duration::from_secs_f32(1.0 / rate_hz);
+
+        // Use a fixed interval starting one period from now, so the schedule is
+        // fixed to the start instant and period, minimizing drift.
+        let start = Instant::now();
+        let mut interval = tokio::time::interval_at(start + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+        Self { interval, period, start, last: start, frame: 0 }
+    }
+
+    async fn tick(&mut self) -> TickContext {
+        let scheduled = self.interval.tick().await;
+
+        // Skip the backlog if we are too far behind, so we don't burst through
+        // a large amount of ticks at once.
+        if scheduled.elapsed() > self.period * MAX_CATCH_UP_FRAMES {
+            self.interval.reset();
+        }
+
+        let tick = TickContext {
+            delta: scheduled - self.last,
+            elapsed: scheduled - self.start,
+            frame: self.frame,
+        };
+
+        self.last = scheduled;
+        self.frame += 1;
+        tick
     }
 }
 
 pub struct ProcessorContext<'sd, 'val> {
     frame: usize,
     show_data: &'sd ShowData,
+    matching_fixtures: &'sd [MatchingFixture],
     values: &'val mut AttributeValues,
 }
 
@@ -73,6 +276,12 @@ impl ProcessorContext<'_, '_> {
         self.show_data
     }
 
+    /// The fixtures satisfying the [Processor::required_attributes], in patch
+    /// order.
+    pub fn matching_fixtures(&self) -> &[MatchingFixture] {
+        self.matching_fixtures
+    }
+
     pub fn values_mut(&mut self) -> &mut AttributeValues {
         self.values
     }
@@ -153,3 +362,182 @@ impl<const N: usize> From<[FixturePath; N]> for FixtureCollection {
         Self(fixture_paths.to_vec())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::dmx::{Address, Multiverse};
+    use crate::fpath;
+    use crate::show::fixture::{Fixture, FixtureChannelFunctionKind};
+    use crate::value::DimmerCurve;
+
+    fn fixture(path: FixturePath, attributes: &[Attribute]) -> Fixture {
+        let channel_functions = attributes
+            .iter()
+            .enumerate()
+            .map(|(ix, attribute)| {
+                let address =
+                    Address::from_absolute(path.root().as_u32() * 10 + ix as u32).unwrap();
+                let function = FixtureChannelFunction {
+                    kind: FixtureChannelFunctionKind::Physical { addresses: vec![address] },
+                    min: ClampedValue::new(0.0),
+                    max: ClampedValue::new(0.5),
+                    default: ClampedValue::new(0.25),
+                };
+                (*attribute, function)
+            })
+            .collect::<HashMap<_, _>>();
+
+        Fixture {
+            path,
+            root_base_address: Address::from_absolute(path.root().as_u32() * 10).unwrap(),
+            name: path.to_string(),
+            gdtf_fixture_type_id: uuid::Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions,
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        }
+    }
+
+    fn patch() -> Patch {
+        let fixtures = [
+            fixture(fpath![1], &[Attribute::Dimmer]),
+            fixture(fpath![2], &[Attribute::Dimmer, Attribute::Pan, Attribute::Tilt]),
+            fixture(fpath![3], &[Attribute::Pan]),
+            fixture(fpath![4], &[]),
+        ];
+        Patch {
+            fixtures: fixtures.into_iter().map(|f| (f.path(), f)).collect(),
+            default_multiverse: Multiverse::new(),
+            groups: BTreeMap::new(),
+        }
+    }
+
+    fn matching_paths(requirements: &[AttributeRequirement]) -> Vec<FixturePath> {
+        match_fixtures(&patch(), requirements).iter().map(MatchingFixture::path).collect()
+    }
+
+    #[test]
+    fn match_fixtures_by_requirements() {
+        use AttributeRequirement as R;
+
+        assert_eq!(matching_paths(&[]), Vec::new());
+        assert_eq!(matching_paths(&[R::required(Attribute::Dimmer)]), vec![fpath![1], fpath![2]]);
+        assert_eq!(
+            matching_paths(&[R::required(Attribute::Pan), R::required(Attribute::Tilt)]),
+            vec![fpath![2]]
+        );
+        assert_eq!(
+            matching_paths(&[R::required(Attribute::Dimmer), R::optional(Attribute::Pan)]),
+            vec![fpath![1], fpath![2]]
+        );
+        assert_eq!(
+            matching_paths(&[R::optional(Attribute::Dimmer), R::optional(Attribute::Pan)]),
+            vec![fpath![1], fpath![2], fpath![3]]
+        );
+        assert_eq!(matching_paths(&[R::required(Attribute::Zoom)]), Vec::new());
+    }
+
+    #[test]
+    fn matching_fixture_has_channel_functions() {
+        let requirements = [
+            AttributeRequirement::required(Attribute::Dimmer),
+            AttributeRequirement::optional(Attribute::Pan),
+            AttributeRequirement::optional(Attribute::Zoom),
+        ];
+        let matches = match_fixtures(&patch(), &requirements);
+
+        assert!(!matches[0].has_attribute(&Attribute::Pan));
+        let function = matches[1].channel_function(&Attribute::Pan).unwrap();
+        assert_eq!(function.min(), ClampedValue::new(0.0));
+        assert_eq!(function.max(), ClampedValue::new(0.5));
+        assert_eq!(function.default(), ClampedValue::new(0.25));
+        // Attributes that were not required are not included.
+        assert!(!matches[1].has_attribute(&Attribute::Tilt));
+        let attributes = matches[1].channel_functions().map(|(a, _)| *a).collect::<Vec<_>>();
+        assert_eq!(attributes, vec![Attribute::Dimmer, Attribute::Pan]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn run_at_rate_passes_matching_fixtures() {
+        use crate::dmx::{Channel, UniverseId};
+        use crate::testing::TestHarness;
+
+        struct Chase;
+
+        impl Processor for Chase {
+            fn required_attributes(&self) -> Vec<AttributeRequirement> {
+                vec![AttributeRequirement::required(Attribute::Dimmer)]
+            }
+
+            fn process(&mut self, _: TickContext, mut cx: ProcessorContext<'_, '_>) {
+                let paths =
+                    cx.matching_fixtures().iter().map(MatchingFixture::path).collect::<Vec<_>>();
+                for (ix, path) in paths.into_iter().enumerate() {
+                    cx.set_attribute(path, Attribute::Dimmer, ix as f32 / 2.0, false);
+                }
+            }
+        }
+
+        let harness = TestHarness::new(3).unwrap();
+        let client = harness.connect().await.unwrap();
+        tokio::select! {
+            result = client.run_at_rate(Chase, 100.0) => panic!("processor stopped: {result:?}"),
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+        }
+
+        let output = harness.server().dmx_output().await;
+        let value = |channel| {
+            let address = Address::new(UniverseId::new(1).unwrap(), Channel::new(channel).unwrap());
+            output.get_value(&address).0
+        };
+        assert_eq!([value(1), value(2), value(3)], [0, 128, 255]);
+    }
+
+    #[tokio::test]
+    async fn ticker_runs_at_rate() {
+        let mut ticker = Ticker::new(100.0);
+        let started = std::time::Instant::now();
+
+        let mut ticks = Vec::new();
+        while started.elapsed() < Duration::from_millis(300) {
+            ticks.push(ticker.tick().await);
+        }
+
+        // Nominally 30 ticks, but be generous for slow test machines.
+        assert!((15..=45).contains(&ticks.len()), "{} ticks", ticks.len());
+        for (ix, tick) in ticks.iter().enumerate() {
+            assert_eq!(tick.frame(), ix as u64);
+        }
+        assert_eq!(ticks[0].delta(), Duration::from_millis(10));
+        let total = ticks.iter().map(TickContext::delta).sum::<Duration>();
+        assert_eq!(total, ticks.last().unwrap().elapsed());
+    }
+
+    #[tokio::test]
+    async fn ticker_caps_catch_up() {
+        let mut ticker = Ticker::new(100.0);
+        ticker.tick().await;
+
+        // Fall behind by about 20 ticks.
+        std::thread::sleep(Duration::from_millis(200));
+
+        // Count the ticks that fire immediately to catch up.
+        let mut immediate_ticks = 0;
+        loop {
+            let before = std::time::Instant::now();
+            let tick = ticker.tick().await;
+            if before.elapsed() >= Duration::from_millis(5) {
+                assert!(tick.delta() >= Duration::from_millis(10));
+                break;
+            }
+            immediate_ticks += 1;
+        }
+
+        assert!(immediate_ticks <= MAX_CATCH_UP_FRAMES, "{immediate_ticks} immediate ticks");
+    }
+}
@@ -9,9 +9,19 @@ pub enum Error {
     /// Error when a universe ID is invalid.
     #[error("universe has invalid id: '{0}'. Should be greater than 1")]
     InvalidUniverseId(u16),
+    /// Error when offsetting an address moves it to a universe outside of
+    /// [UniverseId::MIN]..=[UniverseId::MAX].
+    #[error("universe {0} is out of range, but should be in the range 1..=65535")]
+    UniverseOutOfRange(i64),
     /// Error when a universe with the specified ID cannot be found.
     #[error("universe with id '{0}' not found")]
     UniverseNotFound(UniverseId),
+    /// Error when an absolute address is past the last channel of the last universe.
+    #[error("absolute address {0} is out of range, but should be in the range 1..=33553920")]
+    AbsoluteAddressOutOfRange(u32),
+    /// Error when the number of values for a universe is not 512.
+    #[error("universe has invalid length: {0}, but should be 512")]
+    InvalidUniverseLength(usize),
 
     /// Parsing channel failed.
     #[error("failed to parse channel: '{0}'")]
@@ -20,6 +30,8 @@ pub enum Error {
     #[error("failed to parse universe id: '{0}'")]
     ParseUniverseIdFailed(String),
     /// Parsing address failed.
-    #[error("failed to parse address: '{0}'")]
+    #[error(
+        "failed to parse address: '{0}', expected 'universe.channel', 'universe/channel' or an absolute address"
+    )]
     ParseAddressFailed(String),
 }
@@ -2,10 +2,13 @@
 //! assist working safely with DMX addresses and values.
 
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::{fmt, ops, str};
 
 pub use error::Error;
 
+use crate::value::{ClampedValue, DimmerCurve};
+
 /// This module contains data types for error handling.
 mod error;
 
@@ -129,8 +132,17 @@ impl str::FromStr for Channel {
 /// ```
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[repr(transparent)]
 pub struct Value(pub u8);
 
+impl Value {
+    /// Maps the value through the dimmer curve, at 8-bit resolution.
+    /// [DimmerCurve::Linear] returns the value unchanged.
+    pub fn apply_curve(self, curve: DimmerCurve) -> Self {
+        ClampedValue::from(self).apply_curve(curve).into()
+    }
+}
+
 impl ops::Deref for Value {
     type Target = u8;
     fn deref(&self) -> &Self::Target {
@@ -162,6 +174,42 @@ impl From<Value> for u8 {
     }
 }
 
+/// Adds two values, saturating at [Value::MAX] instead of wrapping around, as
+/// a channel that is pushed past full should stay at full.
+///
+/// # Examples
+///
+/// ```
+/// # use zeevonk::dmx;
+/// assert_eq!(dmx::Value(100) + dmx::Value(55), dmx::Value(155));
+/// assert_eq!(dmx::Value(200) + dmx::Value(100), dmx::Value(255));
+/// ```
+impl ops::Add for Value {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Value(self.0.saturating_add(rhs.0))
+    }
+}
+
+/// Subtracts two values, saturating at [Value::MIN] instead of wrapping
+/// around.
+///
+/// # Examples
+///
+/// ```
+/// # use zeevonk::dmx;
+/// assert_eq!(dmx::Value(100) - dmx::Value(55), dmx::Value(45));
+/// assert_eq!(dmx::Value(50) - dmx::Value(100), dmx::Value(0));
+/// ```
+impl ops::Sub for Value {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Value(self.0.saturating_sub(rhs.0))
+    }
+}
+
 impl str::FromStr for Value {
     type Err = std::num::ParseIntError;
 
@@ -177,6 +225,81 @@ impl Value {
 
     /// The maximum valid DMX value.
     pub const MAX: Self = Value(255);
+
+    /// Creates a value from a percentage.
+    ///
+    /// The percentage is clamped to `0.0..=100.0` and scaled to `0..=255`,
+    /// rounding to the nearest value with halves rounded up. This means 50%
+    /// results in 128, like on most lighting consoles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert_eq!(dmx::Value::from_percent(0.0), dmx::Value(0));
+    /// assert_eq!(dmx::Value::from_percent(50.0), dmx::Value(128));
+    /// assert_eq!(dmx::Value::from_percent(100.0), dmx::Value(255));
+    /// assert_eq!(dmx::Value::from_percent(150.0), dmx::Value(255));
+    /// ```
+    pub fn from_percent(percent: f32) -> Self {
+        Value((percent.clamp(0.0, 100.0) / 100.0 * 255.0).round() as u8)
+    }
+
+    /// Returns the value as a percentage in `0.0..=100.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert_eq!(dmx::Value(255).to_percent(), 100.0);
+    /// assert_eq!(dmx::Value(0).to_percent(), 0.0);
+    /// ```
+    pub fn to_percent(self) -> f32 {
+        self.0 as f32 / 255.0 * 100.0
+    }
+
+    /// Returns the highest of the two values (highest takes precedence).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert_eq!(dmx::Value(10).htp(dmx::Value(200)), dmx::Value(200));
+    /// assert_eq!(dmx::Value(200).htp(dmx::Value(10)), dmx::Value(200));
+    /// ```
+    pub fn htp(self, other: Value) -> Value {
+        self.max(other)
+    }
+
+    /// Returns `latest`, the value that was set last (latest takes
+    /// precedence). This exists to make the merge mode explicit next to
+    /// [Value::htp], e.g. when choosing a merge function per channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert_eq!(dmx::Value(200).ltp(dmx::Value(10)), dmx::Value(10));
+    /// ```
+    pub fn ltp(self, latest: Value) -> Value {
+        latest
+    }
+
+    /// Multiplies the value by `factor`, rounding to the nearest value and
+    /// saturating at [Value::MIN] and [Value::MAX].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert_eq!(dmx::Value(200).scale(0.5), dmx::Value(100));
+    /// assert_eq!(dmx::Value(255).scale(0.5), dmx::Value(128));
+    /// assert_eq!(dmx::Value(200).scale(2.0), dmx::Value(255));
+    /// assert_eq!(dmx::Value(200).scale(-1.0), dmx::Value(0));
+    /// ```
+    pub fn scale(self, factor: f32) -> Value {
+        Value((self.0 as f32 * factor).round().clamp(0.0, 255.0) as u8)
+    }
 }
 
 /// A unique DMX address composed of a [UniverseId] and a [Channel].
@@ -232,6 +355,9 @@ impl Address {
         }
 
         let universe_idx = (absolute_address - 1) / 512;
+        if universe_idx >= u16::MAX as u32 {
+            return Err(Error::AbsoluteAddressOutOfRange(absolute_address));
+        }
         let channel_num = (absolute_address - 1) % 512 + 1;
 
         Ok(Self {
@@ -283,44 +409,80 @@ impl Address {
     ///
     /// # Errors
     ///
-    /// Returns an error if applying the offset would produce a universe ID
-    /// outside the valid range (less than 1 or greater than u16::MAX), or if
-    /// for some reason the computed channel is invalid.
+    /// Returns [Error::UniverseOutOfRange] if applying the offset would
+    /// produce a universe ID outside the valid range (less than 1 or greater
+    /// than u16::MAX).
     pub fn with_channel_offset(self, offset: i32) -> Result<Self, Error> {
-        let current_abs = (self.universe.0 as i64 - 1) * 512 + (self.channel.0 as i64 - 1);
-        let total = current_abs + offset as i64;
+        let channel_offset = self.channel.0 as i64 - 1 + offset as i64;
+        let universe_offset = channel_offset.div_euclid(512); // may be negative
+        let channel = Channel::new(channel_offset.rem_euclid(512) as u16 + 1)?; // 1..=512
 
-        let universe_idx = total.div_euclid(512); // may be negative
-        let new_channel_zero = total.rem_euclid(512) as u16; // 0..=511
-
-        let target_universe_id = 1 + universe_idx;
-        if target_universe_id < 1 || target_universe_id > u16::MAX as i64 {
-            return Err(Error::InvalidUniverseId(target_universe_id as u16));
+        let universe = match u16::try_from(universe_offset.unsigned_abs()) {
+            Ok(universe_offset_abs) if universe_offset >= 0 => {
+                self.universe.checked_add(universe_offset_abs)
+            }
+            Ok(universe_offset_abs) => self.universe.checked_sub(universe_offset_abs),
+            Err(_) => None,
         }
-
-        let new_channel = new_channel_zero + 1; // 1..=512
-        let target_universe_id = target_universe_id as u16;
-
-        let channel = Channel::new(new_channel as u16)?;
-        let universe = UniverseId(target_universe_id);
+        .ok_or(Error::UniverseOutOfRange(self.universe.0 as i64 + universe_offset))?;
 
         Ok(Self { universe, channel })
     }
+
+    /// Returns an iterator over `count` consecutive addresses, starting at
+    /// `start` and rolling over into subsequent universes.
+    ///
+    /// The iterator stops early if the next address would be past the last
+    /// channel of [UniverseId::MAX].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use zeevonk::dmx;
+    /// let start: dmx::Address = "1.511".parse().unwrap();
+    /// let addresses = dmx::Address::range(start, 3).map(|a| a.to_string()).collect::<Vec<_>>();
+    /// assert_eq!(addresses, ["1.511", "1.512", "2.1"]);
+    /// ```
+    pub fn range(start: Address, count: u32) -> impl Iterator<Item = Address> {
+        (0..count).map_while(move |offset| start.with_channel_offset(offset as i32).ok())
+    }
 }
 
+/// Parses an [Address] from one of the following formats:
+///
+/// - `universe.channel`, the canonical form also used by [Display](fmt::Display).
+/// - `universe/channel`, the notation common on lighting consoles.
+/// - A bare absolute address, see [Address::from_absolute].
+///
+/// Surrounding whitespace is ignored.
+///
+/// # Examples
+///
+/// ```rust
+/// # use zeevonk::dmx;
+/// let address: dmx::Address = "2.488".parse().unwrap();
+/// assert_eq!(address, "2/488".parse().unwrap());
+/// assert_eq!(address, "1000".parse().unwrap());
+/// assert_eq!(address.to_string(), "2.488");
+/// ```
 impl str::FromStr for Address {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() != 2 {
-            return Err(Error::ParseAddressFailed(s.to_string()));
+        let invalid = || Error::ParseAddressFailed(s.to_string());
+        let trimmed = s.trim();
+
+        if let Some((universe, channel)) = trimmed.split_once(['.', '/']) {
+            let universe = universe.parse::<u16>().map_err(|_| invalid())?;
+            let channel = channel.parse::<u16>().map_err(|_| invalid())?;
+            return Ok(Self {
+                universe: UniverseId::new(universe)?,
+                channel: Channel::new(channel)?,
+            });
         }
 
-        let universe = parts[0].parse::<UniverseId>()?;
-        let channel = parts[1].parse::<Channel>()?;
-
-        Ok(Self { universe, channel })
+        let absolute_address = trimmed.parse::<u32>().map_err(|_| invalid())?;
+        Self::from_absolute(absolute_address)
     }
 }
 
@@ -330,6 +492,10 @@ impl fmt::Display for Address {
     }
 }
 
+/// Universe numbers that can carry data over sACN (E1.31 6.2.7). Higher
+/// universe numbers are reserved, e.g. for universe discovery.
+pub const SACN_UNIVERSES: RangeInclusive<u16> = 1..=63999;
+
 /// A DMX universe ID.
 ///
 /// Must be greater than 0.
@@ -398,6 +564,40 @@ impl UniverseId {
 
         Ok(Self(id))
     }
+
+    /// Returns `true` if the universe can be sent over sACN as the universe
+    /// with the same number. Universes 64000 to 65535 are reserved by sACN,
+    /// e.g. for universe discovery, but can still be used internally or
+    /// mapped to another sACN universe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// assert!(dmx::UniverseId::new(63999).unwrap().is_sacn_routable());
+    /// assert!(!dmx::UniverseId::new(64214).unwrap().is_sacn_routable());
+    /// ```
+    pub fn is_sacn_routable(&self) -> bool {
+        SACN_UNIVERSES.contains(&self.0)
+    }
+
+    /// Returns the universe `offset` universes after this one, or `None` if
+    /// that is past [UniverseId::MAX].
+    pub const fn checked_add(self, offset: u16) -> Option<Self> {
+        match self.0.checked_add(offset) {
+            Some(id) => Some(Self(id)),
+            None => None,
+        }
+    }
+
+    /// Returns the universe `offset` universes before this one, or `None` if
+    /// that is before [UniverseId::MIN].
+    pub const fn checked_sub(self, offset: u16) -> Option<Self> {
+        match self.0.checked_sub(offset) {
+            Some(0) | None => None,
+            Some(id) => Some(Self(id)),
+        }
+    }
 }
 
 impl Default for UniverseId {
@@ -429,11 +629,111 @@ impl str::FromStr for UniverseId {
     }
 }
 
+/// Serializes an [Address] in its string form, like `"1.100"`, for use with
+/// `#[serde(with = "dmx::address_as_string")]`.
+///
+/// Deserializing accepts every form [Address] parses from, as well as the
+/// default `{ "universe": 1, "channel": 100 }` struct form, so fields can
+/// switch to the string form without breaking existing files.
+///
+/// ```
+/// # use zeevonk::dmx;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Entry {
+///     #[serde(with = "dmx::address_as_string")]
+///     address: dmx::Address,
+/// }
+///
+/// let entry: Entry = serde_json::from_str(r#"{ "address": "2/488" }"#).unwrap();
+/// assert_eq!(serde_json::to_string(&entry).unwrap(), r#"{"address":"2.488"}"#);
+/// ```
+pub mod address_as_string {
+    use std::fmt;
+
+    use serde::de::{self, MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    use super::Address;
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(address)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        struct AddressVisitor;
+
+        impl<'de> Visitor<'de> for AddressVisitor {
+            type Value = Address;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an address like \"1.100\" or a universe and channel")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Address, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Address, A::Error> {
+                Address::deserialize(de::value::MapAccessDeserializer::new(map))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Address, A::Error> {
+                Address::deserialize(de::value::SeqAccessDeserializer::new(seq))
+            }
+        }
+
+        deserializer.deserialize_any(AddressVisitor)
+    }
+}
+
+/// Serializes a [UniverseId] as a string, like `"1"`, for use with
+/// `#[serde(with = "dmx::universe_id_as_string")]`, e.g. for map keys.
+///
+/// Deserializing accepts both the string and the default number form.
+pub mod universe_id_as_string {
+    use std::fmt;
+
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    use super::UniverseId;
+
+    pub fn serialize<S: Serializer>(id: &UniverseId, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UniverseId, D::Error> {
+        struct UniverseIdVisitor;
+
+        impl<'de> Visitor<'de> for UniverseIdVisitor {
+            type Value = UniverseId;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a universe id")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<UniverseId, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, id: u64) -> Result<UniverseId, E> {
+                let id = u16::try_from(id)
+                    .map_err(|_| E::custom(format!("invalid universe id: {id}")))?;
+                UniverseId::new(id).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(UniverseIdVisitor)
+    }
+}
+
 /// A DMX universe that contains 512 [Value]s.
 ///
 /// The universe has:
 /// - A unique ID ([UniverseId])
 /// - An array of 512 DMX values
+/// - A start code, which is [Universe::NULL_START_CODE] for regular dimmer
+///   data, but can be set to send alternate start code frames
 ///
 /// # Examples
 ///
@@ -446,6 +746,10 @@ impl str::FromStr for UniverseId {
 pub struct Universe {
     #[serde(with = "serde_big_array::BigArray")]
     values: [Value; 512],
+    /// Defaults to [Universe::NULL_START_CODE] for universes serialized
+    /// before start codes were supported.
+    #[serde(default)]
+    start_code: u8,
 }
 
 impl Default for Universe {
@@ -455,6 +759,9 @@ impl Default for Universe {
 }
 
 impl Universe {
+    /// The start code of regular DMX512 dimmer data.
+    pub const NULL_START_CODE: u8 = 0x00;
+
     /// Creates a new universe.
     ///
     /// All values are initialized to 0.
@@ -466,7 +773,62 @@ impl Universe {
     /// let universe = dmx::Universe::new();
     /// ```
     pub fn new() -> Self {
-        Self { values: [Value::default(); 512] }
+        Self::from_values([Value::default(); 512])
+    }
+
+    /// Creates a new universe with the given values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let universe = dmx::Universe::from_values([dmx::Value(255); 512]);
+    /// assert_eq!(universe.get_value(&dmx::Channel::new(512).unwrap()), dmx::Value(255));
+    /// ```
+    pub fn from_values(values: [Value; 512]) -> Self {
+        Self { values, start_code: Self::NULL_START_CODE }
+    }
+
+    /// Returns the start code of the universe.
+    pub fn start_code(&self) -> u8 {
+        self.start_code
+    }
+
+    /// Sets the start code of the universe, which is sent in front of the
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut universe = dmx::Universe::new();
+    /// assert_eq!(universe.start_code(), dmx::Universe::NULL_START_CODE);
+    ///
+    /// universe.set_start_code(0x17);
+    /// assert_eq!(universe.start_code(), 0x17);
+    /// ```
+    pub fn set_start_code(&mut self, start_code: u8) {
+        self.start_code = start_code;
+    }
+
+    /// Creates a new universe from raw DMX slot values.
+    ///
+    /// Returns [Error::InvalidUniverseLength] if `slice` does not contain
+    /// exactly 512 values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let universe = dmx::Universe::from_slice(&[128; 512]).unwrap();
+    /// assert_eq!(universe.get_value(&dmx::Channel::new(1).unwrap()), dmx::Value(128));
+    ///
+    /// assert!(dmx::Universe::from_slice(&[128; 511]).is_err());
+    /// ```
+    pub fn from_slice(slice: &[u8]) -> Result<Self, Error> {
+        let bytes: &[u8; 512] =
+            slice.try_into().map_err(|_| Error::InvalidUniverseLength(slice.len()))?;
+        Ok(Self::from_values(bytes.map(Value)))
     }
 
     /// Get the value for the given channel.
@@ -499,6 +861,39 @@ impl Universe {
         self.values[channel.0 as usize - 1] = value;
     }
 
+    /// Returns `true` if every value in the universe is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut universe = dmx::Universe::new();
+    /// assert!(universe.is_empty());
+    ///
+    /// universe.set_value(&dmx::Channel::new(3).unwrap(), dmx::Value(1));
+    /// assert!(!universe.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.max_used_channel().is_none()
+    }
+
+    /// Returns the highest channel with a non-zero value, or `None` if every
+    /// value is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut universe = dmx::Universe::new();
+    /// universe.set_value(&dmx::Channel::new(3).unwrap(), dmx::Value(1));
+    /// universe.set_value(&dmx::Channel::new(10).unwrap(), dmx::Value(1));
+    /// assert_eq!(universe.max_used_channel(), Some(dmx::Channel::new(10).unwrap()));
+    /// ```
+    pub fn max_used_channel(&self) -> Option<Channel> {
+        let index = self.values.iter().rposition(|value| *value != Value::MIN)?;
+        Some(Channel(index as u16 + 1))
+    }
+
     /// Returns an immutable reference to the values.
     ///
     /// **Note**: The indices of this array are 0-based but the channel values
@@ -507,6 +902,65 @@ impl Universe {
         &self.values
     }
 
+    /// Returns the values as raw DMX slot values, without copying.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut universe = dmx::Universe::new();
+    /// universe.set_value(&dmx::Channel::new(2).unwrap(), dmx::Value(42));
+    /// assert_eq!(universe.as_bytes()[..3], [0, 42, 0]);
+    /// ```
+    pub fn as_bytes(&self) -> &[u8; 512] {
+        // SAFETY: `Value` is `repr(transparent)` over `u8`, so `[Value; 512]`
+        // has the same layout as `[u8; 512]`.
+        unsafe { &*(&self.values as *const [Value; 512] as *const [u8; 512]) }
+    }
+
+    /// Sets every value to the highest of its own value and the value in
+    /// `other` (highest takes precedence).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let channel = dmx::Channel::new(1).unwrap();
+    /// let mut universe = dmx::Universe::new();
+    /// universe.set_value(&channel, dmx::Value(100));
+    /// let mut other = dmx::Universe::new();
+    /// other.set_value(&channel, dmx::Value(200));
+    ///
+    /// universe.merge_htp(&other);
+    /// assert_eq!(universe.get_value(&channel), dmx::Value(200));
+    /// ```
+    pub fn merge_htp(&mut self, other: &Universe) {
+        for (value, other) in self.values.iter_mut().zip(other.values()) {
+            *value = value.htp(*other);
+        }
+    }
+
+    /// Multiplies every value by `factor`, like a master fader. See
+    /// [Value::scale].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let channel = dmx::Channel::new(1).unwrap();
+    /// let mut universe = dmx::Universe::from_values([dmx::Value(255); 512]);
+    ///
+    /// universe.scale(0.5);
+    /// assert_eq!(universe.get_value(&channel), dmx::Value(128));
+    /// universe.scale(0.0);
+    /// assert_eq!(universe.get_value(&channel), dmx::Value(0));
+    /// ```
+    pub fn scale(&mut self, factor: f32) {
+        for value in &mut self.values {
+            *value = value.scale(factor);
+        }
+    }
+
     /// Returns a mutable reference to the values.
     /// **Note**: The indices of this array are 0-based but the channel values
     /// are 1-based. For example, channel 1 maps to index 0 in the array.
@@ -534,6 +988,14 @@ impl From<Universe> for Vec<u8> {
     }
 }
 
+impl TryFrom<&[u8]> for Universe {
+    type Error = Error;
+
+    fn try_from(slice: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_slice(slice)
+    }
+}
+
 /// A [Multiverse] contains multiple [Universe]s.
 ///
 /// # Examples
@@ -635,6 +1097,37 @@ impl Multiverse {
         }
     }
 
+    /// Makes this [Multiverse] equal to `source`.
+    ///
+    /// Unlike assigning a clone, universes that exist in both are copied in
+    /// place, so copying into the same [Multiverse] every frame does not
+    /// allocate once it has the universes of `source`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let (one, two) = (dmx::UniverseId::new(1).unwrap(), dmx::UniverseId::new(2).unwrap());
+    /// let mut source = dmx::Multiverse::new();
+    /// source.create_universe(one, dmx::Universe::from_slice(&[255; 512]).unwrap());
+    /// let mut target = dmx::Multiverse::new();
+    /// target.create_universe(two, dmx::Universe::new());
+    ///
+    /// target.copy_from(&source);
+    /// assert_eq!(target, source);
+    /// ```
+    pub fn copy_from(&mut self, source: &Multiverse) {
+        self.universes.retain(|id, _| source.has_universe(id));
+        for (id, universe) in source.universes() {
+            match self.universes.get_mut(id) {
+                Some(target) => target.clone_from(universe),
+                None => {
+                    self.universes.insert(*id, universe.clone());
+                }
+            }
+        }
+    }
+
     /// Returns an immutable reference to the [Universe] with the given
     /// [UniverseId].
     ///
@@ -652,11 +1145,117 @@ impl Multiverse {
     }
 
     /// Returns an iterator over a reference to every [Universe] in the
-    /// [Multiverse].
+    /// [Multiverse], in no particular order. Use [Multiverse::sorted_universes]
+    /// to iterate in ascending [UniverseId] order.
     pub fn universes(&self) -> impl Iterator<Item = (&UniverseId, &Universe)> {
         self.universes.iter()
     }
 
+    /// Returns an iterator over a reference to every [Universe] in the
+    /// [Multiverse], in ascending [UniverseId] order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut multiverse = dmx::Multiverse::new();
+    /// let (one, two) = (dmx::UniverseId::new(1).unwrap(), dmx::UniverseId::new(2).unwrap());
+    /// multiverse.create_universe(two, dmx::Universe::new());
+    /// multiverse.create_universe(one, dmx::Universe::new());
+    ///
+    /// let ids = multiverse.sorted_universes().map(|(id, _)| *id).collect::<Vec<_>>();
+    /// assert_eq!(ids, [one, two]);
+    /// ```
+    pub fn sorted_universes(&self) -> impl Iterator<Item = (&UniverseId, &Universe)> {
+        let mut universes = self.universes.iter().collect::<Vec<_>>();
+        universes.sort_unstable_by_key(|(id, _)| **id);
+        universes.into_iter()
+    }
+
+    /// Returns the ids of all universes in the [Multiverse], in ascending
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut multiverse = dmx::Multiverse::new();
+    /// let (one, two) = (dmx::UniverseId::new(1).unwrap(), dmx::UniverseId::new(2).unwrap());
+    /// multiverse.create_universe(two, dmx::Universe::new());
+    /// multiverse.create_universe(one, dmx::Universe::new());
+    ///
+    /// assert_eq!(multiverse.universe_ids(), [one, two]);
+    /// ```
+    pub fn universe_ids(&self) -> Vec<UniverseId> {
+        let mut ids = self.universes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Copies the raw DMX slot values of the [Universe] with the given
+    /// [UniverseId] into `buf`, without allocating.
+    ///
+    /// Returns `false`, leaving `buf` untouched, if no universe exists with
+    /// that ID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut multiverse = dmx::Multiverse::new();
+    /// let address = dmx::Address::from_absolute(1).unwrap();
+    /// multiverse.set_value(&address, dmx::Value(255));
+    ///
+    /// let mut buf = [0; 512];
+    /// assert!(multiverse.copy_universe_into(&address.universe, &mut buf));
+    /// assert_eq!(buf[0], 255);
+    /// assert!(!multiverse.copy_universe_into(&dmx::UniverseId::new(2).unwrap(), &mut buf));
+    /// ```
+    pub fn copy_universe_into(&self, id: &UniverseId, buf: &mut [u8; 512]) -> bool {
+        match self.universe(id) {
+            Some(universe) => {
+                buf.copy_from_slice(universe.as_bytes());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Merges `other` into this [Multiverse], where for every channel the
+    /// highest value takes precedence (HTP).
+    ///
+    /// Universes that only exist in `other` are created with its start code.
+    /// Existing universes keep their own start code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let (a, b) = (dmx::Address::from_absolute(1).unwrap(), dmx::Address::from_absolute(2).unwrap());
+    /// let mut multiverse = dmx::Multiverse::new();
+    /// multiverse.set_value(&a, dmx::Value(100));
+    /// multiverse.set_value(&b, dmx::Value(100));
+    /// let mut other = dmx::Multiverse::new();
+    /// other.set_value(&a, dmx::Value(50));
+    /// other.set_value(&b, dmx::Value(200));
+    ///
+    /// multiverse.merge_htp(&other);
+    /// assert_eq!(multiverse.get_value(&a), dmx::Value(100));
+    /// assert_eq!(multiverse.get_value(&b), dmx::Value(200));
+    /// ```
+    pub fn merge_htp(&mut self, other: &Multiverse) {
+        for (id, other) in other.universes() {
+            self.universes
+                .entry(*id)
+                .or_insert_with(|| {
+                    let mut universe = Universe::new();
+                    universe.set_start_code(other.start_code());
+                    universe
+                })
+                .merge_htp(other);
+        }
+    }
+
     /// Sets a value at a given [Address].
     ///
     /// Creates a new universe if the target universe does not exist.
@@ -707,12 +1306,383 @@ impl Multiverse {
         };
         universe.get_value(&address.channel)
     }
+
+    /// Computes the changes needed to turn this [Multiverse] into `other`.
+    ///
+    /// Universes that only exist in `other` are reported as created, and all
+    /// of their non-zero values are included as changes. Universes that only
+    /// exist in `self` are reported as removed. Start codes that differ from
+    /// the old universe, or from [Universe::NULL_START_CODE] for created
+    /// universes, are included as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let a = dmx::Multiverse::new();
+    /// let mut b = dmx::Multiverse::new();
+    /// let address = dmx::Address::from_absolute(1).unwrap();
+    /// b.set_value(&address, dmx::Value(255));
+    ///
+    /// let diff = a.diff(&b);
+    /// assert_eq!(diff.created_universes(), &[address.universe]);
+    /// assert_eq!(diff.changes(), &[(address, dmx::Value(255))]);
+    /// ```
+    pub fn diff(&self, other: &Multiverse) -> MultiverseDiff {
+        let mut diff = MultiverseDiff::default();
+
+        let mut ids = other.universes.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        for id in ids {
+            let new_universe = &other.universes[&id];
+            let old_universe = match self.universe(&id) {
+                Some(universe) => universe,
+                None => {
+                    diff.created_universes.push(id);
+                    &Universe::new()
+                }
+            };
+
+            if old_universe.start_code() != new_universe.start_code() {
+                diff.start_codes.push((id, new_universe.start_code()));
+            }
+
+            for (ix, (old, new)) in
+                old_universe.values().iter().zip(new_universe.values()).enumerate()
+            {
+                if old != new {
+                    let channel = Channel(ix as u16 + 1);
+                    diff.changes.push((Address::new(id, channel), *new));
+                }
+            }
+        }
+
+        diff.removed_universes =
+            self.universes.keys().filter(|id| !other.has_universe(id)).copied().collect();
+        diff.removed_universes.sort();
+
+        diff
+    }
+
+    /// Applies a [MultiverseDiff] to this [Multiverse].
+    ///
+    /// Changes targeting a universe that does not exist will create it,
+    /// mirroring the behaviour of [Multiverse::set_value].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use zeevonk::dmx;
+    /// let mut a = dmx::Multiverse::new();
+    /// let mut b = dmx::Multiverse::new();
+    /// b.set_value(&dmx::Address::from_absolute(600).unwrap(), dmx::Value(42));
+    ///
+    /// a.apply_diff(&a.diff(&b));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn apply_diff(&mut self, diff: &MultiverseDiff) {
+        for id in &diff.removed_universes {
+            self.remove_universe(id);
+        }
+
+        for id in &diff.created_universes {
+            if !self.has_universe(id) {
+                self.create_universe(*id, Universe::new());
+            }
+        }
+
+        for (address, value) in &diff.changes {
+            self.set_value(address, *value);
+        }
+
+        for (id, start_code) in &diff.start_codes {
+            match self.universe_mut(id) {
+                Some(universe) => universe.set_start_code(*start_code),
+                None => {
+                    let mut universe = Universe::new();
+                    universe.set_start_code(*start_code);
+                    self.create_universe(*id, universe);
+                }
+            }
+        }
+    }
+}
+
+/// A compact description of the changes between two [Multiverse]s.
+///
+/// Created using [Multiverse::diff] and applied using
+/// [Multiverse::apply_diff].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct MultiverseDiff {
+    created_universes: Vec<UniverseId>,
+    removed_universes: Vec<UniverseId>,
+    changes: Vec<(Address, Value)>,
+    /// Defaults to no changes for diffs serialized before start codes were
+    /// supported.
+    #[serde(default)]
+    start_codes: Vec<(UniverseId, u8)>,
+}
+
+impl MultiverseDiff {
+    /// Returns the ids of the universes that have been created.
+    pub fn created_universes(&self) -> &[UniverseId] {
+        &self.created_universes
+    }
+
+    /// Returns the ids of the universes that have been removed.
+    pub fn removed_universes(&self) -> &[UniverseId] {
+        &self.removed_universes
+    }
+
+    /// Returns the changed values, ordered by [Address].
+    pub fn changes(&self) -> &[(Address, Value)] {
+        &self.changes
+    }
+
+    /// Returns the changed start codes, ordered by [UniverseId].
+    pub fn start_codes(&self) -> &[(UniverseId, u8)] {
+        &self.start_codes
+    }
+
+    /// Returns `true` if applying this diff would not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.created_universes.is_empty()
+            && self.removed_universes.is_empty()
+            && self.changes.is_empty()
+            && self.start_codes.is_empty()
+    }
+}
+
+/// Linearly interpolates every channel value between two [Multiverse]s.
+///
+/// `t` is clamped to `0.0..=1.0`, where `0.0` results in `from` and `1.0` in
+/// `to`. A universe that only exists on one side is faded from or to all
+/// zeros. Values are rounded half to even, so fading halfway from `0` to
+/// `255` always results in `128`. Faded universes have the start code of
+/// `to`, or of `from` if they only exist there.
+///
+/// # Examples
+///
+/// ```
+/// # use zeevonk::dmx;
+/// let address = dmx::Address::from_absolute(1).unwrap();
+/// let from = dmx::Multiverse::new();
+/// let mut to = dmx::Multiverse::new();
+/// to.set_value(&address, dmx::Value(255));
+///
+/// assert_eq!(dmx::fade(&from, &to, 0.5).get_value(&address), dmx::Value(128));
+/// assert_eq!(dmx::fade(&from, &to, 1.0), to);
+/// ```
+pub fn fade(from: &Multiverse, to: &Multiverse, t: f32) -> Multiverse {
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    let empty = Universe::new();
+
+    let mut faded = Multiverse::new();
+    for id in from.universes.keys().chain(to.universes.keys()) {
+        if faded.has_universe(id) {
+            continue;
+        }
+
+        let from_universe = from.universe(id).unwrap_or(&empty);
+        let to_universe = to.universe(id).unwrap_or(&empty);
+        let mut universe = Universe::new();
+        let start_code = to.universe(id).or(from.universe(id)).map(Universe::start_code);
+        universe.set_start_code(start_code.unwrap_or(Universe::NULL_START_CODE));
+        for ((value, from), to) in
+            universe.values_mut().iter_mut().zip(from_universe.values()).zip(to_universe.values())
+        {
+            let (from, to) = (from.0 as f32, to.0 as f32);
+            *value = Value((from + (to - from) * t).round_ties_even() as u8);
+        }
+        faded.create_universe(*id, universe);
+    }
+    faded
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn fade_between_multiverses() {
+        let a = Address::from_absolute(1).unwrap();
+        let b = Address::from_absolute(513).unwrap();
+
+        let mut from = Multiverse::new();
+        from.set_value(&a, Value(255));
+        let mut to = Multiverse::new();
+        to.set_value(&a, Value(55));
+        to.set_value(&b, Value(255));
+
+        let faded = fade(&from, &to, 0.5);
+        assert_eq!(faded.get_value(&a), Value(155));
+        assert_eq!(faded.get_value(&b), Value(128));
+        assert!(faded.has_universe(&a.universe) && faded.has_universe(&b.universe));
+
+        assert_eq!(fade(&from, &to, 0.0).get_value(&b), Value(0));
+        assert_eq!(fade(&from, &to, -1.0).get_value(&a), Value(255));
+        assert_eq!(fade(&from, &to, 2.0).get_value(&a), Value(55));
+
+        // Halfway values are rounded half to even.
+        to.set_value(&a, Value(0));
+        assert_eq!(fade(&from, &to, 0.5).get_value(&a), Value(128));
+        from.set_value(&a, Value(1));
+        assert_eq!(fade(&from, &to, 0.5).get_value(&a), Value(0));
+    }
+
+    #[test]
+    fn universe_checked_arithmetic() {
+        let first = UniverseId::MIN;
+        let last = UniverseId::MAX;
+        assert_eq!(first.checked_add(1), UniverseId::new(2).ok());
+        assert_eq!(first.checked_add(u16::MAX - 1), Some(last));
+        assert_eq!(first.checked_add(u16::MAX), None);
+        assert_eq!(first.checked_sub(0), Some(first));
+        assert_eq!(first.checked_sub(1), None);
+        assert_eq!(last.checked_add(0), Some(last));
+        assert_eq!(last.checked_add(1), None);
+        assert_eq!(last.checked_sub(u16::MAX - 1), Some(first));
+        assert_eq!(last.checked_sub(u16::MAX), None);
+    }
+
+    #[test]
+    fn channel_offset_at_universe_boundaries() {
+        let first = Address::new(UniverseId::MIN, Channel::new(1).unwrap());
+        assert_eq!(first.with_channel_offset(0), Ok(first));
+        assert_eq!(first.with_channel_offset(-1), Err(Error::UniverseOutOfRange(0)));
+        assert_eq!(first.with_channel_offset(-1025), Err(Error::UniverseOutOfRange(-2)));
+        assert_eq!(first.with_channel_offset(512).unwrap().to_string(), "2.1");
+
+        let last = Address::new(UniverseId::MAX, Channel::new(512).unwrap());
+        assert_eq!(last.with_channel_offset(0), Ok(last));
+        assert_eq!(last.with_channel_offset(1), Err(Error::UniverseOutOfRange(65536)));
+        assert_eq!(last.with_channel_offset(-512).unwrap().to_string(), "65534.512");
+        assert!(first.with_channel_offset(i32::MAX).is_err());
+        assert!(last.with_channel_offset(i32::MIN).is_err());
+    }
+
+    #[test]
+    fn sacn_routable_universes() {
+        assert!(UniverseId::MIN.is_sacn_routable());
+        assert!(UniverseId::new(63999).unwrap().is_sacn_routable());
+        assert!(!UniverseId::new(64000).unwrap().is_sacn_routable());
+        assert!(!UniverseId::new(64214).unwrap().is_sacn_routable());
+        assert!(!UniverseId::MAX.is_sacn_routable());
+    }
+
+    #[test]
+    fn address_from_str_formats() {
+        let expected = Address::new(UniverseId::new(2).unwrap(), Channel::new(488).unwrap());
+        assert_eq!("2.488".parse::<Address>(), Ok(expected));
+        assert_eq!("2/488".parse::<Address>(), Ok(expected));
+        assert_eq!("1000".parse::<Address>(), Ok(expected));
+        assert_eq!(" 2.488\t".parse::<Address>(), Ok(expected));
+        assert_eq!("\n1000 ".parse::<Address>(), Ok(expected));
+        assert_eq!(expected.to_string(), "2.488");
+        assert_eq!("1".parse::<Address>().unwrap().to_string(), "1.1");
+        assert_eq!("33553920".parse::<Address>().unwrap().to_string(), "65535.512");
+    }
+
+    #[test]
+    fn address_from_str_out_of_range() {
+        assert_eq!("0".parse::<Address>(), Err(Error::InvalidChannel(0)));
+        assert_eq!("0.1".parse::<Address>(), Err(Error::InvalidUniverseId(0)));
+        assert_eq!("1/0".parse::<Address>(), Err(Error::InvalidChannel(0)));
+        assert_eq!("1.513".parse::<Address>(), Err(Error::InvalidChannel(513)));
+        assert_eq!("33553921".parse::<Address>(), Err(Error::AbsoluteAddressOutOfRange(33553921)));
+        assert_eq!(
+            Address::from_absolute(u32::MAX),
+            Err(Error::AbsoluteAddressOutOfRange(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn address_from_str_invalid_syntax() {
+        for input in [
+            "",
+            " ",
+            "1.",
+            ".1",
+            "1.2.3",
+            "1/2/3",
+            "1 . 2",
+            "a.1",
+            "1.b",
+            "-1",
+            "65536.1",
+            "99999999999",
+        ] {
+            let result = input.parse::<Address>();
+            assert_eq!(result, Err(Error::ParseAddressFailed(input.to_string())), "{input:?}");
+        }
+
+        let message = "x".parse::<Address>().unwrap_err().to_string();
+        assert!(message.contains("'universe.channel'"));
+        assert!(message.contains("'universe/channel'"));
+        assert!(message.contains("absolute address"));
+    }
+
+    #[test]
+    fn address_range_rolls_over_universes() {
+        let start = Address::new(UniverseId::new(1).unwrap(), Channel::new(511).unwrap());
+        let addresses = Address::range(start, 4).collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            [
+                Address::new(UniverseId::new(1).unwrap(), Channel::new(511).unwrap()),
+                Address::new(UniverseId::new(1).unwrap(), Channel::new(512).unwrap()),
+                Address::new(UniverseId::new(2).unwrap(), Channel::new(1).unwrap()),
+                Address::new(UniverseId::new(2).unwrap(), Channel::new(2).unwrap()),
+            ]
+        );
+        assert_eq!(Address::range(start, 0).count(), 0);
+    }
+
+    #[test]
+    fn address_range_stops_at_max_universe() {
+        let start = Address::new(UniverseId::MAX, Channel::new(510).unwrap());
+        assert_eq!(Address::range(start, 10).count(), 3);
+    }
+
+    #[test]
+    fn value_percent_round_trip() {
+        for raw in 0..=255 {
+            assert_eq!(Value::from_percent(Value(raw).to_percent()), Value(raw));
+        }
+        assert_eq!(Value::from_percent(-10.0), Value(0));
+    }
+
+    #[test]
+    fn universe_max_used_channel() {
+        let mut universe = Universe::new();
+        assert!(universe.is_empty());
+        assert_eq!(universe.max_used_channel(), None);
+
+        universe.set_value(&Channel::MAX, Value(1));
+        assert!(!universe.is_empty());
+        assert_eq!(universe.max_used_channel(), Some(Channel::MAX));
+
+        universe.set_value(&Channel::MIN, Value(255));
+        universe.set_value(&Channel::MAX, Value(0));
+        assert_eq!(universe.max_used_channel(), Some(Channel::MIN));
+    }
+
+    #[test]
+    fn universe_try_from_rejects_invalid_length() {
+        assert_eq!(Universe::try_from(&[0; 511][..]), Err(Error::InvalidUniverseLength(511)));
+        assert_eq!(Universe::try_from(&[0; 513][..]), Err(Error::InvalidUniverseLength(513)));
+    }
+
+    #[test]
+    fn universe_try_from_round_trip() {
+        let bytes = (0..512).map(|ix| (ix % 256) as u8).collect::<Vec<_>>();
+        let universe = Universe::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(universe.get_value(&Channel::new(2).unwrap()), Value(1));
+        assert_eq!(Vec::<u8>::from(universe), bytes);
+    }
+
     #[test]
     fn universe_id_value_in_range() {
         let universe_id = Channel::new(3);
@@ -786,6 +1756,92 @@ mod tests {
         assert!(b < c);
     }
 
+    fn multiverse_with(values: &[(u32, u8)]) -> Multiverse {
+        let mut multiverse = Multiverse::new();
+        for (absolute, value) in values {
+            multiverse.set_value(&Address::from_absolute(*absolute).unwrap(), Value(*value));
+        }
+        multiverse
+    }
+
+    #[test]
+    fn multiverse_diff_identical_is_empty() {
+        let a = multiverse_with(&[(1, 10), (513, 20)]);
+        assert!(a.diff(&a.clone()).is_empty());
+    }
+
+    #[test]
+    fn multiverse_diff_changed_values() {
+        let a = multiverse_with(&[(1, 10), (2, 20)]);
+        let b = multiverse_with(&[(1, 10), (2, 30)]);
+        let diff = a.diff(&b);
+        assert!(diff.created_universes().is_empty());
+        assert!(diff.removed_universes().is_empty());
+        assert_eq!(diff.changes(), &[(Address::from_absolute(2).unwrap(), Value(30))]);
+    }
+
+    #[test]
+    fn multiverse_diff_created_and_removed_universes() {
+        let a = multiverse_with(&[(1, 10)]);
+        let b = multiverse_with(&[(513, 20)]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.created_universes(), &[UniverseId::new(2).unwrap()]);
+        assert_eq!(diff.removed_universes(), &[UniverseId::new(1).unwrap()]);
+        assert_eq!(diff.changes(), &[(Address::from_absolute(513).unwrap(), Value(20))]);
+    }
+
+    #[test]
+    fn multiverse_diff_round_trip() {
+        let cases = [
+            (multiverse_with(&[]), multiverse_with(&[(1, 255), (1024, 1)])),
+            (multiverse_with(&[(1, 255), (1024, 1)]), multiverse_with(&[])),
+            (multiverse_with(&[(5, 5), (600, 6)]), multiverse_with(&[(5, 0), (1100, 7)])),
+        ];
+
+        for (a, b) in cases {
+            let mut applied = a.clone();
+            applied.apply_diff(&a.diff(&b));
+            assert_eq!(applied, b);
+        }
+    }
+
+    #[test]
+    fn multiverse_diff_keeps_start_codes() {
+        let id = UniverseId::new(2).unwrap();
+        let a = multiverse_with(&[(1, 1), (513, 1)]);
+        let mut b = a.clone();
+        b.universe_mut(&id).unwrap().set_start_code(0xCF);
+        let mut c = multiverse_with(&[(1025, 1)]);
+        c.universe_mut(&UniverseId::new(3).unwrap()).unwrap().set_start_code(0x17);
+
+        let diff = a.diff(&b);
+        assert!(diff.changes().is_empty());
+        assert_eq!(diff.start_codes(), &[(id, 0xCF)]);
+        for (from, to) in [(&a, &b), (&b, &a), (&a, &c), (&c, &a)] {
+            let mut applied = from.clone();
+            applied.apply_diff(&from.diff(to));
+            assert_eq!(&applied, to);
+        }
+
+        let mut merged = multiverse_with(&[]);
+        merged.merge_htp(&b);
+        assert_eq!(merged, b);
+        merged.merge_htp(&a);
+        assert_eq!(merged.universe(&id).unwrap().start_code(), 0xCF);
+    }
+
+    #[test]
+    fn multiverse_apply_diff_creates_missing_universe() {
+        let a = multiverse_with(&[]);
+        let b = multiverse_with(&[(513, 1)]);
+        let diff = a.diff(&b);
+
+        let mut target = multiverse_with(&[(1, 1)]);
+        target.apply_diff(&MultiverseDiff { removed_universes: vec![], ..diff });
+        assert_eq!(target.get_value(&Address::from_absolute(513).unwrap()), Value(1));
+        assert_eq!(target.get_value(&Address::from_absolute(1).unwrap()), Value(1));
+    }
+
     // ----------
     // Serde
     // ----------
@@ -828,10 +1884,73 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct StringForms {
+        #[serde(with = "address_as_string")]
+        address: Address,
+        #[serde(with = "universe_id_as_string")]
+        universe: UniverseId,
+    }
+
+    #[test]
+    fn serde_string_forms() {
+        let forms = StringForms {
+            address: Address::new(UniverseId::new(1).unwrap(), Channel::new(100).unwrap()),
+            universe: UniverseId::new(3).unwrap(),
+        };
+        let json = serde_json::to_string(&forms).unwrap();
+        assert_eq!(json, r#"{"address":"1.100","universe":"3"}"#);
+
+        let json = r#"{"address":{"universe":1,"channel":100},"universe":3}"#;
+        let struct_forms: StringForms = serde_json::from_str(json).unwrap();
+        assert_eq!((struct_forms.address, struct_forms.universe), (forms.address, forms.universe));
+
+        let json = r#"{"address":"612","universe":"3"}"#;
+        let absolute: StringForms = serde_json::from_str(json).unwrap();
+        assert_eq!(absolute.address.to_string(), "2.100");
+
+        let json = r#"{"address":"1.513","universe":"3"}"#;
+        assert!(serde_json::from_str::<StringForms>(json).is_err());
+        let json = r#"{"address":"1.1","universe":"0"}"#;
+        assert!(serde_json::from_str::<StringForms>(json).is_err());
+    }
+
     #[test]
     fn deserialize_universe() {
         let json = r#"{"id":1,"values":[0,0,0]}"#;
         let universe: Result<Universe, _> = serde_json::from_str(json);
         assert!(universe.is_err()); // Should fail as we need all 512 values
     }
+
+    #[test]
+    fn deserialize_universe_without_start_code() {
+        let json = format!(r#"{{"values":{:?}}}"#, [7; 512]);
+        let universe: Universe = serde_json::from_str(&json).unwrap();
+        assert_eq!(universe.start_code(), Universe::NULL_START_CODE);
+        assert_eq!(universe.get_value(&Channel::new(512).unwrap()), Value(7));
+
+        let mut universe = Universe::new();
+        universe.set_start_code(0xCF);
+        let serialized = serde_json::to_string(&universe).unwrap();
+        assert_eq!(serde_json::from_str::<Universe>(&serialized).unwrap(), universe);
+    }
+
+    #[test]
+    fn serde_multiverse_diff() {
+        let diff = multiverse_with(&[]).diff(&multiverse_with(&[(1, 1)]));
+        let serialized = serde_json::to_string(&diff).unwrap();
+        let deserialized: MultiverseDiff = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(diff, deserialized);
+    }
+
+    #[test]
+    fn sorted_universes_are_ascending() {
+        let values = (1..=32).rev().map(|universe| (universe * 512, 1)).collect::<Vec<_>>();
+        let multiverse = multiverse_with(&values);
+
+        let expected = (1..=32).map(|id| UniverseId::new(id).unwrap()).collect::<Vec<_>>();
+        assert_eq!(multiverse.universe_ids(), expected);
+        let ids = multiverse.sorted_universes().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+    }
 }
@@ -1,14 +1,54 @@
 use std::io;
 
+use crate::attr::Attribute;
+use crate::dmx::{self, Address};
+use crate::show::fixture::FixturePath;
+use crate::showfile::GroupId;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("i/o error: {0}")]
     Io(#[from] io::Error),
 
+    #[error("dmx error: {0}")]
+    Dmx(#[from] dmx::Error),
+
     #[cfg(feature = "server")]
     #[error("server error: {message}")]
     Server { message: String },
 
+    #[error("fixture not found: {0}")]
+    FixtureNotFound(FixturePath),
+
+    #[error("group not found: {0}")]
+    GroupNotFound(GroupId),
+
+    #[error(
+        "address {requested} collides with '{existing_label}' (id {}) occupying {}..={}",
+        existing_fixture.root(),
+        existing_range.0,
+        existing_range.1
+    )]
+    AddressCollision {
+        /// The address the fixture was requested to be patched at.
+        requested: Address,
+        /// The number of channels the requested fixture spans.
+        requested_footprint: u32,
+        /// The fixture occupying one of the requested addresses.
+        existing_fixture: FixturePath,
+        /// The label of the root fixture of `existing_fixture`.
+        existing_label: String,
+        /// The first and last address of the root fixture of
+        /// `existing_fixture`.
+        existing_range: (Address, Address),
+    },
+
+    #[error("fixture {0} does not support color mixing")]
+    NoColorMixing(FixturePath),
+
+    #[error("virtual channel relations of {attribute} on fixture {fixture_path} form a cycle")]
+    RelationCycle { fixture_path: FixturePath, attribute: Attribute },
+
     #[error("{message}")]
     Other { message: String },
 }
@@ -11,6 +11,8 @@ pub mod value;
 pub mod client;
 #[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 mod error;
 
@@ -1,15 +1,162 @@
-use crate::dmx::Multiverse;
-use crate::packet::PacketPayload;
+use std::ops::RangeInclusive;
+
+use crate::dmx::{self, Address, Multiverse};
+use crate::packet::{AttributeValues, PacketPayload, ServerStats, SnapshotInfo};
 use crate::show::ShowData;
+use crate::show::fixture::FixturePath;
+use crate::showfile::ValidationIssue;
 
 /// Packets sent from the server to the client.
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum ClientPacketPayload {
+    /// Response to a `Hello` with a supported protocol version.
+    HelloAck {
+        protocol_version: u16,
+        server_version: String,
+    },
+    /// Response to a `Hello` with an unsupported protocol version. The server
+    /// closes the connection after sending it.
+    IncompatibleVersion {
+        supported: RangeInclusive<u16>,
+    },
     ResponseShowData(ShowData),
     ResponseDmxOutput(Multiverse),
+    ResponseDefaultOutput(Multiverse),
     ResponseSetAttributeValues,
+    ResponseSetAttributeValuesFaded,
+    /// Response to `RequestSetGroupAttributeValue`. `applied` is `false` if
+    /// no group with the given id exists.
+    ResponseSetGroupAttributeValue {
+        applied: bool,
+    },
+    ResponseSetRawDmx,
+    ResponseClearRawDmx,
+    ResponseClearAttributeValues,
+    ResponseSetGrandMaster,
+    ResponseSetUniverseMaster,
+    ResponseSetBlackout,
+    ResponseParkAddress,
+    /// Response to `RequestUnparkAddress`. `was_parked` is `false` if the
+    /// address was not parked.
+    ResponseUnparkAddress {
+        was_parked: bool,
+    },
+    /// Parked DMX channels and their values, sorted by address.
+    ResponseParkedAddresses {
+        parked: Vec<(Address, dmx::Value)>,
+    },
+    /// Response to `FireTrigger`. `applied` is `false` if no trigger with the
+    /// identifier exists.
+    ResponseFireTrigger {
+        applied: bool,
+    },
+    /// Response to `RequestRenameFixture`. `renamed` is `false` if no root
+    /// fixture with the id exists.
+    ResponseRenameFixture {
+        renamed: bool,
+    },
+    /// Response to `RequestReloadProtocols`. The protocols were only reloaded
+    /// if `issues` is empty.
+    ResponseReloadProtocols {
+        issues: Vec<ValidationIssue>,
+    },
+    /// Response to `RequestFixturesWithAttribute`, sorted by path.
+    ResponseFixturesWithAttribute {
+        fixture_paths: Vec<FixturePath>,
+    },
+    /// Response to `RequestAttributeValues`. Attributes that were never set
+    /// have their default value. Unknown attributes are left out.
+    ResponseAttributeValues(AttributeValues),
+    /// Response to `RequestSaveSnapshot`. `overwritten` is `true` if a
+    /// snapshot with the same label was replaced.
+    ResponseSaveSnapshot {
+        overwritten: bool,
+    },
+    /// Response to `RequestRestoreSnapshot`. `restored` is `false` if no
+    /// snapshot with the label exists.
+    ResponseRestoreSnapshot {
+        restored: bool,
+    },
+    /// Response to `RequestListSnapshots`, sorted by label.
+    ResponseListSnapshots {
+        snapshots: Vec<SnapshotInfo>,
+    },
+    /// Response to `RequestDeleteSnapshot`. `deleted` is `false` if no
+    /// snapshot with the label exists.
+    ResponseDeleteSnapshot {
+        deleted: bool,
+    },
+    /// Response to `RequestStats`.
+    ResponseStats(ServerStats),
+    /// Response to `RequestRecordedOutput`. `frame` is `None` if no frame was
+    /// recorded, e.g. because the server sends its output over the network.
+    ResponseRecordedOutput {
+        frame: Option<Multiverse>,
+    },
+    /// Response to a request that the client is not allowed to make, e.g. a
+    /// request that changes the output from a read-only client.
+    PermissionDenied {
+        reason: String,
+    },
+    /// Checks that the connection is alive. The client responds with a `Pong`
+    /// with the same nonce.
+    Ping {
+        nonce: u64,
+    },
+    /// Response to a `Ping` from the client.
+    Pong {
+        nonce: u64,
+    },
+    /// Sent to all other clients when a client has set attribute values.
+    /// Contains the values as they were after being applied, or the target
+    /// values of a fade.
+    AttributeValuesChanged(AttributeValues),
+    /// Sent to all clients when the show data changed, e.g. because the
+    /// server reloaded its showfile. Clients should request it again.
+    ShowDataChanged,
+}
+
+impl ClientPacketPayload {
+    /// Returns the name of the packet type, as used in the `type` tag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HelloAck { .. } => "HelloAck",
+            Self::IncompatibleVersion { .. } => "IncompatibleVersion",
+            Self::ResponseShowData(_) => "ResponseShowData",
+            Self::ResponseDmxOutput(_) => "ResponseDmxOutput",
+            Self::ResponseDefaultOutput(_) => "ResponseDefaultOutput",
+            Self::ResponseSetAttributeValues => "ResponseSetAttributeValues",
+            Self::ResponseSetAttributeValuesFaded => "ResponseSetAttributeValuesFaded",
+            Self::ResponseSetGroupAttributeValue { .. } => "ResponseSetGroupAttributeValue",
+            Self::ResponseSetRawDmx => "ResponseSetRawDmx",
+            Self::ResponseClearRawDmx => "ResponseClearRawDmx",
+            Self::ResponseClearAttributeValues => "ResponseClearAttributeValues",
+            Self::ResponseSetGrandMaster => "ResponseSetGrandMaster",
+            Self::ResponseSetUniverseMaster => "ResponseSetUniverseMaster",
+            Self::ResponseSetBlackout => "ResponseSetBlackout",
+            Self::ResponseParkAddress => "ResponseParkAddress",
+            Self::ResponseUnparkAddress { .. } => "ResponseUnparkAddress",
+            Self::ResponseParkedAddresses { .. } => "ResponseParkedAddresses",
+            Self::ResponseFireTrigger { .. } => "ResponseFireTrigger",
+            Self::ResponseRenameFixture { .. } => "ResponseRenameFixture",
+            Self::ResponseReloadProtocols { .. } => "ResponseReloadProtocols",
+            Self::ResponseFixturesWithAttribute { .. } => "ResponseFixturesWithAttribute",
+            Self::ResponseAttributeValues(_) => "ResponseAttributeValues",
+            Self::ResponseSaveSnapshot { .. } => "ResponseSaveSnapshot",
+            Self::ResponseRestoreSnapshot { .. } => "ResponseRestoreSnapshot",
+            Self::ResponseListSnapshots { .. } => "ResponseListSnapshots",
+            Self::ResponseDeleteSnapshot { .. } => "ResponseDeleteSnapshot",
+            Self::ResponseStats(_) => "ResponseStats",
+            Self::ResponseRecordedOutput { .. } => "ResponseRecordedOutput",
+            Self::PermissionDenied { .. } => "PermissionDenied",
+            Self::Ping { .. } => "Ping",
+            Self::Pong { .. } => "Pong",
+            Self::AttributeValuesChanged(_) => "AttributeValuesChanged",
+            Self::ShowDataChanged => "ShowDataChanged",
+        }
+    }
 }
 
 impl PacketPayload for ClientPacketPayload {}
@@ -1,17 +1,47 @@
+use std::io::{Read as _, Write as _};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
 use tokio_util::bytes::{Buf as _, BufMut as _, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::packet::{Packet, PacketPayload};
+use crate::packet::{DEFAULT_MAX_FRAME_SIZE, Packet, PacketPayload};
+
+/// Payloads larger than this many bytes are compressed by default.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 4 * 1024;
 
-pub const MAX_PACKET_LENGTH: usize = 8 * 1024 * 1024;
+/// Bit in the length prefix that is set if the payload is zlib compressed.
+const COMPRESSED_FLAG: u32 = 1 << 31;
 
 pub struct PacketEncoder<P: PacketPayload> {
+    compression_threshold: Option<usize>,
+    max_frame_size: usize,
     marker: std::marker::PhantomData<P>,
 }
 
+impl<P: PacketPayload> PacketEncoder<P> {
+    /// Creates an encoder that compresses payloads larger than the given
+    /// number of bytes, or never compresses if the threshold is `None`.
+    pub fn with_compression_threshold(compression_threshold: Option<usize>) -> Self {
+        Self {
+            compression_threshold,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the maximum size in bytes of the payload of a frame. Packets with
+    /// a larger payload are not sent, as the other side would reject them.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+}
+
 impl<P: PacketPayload> Default for PacketEncoder<P> {
     fn default() -> Self {
-        Self { marker: std::marker::PhantomData::default() }
+        Self::with_compression_threshold(Some(DEFAULT_COMPRESSION_THRESHOLD))
     }
 }
 
@@ -19,18 +49,35 @@ impl<P: PacketPayload> Encoder<Packet<P>> for PacketEncoder<P> {
     type Error = super::Error;
 
     fn encode(&mut self, packet: Packet<P>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let payload_bytes = packet.encode_payload_bytes()?;
+        let mut payload_bytes = packet.encode_payload_bytes()?;
 
-        // Check if the length of the length prefix + payload bytes is within the limit.
-        if 4 + payload_bytes.len() > MAX_PACKET_LENGTH {
-            return Err(super::Error::PacketTooLarge(payload_bytes.len()));
+        // The decoder checks the size of the decompressed payload as well, so
+        // the limit applies to the uncompressed payload.
+        if payload_bytes.len() > self.max_frame_size {
+            return Err(super::Error::FrameTooLarge {
+                size: payload_bytes.len(),
+                limit: self.max_frame_size,
+            });
+        }
+
+        // Compress large payloads, but only send them compressed if that
+        // actually makes them smaller.
+        let mut length_prefix = payload_bytes.len() as u32;
+        if self.compression_threshold.is_some_and(|threshold| payload_bytes.len() > threshold) {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+            encoder.write_all(&payload_bytes)?;
+            let compressed = encoder.finish()?;
+            if compressed.len() < payload_bytes.len() {
+                length_prefix = compressed.len() as u32 | COMPRESSED_FLAG;
+                payload_bytes = compressed;
+            }
         }
 
         // Reserve space in the buffer.
         dst.reserve(4 + payload_bytes.len());
 
         // Write the length prefix using BufMut and packet payload to the buffer.
-        dst.put_u32_le(payload_bytes.len() as u32);
+        dst.put_u32_le(length_prefix);
         dst.extend_from_slice(&payload_bytes);
 
         Ok(())
@@ -38,12 +85,21 @@ impl<P: PacketPayload> Encoder<Packet<P>> for PacketEncoder<P> {
 }
 
 pub struct PacketDecoder<P: PacketPayload> {
+    max_frame_size: usize,
     marker: std::marker::PhantomData<P>,
 }
 
+impl<P: PacketPayload> PacketDecoder<P> {
+    /// Creates a decoder that rejects frames with a payload larger than the
+    /// given number of bytes, both as received and after decompression.
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self { max_frame_size, marker: std::marker::PhantomData }
+    }
+}
+
 impl<P: PacketPayload> Default for PacketDecoder<P> {
     fn default() -> Self {
-        Self { marker: std::marker::PhantomData::default() }
+        Self::with_max_frame_size(DEFAULT_MAX_FRAME_SIZE)
     }
 }
 
@@ -58,11 +114,23 @@ impl<P: PacketPayload> Decoder for PacketDecoder<P> {
         }
 
         // Peek at the length prefix without consuming it.
-        let payload_length = {
+        let length_prefix = {
             let mut length_bytes = [0u8; 4];
             length_bytes.copy_from_slice(&src[..4]);
-            u32::from_le_bytes(length_bytes) as usize
+            u32::from_le_bytes(length_bytes)
         };
+        let compressed = length_prefix & COMPRESSED_FLAG != 0;
+        let payload_length = (length_prefix & !COMPRESSED_FLAG) as usize;
+
+        // Check that the length is not too large before buffering the payload,
+        // to avoid a denial of service attack where the server runs out of
+        // memory.
+        if payload_length > self.max_frame_size {
+            return Err(Self::Error::FrameTooLarge {
+                size: payload_length,
+                limit: self.max_frame_size,
+            });
+        }
 
         if src.len() < 4 + payload_length {
             // The full packet has not yet arrived.
@@ -74,17 +142,187 @@ impl<P: PacketPayload> Decoder for PacketDecoder<P> {
             return Ok(None);
         }
 
-        // Check that the length is not too large to avoid a denial of
-        // service attack where the server runs out of memory.
-        if payload_length > MAX_PACKET_LENGTH {
-            return Err(Self::Error::PacketTooLarge(payload_length));
-        }
-
         // Now we can consume the length prefix and payload.
         src.advance(4);
         let payload_bytes = src.split_to(payload_length);
-        let packet = Packet::decode_payload_bytes(&payload_bytes)?;
+        let packet = if compressed {
+            // Limit the inflated size as well, as a small compressed payload
+            // can inflate to a huge one.
+            let mut inflated = Vec::new();
+            ZlibDecoder::new(&payload_bytes[..])
+                .take(self.max_frame_size as u64 + 1)
+                .read_to_end(&mut inflated)?;
+            if inflated.len() > self.max_frame_size {
+                return Err(Self::Error::FrameTooLarge {
+                    size: inflated.len(),
+                    limit: self.max_frame_size,
+                });
+            }
+            Packet::decode_payload_bytes(&inflated)?
+        } else {
+            Packet::decode_payload_bytes(&payload_bytes)?
+        };
 
         Ok(Some(packet))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::{self, Address, Multiverse};
+    use crate::packet::{ClientPacketPayload, Error, ServerPacketPayload};
+
+    fn round_trip<P: PacketPayload>(
+        encoder: &mut PacketEncoder<P>,
+        payload: P,
+    ) -> (bool, usize, Packet<P>) {
+        let mut buffer = BytesMut::new();
+        encoder.encode(Packet::new(payload), &mut buffer).unwrap();
+        let compressed = buffer[3] & 0x80 != 0;
+        let encoded_length = buffer.len();
+
+        let packet = PacketDecoder::<P>::default().decode(&mut buffer).unwrap().unwrap();
+        assert!(buffer.is_empty());
+        (compressed, encoded_length, packet)
+    }
+
+    fn large_multiverse() -> Multiverse {
+        let mut multiverse = Multiverse::new();
+        for absolute in 1..=16 * 512 {
+            let address = Address::from_absolute(absolute).unwrap();
+            multiverse.set_value(&address, crate::dmx::Value(absolute as u8));
+        }
+        multiverse
+    }
+
+    #[test]
+    fn small_packet_is_not_compressed() {
+        let (compressed, _, packet) =
+            round_trip(&mut PacketEncoder::default(), ServerPacketPayload::Ping { nonce: 1 });
+        assert!(!compressed);
+        assert_eq!(packet.payload, ServerPacketPayload::Ping { nonce: 1 });
+    }
+
+    #[test]
+    fn large_packet_is_compressed() {
+        let multiverse = large_multiverse();
+        let payload = ClientPacketPayload::ResponseDmxOutput(multiverse.clone());
+
+        let (compressed, compressed_length, packet) =
+            round_trip(&mut PacketEncoder::default(), payload.clone());
+        assert!(compressed);
+        let ClientPacketPayload::ResponseDmxOutput(decoded) = packet.payload else {
+            panic!()
+        };
+        assert_eq!(decoded, multiverse);
+
+        let (compressed, uncompressed_length, packet) =
+            round_trip(&mut PacketEncoder::with_compression_threshold(None), payload);
+        assert!(!compressed);
+        assert!(compressed_length < uncompressed_length);
+        let ClientPacketPayload::ResponseDmxOutput(decoded) = packet.payload else {
+            panic!()
+        };
+        assert_eq!(decoded, multiverse);
+    }
+
+    #[test]
+    fn decode_waits_for_full_compressed_packet() {
+        let mut buffer = BytesMut::new();
+        let payload = ClientPacketPayload::ResponseDmxOutput(large_multiverse());
+        PacketEncoder::default().encode(Packet::new(payload), &mut buffer).unwrap();
+
+        let mut decoder = PacketDecoder::<ClientPacketPayload>::default();
+        let mut partial = buffer.split_to(buffer.len() / 2);
+        assert!(decoder.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buffer);
+        assert!(decoder.decode(&mut partial).unwrap().is_some());
+    }
+
+    #[test]
+    fn parked_addresses_round_trip() {
+        let parked = vec![(Address::from_absolute(3).unwrap(), dmx::Value(10))];
+        let payload = ClientPacketPayload::ResponseParkedAddresses { parked: parked.clone() };
+
+        let (_, _, packet) = round_trip(&mut PacketEncoder::default(), payload);
+        let ClientPacketPayload::ResponseParkedAddresses { parked: decoded } = packet.payload
+        else {
+            panic!()
+        };
+        assert_eq!(decoded, parked);
+    }
+
+    fn ping_frame() -> BytesMut {
+        let mut buffer = BytesMut::new();
+        PacketEncoder::with_compression_threshold(None)
+            .encode(Packet::new(ServerPacketPayload::Ping { nonce: 1 }), &mut buffer)
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn decode_one_byte_at_a_time() {
+        let frame = ping_frame();
+        let mut decoder = PacketDecoder::<ServerPacketPayload>::default();
+        let mut buffer = BytesMut::new();
+        for (ix, byte) in frame.iter().enumerate() {
+            buffer.put_u8(*byte);
+            let packet = decoder.decode(&mut buffer).unwrap();
+            if ix + 1 < frame.len() {
+                assert!(packet.is_none());
+            } else {
+                assert_eq!(packet.unwrap().payload, ServerPacketPayload::Ping { nonce: 1 });
+            }
+        }
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn frame_at_the_limit_passes() {
+        let frame = ping_frame();
+        let size = frame.len() - 4;
+
+        let mut decoder = PacketDecoder::<ServerPacketPayload>::with_max_frame_size(size);
+        assert!(decoder.decode(&mut frame.clone()).unwrap().is_some());
+
+        let mut decoder = PacketDecoder::<ServerPacketPayload>::with_max_frame_size(size - 1);
+        let err = decoder.decode(&mut frame.clone()).unwrap_err();
+        assert!(
+            matches!(err, Error::FrameTooLarge { size: s, limit } if s == size && limit == size - 1)
+        );
+
+        let payload = ServerPacketPayload::Ping { nonce: 1 };
+        let mut encoder =
+            PacketEncoder::with_compression_threshold(None).with_max_frame_size(size - 1);
+        let err = encoder.encode(Packet::new(payload), &mut BytesMut::new()).unwrap_err();
+        assert!(matches!(err, Error::FrameTooLarge { .. }));
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected_before_buffering() {
+        let mut buffer = BytesMut::new();
+        buffer.put_u32_le(!COMPRESSED_FLAG);
+
+        let mut decoder = PacketDecoder::<ServerPacketPayload>::default();
+        let err = decoder.decode(&mut buffer).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FrameTooLarge { size, limit: DEFAULT_MAX_FRAME_SIZE } if size == i32::MAX as usize
+        ));
+        assert!(buffer.capacity() < DEFAULT_MAX_FRAME_SIZE);
+    }
+
+    #[test]
+    fn inflated_size_is_limited() {
+        let mut buffer = BytesMut::new();
+        let payload = ClientPacketPayload::ResponseDmxOutput(large_multiverse());
+        PacketEncoder::default().encode(Packet::new(payload), &mut buffer).unwrap();
+        let compressed_size = buffer.len() - 4;
+
+        let mut decoder =
+            PacketDecoder::<ClientPacketPayload>::with_max_frame_size(compressed_size);
+        let err = decoder.decode(&mut buffer).unwrap_err();
+        assert!(matches!(err, Error::FrameTooLarge { limit, .. } if limit == compressed_size));
+    }
+}
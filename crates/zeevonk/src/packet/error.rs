@@ -1,13 +1,20 @@
+use crate::packet::MAX_MISSED_PONGS;
+
 #[derive(Debug, thiserror::Error)]
 /// Errors that can occur during packet processing.
 pub enum Error {
-    #[error("packet too large: {0} bytes")]
-    PacketTooLarge(usize),
+    /// The payload of a frame is larger than the maximum frame size.
+    #[error("frame too large: {size} bytes, the limit is {limit} bytes")]
+    FrameTooLarge { size: usize, limit: usize },
 
     /// The payload is invalid.
     #[error("invalid payload {message}")]
     InvalidPayload { message: String },
 
+    /// The other side did not respond to pings.
+    #[error("connection lost: no response to {MAX_MISSED_PONGS} pings")]
+    ConnectionLost,
+
     /// An I/O error occurred.
     #[error("i/o error: {0}")]
     Io(#[from] std::io::Error),
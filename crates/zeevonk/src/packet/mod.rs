@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::time::{Duration, SystemTime};
 
 pub use client::*;
 #[cfg(feature = "tokio")]
@@ -8,6 +11,7 @@ pub use server::*;
 
 use crate::attr::Attribute;
 use crate::show::fixture::FixturePath;
+use crate::showfile::Identifier;
 use crate::value::ClampedValue;
 
 mod client;
@@ -16,6 +20,26 @@ mod codec;
 mod error;
 mod server;
 
+/// The version of the packet protocol used by this version of Zeevonk.
+///
+/// Bump this when packets change in a way that older clients or servers
+/// can't decode.
+///
+/// Version 2 added the dimmer curve of patched fixtures and the number of
+/// `applied` flag to `ResponseSetGroupAttributeValue`.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// The protocol versions of clients that the server can serve.
+pub const SUPPORTED_PROTOCOL_VERSIONS: RangeInclusive<u16> = 2..=PROTOCOL_VERSION;
+
+/// Number of consecutive pings without a response after which a connection
+/// is considered lost.
+pub const MAX_MISSED_PONGS: u32 = 3;
+
+/// The default maximum size in bytes of the payload of a frame, both as sent
+/// and after decompression.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
 /// Trait for types that can be used as packet payloads.
 pub trait PacketPayload: serde::Serialize + for<'de> serde::Deserialize<'de> {}
 
@@ -50,10 +74,12 @@ pub struct AttributeValues {
 }
 
 impl AttributeValues {
+    /// Creates an empty set of attribute values.
     pub fn new() -> Self {
         Self { values: HashMap::new() }
     }
 
+    /// Sets the value of an attribute for a fixture, replacing any previous value.
     pub fn set(
         &mut self,
         fixture_path: FixturePath,
@@ -63,11 +89,322 @@ impl AttributeValues {
         self.values.insert((fixture_path, attribute), value.into());
     }
 
+    /// Iterates over all values, in no particular order.
     pub fn values(&self) -> impl Iterator<Item = (&(FixturePath, Attribute), &ClampedValue)> {
         self.values.iter()
     }
 
+    /// Returns the value of an attribute for a fixture, if it is set.
     pub fn get(&self, path: FixturePath, attribute: Attribute) -> Option<ClampedValue> {
         self.values.get(&(path, attribute)).copied()
     }
+
+    /// Sets the same attribute value for each of the given fixtures.
+    pub fn set_for_paths(
+        &mut self,
+        paths: impl IntoIterator<Item = FixturePath>,
+        attribute: Attribute,
+        value: impl Into<ClampedValue>,
+    ) {
+        let value = value.into();
+        for path in paths {
+            self.set(path, attribute, value);
+        }
+    }
+
+    /// Merges all values from `other` into these values.
+    ///
+    /// Values in `other` take precedence over existing values for the same
+    /// fixture and attribute (last write wins).
+    pub fn merge(&mut self, other: AttributeValues) {
+        self.values.extend(other.values);
+    }
+
+    /// Removes the value of an attribute for a fixture, returning the removed value.
+    pub fn remove(&mut self, path: FixturePath, attribute: Attribute) -> Option<ClampedValue> {
+        self.values.remove(&(path, attribute))
+    }
+
+    /// Removes all values that are set for the given fixture.
+    pub fn remove_fixture(&mut self, path: FixturePath) {
+        self.values.retain(|(p, _), _| *p != path);
+    }
+
+    /// Iterates over the values set for the given fixture.
+    pub fn for_fixture(
+        &self,
+        path: FixturePath,
+    ) -> impl Iterator<Item = (Attribute, ClampedValue)> {
+        self.values.iter().filter(move |((p, _), _)| *p == path).map(|((_, a), v)| (*a, *v))
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no values are set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl FromIterator<((FixturePath, Attribute), ClampedValue)> for AttributeValues {
+    fn from_iter<T: IntoIterator<Item = ((FixturePath, Attribute), ClampedValue)>>(
+        iter: T,
+    ) -> Self {
+        Self { values: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<((FixturePath, Attribute), ClampedValue)> for AttributeValues {
+    fn extend<T: IntoIterator<Item = ((FixturePath, Attribute), ClampedValue)>>(
+        &mut self,
+        iter: T,
+    ) {
+        self.values.extend(iter);
+    }
+}
+
+/// The shape of a fade between two attribute values over time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub enum FadeCurve {
+    /// Changes the value at a constant rate.
+    #[default]
+    Linear,
+    /// Starts slow and speeds up towards the end.
+    EaseIn,
+    /// Starts fast and slows down towards the end.
+    EaseOut,
+    /// Starts and ends slow, fastest halfway.
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Maps the linear progress `t` of a fade, from `0.0` to `1.0`, to the
+    /// progress along this curve.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::SCurve => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// A snapshot of the attribute values on the server, which can be restored
+/// later.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SnapshotInfo {
+    /// The label the snapshot was saved under.
+    pub label: Identifier,
+    /// When the snapshot was saved.
+    pub created_at: SystemTime,
+    /// Number of attribute values in the snapshot.
+    pub value_count: usize,
+}
+
+/// Statistics about a running server, for monitoring.
+///
+/// All counters are totals since the server started.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ServerStats {
+    /// Number of packets received from clients, keyed by packet type.
+    pub packets_received: BTreeMap<String, u64>,
+    /// Number of bytes sent to clients, including length prefixes.
+    pub bytes_sent: u64,
+    /// Number of clients that are currently connected.
+    pub active_clients: u32,
+    /// How long resolving the DMX output took the last time it was resolved.
+    pub last_resolve_duration: Duration,
+    /// How long resolving the DMX output took, every time it was resolved.
+    #[serde(default)]
+    pub resolve_durations: DurationHistogram,
+    /// Number of frames sent over the output protocols, keyed by universe.
+    #[serde(default)]
+    pub frames_sent: BTreeMap<u16, u64>,
+}
+
+impl ServerStats {
+    /// Returns the total number of packets received from clients.
+    pub fn total_packets_received(&self) -> u64 {
+        self.packets_received.values().sum()
+    }
+}
+
+/// Durations counted in buckets with fixed bounds, from which percentiles
+/// can be estimated.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct DurationHistogram {
+    /// The upper bounds of the buckets, in increasing order.
+    pub bounds: Vec<Duration>,
+    /// The number of durations in each bucket. The last count is of the
+    /// durations longer than the last bound.
+    pub counts: Vec<u64>,
+    /// The sum of all durations.
+    pub sum: Duration,
+}
+
+impl DurationHistogram {
+    /// Creates an empty histogram with buckets up to the given bounds, which
+    /// have to be in increasing order.
+    pub fn new(bounds: Vec<Duration>) -> Self {
+        let counts = vec![0; bounds.len() + 1];
+        Self { bounds, counts, sum: Duration::ZERO }
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = self.bounds.partition_point(|bound| *bound < duration);
+        self.counts[bucket] += 1;
+        self.sum += duration;
+    }
+
+    /// Returns the number of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the upper bound of the bucket that the given percentile
+    /// (`0.0..=100.0`) of the durations falls in.
+    ///
+    /// Returns `None` if nothing was recorded, or if the percentile falls in
+    /// the last bucket, which has no upper bound.
+    pub fn percentile(&self, percentile: f32) -> Option<Duration> {
+        let rank = (self.count() as f64 * percentile.clamp(0.0, 100.0) as f64 / 100.0).ceil();
+        let rank = (rank as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return self.bounds.get(bucket).copied();
+            }
+        }
+        None
+    }
+}
+
+/// The datagram clients broadcast over UDP to find servers on the network.
+/// Servers with discovery enabled answer it with a [ServerAnnouncement].
+pub const DISCOVERY_PROBE: &[u8] = b"ZVNK?";
+
+/// The msgpack-encoded answer of a server to a [DISCOVERY_PROBE].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ServerAnnouncement {
+    /// The name of the server, from its showfile config.
+    pub name: String,
+    /// The version of Zeevonk the server runs.
+    pub version: String,
+    /// The port the server accepts client connections on.
+    pub tcp_port: u16,
+    /// The number of fixtures in the patch, not counting sub-fixtures.
+    pub fixture_count: u32,
+    /// The address the announcement was received from. This is not sent by
+    /// the server, but filled in by the client that received it.
+    #[serde(skip)]
+    pub ip: Option<IpAddr>,
+}
+
+impl ServerAnnouncement {
+    /// Returns the address to connect to the server at, if the announcement
+    /// was received over the network.
+    pub fn address(&self) -> Option<SocketAddr> {
+        Some(SocketAddr::new(self.ip?, self.tcp_port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fpath;
+
+    #[test]
+    fn duration_histogram_percentiles() {
+        let ms = Duration::from_millis;
+        let mut histogram = DurationHistogram::new(vec![ms(1), ms(5), ms(10)]);
+        assert_eq!(histogram.percentile(50.0), None);
+
+        for duration in [0, 1, 1, 2, 3, 4, 6, 8, 9, 20] {
+            histogram.record(ms(duration));
+        }
+        assert_eq!(histogram.counts, [3, 3, 3, 1]);
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.sum, ms(54));
+        assert_eq!(histogram.percentile(0.0), Some(ms(1)));
+        assert_eq!(histogram.percentile(50.0), Some(ms(5)));
+        assert_eq!(histogram.percentile(90.0), Some(ms(10)));
+        assert_eq!(histogram.percentile(99.0), None);
+    }
+
+    #[test]
+    fn fade_curves_start_and_end_at_the_targets() {
+        for curve in [FadeCurve::Linear, FadeCurve::EaseIn, FadeCurve::EaseOut, FadeCurve::SCurve] {
+            assert_eq!(curve.apply(0.0), 0.0);
+            assert_eq!(curve.apply(1.0), 1.0);
+            assert_eq!(curve.apply(2.0), 1.0);
+        }
+        assert!(FadeCurve::EaseIn.apply(0.5) < 0.5);
+        assert!(FadeCurve::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(FadeCurve::SCurve.apply(0.5), 0.5);
+        assert!(FadeCurve::SCurve.apply(0.25) < 0.25);
+    }
+
+    #[test]
+    fn set_for_paths_and_for_fixture() {
+        let mut values = AttributeValues::new();
+        values.set_for_paths([fpath![1], fpath![2], fpath![3]], Attribute::Dimmer, 0.5);
+        values.set(fpath![2], Attribute::Pan, 1.0);
+
+        assert_eq!(values.len(), 4);
+        assert_eq!(values.get(fpath![3], Attribute::Dimmer), Some(ClampedValue::new(0.5)));
+
+        let mut fixture_values = values.for_fixture(fpath![2]).collect::<Vec<_>>();
+        fixture_values.sort_by_key(|(attribute, _)| *attribute);
+        assert_eq!(
+            fixture_values,
+            vec![
+                (Attribute::Dimmer, ClampedValue::new(0.5)),
+                (Attribute::Pan, ClampedValue::new(1.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_last_write_wins() {
+        let mut values = AttributeValues::new();
+        values.set(fpath![1], Attribute::Dimmer, 0.25);
+        values.set(fpath![2], Attribute::Dimmer, 0.25);
+
+        let other: AttributeValues =
+            [((fpath![1], Attribute::Dimmer), ClampedValue::new(1.0))].into_iter().collect();
+        values.merge(other);
+
+        assert_eq!(values.get(fpath![1], Attribute::Dimmer), Some(ClampedValue::new(1.0)));
+        assert_eq!(values.get(fpath![2], Attribute::Dimmer), Some(ClampedValue::new(0.25)));
+    }
+
+    #[test]
+    fn remove_and_extend() {
+        let mut values = AttributeValues::new();
+        assert!(values.is_empty());
+
+        values.extend([
+            ((fpath![1], Attribute::Dimmer), ClampedValue::new(1.0)),
+            ((fpath![1], Attribute::Tilt), ClampedValue::new(0.5)),
+        ]);
+        assert_eq!(values.remove(fpath![1], Attribute::Dimmer), Some(ClampedValue::new(1.0)));
+        assert_eq!(values.remove(fpath![1], Attribute::Dimmer), None);
+        assert_eq!(values.len(), 1);
+    }
 }
@@ -1,13 +1,207 @@
-use crate::packet::{AttributeValues, PacketPayload};
+use crate::attr::Attribute;
+use crate::dmx::{self, Address, UniverseId};
+use crate::packet::{AttributeValues, FadeCurve, PacketPayload};
+use crate::show::fixture::{FixtureId, FixturePath};
+use crate::showfile::{GroupId, Identifier, Protocols};
+use crate::value::ClampedValue;
 
 /// Packets sent from the client to the server.
 #[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 pub enum ServerPacketPayload {
+    /// The first packet sent by a client after connecting. The server
+    /// responds with a `HelloAck`, or with an `IncompatibleVersion` after
+    /// which it closes the connection.
+    Hello {
+        protocol_version: u16,
+        client_name: String,
+    },
     RequestShowData,
     RequestDmxOutput,
+    /// Requests the default output of all fixtures, without any attribute
+    /// values or raw DMX overrides applied.
+    RequestDefaultOutput,
     RequestSetAttributeValues(AttributeValues),
+    /// Fades the attribute values from their current values to the given
+    /// values over `duration_ms` milliseconds, following the curve.
+    SetAttributeValuesFaded {
+        values: AttributeValues,
+        duration_ms: u32,
+        /// Defaults to [FadeCurve::Linear] for clients that don't send one.
+        #[serde(default)]
+        curve: FadeCurve,
+    },
+    RequestSetGroupAttributeValue {
+        group: GroupId,
+        attribute: Attribute,
+        value: ClampedValue,
+    },
+    /// Forces DMX channels to a value, regardless of the resolved attribute values.
+    RequestSetRawDmx {
+        values: Vec<(Address, dmx::Value)>,
+    },
+    /// Clears raw DMX overrides for the given addresses, or all overrides if `None`.
+    RequestClearRawDmx {
+        addresses: Option<Vec<Address>>,
+    },
+    /// Sets the grand master, which scales the intensity of all fixtures.
+    RequestSetGrandMaster {
+        level: ClampedValue,
+    },
+    /// Sets the master of a universe, which scales the intensity of the
+    /// fixtures in it on top of the grand master. A full master is removed.
+    RequestSetUniverseMaster {
+        universe: UniverseId,
+        level: ClampedValue,
+    },
+    /// Puts the intensity of all fixtures at zero while `blackout` is set,
+    /// without changing their attribute values or the masters.
+    RequestSetBlackout {
+        blackout: bool,
+    },
+    /// Parks a DMX channel at a value. Parked channels keep their value,
+    /// regardless of attribute values and raw DMX overrides, until unparked.
+    RequestParkAddress {
+        address: Address,
+        value: dmx::Value,
+    },
+    /// Unparks a DMX channel.
+    RequestUnparkAddress {
+        address: Address,
+    },
+    /// Requests all parked DMX channels and their values.
+    RequestParkedAddresses,
+    /// Removes the attribute values of the fixture and its sub-fixtures, or of
+    /// all fixtures if `None`, so they return to their defaults.
+    RequestClearAttributeValues {
+        fixture_path: Option<FixturePath>,
+    },
+    /// Applies the attribute values of the trigger with the given identifier.
+    FireTrigger(Identifier),
+    /// Renames the root fixture with the given id. Labels don't have to be
+    /// unique.
+    RequestRenameFixture {
+        root_id: FixtureId,
+        label: String,
+    },
+    /// Requests the paths of all fixtures that have the given attribute.
+    RequestFixturesWithAttribute {
+        attribute: Attribute,
+    },
+    /// Replaces the output protocols without restarting the server. sACN
+    /// outputs whose configuration is unchanged keep sending.
+    RequestReloadProtocols(Protocols),
+    /// Requests the current values of the given attributes, or of every
+    /// attribute of every fixture if `filter` is `None`.
+    RequestAttributeValues {
+        filter: Option<Vec<(FixturePath, Attribute)>>,
+    },
+    /// Saves the current attribute values and raw DMX overrides under the
+    /// label, replacing any snapshot with the same label.
+    RequestSaveSnapshot {
+        label: Identifier,
+    },
+    /// Replaces the attribute values and raw DMX overrides with the ones in
+    /// the snapshot with the label.
+    RequestRestoreSnapshot {
+        label: Identifier,
+    },
+    /// Requests all saved snapshots, sorted by label.
+    RequestListSnapshots,
+    /// Deletes the snapshot with the label.
+    RequestDeleteSnapshot {
+        label: Identifier,
+    },
+    /// Requests statistics about the server, for monitoring.
+    RequestStats,
+    /// Requests the last frame sent to the dummy output of a server that
+    /// records its output instead of sending it.
+    RequestRecordedOutput,
+    /// Checks that the connection is alive. The server responds with a `Pong`
+    /// with the same nonce.
+    Ping {
+        nonce: u64,
+    },
+    /// Response to a `Ping` from the server.
+    Pong {
+        nonce: u64,
+    },
+}
+
+impl ServerPacketPayload {
+    /// Returns the name of the packet type, as used in the `type` tag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hello { .. } => "Hello",
+            Self::RequestShowData => "RequestShowData",
+            Self::RequestDmxOutput => "RequestDmxOutput",
+            Self::RequestDefaultOutput => "RequestDefaultOutput",
+            Self::RequestSetAttributeValues(_) => "RequestSetAttributeValues",
+            Self::SetAttributeValuesFaded { .. } => "SetAttributeValuesFaded",
+            Self::RequestSetGroupAttributeValue { .. } => "RequestSetGroupAttributeValue",
+            Self::RequestSetRawDmx { .. } => "RequestSetRawDmx",
+            Self::RequestClearRawDmx { .. } => "RequestClearRawDmx",
+            Self::RequestSetGrandMaster { .. } => "RequestSetGrandMaster",
+            Self::RequestSetUniverseMaster { .. } => "RequestSetUniverseMaster",
+            Self::RequestSetBlackout { .. } => "RequestSetBlackout",
+            Self::RequestParkAddress { .. } => "RequestParkAddress",
+            Self::RequestUnparkAddress { .. } => "RequestUnparkAddress",
+            Self::RequestParkedAddresses => "RequestParkedAddresses",
+            Self::RequestClearAttributeValues { .. } => "RequestClearAttributeValues",
+            Self::FireTrigger(_) => "FireTrigger",
+            Self::RequestRenameFixture { .. } => "RequestRenameFixture",
+            Self::RequestFixturesWithAttribute { .. } => "RequestFixturesWithAttribute",
+            Self::RequestReloadProtocols(_) => "RequestReloadProtocols",
+            Self::RequestAttributeValues { .. } => "RequestAttributeValues",
+            Self::RequestSaveSnapshot { .. } => "RequestSaveSnapshot",
+            Self::RequestRestoreSnapshot { .. } => "RequestRestoreSnapshot",
+            Self::RequestListSnapshots => "RequestListSnapshots",
+            Self::RequestDeleteSnapshot { .. } => "RequestDeleteSnapshot",
+            Self::RequestStats => "RequestStats",
+            Self::RequestRecordedOutput => "RequestRecordedOutput",
+            Self::Ping { .. } => "Ping",
+            Self::Pong { .. } => "Pong",
+        }
+    }
+}
+
+impl ServerPacketPayload {
+    /// Returns `true` if the packet changes the state of the server, and so
+    /// can't be sent by read-only clients.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Self::RequestSetAttributeValues(_)
+            | Self::SetAttributeValuesFaded { .. }
+            | Self::RequestSetGroupAttributeValue { .. }
+            | Self::RequestSetRawDmx { .. }
+            | Self::RequestClearRawDmx { .. }
+            | Self::RequestSetGrandMaster { .. }
+            | Self::RequestSetUniverseMaster { .. }
+            | Self::RequestSetBlackout { .. }
+            | Self::RequestParkAddress { .. }
+            | Self::RequestUnparkAddress { .. }
+            | Self::RequestClearAttributeValues { .. }
+            | Self::FireTrigger(_)
+            | Self::RequestRenameFixture { .. }
+            | Self::RequestReloadProtocols(_)
+            | Self::RequestSaveSnapshot { .. }
+            | Self::RequestRestoreSnapshot { .. }
+            | Self::RequestDeleteSnapshot { .. } => true,
+            Self::Hello { .. }
+            | Self::RequestShowData
+            | Self::RequestDmxOutput
+            | Self::RequestDefaultOutput
+            | Self::RequestParkedAddresses
+            | Self::RequestFixturesWithAttribute { .. }
+            | Self::RequestAttributeValues { .. }
+            | Self::RequestListSnapshots
+            | Self::RequestStats
+            | Self::RequestRecordedOutput
+            | Self::Ping { .. }
+            | Self::Pong { .. } => false,
+        }
+    }
 }
 
 impl PacketPayload for ServerPacketPayload {}
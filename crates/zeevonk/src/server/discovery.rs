@@ -0,0 +1,96 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use tokio::net::UdpSocket;
+
+use super::ServerState;
+use crate::packet::{DISCOVERY_PROBE, ServerAnnouncement};
+
+/// Binds the socket that discovery probes are received on.
+///
+/// This is a different port than the sACN sockets use, so the two don't
+/// receive each other's packets.
+pub(super) async fn bind(port: u16) -> std::io::Result<UdpSocket> {
+    UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))).await
+}
+
+/// Answers every [DISCOVERY_PROBE] received on the socket with a
+/// [ServerAnnouncement] for the server.
+pub(super) async fn respond(
+    socket: UdpSocket,
+    name: String,
+    tcp_port: u16,
+    state: Arc<ServerState>,
+) {
+    let mut buf = [0; 64];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(err) => {
+                log::warn!("failed to receive discovery probe: {err}");
+                continue;
+            }
+        };
+
+        if &buf[..len] != DISCOVERY_PROBE {
+            continue;
+        }
+
+        let fixture_count = state
+            .show_data
+            .read()
+            .await
+            .patch()
+            .fixtures()
+            .keys()
+            .filter(|path| path.is_root_fixture())
+            .count() as u32;
+        let announcement = ServerAnnouncement {
+            name: name.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            tcp_port,
+            fixture_count,
+            ip: None,
+        };
+
+        let bytes = rmp_serde::to_vec(&announcement).expect("announcement should serialize");
+        if let Err(err) = socket.send_to(&bytes, peer).await {
+            log::warn!("failed to answer discovery probe from {peer}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::server::test_util::example_showfile;
+
+    #[tokio::test]
+    async fn answers_probes() {
+        let showfile = example_showfile();
+        let state = Arc::new(ServerState::new(&showfile).unwrap());
+
+        let socket = bind(0).await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let responder = tokio::spawn(respond(socket, "Test".to_string(), 1234, state));
+
+        let prober = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        prober.send_to(b"something else", ("127.0.0.1", port)).await.unwrap();
+        prober.send_to(DISCOVERY_PROBE, ("127.0.0.1", port)).await.unwrap();
+
+        let mut buf = [0; 256];
+        let len = tokio::time::timeout(Duration::from_secs(1), prober.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        let announcement = rmp_serde::from_slice::<ServerAnnouncement>(&buf[..len]).unwrap();
+        assert_eq!(announcement.name, "Test");
+        assert_eq!(announcement.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(announcement.tcp_port, 1234);
+        assert_eq!(announcement.fixture_count, showfile.patch().fixtures().len() as u32);
+
+        responder.abort();
+    }
+}
@@ -0,0 +1,315 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::attr::Attribute;
+use crate::packet::{AttributeValues, FadeCurve};
+use crate::server::ServerState;
+use crate::show::fixture::FixturePath;
+use crate::value::ClampedValue;
+
+/// A fade of a single attribute value from one value to another.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Fade {
+    from: ClampedValue,
+    to: ClampedValue,
+    start: Instant,
+    duration: Duration,
+    curve: FadeCurve,
+}
+
+impl Fade {
+    /// Returns the value of the fade at the given time.
+    fn value_at(&self, now: Instant) -> ClampedValue {
+        if self.is_finished(now) {
+            return self.to;
+        }
+        let t = (now - self.start).as_secs_f32() / self.duration.as_secs_f32();
+        self.from.lerp(&self.to, self.curve.apply(t))
+    }
+
+    fn is_finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
+
+impl ServerState {
+    /// Fades the attribute values from their current values to the given
+    /// values over the duration, following the curve.
+    ///
+    /// An attribute that is already fading starts from its current in-flight
+    /// value. Other clients are notified of the target values.
+    pub(super) async fn set_attribute_values_faded(
+        &self,
+        values: &AttributeValues,
+        duration: Duration,
+        curve: FadeCurve,
+        peer: SocketAddr,
+    ) {
+        let now = Instant::now();
+        {
+            let mut fades = self.fades.write().await;
+            for ((fixture_path, attribute), to) in values.values() {
+                let from = match fades.get(&(*fixture_path, *attribute)) {
+                    Some(fade) => fade.value_at(now),
+                    None => self.current_attribute_value(*fixture_path, *attribute).await,
+                };
+                let fade = Fade { from, to: *to, start: now, duration, curve };
+                fades.insert((*fixture_path, *attribute), fade);
+            }
+        }
+
+        if self.attribute_changes.receiver_count() > 0 {
+            // Sending only fails if all receivers were dropped in the meantime.
+            let _ = self.attribute_changes.send((peer, values.clone()));
+        }
+
+        self.tick_fades(now).await;
+    }
+
+    /// Stops fading the attribute values, keeping their current values.
+    pub(super) async fn cancel_fades(&self, values: &AttributeValues) {
+        let mut fades = self.fades.write().await;
+        for (key, _) in values.values() {
+            fades.remove(key);
+        }
+    }
+
+    /// Stops fading all attribute values of fixtures for which `f` returns
+    /// `true`.
+    pub(super) async fn cancel_fades_where(&self, f: impl Fn(&FixturePath) -> bool) {
+        self.fades.write().await.retain(|(fixture_path, _), _| !f(fixture_path));
+    }
+
+    /// Sets all fading attribute values to their value at the given time,
+    /// removes finished fades and resolves the output if anything changed.
+    pub(super) async fn tick_fades(&self, now: Instant) {
+        let mut fades = self.fades.write().await;
+        if fades.is_empty() {
+            return;
+        }
+
+        {
+            let mut pending_attribute_values = self.pending_attribute_values.write().await;
+            for ((fixture_path, attribute), fade) in fades.iter() {
+                pending_attribute_values.set(*fixture_path, *attribute, fade.value_at(now));
+            }
+        }
+        for (fixture_path, _) in fades.keys() {
+            self.mark_dirty(*fixture_path).await;
+        }
+        fades.retain(|_, fade| !fade.is_finished(now));
+        drop(fades);
+
+        self.resolve_dirty().await;
+    }
+
+    /// Returns the value of an attribute as it is currently used for the
+    /// output: the set value, or the default value of its channel function.
+    async fn current_attribute_value(
+        &self,
+        fixture_path: FixturePath,
+        attribute: Attribute,
+    ) -> ClampedValue {
+        if let Some(value) = self.pending_attribute_values.read().await.get(fixture_path, attribute)
+        {
+            return value;
+        }
+
+        let show_data = self.show_data.read().await;
+        show_data
+            .patch()
+            .fixtures()
+            .get(&fixture_path)
+            .and_then(|fixture| fixture.channel_function(&attribute))
+            .map(|channel_function| channel_function.relative_default())
+            .unwrap_or_default()
+    }
+}
+
+/// Updates the fading attribute values every frame.
+pub(super) async fn run(state: Arc<ServerState>, frame_time: Duration) {
+    let mut interval = tokio::time::interval(frame_time);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        state.tick_fades(Instant::now()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::{self, Address};
+    use crate::fpath;
+    use crate::server::test_util::example_state;
+    use crate::show::fixture::FixtureId;
+    use crate::showfile::{Group, GroupId};
+
+    fn dimmer(value: f32) -> AttributeValues {
+        let mut values = AttributeValues::new();
+        values.set(fpath![101, 1, 1], Attribute::Dimmer, value);
+        values
+    }
+
+    async fn dimmer_value(state: &ServerState) -> f32 {
+        let values = state.pending_attribute_values.read().await;
+        values.get(fpath![101, 1, 1], Attribute::Dimmer).unwrap().as_f32()
+    }
+
+    const PEER: SocketAddr =
+        SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+
+    #[test]
+    fn fade_value() {
+        let start = Instant::now();
+        let fade = Fade {
+            from: ClampedValue::new(0.2),
+            to: ClampedValue::new(1.0),
+            start,
+            duration: Duration::from_secs(2),
+            curve: FadeCurve::Linear,
+        };
+        assert_eq!(fade.value_at(start), ClampedValue::new(0.2));
+        assert_eq!(fade.value_at(start + Duration::from_secs(1)), ClampedValue::new(0.6));
+        assert_eq!(fade.value_at(start + Duration::from_secs(3)), ClampedValue::new(1.0));
+        assert!(fade.is_finished(start + Duration::from_secs(2)));
+    }
+
+    #[tokio::test]
+    async fn fade_updates_output() {
+        let state = example_state();
+        let address = "1.3".parse::<Address>().unwrap();
+        let start = Instant::now();
+
+        state
+            .set_attribute_values_faded(
+                &dimmer(1.0),
+                Duration::from_secs(10),
+                FadeCurve::Linear,
+                PEER,
+            )
+            .await;
+        assert!(dimmer_value(&state).await < 0.01);
+
+        state.tick_fades(start + Duration::from_secs(5)).await;
+        let value = state.output_multiverse.read().await.get_value(&address);
+        assert!((126..=128).contains(&value.0), "{value}");
+
+        state.tick_fades(start + Duration::from_secs(11)).await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&address), dmx::Value(255));
+        assert!(state.fades.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fade_along_curve_is_monotonic() {
+        let address = "1.3".parse::<Address>().unwrap();
+        for curve in [FadeCurve::Linear, FadeCurve::EaseIn, FadeCurve::EaseOut, FadeCurve::SCurve] {
+            let state = example_state();
+            let start = Instant::now();
+            state
+                .set_attribute_values_faded(&dimmer(1.0), Duration::from_secs(10), curve, PEER)
+                .await;
+
+            let mut values = Vec::new();
+            for second in 0..=11 {
+                state.tick_fades(start + Duration::from_secs(second)).await;
+                values.push(state.output_multiverse.read().await.get_value(&address).0);
+            }
+
+            assert!(values.is_sorted(), "{curve:?}: {values:?}");
+            assert_eq!(values.last(), Some(&255), "{curve:?}");
+            assert!(state.fades.read().await.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn ease_in_fade_lags_behind_linear() {
+        let state = example_state();
+        let start = Instant::now();
+        state
+            .set_attribute_values_faded(
+                &dimmer(1.0),
+                Duration::from_secs(10),
+                FadeCurve::EaseIn,
+                PEER,
+            )
+            .await;
+
+        state.tick_fades(start + Duration::from_secs(5)).await;
+        let value = dimmer_value(&state).await;
+        assert!((0.2..0.3).contains(&value), "{value}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn overlapping_fade_starts_from_current_value() {
+        let state = example_state();
+
+        state
+            .set_attribute_values_faded(
+                &dimmer(1.0),
+                Duration::from_millis(200),
+                FadeCurve::Linear,
+                PEER,
+            )
+            .await;
+        tokio::time::advance(Duration::from_millis(100)).await;
+        state.tick_fades(Instant::now()).await;
+        assert!((dimmer_value(&state).await - 0.5).abs() < 0.01);
+
+        state
+            .set_attribute_values_faded(
+                &dimmer(0.0),
+                Duration::from_secs(10),
+                FadeCurve::Linear,
+                PEER,
+            )
+            .await;
+
+        let value = dimmer_value(&state).await;
+        assert!((value - 0.5).abs() < 0.01, "{value}");
+    }
+
+    #[tokio::test]
+    async fn setting_a_value_cancels_its_fade() {
+        let state = example_state();
+
+        state
+            .set_attribute_values_faded(
+                &dimmer(1.0),
+                Duration::from_secs(10),
+                FadeCurve::Linear,
+                PEER,
+            )
+            .await;
+        state.set_attribute_values(&dimmer(0.5), PEER).await;
+        state.tick_fades(Instant::now() + Duration::from_secs(11)).await;
+
+        assert_eq!(dimmer_value(&state).await, 0.5);
+    }
+
+    #[tokio::test]
+    async fn setting_a_group_value_cancels_its_fade() {
+        let mut state = example_state();
+        let group = Group::new(GroupId(1), "Sharpy", vec![FixtureId::new(101).unwrap()]);
+        state.show_data.get_mut().patch.groups.insert(group.id(), group);
+
+        state
+            .set_attribute_values_faded(
+                &dimmer(1.0),
+                Duration::from_secs(10),
+                FadeCurve::Linear,
+                PEER,
+            )
+            .await;
+        state
+            .set_group_attribute_value(GroupId(1), Attribute::Dimmer, ClampedValue::new(0.5), PEER)
+            .await
+            .unwrap();
+        state.tick_fades(Instant::now() + Duration::from_secs(11)).await;
+
+        assert_eq!(dimmer_value(&state).await, 0.5);
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::attr::{Attribute, AttributeCategory};
+use crate::dmx::{self, Address, Multiverse, UniverseId};
+use crate::server::ServerState;
+use crate::show::fixture::{
+    Fixture, FixtureChannelFunction, FixtureChannelFunctionKind, FixturePath, RelationKind,
+};
+use crate::show::patch::Patch;
+use crate::value::ClampedValue;
+
+/// Master levels that scale the intensity of the output.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Masters {
+    grand_master: ClampedValue,
+    /// Universes without a master are at full.
+    universe_masters: HashMap<UniverseId, ClampedValue>,
+    /// Puts all intensity channels at zero, regardless of the masters.
+    blackout: bool,
+}
+
+impl Default for Masters {
+    fn default() -> Self {
+        Self {
+            grand_master: ClampedValue::new(1.0),
+            universe_masters: HashMap::new(),
+            blackout: false,
+        }
+    }
+}
+
+impl Masters {
+    pub fn set_grand_master(&mut self, level: ClampedValue) {
+        self.grand_master = level;
+    }
+
+    /// Sets the master of the universe. Setting it to full removes it.
+    pub fn set_universe_master(&mut self, universe: UniverseId, level: ClampedValue) {
+        if level.as_f32() >= 1.0 {
+            self.universe_masters.remove(&universe);
+        } else {
+            self.universe_masters.insert(universe, level);
+        }
+    }
+
+    pub fn set_blackout(&mut self, blackout: bool) {
+        self.blackout = blackout;
+    }
+
+    /// Returns the factor intensity channels in the universe are scaled by.
+    pub fn level(&self, universe: &UniverseId) -> f32 {
+        if self.blackout {
+            return 0.0;
+        }
+        let universe_master = self.universe_masters.get(universe).map_or(1.0, |l| l.as_f32());
+        self.grand_master.as_f32() * universe_master
+    }
+
+    /// Returns `true` if no master scales the output.
+    pub fn is_full(&self) -> bool {
+        !self.blackout && self.grand_master.as_f32() >= 1.0 && self.universe_masters.is_empty()
+    }
+
+    /// Scales the values of all intensity channels in the multiverse by the
+    /// master level of their universe.
+    ///
+    /// Intensity channels are the addresses of physical channel functions
+    /// with an [AttributeCategory::Intensity] attribute, and the addresses
+    /// that virtual intensity channel functions multiply, like the color
+    /// channels of an LED fixture with a virtual dimmer. Multi-byte channels
+    /// are scaled as a whole.
+    pub fn apply(&self, patch: &Patch, multiverse: &mut Multiverse) {
+        if self.is_full() {
+            return;
+        }
+
+        for addresses in intensity_addresses(patch, patch.fixtures().values()) {
+            let level = self.level(&addresses[0].universe);
+            scale_address_values(multiverse, &addresses, level);
+        }
+    }
+
+    /// Like [Masters::apply], but only scales the intensity channels of the
+    /// given fixtures and the followers of their virtual intensity channel
+    /// functions.
+    pub fn apply_to_fixtures<'a>(
+        &self,
+        patch: &Patch,
+        fixture_paths: impl IntoIterator<Item = &'a FixturePath>,
+        multiverse: &mut Multiverse,
+    ) {
+        if self.is_full() {
+            return;
+        }
+
+        let fixtures = fixture_paths.into_iter().filter_map(|path| patch.fixture(path));
+        for addresses in intensity_addresses(patch, fixtures) {
+            let level = self.level(&addresses[0].universe);
+            scale_address_values(multiverse, &addresses, level);
+        }
+    }
+}
+
+impl ServerState {
+    pub(super) async fn set_grand_master(&self, level: ClampedValue) {
+        self.masters.write().await.set_grand_master(level);
+    }
+
+    pub(super) async fn set_universe_master(&self, universe: UniverseId, level: ClampedValue) {
+        self.masters.write().await.set_universe_master(universe, level);
+    }
+
+    pub(super) async fn set_blackout(&self, blackout: bool) {
+        self.masters.write().await.set_blackout(blackout);
+    }
+}
+
+/// Returns the addresses of every intensity channel of the fixtures, and of
+/// the followers of their virtual intensity channel functions.
+fn intensity_addresses<'a>(
+    patch: &Patch,
+    fixtures: impl Iterator<Item = &'a Fixture>,
+) -> HashSet<Vec<Address>> {
+    let mut addresses = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut pending: Vec<_> = fixtures
+        .flat_map(|fixture| {
+            fixture
+                .channel_functions()
+                .filter(|(attribute, _)| attribute.category() == AttributeCategory::Intensity)
+                .map(|(attribute, _)| (fixture.path(), *attribute))
+        })
+        .collect();
+
+    while let Some(node) = pending.pop() {
+        if !visited.insert(node) {
+            continue;
+        }
+        let Some(channel_function) = channel_function(patch, node.0, &node.1) else {
+            continue;
+        };
+
+        match channel_function.kind() {
+            FixtureChannelFunctionKind::Physical { addresses: physical } => {
+                if !physical.is_empty() {
+                    addresses.insert(physical.clone());
+                }
+            }
+            FixtureChannelFunctionKind::Virtual { relations } => pending.extend(
+                relations
+                    .iter()
+                    .filter(|relation| matches!(relation.kind(), RelationKind::Multiply))
+                    .map(|relation| (relation.fixture_path(), relation.attribute())),
+            ),
+        }
+    }
+
+    addresses
+}
+
+fn channel_function<'a>(
+    patch: &'a Patch,
+    path: FixturePath,
+    attribute: &Attribute,
+) -> Option<&'a FixtureChannelFunction> {
+    patch.fixture(&path)?.channel_function(attribute)
+}
+
+/// Scales the value of a channel that spans the addresses, most significant
+/// byte first.
+fn scale_address_values(multiverse: &mut Multiverse, addresses: &[Address], factor: f32) {
+    if addresses.len() > 4 {
+        return;
+    }
+
+    let value = addresses
+        .iter()
+        .fold(0u64, |value, address| value << 8 | *multiverse.get_value(address) as u64);
+    let scaled = (value as f64 * factor as f64).round() as u64;
+    for (ix, address) in addresses.iter().rev().enumerate() {
+        multiverse.set_value(address, dmx::Value((scaled >> (8 * ix)) as u8));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fpath;
+    use crate::server::test_util::example_state;
+
+    /// Returns the fixture with the attribute on the first Sharpy or one of
+    /// its sub-fixtures, together with the addresses of the attribute.
+    async fn sharpy_channel(
+        state: &ServerState,
+        attribute: Attribute,
+    ) -> (FixturePath, Vec<Address>) {
+        let show_data = state.show_data.read().await;
+        show_data
+            .patch()
+            .fixtures()
+            .values()
+            .filter(|fixture| fixture.path().contains(&fpath![101]))
+            .find_map(|fixture| match fixture.channel_function(&attribute)?.kind() {
+                FixtureChannelFunctionKind::Physical { addresses } => {
+                    Some((fixture.path(), addresses.clone()))
+                }
+                FixtureChannelFunctionKind::Virtual { .. } => None,
+            })
+            .unwrap()
+    }
+
+    /// Sets the attribute of the first Sharpy to full, and returns its
+    /// addresses.
+    async fn set_full(state: &ServerState, attribute: Attribute) -> Vec<Address> {
+        let (path, addresses) = sharpy_channel(state, attribute).await;
+        state.set_attribute_value(path, attribute, ClampedValue::new(1.0)).await;
+        addresses
+    }
+
+    async fn output(state: &ServerState, addresses: &[Address]) -> Vec<dmx::Value> {
+        state.resolve_values().await;
+        let output = state.output_multiverse.read().await;
+        addresses.iter().map(|address| output.get_value(address)).collect()
+    }
+
+    #[tokio::test]
+    async fn grand_master_scales_only_intensity() {
+        let state = example_state();
+        let dimmer = set_full(&state, Attribute::Dimmer).await;
+        let pan = set_full(&state, Attribute::Pan).await;
+        let color = set_full(&state, Attribute::Color(1)).await;
+        let full_dimmer = output(&state, &dimmer).await;
+        let full_pan = output(&state, &pan).await;
+        let full_color = output(&state, &color).await;
+        assert_eq!(full_dimmer[0], dmx::Value(255));
+        assert!(full_pan.iter().any(|value| *value != dmx::Value(0)));
+
+        state.set_grand_master(ClampedValue::new(0.5)).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(128));
+        assert_eq!(output(&state, &pan).await, full_pan);
+        assert_eq!(output(&state, &color).await, full_color);
+
+        state.set_grand_master(ClampedValue::new(0.0)).await;
+        assert!(output(&state, &dimmer).await.iter().all(|value| *value == dmx::Value(0)));
+        assert_eq!(output(&state, &pan).await, full_pan);
+        assert_eq!(output(&state, &color).await, full_color);
+
+        // The programmed values are kept.
+        state.set_grand_master(ClampedValue::new(1.0)).await;
+        assert_eq!(output(&state, &dimmer).await, full_dimmer);
+    }
+
+    #[tokio::test]
+    async fn universe_masters_scale_their_universe() {
+        let state = example_state();
+        let dimmer = set_full(&state, Attribute::Dimmer).await;
+        let universe = dimmer[0].universe;
+
+        let other = UniverseId::new(u16::from(universe) + 1).unwrap();
+        state.set_universe_master(other, ClampedValue::new(0.0)).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(255));
+
+        state.set_universe_master(universe, ClampedValue::new(0.5)).await;
+        state.set_grand_master(ClampedValue::new(0.5)).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(64));
+
+        state.set_universe_master(universe, ClampedValue::new(1.0)).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(128));
+    }
+
+    #[tokio::test]
+    async fn blackout_keeps_programmed_values() {
+        let state = example_state();
+        let dimmer = set_full(&state, Attribute::Dimmer).await;
+        let pan = set_full(&state, Attribute::Pan).await;
+        let full_pan = output(&state, &pan).await;
+        state.set_grand_master(ClampedValue::new(0.5)).await;
+
+        state.set_blackout(true).await;
+        assert!(output(&state, &dimmer).await.iter().all(|value| *value == dmx::Value(0)));
+        assert_eq!(output(&state, &pan).await, full_pan);
+
+        // Setting the grand master during a blackout does not release it.
+        state.set_grand_master(ClampedValue::new(1.0)).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(0));
+
+        state.set_blackout(false).await;
+        assert_eq!(output(&state, &dimmer).await[0], dmx::Value(255));
+        assert_eq!(output(&state, &pan).await, full_pan);
+    }
+
+    #[test]
+    fn scales_multi_byte_values() {
+        let mut multiverse = Multiverse::new();
+        let addresses = [1, 2].map(|address| Address::from_absolute(address).unwrap());
+        multiverse.set_value(&addresses[0], dmx::Value(0xFF));
+        multiverse.set_value(&addresses[1], dmx::Value(0xFF));
+
+        scale_address_values(&mut multiverse, &addresses, 0.5);
+        assert_eq!(multiverse.get_value(&addresses[0]), dmx::Value(0x80));
+        assert_eq!(multiverse.get_value(&addresses[1]), dmx::Value(0x00));
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::Write as _;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::ServerState;
+use crate::packet::ServerStats;
+
+/// The longest request that is read, which is more than enough for the
+/// request line and headers of a scraper.
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+
+/// How long a client can take to send its request before the connection is
+/// closed, so clients that never finish their request don't hold a task.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the stats of the server as Prometheus metrics at `/metrics` to
+/// every client that connects to the listener.
+pub(super) async fn serve(listener: TcpListener, state: Arc<ServerState>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    if let Err(err) = respond(stream, &state).await {
+                        log::warn!("failed to serve metrics to {peer}: {err}");
+                    }
+                });
+            }
+            Err(err) => {
+                log::error!("metrics accept error: {err}");
+                break;
+            }
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, state: &ServerState) -> io::Result<()> {
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out reading the request"))??;
+
+    let request_line = request.split(|byte| *byte == b'\n').next().unwrap_or_default();
+    let mut parts = std::str::from_utf8(request_line).unwrap_or_default().split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = render(&state.stats.snapshot());
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads the request line and headers of a request, up to
+/// [MAX_REQUEST_LEN] bytes.
+async fn read_request(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let len = stream.read(&mut buf).await?;
+        if len == 0 || request.len() + len > MAX_REQUEST_LEN {
+            break;
+        }
+        request.extend_from_slice(&buf[..len]);
+    }
+    Ok(request)
+}
+
+/// Renders the stats in the Prometheus text exposition format.
+fn render(stats: &ServerStats) -> String {
+    let mut out = String::new();
+
+    // Writing to a string can't fail.
+    let _ = writeln!(out, "# TYPE zeevonk_packets_received_total counter");
+    for (packet_type, count) in &stats.packets_received {
+        let _ = writeln!(out, "zeevonk_packets_received_total{{type=\"{packet_type}\"}} {count}");
+    }
+
+    let _ = writeln!(out, "# TYPE zeevonk_bytes_sent_total counter");
+    let _ = writeln!(out, "zeevonk_bytes_sent_total {}", stats.bytes_sent);
+
+    let _ = writeln!(out, "# TYPE zeevonk_active_clients gauge");
+    let _ = writeln!(out, "zeevonk_active_clients {}", stats.active_clients);
+
+    let _ = writeln!(out, "# TYPE zeevonk_resolve_duration_seconds histogram");
+    let histogram = &stats.resolve_durations;
+    let mut cumulative = 0;
+    for (bound, count) in histogram.bounds.iter().zip(&histogram.counts) {
+        cumulative += count;
+        let le = bound.as_secs_f64();
+        let _ =
+            writeln!(out, "zeevonk_resolve_duration_seconds_bucket{{le=\"{le}\"}} {cumulative}");
+    }
+    let count = histogram.count();
+    let _ = writeln!(out, "zeevonk_resolve_duration_seconds_bucket{{le=\"+Inf\"}} {count}");
+    let _ = writeln!(out, "zeevonk_resolve_duration_seconds_sum {}", histogram.sum.as_secs_f64());
+    let _ = writeln!(out, "zeevonk_resolve_duration_seconds_count {count}");
+
+    let _ = writeln!(out, "# TYPE zeevonk_frames_sent_total counter");
+    for (universe, count) in &stats.frames_sent {
+        let _ = writeln!(out, "zeevonk_frames_sent_total{{universe=\"{universe}\"}} {count}");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::dmx::UniverseId;
+    use crate::server::test_util::example_state;
+
+    async fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn renders_cumulative_histogram_buckets() {
+        let state = example_state();
+        state.stats.packet_received("RequestStats");
+        state.stats.resolved(Duration::from_micros(40));
+        state.stats.resolved(Duration::from_millis(2));
+        state.stats.resolved(Duration::from_secs(1));
+        state.stats.frame_sent(UniverseId::new(2).unwrap());
+
+        let metrics = render(&state.stats.snapshot());
+        let lines = metrics.lines().collect::<Vec<_>>();
+        assert!(lines.contains(&"zeevonk_packets_received_total{type=\"RequestStats\"} 1"));
+        assert!(lines.contains(&"zeevonk_active_clients 0"));
+        assert!(lines.contains(&"zeevonk_resolve_duration_seconds_bucket{le=\"0.00005\"} 1"));
+        assert!(lines.contains(&"zeevonk_resolve_duration_seconds_bucket{le=\"0.0025\"} 2"));
+        assert!(lines.contains(&"zeevonk_resolve_duration_seconds_bucket{le=\"0.25\"} 2"));
+        assert!(lines.contains(&"zeevonk_resolve_duration_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(lines.contains(&"zeevonk_resolve_duration_seconds_count 3"));
+        assert!(lines.contains(&"zeevonk_frames_sent_total{universe=\"2\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, Arc::new(example_state())));
+
+        let response = get(address, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("\r\n\r\n# TYPE zeevonk_packets_received_total counter\n"));
+        assert!(response.contains("zeevonk_active_clients 0\n"));
+
+        let response = get(address, "/").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"), "{response}");
+
+        server.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closes_connections_without_a_request() {
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, Arc::new(example_state())));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n").await.unwrap();
+        let mut response = Vec::new();
+        let read = tokio::time::timeout(REQUEST_TIMEOUT * 2, stream.read_to_end(&mut response));
+        assert_eq!(read.await.unwrap().unwrap(), 0);
+
+        server.abort();
+    }
+}
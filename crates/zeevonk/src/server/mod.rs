@@ -1,42 +1,141 @@
 //! The Zeevonk server serves as a hub to connect multiple clients
 //! together and generating DMX output over various protocols.
 
-use std::net::SocketAddr;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
 
 use futures::{SinkExt as _, StreamExt};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{RwLock, RwLockReadGuard};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf};
+use tokio::net::TcpListener;
+use tokio::sync::{RwLock, RwLockReadGuard, broadcast, watch};
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
 use crate::Error;
-use crate::attr::Attribute;
-use crate::dmx::Multiverse;
+use crate::attr::{Attribute, color};
+use crate::dmx::{self, Address, Multiverse};
 use crate::packet::{
-    AttributeValues, ClientPacketPayload, Packet, PacketDecoder, PacketEncoder, ServerPacketPayload,
+    self, AttributeValues, ClientPacketPayload, MAX_MISSED_PONGS, PROTOCOL_VERSION, Packet,
+    PacketDecoder, PacketEncoder, SUPPORTED_PROTOCOL_VERSIONS, ServerPacketPayload, ServerStats,
 };
 use crate::show::ShowData;
-use crate::show::fixture::FixturePath;
-use crate::showfile::Showfile;
+use crate::show::fixture::{FixtureId, FixturePath, FixtureTypeInfo};
+use crate::showfile::{
+    self, GroupId, Identifier, Permissions, Protocols, ServerAddress, Showfile, ValidationIssue,
+};
 use crate::value::ClampedValue;
 
+mod discovery;
+mod fades;
+mod masters;
+mod metrics;
 mod protocols;
+mod reload;
 mod resolver;
 mod show_data_builder;
+mod snapshots;
+mod stats;
+#[cfg(test)]
+mod test_util;
+mod validation;
+
+pub(crate) use validation::validate_showfile;
 
 pub struct Server<'sf> {
     showfile: &'sf Showfile,
     state: Arc<ServerState>,
 
+    /// Overrides the address in the config of the showfile.
+    address_override: Option<SocketAddr>,
+    listener: Option<TcpListener>,
     bound_addr: Option<SocketAddr>,
+    /// The running output protocols, which are stopped when the server is dropped.
+    protocols: Option<protocols::agent::AgentHandle>,
+    /// The background tasks of the running server, which are aborted on
+    /// [Server::shutdown].
+    tasks: Vec<AbortHandle>,
+    /// Whether output is recorded in memory instead of sent over the network.
+    dummy_output: bool,
+    /// The path the showfile was loaded from, which is watched for changes
+    /// if hot reloading is enabled.
+    showfile_path: Option<PathBuf>,
 }
 
 impl<'sf> Server<'sf> {
     pub fn new(showfile: &'sf Showfile) -> Result<Self, Error> {
         let state = Arc::new(ServerState::new(showfile)?);
 
-        Ok(Self { showfile, state, bound_addr: None })
+        Ok(Self {
+            showfile,
+            state,
+            address_override: None,
+            listener: None,
+            bound_addr: None,
+            protocols: None,
+            tasks: Vec::new(),
+            dummy_output: false,
+            showfile_path: None,
+        })
+    }
+
+    /// Records the output frames in memory instead of sending them over the
+    /// configured protocols, e.g. to run the server in CI without a network.
+    /// The recorded frames can be inspected with [Server::recorded_frames],
+    /// or by clients with a `RequestRecordedOutput` packet.
+    ///
+    /// This has to be set before the server is started.
+    pub fn set_dummy_output(&mut self, dummy_output: bool) {
+        self.dummy_output = dummy_output;
+    }
+
+    /// Sets the path the showfile was loaded from. If hot reloading is
+    /// enabled in the config, the server reloads the showfile from this path
+    /// when it changes, and notifies clients with a `ShowDataChanged`.
+    ///
+    /// This has to be set before the server is started.
+    pub fn set_showfile_path(&mut self, path: impl Into<PathBuf>) {
+        self.showfile_path = Some(path.into());
+    }
+
+    /// Binds to the given address instead of the one in the config of the
+    /// showfile. Port `0` binds to a port assigned by the operating system,
+    /// which can be read with [Server::address] after binding.
+    ///
+    /// This has to be set before the server is bound.
+    pub fn set_address(&mut self, address: SocketAddr) {
+        self.address_override = Some(address);
+    }
+
+    /// Binds the listener for clients without accepting them yet, and
+    /// returns the bound address. [Server::start] binds the listener if this
+    /// was not called before.
+    pub async fn bind(&mut self) -> Result<SocketAddr, Error> {
+        log::debug!("binding listener...");
+        let address = match self.address_override {
+            Some(address) => address,
+            None => {
+                let config = self.showfile.config();
+                resolve_address(config.address(), config.prefer_ipv4()).await?
+            }
+        };
+        let listener = bind_listener(address)?;
+        let bound_addr = listener.local_addr()?;
+        self.listener = Some(listener);
+        self.bound_addr = Some(bound_addr);
+        log::debug!("listener bound to {bound_addr}");
+        Ok(bound_addr)
+    }
+
+    /// Returns the last frames sent to the dummy output, oldest first. See
+    /// [Server::set_dummy_output].
+    pub fn recorded_frames(&self) -> Vec<Multiverse> {
+        self.state.recorded_frames.to_vec()
     }
 
     pub async fn start(&mut self) -> Result<(), Error> {
@@ -44,22 +143,86 @@ impl<'sf> Server<'sf> {
 
         let state = Arc::clone(&self.state);
 
-        log::debug!("binding listener...");
-        let address = self.showfile.config().address();
-        let listener = TcpListener::bind(address).await?;
-        self.bound_addr = Some(listener.local_addr().unwrap());
-        log::debug!("listener bound");
+        if self.listener.is_none() {
+            self.bind().await?;
+        }
+        let listener = self.listener.take().expect("listener should be bound");
+
+        if self.showfile.config().persist_snapshots() {
+            match &self.showfile_path {
+                Some(path) => {
+                    let path = snapshots::Snapshots::path_for_showfile(path);
+                    log::debug!("loading snapshots from {}", path.display());
+                    state.load_snapshots(path).await?;
+                }
+                None => {
+                    log::warn!("snapshots are persisted, but the path of the showfile is unknown")
+                }
+            }
+        }
 
         log::debug!("starting protocol manager");
-        protocols::agent::start(self.showfile.protocols().clone(), Arc::clone(&state));
+        self.protocols = Some(protocols::agent::start(
+            self.showfile.protocols().clone(),
+            self.showfile.config().clone(),
+            Arc::clone(&state),
+            self.dummy_output,
+        )?);
         log::debug!("protocol manager started");
 
+        let frame_time =
+            Duration::from_secs_f32(1.0 / self.showfile.config().output_refresh_rate_hz());
+        self.tasks.push(tokio::spawn(fades::run(Arc::clone(&state), frame_time)).abort_handle());
+
+        if self.showfile.config().enable_discovery() {
+            let socket = discovery::bind(self.showfile.config().discovery_port()).await?;
+            log::debug!("answering discovery probes on {}", socket.local_addr()?);
+            let discovery = tokio::spawn(discovery::respond(
+                socket,
+                self.showfile.config().name().to_string(),
+                self.address().port(),
+                Arc::clone(&state),
+            ));
+            self.tasks.push(discovery.abort_handle());
+        }
+
+        match &self.showfile_path {
+            Some(path) if self.showfile.config().hot_reload() => {
+                log::debug!("watching {} for changes", path.display());
+                let reload = tokio::spawn(reload::watch(
+                    Arc::clone(&state),
+                    path.clone(),
+                    reload::POLL_INTERVAL,
+                ));
+                self.tasks.push(reload.abort_handle());
+            }
+            None if self.showfile.config().hot_reload() => {
+                log::warn!("hot reload is enabled, but the path of the showfile is unknown");
+            }
+            _ => {}
+        }
+
+        if let Some(port) = self.showfile.config().metrics_port() {
+            let listener = TcpListener::bind(SocketAddr::new(self.address().ip(), port)).await?;
+            log::info!("serving metrics on http://{}/metrics", listener.local_addr()?);
+            let metrics = tokio::spawn(metrics::serve(listener, Arc::clone(&state)));
+            self.tasks.push(metrics.abort_handle());
+        }
+
         log::info!("zeevonk server started!");
         log::debug!("now accepting streams");
         loop {
             match listener.accept().await {
                 Ok((stream, peer)) => {
-                    let handler = ClientHandler::new(stream, peer, Arc::clone(&state));
+                    let config = self.showfile.config();
+                    let handler = ClientHandler::new(
+                        stream,
+                        peer,
+                        Arc::clone(&state),
+                        config.ping_interval(),
+                        config.idle_timeout(),
+                        config.max_frame_size_bytes(),
+                    );
                     tokio::spawn(async move { handler.run().await });
                 }
                 Err(e) => {
@@ -69,29 +232,346 @@ impl<'sf> Server<'sf> {
             }
         }
 
+        self.shutdown();
+
         Ok(())
     }
 
+    /// Stops the background tasks and the output protocols of the server.
+    ///
+    /// The outputs send their final packets before they stop: a blackout if
+    /// `blackout_on_exit` is set in the config, and stream termination
+    /// packets for sACN, so receivers don't hold the last frame. Call this
+    /// after cancelling [Server::start], e.g. on Ctrl-C.
+    pub fn shutdown(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        if let Some(mut protocols) = self.protocols.take() {
+            log::debug!("stopping protocol manager");
+            protocols.stop();
+        }
+    }
+
     /// Returns the address the socket has been bound to.
     ///
     /// # Panics
     ///
-    /// Panics if the server has not been started yet.
+    /// Panics if the server has not been bound or started yet.
     pub fn address(&self) -> SocketAddr {
         self.bound_addr.expect("server should have been started before calling this")
     }
 
+    /// Serves a client connected over the given stream, e.g. one half of a
+    /// [tokio::io::duplex] pipe, without a network connection. The client is
+    /// served by a background task until the stream is closed.
+    ///
+    /// This does not require the server to be started, which makes it useful
+    /// for testing clients against a server.
+    pub fn handle_connection(&self, io: impl AsyncRead + AsyncWrite + Send + 'static) {
+        // In-memory clients don't have an address, but clients are told apart
+        // by their address, so give every client its own port.
+        static NEXT_PORT: AtomicU16 = AtomicU16::new(1);
+        let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+        let peer = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+
+        let config = self.showfile.config();
+        let handler = ClientHandler::new(
+            io,
+            peer,
+            Arc::clone(&self.state),
+            config.ping_interval(),
+            config.idle_timeout(),
+            config.max_frame_size_bytes(),
+        );
+        tokio::spawn(handler.run());
+    }
+
     pub fn show_data(&'_ self) -> RwLockReadGuard<'_, ShowData> {
         self.state.show_data.blocking_read()
     }
+
+    /// Resolves all attribute values and returns the resulting DMX output.
+    ///
+    /// Without any attribute values set, this is the output with every
+    /// channel function at its default value.
+    pub async fn dmx_output(&self) -> Multiverse {
+        self.state.resolve_values().await;
+        self.state.output_multiverse.read().await.clone()
+    }
+
+    /// Resolves all attribute values into `target`, like [Server::dmx_output]
+    /// but without cloning. Universes already in `target` are overwritten in
+    /// place, so polling into the same multiverse does not allocate.
+    pub async fn resolve_into(&self, target: &mut Multiverse) {
+        self.state.resolve_into(target).await;
+    }
+
+    /// Unpatches the root fixture with the given id, together with all of
+    /// its sub-fixtures and any attribute values set for them.
+    pub async fn remove_fixture(&self, root_id: FixtureId) -> Result<(), Error> {
+        self.state.remove_fixture(root_id).await
+    }
+
+    /// Renames the root fixture with the given id. Labels don't have to be
+    /// unique, see [Patch::fixture_by_label](crate::show::patch::Patch::fixture_by_label).
+    pub async fn rename_fixture(&self, root_id: FixtureId, label: String) -> Result<(), Error> {
+        self.state.rename_fixture(root_id, label).await
+    }
+
+    /// Replaces the output protocols while the server runs, without
+    /// interrupting sACN outputs whose configuration is unchanged. Returns the
+    /// issues that prevented the protocols from being reloaded.
+    ///
+    /// The protocols are applied by the protocols thread before the next
+    /// frame. They are ignored if the server uses the dummy output.
+    pub async fn reload_protocols(&self, protocols: Protocols) -> Vec<ValidationIssue> {
+        self.state.reload_protocols(protocols).await
+    }
+
+    /// Moves the root fixture with the given id to a new base address,
+    /// keeping all attribute values set for it.
+    pub async fn set_fixture_address(
+        &self,
+        root_id: FixtureId,
+        address: Address,
+    ) -> Result<(), Error> {
+        self.state.set_fixture_address(root_id, address).await
+    }
+
+    /// Forces DMX channels to a value, on top of the resolved attribute
+    /// values, until the overrides are cleared.
+    pub async fn set_raw_dmx(&self, values: Vec<(Address, dmx::Value)>) {
+        self.state.set_raw_dmx(values).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Clears the raw DMX overrides for the given addresses, or all
+    /// overrides if `addresses` is `None`.
+    pub async fn clear_raw_dmx(&self, addresses: Option<Vec<Address>>) {
+        self.state.clear_raw_dmx(addresses).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Sets the grand master, which scales the intensity of all fixtures.
+    pub async fn set_grand_master(&self, level: ClampedValue) {
+        self.state.set_grand_master(level).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Sets the master of a universe, which scales the intensity of the
+    /// fixtures in it on top of the grand master.
+    pub async fn set_universe_master(&self, universe: dmx::UniverseId, level: ClampedValue) {
+        self.state.set_universe_master(universe, level).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Puts the intensity of all fixtures at zero while `blackout` is set,
+    /// without changing their attribute values or the masters.
+    pub async fn set_blackout(&self, blackout: bool) {
+        self.state.set_blackout(blackout).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Parks the DMX channel at the given value. Parked channels keep their
+    /// value, regardless of attribute values, raw DMX overrides and patch
+    /// changes, until they are unparked.
+    pub async fn park_address(&self, address: Address, value: dmx::Value) {
+        self.state.park_address(address, value).await;
+        self.state.resolve_values().await;
+    }
+
+    /// Unparks the DMX channel, returning `false` if it was not parked.
+    pub async fn unpark_address(&self, address: &Address) -> bool {
+        let was_parked = self.state.unpark_address(address).await;
+        self.state.resolve_values().await;
+        was_parked
+    }
+
+    /// Returns all parked DMX channels and their values, sorted by address.
+    pub async fn parked(&self) -> Vec<(Address, dmx::Value)> {
+        self.state.parked().await
+    }
+
+    /// Sets the color of a fixture, using the color mixing attributes the
+    /// fixture has. See [color::fixture_color_values].
+    pub async fn set_fixture_color(
+        &self,
+        fixture_path: FixturePath,
+        r: dmx::Value,
+        g: dmx::Value,
+        b: dmx::Value,
+    ) -> Result<(), Error> {
+        self.state.set_fixture_color(fixture_path, r, g, b).await
+    }
+
+    /// Returns statistics about the server, for monitoring.
+    pub fn stats(&self) -> ServerStats {
+        self.state.stats.snapshot()
+    }
 }
 
+/// Resolves the host of the address to the socket address the server binds
+/// to. If `prefer_ipv4` is set, the first IPv4 address is taken if there is
+/// one, otherwise the first address.
+pub async fn resolve_address(
+    address: &ServerAddress,
+    prefer_ipv4: bool,
+) -> Result<SocketAddr, Error> {
+    if let Some(ip) = address.ip() {
+        return Ok(SocketAddr::new(ip, address.port()));
+    }
+
+    let host = address.host();
+    let addresses = tokio::net::lookup_host((host, address.port()))
+        .await
+        .map_err(|err| Error::server(format!("failed to resolve hostname '{host}': {err}")))?
+        .collect::<Vec<_>>();
+    let preferred = prefer_ipv4.then(|| addresses.iter().find(|a| a.is_ipv4())).flatten();
+    preferred
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| Error::server(format!("hostname '{host}' did not resolve to any address")))
+}
+
+/// Binds a listener to the address. A listener bound to the unspecified IPv6
+/// address `::` also accepts IPv4 clients, regardless of the system default.
+fn bind_listener(address: SocketAddr) -> Result<TcpListener, Error> {
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    if let SocketAddr::V6(v6) = address
+        && v6.ip().is_unspecified()
+    {
+        socket.set_only_v6(false)?;
+    }
+    // Like `TcpListener::bind`, allow restarting the server right away.
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+    Ok(TcpListener::from_std(socket.into())?)
+}
+
+/// Returns information about all fixture types in the GDTF files of the
+/// showfile, sorted by name.
+pub fn fixture_types(showfile: &Showfile) -> Result<Vec<FixtureTypeInfo>, Error> {
+    Ok(GdtfFixtureTypes::load(showfile)?.infos())
+}
+
+/// Returns the number of DMX channels occupied by a fixture of the given
+/// fixture type in the given DMX mode.
+pub fn dmx_mode_channel_count(
+    showfile: &Showfile,
+    fixture_type_id: uuid::Uuid,
+    dmx_mode: &str,
+) -> Result<u32, Error> {
+    GdtfFixtureTypes::load(showfile)?.dmx_mode_channel_count(fixture_type_id, dmx_mode)
+}
+
+/// The fixture types in the GDTF files of a showfile.
+///
+/// Reading GDTF files is slow, so tools that build show data for several
+/// versions of a patch should load them once and reuse them.
+pub struct GdtfFixtureTypes {
+    fixture_types: HashMap<uuid::Uuid, gdtf::fixture_type::FixtureType>,
+}
+
+impl GdtfFixtureTypes {
+    /// Reads the fixture types from the GDTF files of the showfile.
+    pub fn load(showfile: &Showfile) -> Result<Self, Error> {
+        Ok(Self { fixture_types: show_data_builder::load_fixture_types(showfile)? })
+    }
+
+    /// Returns information about all fixture types, sorted by name.
+    pub fn infos(&self) -> Vec<FixtureTypeInfo> {
+        let mut infos = self
+            .fixture_types
+            .values()
+            .map(show_data_builder::fixture_type_info)
+            .collect::<Vec<_>>();
+
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Returns the number of DMX channels occupied by a fixture of the given
+    /// fixture type in the given DMX mode.
+    pub fn dmx_mode_channel_count(
+        &self,
+        fixture_type_id: uuid::Uuid,
+        dmx_mode: &str,
+    ) -> Result<u32, Error> {
+        // Patch a fixture at the first address, so its last address is its
+        // channel count.
+        let fixture = showfile::Fixture::new(
+            FixtureId::new(1)?,
+            "",
+            Address::from_absolute(1)?,
+            showfile::FixtureKind::new(fixture_type_id, dmx_mode),
+        );
+        let (fixtures, _) = show_data_builder::build_fixture(&fixture, &self.fixture_types)?;
+
+        Ok(fixtures
+            .iter()
+            .flat_map(|fixture| fixture.physical_addresses())
+            .map(Address::to_absolute)
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Builds the show data of the showfile, like the server does when it
+    /// starts, using these fixture types instead of reading its GDTF files.
+    pub fn build_show_data(&self, showfile: &Showfile) -> Result<ShowData, Error> {
+        show_data_builder::build_with_fixture_types(showfile, &self.fixture_types)
+    }
+}
+
+/// Capacity of the channel used to notify client handlers of attribute changes.
+const ATTRIBUTE_CHANGES_CAPACITY: usize = 64;
+
+/// Capacity of the channel used to notify client handlers of show data changes.
+const SHOW_DATA_CHANGES_CAPACITY: usize = 8;
+
 #[derive(Debug)]
 struct ServerState {
     show_data: RwLock<ShowData>,
 
     pending_attribute_values: RwLock<AttributeValues>,
+    /// Fixtures whose attribute values changed since the last resolve.
+    dirty_fixtures: RwLock<resolver::DirtyFixtures>,
     output_multiverse: RwLock<Multiverse>,
+    /// DMX values that are applied on top of the resolved output until cleared.
+    raw_dmx_overrides: RwLock<HashMap<Address, dmx::Value>>,
+    /// Master levels that scale the intensity of the resolved output.
+    masters: RwLock<masters::Masters>,
+    /// DMX values that are applied on top of everything else until unparked.
+    parked_addresses: RwLock<HashMap<Address, dmx::Value>>,
+    /// Attribute values that are fading to a new value.
+    fades: RwLock<HashMap<(FixturePath, Attribute), fades::Fade>>,
+    /// Saved attribute values and raw DMX overrides, by label.
+    snapshots: RwLock<snapshots::Snapshots>,
+
+    /// The attribute values applied by each trigger.
+    triggers: HashMap<Identifier, AttributeValues>,
+
+    /// Whether channels without an attribute value output the default value
+    /// of their channel function, instead of zero.
+    output_defaults_when_idle: bool,
+
+    /// Which clients are allowed to change the output.
+    permissions: Permissions,
+
+    /// Attribute values set by a client, together with the address of that client.
+    attribute_changes: broadcast::Sender<(SocketAddr, AttributeValues)>,
+    /// Notifies client handlers that the show data was replaced.
+    show_data_changes: broadcast::Sender<()>,
+    /// The output protocols, which the protocols thread reloads when they change.
+    protocols: watch::Sender<Protocols>,
+
+    stats: stats::StatsCounters,
+
+    /// The last frames sent to the dummy output, if it is used.
+    recorded_frames: protocols::dummy::RecordedFrames,
 }
 
 impl ServerState {
@@ -102,7 +582,31 @@ impl ServerState {
             show_data: RwLock::new(show_data),
 
             pending_attribute_values: RwLock::new(AttributeValues::new()),
+            dirty_fixtures: RwLock::new(resolver::DirtyFixtures::default()),
             output_multiverse: RwLock::new(Multiverse::new()),
+            raw_dmx_overrides: RwLock::new(HashMap::new()),
+            masters: RwLock::new(masters::Masters::default()),
+            parked_addresses: RwLock::new(HashMap::new()),
+            fades: RwLock::new(HashMap::new()),
+            snapshots: RwLock::new(snapshots::Snapshots::default()),
+
+            triggers: showfile
+                .triggers()
+                .iter()
+                .map(|trigger| (trigger.id().clone(), trigger.attribute_values()))
+                .collect(),
+
+            output_defaults_when_idle: showfile.config().output_defaults_when_idle(),
+
+            permissions: showfile.config().permissions().clone(),
+
+            attribute_changes: broadcast::channel(ATTRIBUTE_CHANGES_CAPACITY).0,
+            show_data_changes: broadcast::channel(SHOW_DATA_CHANGES_CAPACITY).0,
+            protocols: watch::Sender::new(showfile.protocols().clone()),
+
+            stats: stats::StatsCounters::default(),
+
+            recorded_frames: protocols::dummy::RecordedFrames::default(),
         })
     }
 
@@ -110,11 +614,19 @@ impl ServerState {
         &self,
         packet: Packet<ServerPacketPayload>,
         peer: SocketAddr,
-        writer: &mut FramedWrite<OwnedWriteHalf, PacketEncoder<ClientPacketPayload>>,
+        read_only: bool,
+        writer: &mut FramedWrite<impl AsyncWrite + Unpin, PacketEncoder<ClientPacketPayload>>,
     ) {
         log::trace!("processing packet from {}", peer);
+        self.stats.packet_received(packet.payload.name());
 
         let response = match packet.payload {
+            payload if read_only && payload.is_mutating() => {
+                log::warn!("read-only client {} sent {}, denying", peer, payload.name());
+                Some(ClientPacketPayload::PermissionDenied {
+                    reason: format!("client is read-only and can't send {}", payload.name()),
+                })
+            }
             ServerPacketPayload::RequestShowData => {
                 let show_data = self.show_data.read().await.clone();
                 Some(ClientPacketPayload::ResponseShowData(show_data))
@@ -124,24 +636,414 @@ impl ServerState {
                 let multiverse = self.output_multiverse.read().await.clone();
                 Some(ClientPacketPayload::ResponseDmxOutput(multiverse))
             }
+            ServerPacketPayload::RequestDefaultOutput => {
+                Some(ClientPacketPayload::ResponseDefaultOutput(self.default_output().await))
+            }
             ServerPacketPayload::RequestSetAttributeValues(values) => {
-                for ((fixture_path, attribute), value) in values.values() {
-                    self.set_attribute_value(*fixture_path, *attribute, *value).await;
+                self.set_attribute_values(&values, peer).await;
+                Some(ClientPacketPayload::ResponseSetAttributeValues)
+            }
+            ServerPacketPayload::SetAttributeValuesFaded { values, duration_ms, curve } => {
+                let duration = Duration::from_millis(duration_ms.into());
+                self.set_attribute_values_faded(&values, duration, curve, peer).await;
+                Some(ClientPacketPayload::ResponseSetAttributeValuesFaded)
+            }
+            ServerPacketPayload::RequestSetGroupAttributeValue { group, attribute, value } => {
+                let applied = match self
+                    .set_group_attribute_value(group, attribute, value, peer)
+                    .await
+                {
+                    Ok(()) => true,
+                    Err(err) => {
+                        log::warn!("client {} failed to set group attribute value: {}", peer, err);
+                        false
+                    }
+                };
+                Some(ClientPacketPayload::ResponseSetGroupAttributeValue { applied })
+            }
+            ServerPacketPayload::RequestSetRawDmx { values } => {
+                self.set_raw_dmx(values).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseSetRawDmx)
+            }
+            ServerPacketPayload::RequestClearRawDmx { addresses } => {
+                self.clear_raw_dmx(addresses).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseClearRawDmx)
+            }
+            ServerPacketPayload::RequestSetGrandMaster { level } => {
+                self.set_grand_master(level).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseSetGrandMaster)
+            }
+            ServerPacketPayload::RequestSetUniverseMaster { universe, level } => {
+                self.set_universe_master(universe, level).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseSetUniverseMaster)
+            }
+            ServerPacketPayload::RequestSetBlackout { blackout } => {
+                self.set_blackout(blackout).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseSetBlackout)
+            }
+            ServerPacketPayload::RequestParkAddress { address, value } => {
+                self.park_address(address, value).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseParkAddress)
+            }
+            ServerPacketPayload::RequestUnparkAddress { address } => {
+                let was_parked = self.unpark_address(&address).await;
+                self.resolve_values().await;
+                Some(ClientPacketPayload::ResponseUnparkAddress { was_parked })
+            }
+            ServerPacketPayload::RequestParkedAddresses => {
+                Some(ClientPacketPayload::ResponseParkedAddresses { parked: self.parked().await })
+            }
+            ServerPacketPayload::RequestClearAttributeValues { fixture_path } => {
+                match fixture_path {
+                    Some(fixture_path) => self.clear_attribute_values(fixture_path).await,
+                    None => self.clear_all_attribute_values().await,
                 }
                 self.resolve_values().await;
-                Some(ClientPacketPayload::ResponseSetAttributeValues)
+                Some(ClientPacketPayload::ResponseClearAttributeValues)
+            }
+            ServerPacketPayload::FireTrigger(id) => {
+                let applied = self.fire_trigger(&id, peer).await;
+                if !applied {
+                    log::warn!("client {} fired unknown trigger '{}'", peer, id);
+                }
+                Some(ClientPacketPayload::ResponseFireTrigger { applied })
             }
+            ServerPacketPayload::RequestRenameFixture { root_id, label } => {
+                let renamed = match self.rename_fixture(root_id, label).await {
+                    Ok(()) => true,
+                    Err(err) => {
+                        log::warn!("client {} failed to rename fixture: {}", peer, err);
+                        false
+                    }
+                };
+                Some(ClientPacketPayload::ResponseRenameFixture { renamed })
+            }
+            ServerPacketPayload::RequestReloadProtocols(protocols) => {
+                let issues = self.reload_protocols(protocols).await;
+                for issue in &issues {
+                    log::warn!("client {} sent invalid protocols: {}", peer, issue);
+                }
+                Some(ClientPacketPayload::ResponseReloadProtocols { issues })
+            }
+            ServerPacketPayload::RequestFixturesWithAttribute { attribute } => {
+                let show_data = self.show_data.read().await;
+                let fixture_paths = show_data
+                    .patch()
+                    .fixtures_with_attribute(&attribute)
+                    .map(|fixture| fixture.path())
+                    .collect();
+                Some(ClientPacketPayload::ResponseFixturesWithAttribute { fixture_paths })
+            }
+            ServerPacketPayload::RequestAttributeValues { filter } => Some(
+                ClientPacketPayload::ResponseAttributeValues(self.attribute_values(filter).await),
+            ),
+            ServerPacketPayload::RequestSaveSnapshot { label } => {
+                let overwritten = self.save_snapshot(label.clone()).await;
+                if overwritten {
+                    log::info!("client {} overwrote snapshot '{}'", peer, label);
+                }
+                Some(ClientPacketPayload::ResponseSaveSnapshot { overwritten })
+            }
+            ServerPacketPayload::RequestRestoreSnapshot { label } => {
+                let restored = self.restore_snapshot(&label, peer).await;
+                if !restored {
+                    log::warn!("client {} restored unknown snapshot '{}'", peer, label);
+                }
+                Some(ClientPacketPayload::ResponseRestoreSnapshot { restored })
+            }
+            ServerPacketPayload::RequestListSnapshots => {
+                Some(ClientPacketPayload::ResponseListSnapshots {
+                    snapshots: self.snapshot_infos().await,
+                })
+            }
+            ServerPacketPayload::RequestDeleteSnapshot { label } => {
+                let deleted = self.delete_snapshot(&label).await;
+                Some(ClientPacketPayload::ResponseDeleteSnapshot { deleted })
+            }
+            ServerPacketPayload::RequestStats => {
+                Some(ClientPacketPayload::ResponseStats(self.stats.snapshot()))
+            }
+            ServerPacketPayload::RequestRecordedOutput => {
+                Some(ClientPacketPayload::ResponseRecordedOutput {
+                    frame: self.recorded_frames.last(),
+                })
+            }
+            ServerPacketPayload::Ping { nonce } => Some(ClientPacketPayload::Pong { nonce }),
+            ServerPacketPayload::Pong { .. } => None,
+            // Handled by the `ClientHandler`.
+            ServerPacketPayload::Hello { .. } => None,
         };
 
         // If we have a response, send it back to the client.
-        if let Some(payload) = response {
-            let packet = Packet::new(payload);
-            if let Err(e) = writer.send(packet).await {
-                log::error!("failed to send response to {}: {}", peer, e);
+        if let Some(payload) = response
+            && let Err(e) = self.send(writer, payload).await
+        {
+            log::error!("failed to send response to {}: {}", peer, e);
+        }
+    }
+
+    /// Sends a packet to a client, counting the bytes sent.
+    async fn send(
+        &self,
+        writer: &mut FramedWrite<impl AsyncWrite + Unpin, PacketEncoder<ClientPacketPayload>>,
+        payload: ClientPacketPayload,
+    ) -> Result<(), packet::Error> {
+        let buffered = writer.write_buffer().len();
+        writer.feed(Packet::new(payload)).await?;
+        self.stats.bytes_sent(writer.write_buffer().len().saturating_sub(buffered));
+        writer.flush().await
+    }
+
+    /// Returns the output with every fixture at its default values.
+    async fn default_output(&self) -> Multiverse {
+        self.show_data.read().await.patch().default_multiverse().clone()
+    }
+
+    /// Sets the attribute values, notifies other clients and resolves the output.
+    async fn set_attribute_values(&self, values: &AttributeValues, peer: SocketAddr) {
+        self.cancel_fades(values).await;
+        for ((fixture_path, attribute), value) in values.values() {
+            self.set_attribute_value(*fixture_path, *attribute, *value).await;
+        }
+        self.notify_attribute_changes(values, peer).await;
+        self.resolve_dirty().await;
+    }
+
+    /// Applies the attribute values of the trigger with the given identifier.
+    ///
+    /// Returns `false` if no such trigger exists.
+    async fn fire_trigger(&self, id: &Identifier, peer: SocketAddr) -> bool {
+        let Some(values) = self.triggers.get(id) else { return false };
+        self.set_attribute_values(values, peer).await;
+        true
+    }
+
+    async fn remove_fixture(&self, root_id: FixtureId) -> Result<(), Error> {
+        let removed_paths = self.show_data.write().await.patch.remove_fixture(root_id)?;
+        self.mark_all_dirty().await;
+
+        let mut pending_attribute_values = self.pending_attribute_values.write().await;
+        for path in removed_paths {
+            pending_attribute_values.remove_fixture(path);
+        }
+        drop(pending_attribute_values);
+
+        self.resolve_values().await;
+        Ok(())
+    }
+
+    /// Renames the root fixture and notifies all clients that the show data
+    /// changed.
+    async fn rename_fixture(&self, root_id: FixtureId, label: String) -> Result<(), Error> {
+        self.show_data.write().await.patch.rename_fixture(root_id, label)?;
+        // Sending only fails if no clients are connected.
+        let _ = self.show_data_changes.send(());
+        Ok(())
+    }
+
+    /// Validates the protocols and, if they are valid, hands them to the
+    /// protocols thread, which applies them between frames. Returns the
+    /// issues that prevented the protocols from being reloaded.
+    async fn reload_protocols(&self, protocols: Protocols) -> Vec<ValidationIssue> {
+        let show_data = self.show_data.read().await;
+        let patched_universes = show_data
+            .patch()
+            .fixtures()
+            .values()
+            .flat_map(|fixture| fixture.physical_addresses())
+            .map(|address| address.universe)
+            .collect::<BTreeSet<_>>();
+        drop(show_data);
+
+        let mut issues = validation::protocol_issues(&protocols, Some(&patched_universes));
+        if issues.is_empty() {
+            issues = validation::interface_binding_issues(&protocols);
+        }
+        if issues.is_empty() {
+            self.protocols.send_replace(protocols);
+        }
+        issues
+    }
+
+    async fn set_fixture_address(&self, root_id: FixtureId, address: Address) -> Result<(), Error> {
+        self.show_data.write().await.patch.set_fixture_address(root_id, address)?;
+        self.mark_all_dirty().await;
+        self.resolve_values().await;
+        Ok(())
+    }
+
+    async fn set_fixture_color(
+        &self,
+        fixture_path: FixturePath,
+        r: dmx::Value,
+        g: dmx::Value,
+        b: dmx::Value,
+    ) -> Result<(), Error> {
+        let values = {
+            let show_data = self.show_data.read().await;
+            let fixture = show_data
+                .patch()
+                .fixtures()
+                .get(&fixture_path)
+                .ok_or(Error::FixtureNotFound(fixture_path))?;
+            color::fixture_color_values(fixture, r, g, b)
+                .ok_or(Error::NoColorMixing(fixture_path))?
+        };
+
+        for (attribute, value) in values {
+            self.set_attribute_value(fixture_path, attribute, value.into()).await;
+        }
+        self.resolve_dirty().await;
+        Ok(())
+    }
+
+    async fn set_raw_dmx(&self, values: Vec<(Address, dmx::Value)>) {
+        self.raw_dmx_overrides.write().await.extend(values);
+    }
+
+    /// Clears the raw DMX overrides for the given addresses, or all
+    /// overrides if `addresses` is `None`.
+    async fn clear_raw_dmx(&self, addresses: Option<Vec<Address>>) {
+        let mut raw_dmx_overrides = self.raw_dmx_overrides.write().await;
+        match addresses {
+            Some(addresses) => {
+                for address in addresses {
+                    raw_dmx_overrides.remove(&address);
+                }
             }
+            None => raw_dmx_overrides.clear(),
         }
     }
 
+    /// Parks the DMX channel at the given value.
+    async fn park_address(&self, address: Address, value: dmx::Value) {
+        self.parked_addresses.write().await.insert(address, value);
+    }
+
+    /// Unparks the DMX channel, returning `false` if it was not parked.
+    async fn unpark_address(&self, address: &Address) -> bool {
+        self.parked_addresses.write().await.remove(address).is_some()
+    }
+
+    /// Returns all parked DMX channels and their values, sorted by address.
+    async fn parked(&self) -> Vec<(Address, dmx::Value)> {
+        let mut parked = self
+            .parked_addresses
+            .read()
+            .await
+            .iter()
+            .map(|(address, value)| (*address, *value))
+            .collect::<Vec<_>>();
+        parked.sort_by_key(|(address, _)| *address);
+        parked
+    }
+
+    /// Returns the current values of the given attributes, or of every
+    /// attribute of every fixture if `filter` is `None`.
+    ///
+    /// These are the values the output is resolved from, including the
+    /// current step of running fades. Attributes that were never set have
+    /// the default value of their channel function, and attributes that
+    /// don't exist in the patch are left out.
+    async fn attribute_values(
+        &self,
+        filter: Option<Vec<(FixturePath, Attribute)>>,
+    ) -> AttributeValues {
+        let show_data = self.show_data.read().await;
+        let patch = show_data.patch();
+        let keys = match filter {
+            Some(filter) => filter,
+            None => patch
+                .fixtures()
+                .values()
+                .flat_map(|fixture| {
+                    fixture.channel_functions().map(|(attribute, _)| (fixture.path(), *attribute))
+                })
+                .collect(),
+        };
+
+        let pending_attribute_values = self.pending_attribute_values.read().await;
+        keys.into_iter()
+            .filter_map(|(fixture_path, attribute)| {
+                let channel_function =
+                    patch.fixture(&fixture_path)?.channel_function(&attribute)?;
+                let value = pending_attribute_values
+                    .get(fixture_path, attribute)
+                    .unwrap_or_else(|| channel_function.relative_default());
+                Some(((fixture_path, attribute), value))
+            })
+            .collect()
+    }
+
+    /// Removes the attribute values of the fixture and all its sub-fixtures,
+    /// so they return to their defaults when resolved.
+    async fn clear_attribute_values(&self, fixture_path: FixturePath) {
+        self.cancel_fades_where(|path| path.contains(&fixture_path)).await;
+        let mut pending_attribute_values = self.pending_attribute_values.write().await;
+        let paths = pending_attribute_values
+            .values()
+            .map(|((path, _), _)| *path)
+            .filter(|path| path.contains(&fixture_path))
+            .collect::<HashSet<_>>();
+        for path in &paths {
+            pending_attribute_values.remove_fixture(*path);
+        }
+        drop(pending_attribute_values);
+
+        for path in paths {
+            self.mark_dirty(path).await;
+        }
+    }
+
+    /// Removes the attribute values of all fixtures, so they return to their
+    /// defaults when resolved.
+    async fn clear_all_attribute_values(&self) {
+        self.cancel_fades_where(|_| true).await;
+        self.pending_attribute_values.write().await.clear();
+        self.mark_all_dirty().await;
+    }
+
+    /// Sets the value of an attribute on every fixture in the group that
+    /// has that attribute, like [Self::set_attribute_values].
+    async fn set_group_attribute_value(
+        &self,
+        group_id: GroupId,
+        attribute: Attribute,
+        value: ClampedValue,
+        peer: SocketAddr,
+    ) -> Result<(), Error> {
+        let paths = self.show_data.read().await.patch().group_fixture_paths(group_id, attribute)?;
+        let values = paths.into_iter().map(|path| ((path, attribute), value)).collect();
+        self.set_attribute_values(&values, peer).await;
+        Ok(())
+    }
+
+    /// Notifies all other clients of the current values of the given attributes.
+    async fn notify_attribute_changes(&self, values: &AttributeValues, peer: SocketAddr) {
+        // Nobody to notify.
+        if self.attribute_changes.receiver_count() == 0 {
+            return;
+        }
+
+        let pending_attribute_values = self.pending_attribute_values.read().await;
+        let mut changes = AttributeValues::new();
+        for ((fixture_path, attribute), _) in values.values() {
+            if let Some(value) = pending_attribute_values.get(*fixture_path, *attribute) {
+                changes.set(*fixture_path, *attribute, value);
+            }
+        }
+        drop(pending_attribute_values);
+
+        // Sending only fails if all receivers were dropped in the meantime.
+        let _ = self.attribute_changes.send((peer, changes));
+    }
+
     async fn set_attribute_value(
         &self,
         fixture_path: FixturePath,
@@ -149,43 +1051,1066 @@ impl ServerState {
         value: ClampedValue,
     ) {
         self.pending_attribute_values.write().await.set(fixture_path, attribute, value);
+        self.mark_dirty(fixture_path).await;
     }
 }
 
-struct ClientHandler {
+struct ClientHandler<IO> {
     peer: SocketAddr,
-    reader: FramedRead<OwnedReadHalf, PacketDecoder<ServerPacketPayload>>,
-    writer: FramedWrite<OwnedWriteHalf, PacketEncoder<ClientPacketPayload>>,
+    reader: FramedRead<ReadHalf<IO>, PacketDecoder<ServerPacketPayload>>,
+    writer: FramedWrite<WriteHalf<IO>, PacketEncoder<ClientPacketPayload>>,
     state: Arc<ServerState>,
+    /// Time without packets from the client after which it is pinged.
+    ping_interval: Option<Duration>,
+    /// Time without packets from the client after which the connection is closed.
+    idle_timeout: Option<Duration>,
+    /// Whether the client is only allowed to send packets that don't change
+    /// the output. Determined by the name of the client in its first `Hello`.
+    read_only: bool,
 }
 
-impl ClientHandler {
-    fn new(stream: TcpStream, peer: SocketAddr, state: Arc<ServerState>) -> Self {
-        let (read_half, write_half) = stream.into_split();
-        let decoder = PacketDecoder::<ServerPacketPayload>::default();
+impl<IO: AsyncRead + AsyncWrite + Send + 'static> ClientHandler<IO> {
+    fn new(
+        io: IO,
+        peer: SocketAddr,
+        state: Arc<ServerState>,
+        ping_interval: Option<Duration>,
+        idle_timeout: Option<Duration>,
+        max_frame_size: usize,
+    ) -> Self {
+        let (read_half, write_half) = tokio::io::split(io);
+        let decoder = PacketDecoder::<ServerPacketPayload>::with_max_frame_size(max_frame_size);
         let encoder = PacketEncoder::<ClientPacketPayload>::default();
 
         let framed_reader = FramedRead::new(read_half, decoder);
         let framed_writer = FramedWrite::new(write_half, encoder);
 
-        Self { peer, reader: framed_reader, writer: framed_writer, state }
+        let read_only = state.permissions.default_read_only();
+        Self {
+            peer,
+            reader: framed_reader,
+            writer: framed_writer,
+            state,
+            ping_interval,
+            idle_timeout,
+            read_only,
+        }
     }
 
     async fn run(mut self) {
         log::info!("client connected: {}", self.peer);
+        self.state.stats.client_connected();
 
-        while let Some(frame_res) = self.reader.next().await {
-            match frame_res {
-                Ok(packet) => {
-                    self.state.process_packet(packet, self.peer, &mut self.writer).await;
+        // The receiver is dropped when this handler stops, which removes this
+        // client from the set of clients that will be notified.
+        let mut attribute_changes = self.state.attribute_changes.subscribe();
+        let mut show_data_changes = self.state.show_data_changes.subscribe();
+
+        // Ping the client after a period without packets from it, and again
+        // every period until it responds or has missed too many pings.
+        let mut last_seen = Instant::now();
+        let mut missed_pongs = 0;
+        let mut next_nonce = 0;
+        let mut is_first_packet = true;
+        // Permissions are determined by the first `Hello`, so later ones are rejected.
+        let mut said_hello = false;
+        loop {
+            let ping_interval = self.ping_interval.unwrap_or_default();
+            let ping_deadline = last_seen + ping_interval * (missed_pongs + 1);
+            let idle_deadline = last_seen + self.idle_timeout.unwrap_or_default();
+            tokio::select! {
+                frame_res = self.reader.next() => match frame_res {
+                    Some(Ok(packet)) => {
+                        last_seen = Instant::now();
+                        missed_pongs = 0;
+                        match packet.payload {
+                            ServerPacketPayload::Hello { .. } if said_hello => {
+                                self.state.stats.packet_received("Hello");
+                                log::warn!("client {} sent a second hello, denying", self.peer);
+                                let payload = ClientPacketPayload::PermissionDenied {
+                                    reason: "client already sent a Hello".to_string(),
+                                };
+                                if let Err(e) = self.state.send(&mut self.writer, payload).await {
+                                    log::error!("failed to send response to {}: {}", self.peer, e);
+                                }
+                            }
+                            ServerPacketPayload::Hello { protocol_version, client_name } => {
+                                self.state.stats.packet_received("Hello");
+                                if !self.hello(protocol_version, &client_name).await {
+                                    break;
+                                }
+                                said_hello = true;
+                            }
+                            payload => {
+                                // FIXME: Disconnect clients that don't send a `Hello` once
+                                // all clients have been updated to send one.
+                                if is_first_packet {
+                                    log::warn!(
+                                        "client {} did not send a hello, assuming protocol version {}",
+                                        self.peer,
+                                        PROTOCOL_VERSION
+                                    );
+                                }
+                                let packet = Packet::new(payload);
+                                self.state
+                                    .process_packet(packet, self.peer, self.read_only, &mut self.writer)
+                                    .await;
+                            }
+                        }
+                        is_first_packet = false;
+                    }
+                    Some(Err(packet::Error::FrameTooLarge { size, limit })) => {
+                        log::warn!(
+                            "client {} sent a frame of {} bytes, exceeding the limit of {} bytes, disconnecting",
+                            self.peer,
+                            size,
+                            limit
+                        );
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        log::error!("error reading packet from {}: {}", self.peer, e);
+                        break;
+                    }
+                    None => break,
+                },
+                change = attribute_changes.recv() => match change {
+                    Ok((origin, _)) if origin == self.peer => {}
+                    Ok((_, values)) => {
+                        let payload = ClientPacketPayload::AttributeValuesChanged(values);
+                        if let Err(e) = self.state.send(&mut self.writer, payload).await {
+                            log::error!("failed to send attribute changes to {}: {}", self.peer, e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("client {} missed {} attribute changes", self.peer, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                change = show_data_changes.recv() => match change {
+                    // Missed changes only mean the show data has to be requested once.
+                    Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let payload = ClientPacketPayload::ShowDataChanged;
+                        if let Err(e) = self.state.send(&mut self.writer, payload).await {
+                            log::error!("failed to send show data change to {}: {}", self.peer, e);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = tokio::time::sleep_until(ping_deadline), if self.ping_interval.is_some() => {
+                    if missed_pongs >= MAX_MISSED_PONGS {
+                        log::warn!("client {} missed {} pings, disconnecting", self.peer, missed_pongs);
+                        break;
+                    }
+
+                    let payload = ClientPacketPayload::Ping { nonce: next_nonce };
+                    if let Err(e) = self.state.send(&mut self.writer, payload).await {
+                        log::error!("failed to send ping to {}: {}", self.peer, e);
+                    }
+                    next_nonce += 1;
+                    missed_pongs += 1;
                 }
-                Err(e) => {
-                    log::error!("error reading packet from {}: {}", self.peer, e);
+                _ = tokio::time::sleep_until(idle_deadline), if self.idle_timeout.is_some() => {
+                    log::info!("closing idle connection: {}", self.peer);
                     break;
                 }
             }
         }
 
+        self.state.stats.client_disconnected();
         log::info!("client disconnected: {}", self.peer);
     }
+
+    /// Responds to the `Hello` of the client, returning `false` if the
+    /// client uses an unsupported protocol version.
+    async fn hello(&mut self, protocol_version: u16, client_name: &str) -> bool {
+        let is_supported = SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version);
+        let response = if is_supported {
+            self.read_only = self.state.permissions.is_read_only(client_name);
+            log::info!(
+                "client {} is '{}'{}, using protocol version {}",
+                self.peer,
+                client_name,
+                if self.read_only { " (read-only)" } else { "" },
+                protocol_version
+            );
+            ClientPacketPayload::HelloAck {
+                protocol_version,
+                server_version: env!("CARGO_PKG_VERSION").to_string(),
+            }
+        } else {
+            log::warn!(
+                "client {} ('{}') uses unsupported protocol version {}, disconnecting",
+                self.peer,
+                client_name,
+                protocol_version
+            );
+            ClientPacketPayload::IncompatibleVersion { supported: SUPPORTED_PROTOCOL_VERSIONS }
+        };
+
+        if let Err(e) = self.state.send(&mut self.writer, response).await {
+            log::error!("failed to send hello response to {}: {}", self.peer, e);
+        }
+        is_supported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use tokio::io::DuplexStream;
+
+    use super::*;
+    use crate::fpath;
+    use crate::server::test_util::{example_showfile, example_state};
+
+    const PING_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Returns a state with group 1 containing the first two Sharpys.
+    fn state_with_group() -> ServerState {
+        let mut state = example_state();
+        let members = [101, 102].map(|id| FixtureId::new(id).unwrap()).to_vec();
+        let group = showfile::Group::new(GroupId(1), "Sharpys", members);
+        state.show_data.get_mut().patch.groups.insert(group.id(), group);
+        state
+    }
+
+    #[tokio::test]
+    async fn set_color_of_unknown_sub_fixture_reports_its_path() {
+        let state = example_state();
+        let path = fpath![101, 9];
+        let result = state.set_fixture_color(path, dmx::Value(255), dmx::Value(0), dmx::Value(0));
+        assert!(matches!(result.await, Err(Error::FixtureNotFound(p)) if p == path));
+    }
+
+    #[test]
+    fn channel_count_of_dmx_mode() {
+        let showfile = example_showfile();
+        let sharpy = showfile.patch().fixture(FixtureId::new(101).unwrap()).unwrap().kind();
+
+        let count = dmx_mode_channel_count(
+            &showfile,
+            sharpy.gdtf_fixture_type_id(),
+            sharpy.gdtf_dmx_mode(),
+        );
+        assert_eq!(count.unwrap(), 16);
+        assert!(dmx_mode_channel_count(&showfile, sharpy.gdtf_fixture_type_id(), "None").is_err());
+    }
+
+    type Reader = FramedRead<ReadHalf<DuplexStream>, PacketDecoder<ClientPacketPayload>>;
+    type Writer = FramedWrite<WriteHalf<DuplexStream>, PacketEncoder<ServerPacketPayload>>;
+
+    /// Connects a single client and returns its framed connection.
+    async fn connect() -> (Reader, Writer) {
+        connect_to(Arc::new(example_state())).await
+    }
+
+    /// Connects a single client, handled with the given state, and returns
+    /// its framed connection.
+    async fn connect_to(state: Arc<ServerState>) -> (Reader, Writer) {
+        connect_with_max_frame_size(state, packet::DEFAULT_MAX_FRAME_SIZE).await
+    }
+
+    /// Connects a single client to a handler that accepts frames up to the
+    /// given size, and returns its framed connection.
+    async fn connect_with_max_frame_size(
+        state: Arc<ServerState>,
+        max_frame_size: usize,
+    ) -> (Reader, Writer) {
+        // Every client needs its own address, as clients are not notified of
+        // their own attribute changes.
+        static NEXT_PORT: AtomicU16 = AtomicU16::new(1);
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let peer =
+            SocketAddr::from((Ipv4Addr::LOCALHOST, NEXT_PORT.fetch_add(1, Ordering::Relaxed)));
+        let handler =
+            ClientHandler::new(server, peer, state, Some(PING_INTERVAL), None, max_frame_size);
+        tokio::spawn(handler.run());
+
+        let (reader, writer) = tokio::io::split(client);
+        (
+            FramedRead::new(reader, PacketDecoder::default()),
+            FramedWrite::new(writer, PacketEncoder::default()),
+        )
+    }
+
+    /// Sends a packet and returns the next packet that is not a ping.
+    async fn request(
+        reader: &mut Reader,
+        writer: &mut Writer,
+        payload: ServerPacketPayload,
+    ) -> Option<ClientPacketPayload> {
+        writer.send(Packet::new(payload)).await.unwrap();
+        next_payload(reader).await
+    }
+
+    /// Returns the next packet that is not a ping.
+    async fn next_payload(reader: &mut Reader) -> Option<ClientPacketPayload> {
+        loop {
+            match reader.next().await?.unwrap().payload {
+                ClientPacketPayload::Ping { .. } => continue,
+                payload => return Some(payload),
+            }
+        }
+    }
+
+    #[test]
+    fn build_show_data_from_loaded_fixture_types() {
+        let mut showfile = example_showfile();
+        let fixture_types = GdtfFixtureTypes::load(&showfile).unwrap();
+
+        let sharpy = FixtureId::new(101).unwrap();
+        showfile.patch_mut().remove_fixture(sharpy).unwrap();
+        let show_data = fixture_types.build_show_data(&showfile).unwrap();
+
+        assert!(show_data.patch().fixture(&fpath![101]).is_none());
+        assert!(show_data.patch().fixture(&fpath![102]).is_some());
+        assert_eq!(show_data.fixture_types().count(), fixture_types.infos().len());
+    }
+
+    #[tokio::test]
+    async fn bind_to_address_override() {
+        let showfile = example_showfile();
+        let mut server = Server::new(&showfile).unwrap();
+        server.set_address(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+
+        let address = server.bind().await.unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+        assert_ne!(address.port(), 0);
+        assert_eq!(server.address(), address);
+        tokio::net::TcpStream::connect(address).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn start_fails_for_unknown_interfaces() {
+        let mut showfile = example_showfile();
+        // Not an address of this machine (TEST-NET-1).
+        *showfile.protocols_mut() = serde_json::from_value(serde_json::json!({ "sacn": {
+            "outputs": [{
+                "label": "Node",
+                "mode": "multicast",
+                "local_universe": 1,
+                "destination_universe": 1,
+                "priority": 100,
+                "preview_data": false,
+                "interface": "192.0.2.1",
+            }]
+        } }))
+        .unwrap();
+        let mut server = Server::new(&showfile).unwrap();
+        server.set_address(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+
+        let result = tokio::time::timeout(Duration::from_secs(5), server.start()).await;
+        let err = result.expect("server should fail to start").unwrap_err();
+        assert!(err.to_string().contains("sACN output 'Node'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn shutdown_sends_a_blackout() {
+        let showfile = example_showfile();
+        assert!(showfile.config().blackout_on_exit());
+        let mut server = Server::new(&showfile).unwrap();
+        server.set_dummy_output(true);
+        server.set_address(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+
+        let address = Address::from_absolute(1).unwrap();
+        server.state.output_multiverse.write().await.set_value(&address, dmx::Value(255));
+        let result = tokio::time::timeout(Duration::from_millis(200), server.start()).await;
+        assert!(result.is_err(), "server should keep running until cancelled");
+        server.shutdown();
+
+        let frames = server.recorded_frames();
+        let (last, frames) = frames.split_last().unwrap();
+        assert!(frames.iter().any(|frame| frame.get_value(&address) == dmx::Value(255)));
+        assert_eq!(last.get_value(&address), dmx::Value(0));
+    }
+
+    #[tokio::test]
+    async fn resolve_configured_addresses() {
+        let address = "127.0.0.1:7334".parse().unwrap();
+        let resolved = resolve_address(&address, false).await.unwrap();
+        assert_eq!(resolved, SocketAddr::from((Ipv4Addr::LOCALHOST, 7334)));
+
+        let address = "[::1]:7334".parse().unwrap();
+        let resolved = resolve_address(&address, true).await.unwrap();
+        assert_eq!(resolved, SocketAddr::from((Ipv6Addr::LOCALHOST, 7334)));
+
+        let address = "localhost:7334".parse().unwrap();
+        let resolved = resolve_address(&address, true).await.unwrap();
+        assert!(resolved.ip().is_loopback());
+        assert_eq!(resolved.port(), 7334);
+
+        let address = "zeevonk.invalid:7334".parse().unwrap();
+        let err = resolve_address(&address, false).await.unwrap_err();
+        assert!(err.to_string().contains("'zeevonk.invalid'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn unspecified_ipv6_listener_accepts_ipv4_clients() {
+        let Ok(listener) = bind_listener(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0))) else {
+            // IPv6 is not available on this host.
+            return;
+        };
+        let port = listener.local_addr().unwrap().port();
+        tokio::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+        tokio::net::TcpStream::connect((Ipv6Addr::LOCALHOST, port)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hello_handshake() {
+        let (mut reader, mut writer) = connect().await;
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+        };
+        let response = request(&mut reader, &mut writer, hello).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::HelloAck { protocol_version: PROTOCOL_VERSION, .. })
+        ));
+
+        // Clients that skip the hello are still served.
+        let (mut reader, mut writer) = connect().await;
+        let response =
+            request(&mut reader, &mut writer, ServerPacketPayload::RequestParkedAddresses).await;
+        assert!(matches!(response, Some(ClientPacketPayload::ResponseParkedAddresses { .. })));
+    }
+
+    #[tokio::test]
+    async fn recorded_output() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+        };
+        request(&mut reader, &mut writer, hello).await.unwrap();
+
+        let payload = ServerPacketPayload::RequestRecordedOutput;
+        let response = request(&mut reader, &mut writer, payload.clone()).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseRecordedOutput { frame: None })
+        ));
+
+        let address = Address::from_absolute(1).unwrap();
+        let mut frame = Multiverse::new();
+        frame.set_value(&address, dmx::Value(255));
+        state.recorded_frames.push(frame.clone());
+
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseRecordedOutput { frame: Some(recorded) }) = response
+        else {
+            panic!("expected recorded frame, got {response:?}");
+        };
+        assert_eq!(recorded, frame);
+    }
+
+    #[tokio::test]
+    async fn stats_count_packets_and_clients() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: "test".to_string(),
+        };
+        request(&mut reader, &mut writer, hello).await.unwrap();
+        request(&mut reader, &mut writer, ServerPacketPayload::RequestDmxOutput).await.unwrap();
+        request(&mut reader, &mut writer, ServerPacketPayload::RequestDmxOutput).await.unwrap();
+
+        let response = request(&mut reader, &mut writer, ServerPacketPayload::RequestStats).await;
+        let Some(ClientPacketPayload::ResponseStats(stats)) = response else {
+            panic!("expected stats, got {response:?}");
+        };
+        assert_eq!(stats.packets_received["Hello"], 1);
+        assert_eq!(stats.packets_received["RequestDmxOutput"], 2);
+        assert_eq!(stats.packets_received["RequestStats"], 1);
+        assert_eq!(stats.total_packets_received(), 4);
+        assert_eq!(stats.active_clients, 1);
+        assert!(stats.bytes_sent > 0);
+        assert!(stats.last_resolve_duration > Duration::ZERO);
+        assert_eq!(stats.resolve_durations.count(), 2);
+
+        drop((reader, writer));
+        for _ in 0..100 {
+            if state.stats.snapshot().active_clients == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("client should have been counted as disconnected");
+    }
+
+    #[tokio::test]
+    async fn hello_with_unsupported_version_closes_connection() {
+        let (mut reader, mut writer) = connect().await;
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: SUPPORTED_PROTOCOL_VERSIONS.end() + 1,
+            client_name: "test".to_string(),
+        };
+        let response = request(&mut reader, &mut writer, hello).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::IncompatibleVersion { supported })
+                if supported == SUPPORTED_PROTOCOL_VERSIONS
+        ));
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn hello_with_previous_version_closes_connection() {
+        let (mut reader, mut writer) = connect().await;
+        let hello = ServerPacketPayload::Hello {
+            protocol_version: SUPPORTED_PROTOCOL_VERSIONS.start() - 1,
+            client_name: "test".to_string(),
+        };
+        let response = request(&mut reader, &mut writer, hello).await;
+        assert!(matches!(response, Some(ClientPacketPayload::IncompatibleVersion { .. })));
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn fire_trigger() {
+        let state = example_state();
+        let peer = SocketAddr::from(([127, 0, 0, 1], 0));
+        let dimmers = ["1.3", "1.19"].map(|address| address.parse::<Address>().unwrap());
+
+        assert!(!state.fire_trigger(&"unknown".parse().unwrap(), peer).await);
+        for dimmer in &dimmers {
+            assert_eq!(state.output_multiverse.read().await.get_value(dimmer), dmx::Value(0));
+        }
+
+        assert!(state.fire_trigger(&"sharpys-full".parse().unwrap(), peer).await);
+        for dimmer in &dimmers {
+            assert_eq!(state.output_multiverse.read().await.get_value(dimmer), dmx::Value(255));
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_clients_cannot_change_output() {
+        let mut state = example_state();
+        state.permissions = serde_json::from_value(serde_json::json!({
+            "clients": { "visualizer": { "read_only": true } }
+        }))
+        .unwrap();
+        let state = Arc::new(state);
+        let relay = "2.1".parse::<Address>().unwrap();
+        let set_relay = |value| ServerPacketPayload::RequestSetRawDmx {
+            values: vec![(relay, dmx::Value(value))],
+        };
+
+        let mut clients = Vec::new();
+        for client_name in ["visualizer", "console"] {
+            let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+            let hello = ServerPacketPayload::Hello {
+                protocol_version: PROTOCOL_VERSION,
+                client_name: client_name.to_string(),
+            };
+            request(&mut reader, &mut writer, hello).await.unwrap();
+            clients.push((reader, writer));
+        }
+        let [(visualizer_reader, visualizer_writer), (console_reader, console_writer)] =
+            &mut clients[..]
+        else {
+            unreachable!()
+        };
+
+        let response = request(visualizer_reader, visualizer_writer, set_relay(100)).await;
+        assert!(matches!(response, Some(ClientPacketPayload::PermissionDenied { .. })));
+        let response =
+            request(visualizer_reader, visualizer_writer, ServerPacketPayload::RequestDmxOutput)
+                .await;
+        let Some(ClientPacketPayload::ResponseDmxOutput(output)) = response else {
+            panic!("read-only clients can request the output");
+        };
+        assert_eq!(output.get_value(&relay), dmx::Value(0));
+
+        let response = request(console_reader, console_writer, set_relay(200)).await;
+        assert!(matches!(response, Some(ClientPacketPayload::ResponseSetRawDmx)));
+        assert_eq!(state.output_multiverse.read().await.get_value(&relay), dmx::Value(200));
+    }
+
+    #[tokio::test]
+    async fn second_hello_cannot_change_permissions() {
+        let mut state = example_state();
+        state.permissions = serde_json::from_value(serde_json::json!({
+            "clients": { "visualizer": { "read_only": true } }
+        }))
+        .unwrap();
+        let state = Arc::new(state);
+        let hello = |client_name: &str| ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: client_name.to_string(),
+        };
+        let set_blackout = ServerPacketPayload::RequestSetBlackout { blackout: true };
+
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let response = request(&mut reader, &mut writer, hello("visualizer")).await;
+        assert!(matches!(response, Some(ClientPacketPayload::HelloAck { .. })));
+        let response = request(&mut reader, &mut writer, hello("console")).await;
+        assert!(matches!(response, Some(ClientPacketPayload::PermissionDenied { .. })));
+        let response = request(&mut reader, &mut writer, set_blackout.clone()).await;
+        assert!(matches!(response, Some(ClientPacketPayload::PermissionDenied { .. })));
+
+        // A read-write client stays read-write.
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        request(&mut reader, &mut writer, hello("console")).await;
+        let response = request(&mut reader, &mut writer, hello("visualizer")).await;
+        assert!(matches!(response, Some(ClientPacketPayload::PermissionDenied { .. })));
+        let response = request(&mut reader, &mut writer, set_blackout).await;
+        assert!(matches!(response, Some(ClientPacketPayload::ResponseSetBlackout)));
+    }
+
+    #[tokio::test]
+    async fn fixtures_with_attribute() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+
+        let payload =
+            ServerPacketPayload::RequestFixturesWithAttribute { attribute: Attribute::Pan };
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseFixturesWithAttribute { fixture_paths }) = response
+        else {
+            panic!("expected fixture paths, got {response:?}");
+        };
+        assert!(fixture_paths.contains(&fpath![101, 1]));
+        let show_data = state.show_data.read().await;
+        for path in &fixture_paths {
+            assert!(show_data.patch().fixtures()[path].has_attribute(&Attribute::Pan));
+        }
+        assert!(fixture_paths.is_sorted());
+    }
+
+    #[tokio::test]
+    async fn attribute_values_fall_back_to_defaults() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let dimmer = (fpath![101, 1, 1], Attribute::Dimmer);
+        let pan = (fpath![101, 1], Attribute::Pan);
+        let unknown = (fpath![101], Attribute::Pan);
+
+        let mut values = AttributeValues::new();
+        values.set(dimmer.0, dimmer.1, 0.25);
+        request(&mut reader, &mut writer, ServerPacketPayload::RequestSetAttributeValues(values))
+            .await;
+
+        let filter = Some(vec![dimmer, pan, unknown]);
+        let payload = ServerPacketPayload::RequestAttributeValues { filter };
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseAttributeValues(values)) = response else {
+            panic!("expected attribute values, got {response:?}");
+        };
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get(dimmer.0, dimmer.1), Some(ClampedValue::new(0.25)));
+        let show_data = state.show_data.read().await;
+        let pan_function =
+            show_data.patch().fixture(&pan.0).unwrap().channel_function(&pan.1).unwrap();
+        assert_eq!(values.get(pan.0, pan.1), Some(pan_function.relative_default()));
+        drop(show_data);
+
+        let payload = ServerPacketPayload::RequestAttributeValues { filter: None };
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseAttributeValues(all)) = response else {
+            panic!("expected attribute values, got {response:?}");
+        };
+        assert_eq!(all.get(dimmer.0, dimmer.1), Some(ClampedValue::new(0.25)));
+        assert!(all.get(pan.0, pan.1).is_some());
+        assert!(all.len() > values.len());
+    }
+
+    #[tokio::test]
+    async fn clients_are_notified_of_show_data_changes() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        // Make sure the client is being handled before the show data changes.
+        request(&mut reader, &mut writer, ServerPacketPayload::RequestStats).await;
+
+        let show_data = state.show_data.read().await.clone();
+        state.replace_show_data(show_data).await;
+        let payload = next_payload(&mut reader).await;
+        assert!(matches!(payload, Some(ClientPacketPayload::ShowDataChanged)));
+    }
+
+    #[tokio::test]
+    async fn reload_protocols_rejects_invalid_protocols() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let protocols = |destination_ip: &str| -> Protocols {
+            serde_json::from_value(serde_json::json!({ "sacn": { "outputs": [{
+                "label": "Node",
+                "mode": { "unicast": { "destination_ip": destination_ip } },
+                "local_universe": 1,
+                "destination_universe": 1,
+                "priority": 100,
+                "preview_data": false,
+            }] } }))
+            .unwrap()
+        };
+        let before = state.protocols.borrow().clone();
+
+        let payload = ServerPacketPayload::RequestReloadProtocols(protocols("0.0.0.0"));
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseReloadProtocols { issues }) = response else {
+            panic!("expected reload response, got {response:?}");
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(*state.protocols.borrow(), before);
+
+        let payload = ServerPacketPayload::RequestReloadProtocols(protocols("10.0.0.2"));
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseReloadProtocols { issues }) = response else {
+            panic!("expected reload response, got {response:?}");
+        };
+        assert!(issues.is_empty());
+        assert_eq!(*state.protocols.borrow(), protocols("10.0.0.2"));
+    }
+
+    #[tokio::test]
+    async fn reload_protocols_rejects_unknown_interfaces() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let protocols = |interface: &str| -> Protocols {
+            serde_json::from_value(serde_json::json!({ "sacn": { "outputs": [{
+                "label": "Node",
+                "mode": "multicast",
+                "local_universe": 1,
+                "destination_universe": 1,
+                "priority": 100,
+                "preview_data": false,
+                "interface": interface,
+            }] } }))
+            .unwrap()
+        };
+
+        // Not an address of this machine (TEST-NET-1), and of another IP version.
+        for interface in ["192.0.2.1", "::1"] {
+            let payload = ServerPacketPayload::RequestReloadProtocols(protocols(interface));
+            let response = request(&mut reader, &mut writer, payload).await;
+            let Some(ClientPacketPayload::ResponseReloadProtocols { issues }) = response else {
+                panic!("expected reload response, got {response:?}");
+            };
+            assert_eq!(issues.len(), 1, "{issues:?}");
+            assert!(issues[0].message().contains(&format!("interface {interface}")));
+        }
+
+        let payload = ServerPacketPayload::RequestReloadProtocols(protocols("127.0.0.1"));
+        let response = request(&mut reader, &mut writer, payload).await;
+        let Some(ClientPacketPayload::ResponseReloadProtocols { issues }) = response else {
+            panic!("expected reload response, got {response:?}");
+        };
+        assert!(issues.is_empty());
+        let interface = state.protocols.borrow().sacn().outputs()[0].interface();
+        assert_eq!(interface, Some(std::net::Ipv4Addr::LOCALHOST.into()));
+    }
+
+    #[tokio::test]
+    async fn save_snapshot_reports_overwrites() {
+        let state = Arc::new(example_state());
+        let (mut reader_a, mut writer_a) = connect_to(Arc::clone(&state)).await;
+        let (mut reader_b, mut writer_b) = connect_to(Arc::clone(&state)).await;
+        let label = "look".parse::<Identifier>().unwrap();
+
+        let payload = ServerPacketPayload::RequestSaveSnapshot { label: label.clone() };
+        let response = request(&mut reader_a, &mut writer_a, payload.clone()).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseSaveSnapshot { overwritten: false })
+        ));
+        let response = request(&mut reader_b, &mut writer_b, payload).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseSaveSnapshot { overwritten: true })
+        ));
+
+        let payload = ServerPacketPayload::RequestListSnapshots;
+        let response = request(&mut reader_a, &mut writer_a, payload).await;
+        let Some(ClientPacketPayload::ResponseListSnapshots { snapshots }) = response else {
+            panic!("expected snapshots, got {response:?}");
+        };
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].label, label);
+    }
+
+    #[tokio::test]
+    async fn rename_fixture() {
+        let state = Arc::new(example_state());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let root_id = FixtureId::new(101).unwrap();
+
+        let payload =
+            ServerPacketPayload::RequestRenameFixture { root_id, label: "Left".to_string() };
+        let response = request(&mut reader, &mut writer, payload).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseRenameFixture { renamed: true })
+        ));
+        let show_data = state.show_data.read().await;
+        assert_eq!(show_data.patch().fixture_by_label("left").unwrap().path(), fpath![101]);
+        drop(show_data);
+        // Clients are told to request the renamed show data.
+        let payload = next_payload(&mut reader).await;
+        assert!(matches!(payload, Some(ClientPacketPayload::ShowDataChanged)));
+
+        let root_id = FixtureId::new(999).unwrap();
+        let payload =
+            ServerPacketPayload::RequestRenameFixture { root_id, label: "Right".to_string() };
+        let response = request(&mut reader, &mut writer, payload).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseRenameFixture { renamed: false })
+        ));
+    }
+
+    #[tokio::test]
+    async fn set_group_attribute_value_reports_unknown_groups() {
+        let state = Arc::new(state_with_group());
+        let (mut reader, mut writer) = connect_to(Arc::clone(&state)).await;
+        let dimmers = ["1.3", "1.19"].map(|address| address.parse::<Address>().unwrap());
+        let set_dimmer = |group| ServerPacketPayload::RequestSetGroupAttributeValue {
+            group,
+            attribute: Attribute::Dimmer,
+            value: ClampedValue::new(1.0),
+        };
+
+        let response = request(&mut reader, &mut writer, set_dimmer(GroupId(2))).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseSetGroupAttributeValue { applied: false })
+        ));
+        for dimmer in &dimmers {
+            assert_eq!(state.output_multiverse.read().await.get_value(dimmer), dmx::Value(0));
+        }
+
+        let response = request(&mut reader, &mut writer, set_dimmer(GroupId(1))).await;
+        assert!(matches!(
+            response,
+            Some(ClientPacketPayload::ResponseSetGroupAttributeValue { applied: true })
+        ));
+        for dimmer in &dimmers {
+            assert_eq!(state.output_multiverse.read().await.get_value(dimmer), dmx::Value(255));
+        }
+    }
+
+    #[tokio::test]
+    async fn clients_are_notified_of_group_attribute_changes() {
+        let state = Arc::new(state_with_group());
+        let (mut console_reader, mut console_writer) = connect_to(Arc::clone(&state)).await;
+        let (mut visualizer_reader, mut visualizer_writer) = connect_to(Arc::clone(&state)).await;
+        // Make sure the visualizer is being handled before the values change.
+        request(&mut visualizer_reader, &mut visualizer_writer, ServerPacketPayload::RequestStats)
+            .await;
+
+        let payload = ServerPacketPayload::RequestSetGroupAttributeValue {
+            group: GroupId(1),
+            attribute: Attribute::Dimmer,
+            value: ClampedValue::new(0.5),
+        };
+        request(&mut console_reader, &mut console_writer, payload).await;
+
+        let payload = next_payload(&mut visualizer_reader).await;
+        let Some(ClientPacketPayload::AttributeValuesChanged(values)) = payload else {
+            panic!("expected attribute changes, got {payload:?}");
+        };
+        let show_data = state.show_data.read().await;
+        let paths = show_data.patch().group_fixture_paths(GroupId(1), Attribute::Dimmer).unwrap();
+        assert!(!paths.is_empty());
+        assert_eq!(values.values().count(), paths.len());
+        for path in paths {
+            assert_eq!(values.get(path, Attribute::Dimmer), Some(ClampedValue::new(0.5)));
+        }
+    }
+
+    #[tokio::test]
+    async fn show_data_contains_fixture_types() {
+        let show_data = example_state().show_data.read().await.clone();
+        let sharpy = &show_data.patch().fixtures()[&fpath![101]];
+
+        let fixture_type = show_data.fixture_type_of(sharpy).unwrap();
+        assert_eq!(fixture_type.manufacturer(), "Clay Paky");
+        assert_eq!(fixture_type.id(), sharpy.gdtf_fixture_type_id());
+    }
+
+    #[tokio::test]
+    async fn default_output_ignores_overrides() {
+        let state = example_state();
+        let peer = SocketAddr::from(([127, 0, 0, 1], 0));
+        let dimmer = "1.3".parse::<Address>().unwrap();
+        let relay = "2.1".parse::<Address>().unwrap();
+
+        state.fire_trigger(&"sharpys-full".parse().unwrap(), peer).await;
+        state.set_raw_dmx(vec![(relay, dmx::Value(255))]).await;
+        state.resolve_values().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&dimmer), dmx::Value(255));
+
+        let default_output = state.default_output().await;
+        assert_eq!(default_output.get_value(&dimmer), dmx::Value(0));
+        assert_eq!(default_output.get_value(&relay), dmx::Value(0));
+    }
+
+    #[tokio::test]
+    async fn clear_attribute_values() {
+        let state = example_state();
+        let peer = SocketAddr::from(([127, 0, 0, 1], 0));
+        let dimmers = ["1.3", "1.19"].map(|address| address.parse::<Address>().unwrap());
+        state.fire_trigger(&"sharpys-full".parse().unwrap(), peer).await;
+        state.set_attribute_value(fpath![101], Attribute::Pan, ClampedValue::new(1.0)).await;
+
+        // Clearing a root fixture also clears its sub-fixtures.
+        state.clear_attribute_values(fpath![101]).await;
+        state.resolve_values().await;
+        let output = state.output_multiverse.read().await.clone();
+        assert_eq!(output.get_value(&dimmers[0]), dmx::Value(0));
+        assert_eq!(output.get_value(&dimmers[1]), dmx::Value(255));
+
+        state.clear_all_attribute_values().await;
+        state.resolve_values().await;
+        assert_eq!(*state.output_multiverse.read().await, state.default_output().await);
+    }
+
+    #[tokio::test]
+    async fn parked_addresses_win_and_survive_patch_changes() {
+        let state = example_state();
+        let peer = SocketAddr::from(([127, 0, 0, 1], 0));
+        let dimmer = "1.3".parse::<Address>().unwrap();
+
+        state.park_address(dimmer, dmx::Value(10)).await;
+        state.fire_trigger(&"sharpys-full".parse().unwrap(), peer).await;
+        state.set_raw_dmx(vec![(dimmer, dmx::Value(20))]).await;
+        state.resolve_values().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&dimmer), dmx::Value(10));
+        assert_eq!(state.parked().await, vec![(dimmer, dmx::Value(10))]);
+
+        // Parks are kept when the fixture at the address is unpatched.
+        state.remove_fixture(FixtureId::new(101).unwrap()).await.unwrap();
+        assert_eq!(state.output_multiverse.read().await.get_value(&dimmer), dmx::Value(10));
+
+        assert!(state.unpark_address(&dimmer).await);
+        assert!(!state.unpark_address(&dimmer).await);
+        state.resolve_values().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&dimmer), dmx::Value(20));
+        assert!(state.parked().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unresponsive_client_is_disconnected() {
+        let (mut reader, _writer) = connect().await;
+
+        let mut nonces = Vec::new();
+        let closed = tokio::time::timeout(PING_INTERVAL * 40, async {
+            while let Some(packet) = reader.next().await {
+                if let ClientPacketPayload::Ping { nonce } = packet.unwrap().payload {
+                    nonces.push(nonce);
+                }
+            }
+        })
+        .await;
+
+        assert!(closed.is_ok());
+        assert_eq!(nonces, [0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn idle_connection_is_closed() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let peer = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let state = Arc::new(example_state());
+        let idle_timeout = Some(PING_INTERVAL * 2);
+        let handler = ClientHandler::new(
+            server,
+            peer,
+            state,
+            None,
+            idle_timeout,
+            packet::DEFAULT_MAX_FRAME_SIZE,
+        );
+        tokio::spawn(handler.run());
+        let (reader, writer) = tokio::io::split(client);
+        let mut reader = FramedRead::new(reader, PacketDecoder::<ClientPacketPayload>::default());
+        let mut writer = FramedWrite::new(writer, PacketEncoder::<ServerPacketPayload>::default());
+
+        // Packets from the client keep the connection open.
+        for nonce in 0..4 {
+            tokio::time::sleep(PING_INTERVAL).await;
+            writer.send(Packet::new(ServerPacketPayload::Ping { nonce })).await.unwrap();
+            let response = reader.next().await.unwrap().unwrap();
+            assert!(
+                matches!(response.payload, ClientPacketPayload::Pong { nonce: n } if n == nonce)
+            );
+        }
+
+        let closed = tokio::time::timeout(PING_INTERVAL * 20, reader.next()).await;
+        assert!(matches!(closed, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_disconnects_client() {
+        use tokio::io::AsyncWriteExt as _;
+
+        let (mut reader, mut writer) = connect().await;
+        let length_prefix = (packet::DEFAULT_MAX_FRAME_SIZE as u32 + 1).to_le_bytes();
+        writer.get_mut().write_all(&length_prefix).await.unwrap();
+        writer.get_mut().flush().await.unwrap();
+
+        // The client is disconnected right away, before it could miss a ping.
+        let closed = tokio::time::timeout(PING_INTERVAL / 2, async {
+            while let Some(packet) = reader.next().await {
+                packet.unwrap();
+            }
+        })
+        .await;
+        assert!(closed.is_ok());
+    }
+
+    #[tokio::test]
+    async fn frame_over_configured_size_disconnects_client() {
+        let (mut reader, mut writer) =
+            connect_with_max_frame_size(Arc::new(example_state()), 1024).await;
+
+        let payload = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: "a".repeat(512),
+        };
+        assert!(matches!(
+            request(&mut reader, &mut writer, payload).await,
+            Some(ClientPacketPayload::HelloAck { .. })
+        ));
+
+        let payload = ServerPacketPayload::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: "a".repeat(2048),
+        };
+        writer.send(Packet::new(payload)).await.unwrap();
+        let closed = tokio::time::timeout(PING_INTERVAL / 2, async {
+            while let Some(packet) = reader.next().await {
+                packet.unwrap();
+            }
+        })
+        .await;
+        assert!(closed.is_ok());
+    }
+
+    #[tokio::test]
+    async fn responsive_client_stays_connected() {
+        let (mut reader, mut writer) = connect().await;
+
+        let started = Instant::now();
+        let mut pings = 0;
+        while started.elapsed() < PING_INTERVAL * 10 {
+            let packet = reader.next().await.unwrap().unwrap();
+            let ClientPacketPayload::Ping { nonce } = packet.payload else { continue };
+            writer.send(Packet::new(ServerPacketPayload::Pong { nonce })).await.unwrap();
+            pings += 1;
+        }
+        assert!(pings >= 3);
+
+        writer.send(Packet::new(ServerPacketPayload::Ping { nonce: 42 })).await.unwrap();
+        loop {
+            let packet = reader.next().await.unwrap().unwrap();
+            if let ClientPacketPayload::Pong { nonce } = packet.payload {
+                assert_eq!(nonce, 42);
+                break;
+            }
+        }
+    }
 }
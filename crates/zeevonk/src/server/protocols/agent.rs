@@ -1,78 +1,335 @@
 use std::cell::RefCell;
-use std::net::IpAddr;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
+use tokio::sync::watch;
+
 use crate::Error;
+use crate::dmx::{Multiverse, Universe};
 use crate::server::ServerState;
+use crate::server::protocols::dummy::DummyOutput;
 use crate::server::protocols::sacn;
-use crate::showfile::{Protocols, SacnMode};
-
-const DMX_OUTPUT_FRAME_TIME: Duration = Duration::from_millis(44);
-
-// FIXME: We should find a way to create a unique UUID for a device, without it
-// changing over it's lifetime.
-const SACN_CID: sacn::ComponentIdentifier = sacn::ComponentIdentifier::from_bytes([
-    0xa1, 0xa2, 0xa3, 0xa4, 0xb1, 0xb2, 0xc1, 0xc2, 0xd1, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8,
-]);
+use crate::showfile::{Config, Protocols, Sacn, SacnMode, SacnOutput, SacnUniverses};
 
-pub fn start(protocols: Protocols, server_state: Arc<ServerState>) {
-    thread::Builder::new()
+/// Starts sending output over the configured protocols, or to a
+/// [DummyOutput] if `dummy_output` is set.
+///
+/// Fails if the outputs can't be started, e.g. because an sACN output can't
+/// bind to its interface.
+pub fn start(
+    protocols: Protocols,
+    config: Config,
+    server_state: Arc<ServerState>,
+    dummy_output: bool,
+) -> Result<AgentHandle, Error> {
+    let stop = Arc::new(AtomicBool::new(false));
+    // The process can't be sent to another thread, so it is created on the
+    // protocols thread, which reports back whether that succeeded.
+    let (started_tx, started_rx) = mpsc::sync_channel(1);
+    let thread = thread::Builder::new()
         .name("protocols".to_string())
-        .spawn(move || {
-            ProtocolsProcess::new(protocols, server_state)
-                .expect("should create new protocols process")
-                .start();
+        .spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                let protocols = if dummy_output { Protocols::default() } else { protocols };
+                let mut process =
+                    match ProtocolsProcess::new(&protocols, &config, Arc::clone(&server_state)) {
+                        Ok(process) => process,
+                        Err(err) => {
+                            let _ = started_tx.send(Err(err));
+                            return;
+                        }
+                    };
+                if dummy_output {
+                    // The dummy output replaces the protocols, so changes to
+                    // them are ignored.
+                    process.protocol_changes = None;
+                    process.add_output(DummyOutput::new(server_state));
+                }
+                let _ = started_tx.send(Ok(()));
+                process.start(&stop);
+            }
         })
         .unwrap();
+
+    match started_rx.recv() {
+        Ok(Ok(())) => Ok(AgentHandle { stop, thread: Some(thread) }),
+        Ok(Err(err)) => {
+            let _ = thread.join();
+            Err(err)
+        }
+        Err(_) => Err(Error::server("protocols thread stopped before starting")),
+    }
+}
+
+/// Handle to the protocols thread started with [start].
+///
+/// The protocols are stopped when the handle is dropped.
+pub struct AgentHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AgentHandle {
+    /// Stops sending output and waits for the protocols thread to finish.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for AgentHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Something that DMX output can be sent to.
+pub trait Output: Send {
+    fn send(&mut self, multiverse: &Multiverse);
+
+    /// Sends the last frame before the output stops. Outputs that skip
+    /// frames should always send this one.
+    fn send_last(&mut self, multiverse: &Multiverse) {
+        self.send(multiverse);
+    }
+}
+
+/// An sACN source that sends universes as the sACN universes they are mapped
+/// to.
+struct SacnSender {
+    source: sacn::Source,
+    server_state: Arc<ServerState>,
+    universes: SacnUniverses,
+    schedule: SacnSchedule,
+}
+
+impl SacnSender {
+    /// Sends the universes of the multiverse that are due, or all of them
+    /// if `force` is set.
+    fn send_universes(&mut self, multiverse: &Multiverse, force: bool) {
+        let now = Instant::now();
+        if !force && !self.schedule.frame_due(now) {
+            return;
+        }
+
+        let mut sent_universes = Vec::new();
+        for (id, universe) in multiverse.sorted_universes() {
+            // Unmapped universes are not sent. Loading the showfile fails if
+            // fixtures are patched outside of the sACN range without a mapping.
+            let Some(number) = self.universes.sacn_universe(*id) else { continue };
+            sent_universes.push(number);
+
+            if !self.schedule.universe_due(number, universe, now) && !force {
+                continue;
+            }
+            match self.source.send_universe_data_packet(sacn::Universe::from_dmx(number, universe))
+            {
+                Ok(()) => self.server_state.stats.frame_sent(*id),
+                Err(err) => log::error!("failed to send universe data over sACN: {err}"),
+            }
+        }
+        self.schedule.retain_universes(&sent_universes);
+
+        // Receivers release universes that are no longer sent right away,
+        // instead of holding their last values until they time out.
+        self.source
+            .terminate_universes_except(&sent_universes)
+            .map_err(|err| log::error!("failed to terminate sACN universes: {err}"))
+            .ok();
+        self.source
+            .send_universe_discovery_if_due(&sent_universes)
+            .map_err(|err| log::error!("failed to send universe discovery over sACN: {err}"))
+            .ok();
+    }
+}
+
+impl Output for SacnSender {
+    fn send(&mut self, multiverse: &Multiverse) {
+        self.send_universes(multiverse, false);
+    }
+
+    fn send_last(&mut self, multiverse: &Multiverse) {
+        self.send_universes(multiverse, true);
+    }
+}
+
+/// Decides when an [SacnSender] sends frames, and which universes it sends
+/// in them.
+///
+/// Frames are limited to the maximum sACN refresh rate. Within a frame, a
+/// universe is only sent if it changed, or if it was last sent longer than
+/// the keep-alive interval ago.
+struct SacnSchedule {
+    frame_interval: Duration,
+    keep_alive_interval: Duration,
+    next_frame: Option<Instant>,
+    /// When each universe was last sent, and what was sent.
+    last_sent: HashMap<sacn::UniverseNumber, (Instant, Universe)>,
+}
+
+impl SacnSchedule {
+    fn new(max_refresh_rate_hz: f32, keep_alive_interval: Duration) -> Self {
+        Self {
+            frame_interval: Duration::from_secs_f32(1.0 / max_refresh_rate_hz),
+            keep_alive_interval,
+            next_frame: None,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    /// Returns whether a frame can be sent at `now`, and if so, schedules the
+    /// next one.
+    fn frame_due(&mut self, now: Instant) -> bool {
+        let next_frame = match self.next_frame {
+            // Allow frames to be a little early, so jitter doesn't cause every
+            // other frame to be skipped when the server sends at the maximum
+            // rate.
+            Some(next_frame) if now + self.frame_interval / 10 < next_frame => return false,
+            // Stay on the schedule, unless frames have not been sent for a
+            // while.
+            Some(next_frame) if now < next_frame + self.frame_interval => next_frame,
+            _ => now,
+        };
+        self.next_frame = Some(next_frame + self.frame_interval);
+        true
+    }
+
+    /// Returns whether the universe has to be sent at `now`, and if so,
+    /// records it as sent.
+    fn universe_due(
+        &mut self,
+        number: sacn::UniverseNumber,
+        universe: &Universe,
+        now: Instant,
+    ) -> bool {
+        if let Some((sent_at, sent)) = self.last_sent.get(&number)
+            && sent == universe
+            && now - *sent_at < self.keep_alive_interval
+        {
+            return false;
+        }
+        self.last_sent.insert(number, (now, universe.clone()));
+        true
+    }
+
+    /// Forgets universes that are no longer sent, so they are sent right away
+    /// if they return.
+    fn retain_universes(&mut self, numbers: &[sacn::UniverseNumber]) {
+        self.last_sent.retain(|number, _| numbers.contains(number));
+    }
+}
+
+/// Identifies the configuration an sACN source was created with. A source
+/// keeps running when the protocols are reloaded if its key is unchanged.
+#[derive(Debug, Clone, PartialEq)]
+struct SacnSourceKey {
+    output: SacnOutput,
+    cid: sacn::ComponentIdentifier,
+    source_name: String,
+    universes: SacnUniverses,
+    max_refresh_rate_hz: f32,
+    keep_alive_interval: Duration,
+}
+
+impl SacnSourceKey {
+    fn new(sacn: &Sacn, output: &SacnOutput, cid: sacn::ComponentIdentifier) -> Self {
+        Self {
+            output: output.clone(),
+            cid,
+            source_name: sacn.source_name().to_owned(),
+            universes: sacn.universes().clone(),
+            max_refresh_rate_hz: sacn.max_refresh_rate_hz(),
+            keep_alive_interval: sacn.keep_alive_interval(),
+        }
+    }
+}
+
+/// A running output thread, and the frames sent to it.
+struct OutputThread {
+    /// The configuration of the sACN source, or `None` for other outputs,
+    /// which are not affected by reloading the protocols.
+    sacn_key: Option<SacnSourceKey>,
+    tx: crossbeam_channel::Sender<Frame>,
+    handle: JoinHandle<()>,
+}
+
+impl OutputThread {
+    /// Stops the output after it has handled all frames.
+    fn stop(self) {
+        // Dropping the sender stops the thread, which drops the output. An
+        // sACN source terminates the streams of its universes when dropped.
+        drop(self.tx);
+        let _ = self.handle.join();
+    }
+}
+
+/// A frame sent to the output threads.
+enum Frame {
+    /// Send the current output.
+    Output,
+    /// Send all universes of the current output with every channel at zero.
+    Blackout,
 }
 
 pub struct ProtocolsProcess {
     server_state: Arc<ServerState>,
-    tx: crossbeam_channel::Sender<()>,
-    rx: crossbeam_channel::Receiver<()>,
-    sacn_sources: RefCell<Vec<JoinHandle<()>>>,
+    frame_time: Duration,
+    blackout_on_exit: bool,
+    outputs: RefCell<Vec<OutputThread>>,
     shutdown: RefCell<bool>,
+    /// The CID of sources that have no CID configured.
+    default_cid: sacn::ComponentIdentifier,
+    /// Receives the protocols to reload, see [ServerState::reload_protocols].
+    protocol_changes: Option<watch::Receiver<Protocols>>,
 }
 
 impl ProtocolsProcess {
-    pub fn new(protocols: Protocols, server_state: Arc<ServerState>) -> Result<Self, Error> {
-        let (tx, rx) = crossbeam_channel::unbounded();
+    pub fn new(
+        protocols: &Protocols,
+        config: &Config,
+        server_state: Arc<ServerState>,
+    ) -> Result<Self, Error> {
+        let mut protocol_changes = server_state.protocols.subscribe();
+        protocol_changes.mark_unchanged();
+
         let this = Self {
             server_state,
-            tx,
-            rx,
-            sacn_sources: RefCell::new(Vec::new()),
+            frame_time: Duration::from_secs_f32(1.0 / config.output_refresh_rate_hz()),
+            blackout_on_exit: config.blackout_on_exit(),
+            outputs: RefCell::new(Vec::new()),
             shutdown: RefCell::new(false),
+            // Without a configured CID, receivers see a new source every run.
+            default_cid: sacn::ComponentIdentifier::new_v4(),
+            protocol_changes: Some(protocol_changes),
         };
 
+        let cid = protocols.sacn().cid().unwrap_or(this.default_cid);
         for sacn_output in protocols.sacn().outputs() {
-            let ip = match sacn_output.mode() {
-                SacnMode::Unicast { destination_ip } => destination_ip,
-                SacnMode::Multicast => todo!(),
-            };
-
-            this.add_sacn_source(
-                sacn_output.label().to_owned(),
-                ip,
-                sacn_output.priority(),
-                sacn_output.preview_data(),
-            )?;
+            this.add_sacn_source(protocols.sacn(), sacn_output, cid)?;
         }
 
         Ok(this)
     }
 
-    pub fn start(self) {
+    /// Sends output at the configured refresh rate until `stop` is set, and
+    /// then shuts down. The protocols are reloaded between frames when they
+    /// change.
+    pub fn start(mut self, stop: &AtomicBool) {
         let start_time = Instant::now();
         let mut frame_count = 0;
         let mut total_frame_time = Duration::ZERO;
 
-        loop {
+        while !stop.load(Ordering::Relaxed) {
             let frame_start = Instant::now();
 
-            let target_time = start_time + DMX_OUTPUT_FRAME_TIME * frame_count;
+            let target_time = start_time + self.frame_time * frame_count;
             let now = Instant::now();
 
             if frame_count != 0 {
@@ -80,13 +337,17 @@ impl ProtocolsProcess {
                     spin_sleep::sleep(target_time - now);
                 } else {
                     let overrun = now - target_time;
-                    if overrun > DMX_OUTPUT_FRAME_TIME {
+                    if overrun > self.frame_time {
                         log::warn!("frame {frame_count} overrun by {overrun:?}");
                     }
                 }
             }
 
-            self.tx.send(()).expect("should send new frame notifier to protocols");
+            self.reload_changed_protocols();
+
+            for output in self.outputs.borrow().iter() {
+                output.tx.send(Frame::Output).expect("should send new frame notifier to protocols");
+            }
 
             let frame_end = Instant::now();
             let frame_time = frame_end - frame_start;
@@ -96,8 +357,11 @@ impl ProtocolsProcess {
 
             frame_count += 1;
         }
+
+        self.shutdown();
     }
 
+    /// Stops all outputs, sending a final blackout frame first if configured.
     pub fn shutdown(&self) {
         let mut shutdown = self.shutdown.borrow_mut();
         if *shutdown {
@@ -105,54 +369,138 @@ impl ProtocolsProcess {
         }
         *shutdown = true;
 
-        // Join all threads
-        for handle in self.sacn_sources.borrow_mut().drain(..) {
-            let _ = handle.join();
+        // Dropping the sender stops the output thread after it has handled
+        // all frames, so join all threads after the final frame.
+        for output in self.outputs.borrow_mut().drain(..) {
+            if self.blackout_on_exit {
+                let _ = output.tx.send(Frame::Blackout);
+            }
+            output.stop();
         }
     }
 
+    /// Reloads the protocols if they changed since the last reload.
+    fn reload_changed_protocols(&mut self) {
+        let Some(protocol_changes) = &mut self.protocol_changes else { return };
+        if !protocol_changes.has_changed().unwrap_or(false) {
+            return;
+        }
+        let protocols = protocol_changes.borrow_and_update().clone();
+        match self.reload(&protocols) {
+            Ok(()) => log::info!("reloaded protocols"),
+            Err(err) => log::error!("failed to reload protocols: {err}"),
+        }
+    }
+
+    /// Changes the running sACN sources to match the protocols.
+    ///
+    /// Sources whose configuration is unchanged keep running without
+    /// interruption. Removed sources terminate the streams of their universes,
+    /// and new sources are started. Any change to the sACN settings that are
+    /// shared by all outputs, like the universe mapping, restarts every source.
+    pub fn reload(&self, protocols: &Protocols) -> Result<(), Error> {
+        let sacn = protocols.sacn();
+        let cid = sacn.cid().unwrap_or(self.default_cid);
+
+        let mut removed = self.outputs.borrow_mut().drain(..).collect::<Vec<_>>();
+        let mut kept = Vec::new();
+        let mut added = Vec::new();
+        for sacn_output in sacn.outputs() {
+            let key = SacnSourceKey::new(sacn, sacn_output, cid);
+            match removed.iter().position(|output| output.sacn_key.as_ref() == Some(&key)) {
+                Some(index) => kept.push(removed.swap_remove(index)),
+                None => added.push(sacn_output),
+            }
+        }
+        let (other, removed) =
+            removed.into_iter().partition::<Vec<_>, _>(|output| output.sacn_key.is_none());
+        kept.extend(other);
+        *self.outputs.borrow_mut() = kept;
+
+        for output in removed {
+            output.stop();
+        }
+        for sacn_output in added {
+            self.add_sacn_source(sacn, sacn_output, cid)?;
+        }
+
+        Ok(())
+    }
+
     fn add_sacn_source(
         &self,
-        name: String,
-        ip: IpAddr,
-        priority: u8,
-        preview_data: bool,
+        sacn: &Sacn,
+        output: &SacnOutput,
+        cid: sacn::ComponentIdentifier,
     ) -> Result<(), Error> {
+        let (ip, multicast) = match output.mode() {
+            SacnMode::Unicast { destination_ip } => (destination_ip, false),
+            SacnMode::Multicast => (Ipv4Addr::UNSPECIFIED.into(), true),
+        };
+
+        let universe_destinations = match sacn.universes() {
+            SacnUniverses::Auto => BTreeMap::new(),
+            SacnUniverses::Mapped(mappings) => mappings
+                .iter()
+                .filter_map(|mapping| Some((mapping.sacn(), mapping.destination()?)))
+                .map(|(universe, destination)| match destination {
+                    SacnMode::Unicast { destination_ip } => {
+                        (universe, sacn::Destination::Unicast(destination_ip))
+                    }
+                    SacnMode::Multicast => (universe, sacn::Destination::Multicast),
+                })
+                .collect(),
+        };
+
         let source = sacn::Source::new(sacn::SourceConfig {
-            cid: SACN_CID,
-            name,
+            cid,
+            name: sacn.source_name().to_owned(),
             ip,
             port: sacn::DEFAULT_PORT,
-            priority,
-            preview_data,
+            interface: output.interface(),
+            multicast,
+            universe_destinations,
+            priority: output.priority(),
+            preview_data: output.preview_data(),
             synchronization_address: 0,
             force_synchronization: false,
         })
-        .map_err(|err| Error::Server { message: err.to_string() })?;
+        .map_err(|err| Error::Server {
+            message: format!("failed to start sACN output '{}': {err}", output.label()),
+        })?;
 
-        self.spawn_sacn_source_thread(source);
+        let sender = SacnSender {
+            source,
+            server_state: Arc::clone(&self.server_state),
+            universes: sacn.universes().clone(),
+            schedule: SacnSchedule::new(sacn.max_refresh_rate_hz(), sacn.keep_alive_interval()),
+        };
+        self.spawn_output(sender, Some(SacnSourceKey::new(sacn, output, cid)));
 
         Ok(())
     }
 
-    fn spawn_sacn_source_thread(&self, source: sacn::Source) {
-        let rx = self.rx.clone();
+    /// Spawns a thread that sends every frame to the output.
+    fn add_output(&self, output: impl Output + 'static) {
+        self.spawn_output(output, None);
+    }
+
+    fn spawn_output(&self, mut output: impl Output + 'static, sacn_key: Option<SacnSourceKey>) {
+        let (tx, rx) = crossbeam_channel::unbounded();
         let server_state = self.server_state.clone();
         let handle = thread::spawn(move || {
-            while let Ok(()) = rx.recv() {
+            while let Ok(frame) = rx.recv() {
+                // The output is cloned, so the lock isn't held while sending.
+                // Cloning is cheap, see `benches/multiverse.rs`.
                 let multiverse = server_state.output_multiverse.blocking_read().clone();
-                for (id, universe) in multiverse.universes() {
-                    let mut sacn_universe = sacn::Universe::new(**id);
-                    sacn_universe.data_slots = universe.values().iter().map(|v| v.0).collect();
-                    source
-                        .send_universe_data_packet(sacn_universe)
-                        .map_err(|err| log::error!("failed to send universe data over sACN: {err}"))
-                        .ok();
+                match frame {
+                    Frame::Output => output.send(&multiverse),
+                    Frame::Blackout => output.send_last(&blackout(&multiverse)),
                 }
             }
         });
 
-        self.sacn_sources.borrow_mut().push(handle);
+        self.outputs.borrow_mut().push(OutputThread { sacn_key, tx, handle });
     }
 }
 
@@ -161,3 +509,216 @@ impl Drop for ProtocolsProcess {
         self.shutdown();
     }
 }
+
+/// Returns a multiverse with the same universes, but every channel at zero.
+fn blackout(multiverse: &Multiverse) -> Multiverse {
+    let mut blackout = Multiverse::new();
+    for (id, _) in multiverse.universes() {
+        blackout.create_universe(*id, Universe::new());
+    }
+    blackout
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::dmx::{Address, Value};
+    use crate::server::test_util::example_state;
+
+    /// Records every multiverse it is sent.
+    struct MockOutput(Arc<Mutex<Vec<Multiverse>>>);
+
+    impl Output for MockOutput {
+        fn send(&mut self, multiverse: &Multiverse) {
+            self.0.lock().unwrap().push(multiverse.clone());
+        }
+    }
+
+    /// Runs the protocols for the given duration with a mock output and
+    /// returns all multiverses sent to it.
+    fn run(config: &str, duration: Duration) -> Vec<Multiverse> {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        run_with_output(config, duration, |_| MockOutput(Arc::clone(&sent)));
+        Arc::into_inner(sent).unwrap().into_inner().unwrap()
+    }
+
+    /// Runs the protocols for the given duration with the output returned by
+    /// `output`, and returns the server state.
+    fn run_with_output<O: Output + 'static>(
+        config: &str,
+        duration: Duration,
+        output: impl FnOnce(Arc<ServerState>) -> O,
+    ) -> Arc<ServerState> {
+        let state = Arc::new(example_state());
+        let address = Address::from_absolute(1).unwrap();
+        state.output_multiverse.blocking_write().set_value(&address, Value(255));
+
+        let config = serde_json::from_str::<Config>(config).unwrap();
+        let process =
+            ProtocolsProcess::new(&Protocols::default(), &config, Arc::clone(&state)).unwrap();
+        process.add_output(output(Arc::clone(&state)));
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || process.start(&stop)
+        });
+        thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+        thread.join().unwrap();
+
+        state
+    }
+
+    /// Returns protocols with a unicast sACN output for each priority.
+    fn sacn_protocols(priorities: &[u8]) -> Protocols {
+        let outputs = priorities
+            .iter()
+            .map(|priority| {
+                serde_json::json!({
+                    "label": format!("Priority {priority}"),
+                    "mode": { "unicast": { "destination_ip": "127.0.0.1" } },
+                    "local_universe": 1,
+                    "destination_universe": 1,
+                    "priority": priority,
+                    "preview_data": false,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({ "sacn": { "outputs": outputs } })).unwrap()
+    }
+
+    #[test]
+    fn reload_keeps_unchanged_sacn_sources() {
+        let state = Arc::new(example_state());
+        let config = serde_json::from_str::<Config>(r#"{ "blackout_on_exit": false }"#).unwrap();
+        let mut process =
+            ProtocolsProcess::new(&sacn_protocols(&[100, 110]), &config, Arc::clone(&state))
+                .unwrap();
+        let threads = |process: &ProtocolsProcess| {
+            let outputs = process.outputs.borrow();
+            let mut threads = outputs
+                .iter()
+                .map(|output| {
+                    let key = output.sacn_key.as_ref().unwrap();
+                    (key.output.priority(), output.handle.thread().id())
+                })
+                .collect::<Vec<_>>();
+            threads.sort_by_key(|(priority, _)| *priority);
+            threads
+        };
+        let before = threads(&process);
+
+        state.protocols.send_replace(sacn_protocols(&[110, 120]));
+        process.reload_changed_protocols();
+        let after = threads(&process);
+        assert_eq!(after.iter().map(|(priority, _)| *priority).collect::<Vec<_>>(), [110, 120]);
+        assert_eq!(after[0], before[1]);
+
+        // Nothing happens until the protocols change again.
+        process.reload_changed_protocols();
+        assert_eq!(threads(&process), after);
+    }
+
+    #[test]
+    fn dummy_output_records_frames() {
+        let state = run_with_output(
+            r#"{ "blackout_on_exit": false }"#,
+            Duration::from_millis(100),
+            DummyOutput::new,
+        );
+
+        let address = Address::from_absolute(1).unwrap();
+        let frames = state.recorded_frames.to_vec();
+        assert!(!frames.is_empty());
+        assert!(frames.iter().all(|frame| frame.get_value(&address) == Value(255)));
+    }
+
+    /// Records whether the output could be written while it was being sent.
+    struct LockCheckingOutput(Arc<ServerState>, Arc<Mutex<Vec<bool>>>);
+
+    impl Output for LockCheckingOutput {
+        fn send(&mut self, _: &Multiverse) {
+            let writable = self.0.output_multiverse.try_write().is_ok();
+            self.1.lock().unwrap().push(writable);
+        }
+    }
+
+    #[test]
+    fn output_is_not_locked_while_sending() {
+        let writable = Arc::new(Mutex::new(Vec::new()));
+        run_with_output(r#"{ "blackout_on_exit": true }"#, Duration::from_millis(100), |state| {
+            LockCheckingOutput(state, Arc::clone(&writable))
+        });
+
+        let writable = writable.lock().unwrap();
+        assert!(!writable.is_empty());
+        assert!(writable.iter().all(|writable| *writable));
+    }
+
+    #[test]
+    fn sends_at_refresh_rate() {
+        let sent = run(
+            r#"{ "output_refresh_rate_hz": 100, "blackout_on_exit": false }"#,
+            Duration::from_millis(500),
+        );
+
+        // Nominally 50 frames, but be generous for slow test machines.
+        assert!((35..=65).contains(&sent.len()), "{} frames", sent.len());
+    }
+
+    #[test]
+    fn sacn_schedule_limits_frame_rate() {
+        let mut schedule = SacnSchedule::new(40.0, Duration::from_secs(1));
+        let start = Instant::now();
+
+        // Frames of a server at 100 Hz, of which at most 40 per second are sent.
+        let sent = (0..100)
+            .filter(|frame| schedule.frame_due(start + Duration::from_millis(frame * 10)))
+            .count();
+        assert!((39..=41).contains(&sent), "{sent} frames");
+
+        // Frames that are a little early are not skipped.
+        let mut schedule = SacnSchedule::new(40.0, Duration::from_secs(1));
+        assert!(schedule.frame_due(start));
+        assert!(schedule.frame_due(start + Duration::from_millis(24)));
+        assert!(!schedule.frame_due(start + Duration::from_millis(30)));
+        assert!(schedule.frame_due(start + Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn sacn_schedule_sends_changes_and_keep_alives() {
+        let mut schedule = SacnSchedule::new(40.0, Duration::from_millis(1000));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut universe = Universe::new();
+
+        assert!(schedule.universe_due(1, &universe, at(0)));
+        assert!(!schedule.universe_due(1, &universe, at(500)));
+        assert!(schedule.universe_due(2, &universe, at(500)));
+
+        universe.set_value(&crate::dmx::Channel::new(1).unwrap(), Value(10));
+        assert!(schedule.universe_due(1, &universe, at(600)));
+        assert!(!schedule.universe_due(1, &universe, at(1500)));
+        assert!(schedule.universe_due(1, &universe, at(1600)));
+
+        schedule.retain_universes(&[2]);
+        assert!(schedule.universe_due(1, &universe, at(1700)));
+    }
+
+    #[test]
+    fn blackout_on_exit() {
+        let address = Address::from_absolute(1).unwrap();
+
+        let sent = run(r#"{ "blackout_on_exit": true }"#, Duration::from_millis(100));
+        let (last, frames) = sent.split_last().unwrap();
+        assert!(frames.iter().all(|frame| frame.get_value(&address) == Value(255)));
+        assert_eq!(last.get_value(&address), Value(0));
+        assert!(last.has_universe(&address.universe));
+
+        let sent = run(r#"{ "blackout_on_exit": false }"#, Duration::from_millis(100));
+        assert_eq!(sent.last().unwrap().get_value(&address), Value(255));
+    }
+}
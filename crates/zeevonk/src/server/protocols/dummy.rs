@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::dmx::Multiverse;
+use crate::server::ServerState;
+use crate::server::protocols::agent::Output;
+
+/// The number of frames kept by [RecordedFrames].
+const RECORDED_FRAMES_CAPACITY: usize = 64;
+
+/// The last frames sent to a [DummyOutput], oldest first.
+#[derive(Debug, Default)]
+pub struct RecordedFrames(Mutex<VecDeque<Multiverse>>);
+
+impl RecordedFrames {
+    pub fn push(&self, frame: Multiverse) {
+        let mut frames = self.0.lock().unwrap();
+        if frames.len() == RECORDED_FRAMES_CAPACITY {
+            frames.pop_front();
+        }
+        frames.push_back(frame);
+    }
+
+    /// Returns the last recorded frame, if any.
+    pub fn last(&self) -> Option<Multiverse> {
+        self.0.lock().unwrap().back().cloned()
+    }
+
+    /// Returns all recorded frames, oldest first.
+    pub fn to_vec(&self) -> Vec<Multiverse> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// An output that records the frames sent to it in the server state, instead
+/// of sending them over the network.
+pub struct DummyOutput {
+    server_state: Arc<ServerState>,
+}
+
+impl DummyOutput {
+    pub fn new(server_state: Arc<ServerState>) -> Self {
+        Self { server_state }
+    }
+}
+
+impl Output for DummyOutput {
+    fn send(&mut self, multiverse: &Multiverse) {
+        log::trace!("dummy output frame with {} universe(s)", multiverse.universe_ids().len());
+        self.server_state.recorded_frames.push(multiverse.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::{Address, Value};
+
+    #[test]
+    fn keeps_last_frames() {
+        let frames = RecordedFrames::default();
+        assert_eq!(frames.last(), None);
+
+        let address = Address::from_absolute(1).unwrap();
+        for value in 0..100 {
+            let mut frame = Multiverse::new();
+            frame.set_value(&address, Value(value));
+            frames.push(frame);
+        }
+
+        let recorded = frames.to_vec();
+        assert_eq!(recorded.len(), RECORDED_FRAMES_CAPACITY);
+        assert_eq!(recorded[0].get_value(&address), Value(100 - RECORDED_FRAMES_CAPACITY as u8));
+        assert_eq!(frames.last().unwrap().get_value(&address), Value(99));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod agent;
+pub mod dummy;
 
 mod sacn;
@@ -11,8 +11,12 @@ pub mod packet;
 pub mod receiver;
 pub mod source;
 
+use std::net::Ipv4Addr;
+
 use arrayvec::ArrayVec;
 
+use crate::dmx;
+
 #[allow(unused_imports)]
 pub use receiver::*;
 pub use source::*;
@@ -46,6 +50,25 @@ impl Universe {
         Universe { number, start_code_slot: start_code, data_slots: ArrayVec::new() }
     }
 
+    /// Creates a universe with the given number from a DMX universe, keeping
+    /// its start code.
+    pub fn from_dmx(number: UniverseNumber, universe: &dmx::Universe) -> Self {
+        let mut sacn_universe = Universe::with_start_code(number, universe.start_code());
+        sacn_universe.data_slots.extend(universe.as_bytes().iter().copied());
+        sacn_universe
+    }
+
+    /// Converts the universe into a DMX universe, keeping its start code.
+    /// Missing data slots are set to 0.
+    pub fn to_dmx(&self) -> dmx::Universe {
+        let mut universe = dmx::Universe::new();
+        universe.set_start_code(self.start_code_slot);
+        for (value, slot) in universe.values_mut().iter_mut().zip(&self.data_slots) {
+            *value = dmx::Value(*slot);
+        }
+        universe
+    }
+
     /// Returns the start code slot and data slots.
     pub fn slots(&self) -> ArrayVec<Slot, { 1 + MAX_UNIVERSE_SIZE }> {
         let mut slots = ArrayVec::new();
@@ -86,7 +109,75 @@ pub type ComponentIdentifier = uuid::Uuid;
 pub const DEFAULT_PORT: u16 = 5568;
 
 /// The universe number on which discovery packets will be sent.
-pub const DISCOVERY_UNIVERSE: u32 = 64214;
+pub const DISCOVERY_UNIVERSE: UniverseNumber = 64214;
+
+/// The lowest universe number that can carry data, see [dmx::SACN_UNIVERSES].
+pub const MIN_UNIVERSE: UniverseNumber = *dmx::SACN_UNIVERSES.start();
+
+/// The highest universe number that can carry data. Higher universe numbers
+/// are reserved, e.g. for the [DISCOVERY_UNIVERSE].
+pub const MAX_UNIVERSE: UniverseNumber = *dmx::SACN_UNIVERSES.end();
 
 /// The maximum size of a universe.
 pub const MAX_UNIVERSE_SIZE: usize = 512;
+
+/// # E1.31 9.3.1 Multicast Addressing
+///
+/// Returns the multicast group `239.255.<high byte>.<low byte>` that packets
+/// for the given universe are sent to.
+///
+/// Returns `None` for universe numbers that can't carry data, which are
+/// universe 0 and the reserved universes above [MAX_UNIVERSE], except for the
+/// [DISCOVERY_UNIVERSE].
+pub fn multicast_addr(universe: UniverseNumber) -> Option<Ipv4Addr> {
+    if !(MIN_UNIVERSE..=MAX_UNIVERSE).contains(&universe) && universe != DISCOVERY_UNIVERSE {
+        return None;
+    }
+
+    let [high, low] = universe.to_be_bytes();
+    Some(Ipv4Addr::new(239, 255, high, low))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::protocols::sacn::packet::{DataFraming, Dmp, Packet, Pdu};
+
+    #[test]
+    fn multicast_addresses() {
+        assert_eq!(multicast_addr(1), Some(Ipv4Addr::new(239, 255, 0, 1)));
+        assert_eq!(multicast_addr(256), Some(Ipv4Addr::new(239, 255, 1, 0)));
+        assert_eq!(multicast_addr(MAX_UNIVERSE), Some(Ipv4Addr::new(239, 255, 249, 255)));
+        assert_eq!(multicast_addr(DISCOVERY_UNIVERSE), Some(Ipv4Addr::new(239, 255, 250, 214)));
+
+        assert_eq!(multicast_addr(0), None);
+        assert_eq!(multicast_addr(MAX_UNIVERSE + 1), None);
+        assert_eq!(multicast_addr(u16::MAX), None);
+    }
+
+    #[test]
+    fn alternate_start_code_frames() {
+        let mut universe = dmx::Universe::new();
+        universe.set_start_code(0xCF);
+        universe.set_value(&dmx::Channel::new(1).unwrap(), dmx::Value(100));
+        universe.set_value(&dmx::Channel::new(512).unwrap(), dmx::Value(200));
+
+        let sacn_universe = Universe::from_dmx(5, &universe);
+        let dmp = Dmp::new(sacn_universe.slots());
+        let data_framing =
+            DataFraming::new("Zeevonk", 100, 0, 0, false, false, false, 5, dmp).unwrap();
+        let packet = Packet::new(ComponentIdentifier::new_v4(), Pdu::DataFraming(data_framing));
+
+        let decoded = Packet::decode(&packet.encode()).unwrap();
+        let Pdu::DataFraming(data_framing) = decoded.block.pdus()[0].pdu() else {
+            panic!("expected a data packet");
+        };
+        assert_eq!(data_framing.dmp().start_code_slot(), 0xCF);
+        assert_eq!(data_framing.dmp().data_slots().len(), MAX_UNIVERSE_SIZE);
+
+        let mut received = Universe::with_start_code(5, data_framing.dmp().start_code_slot());
+        received.data_slots.extend(data_framing.dmp().data_slots().iter().copied());
+        assert_eq!(received, sacn_universe);
+        assert_eq!(received.to_dmx(), universe);
+    }
+}
@@ -1,4 +1,5 @@
-use super::super::{MAX_UNIVERSE_SIZE, Slot, acn, source::SourceConfig};
+use super::super::source::SourceConfig;
+use super::super::{MAX_UNIVERSE_SIZE, Slot, acn};
 use super::{PacketError, flags_and_length, source_name_from_str};
 use arrayvec::ArrayVec;
 
@@ -1,4 +1,5 @@
-use super::super::{acn, source::SourceConfig};
+use super::super::acn;
+use super::super::source::SourceConfig;
 use super::{PacketError, flags_and_length, source_name_from_str};
 
 /// An E1.31 Universe Discovery Packet Framing Layer.
@@ -26,6 +27,11 @@ impl DiscoveryFraming {
         core::str::from_utf8(&self.source_name).unwrap()
     }
 
+    /// The Universe Discovery layer in this packet.
+    pub fn universe_discovery(&self) -> &UniverseDiscovery {
+        &self.universe_discovery
+    }
+
     pub(crate) fn from_source_config(
         config: &SourceConfig,
         universe_discovery: UniverseDiscovery,
@@ -26,7 +26,7 @@ pub enum PacketError {
     #[error("Invalid priority: {0}. Must be between 0 and 200.")]
     InvalidPriority(u8),
     /// Invalid source name length.
-    #[error("Invalid source name length: {0}. Must be between 0 and 64.")]
+    #[error("Invalid source name length: {0}. Must be between 0 and 63.")]
     InvalidSourceNameLength(usize),
 
     /// Invalid root vector.
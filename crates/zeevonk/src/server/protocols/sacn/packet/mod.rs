@@ -15,7 +15,7 @@ mod root;
 mod sync;
 
 pub use data::{DataFraming, Dmp};
-pub use discovery::DiscoveryFraming;
+pub use discovery::{DiscoveryFraming, UniverseDiscovery};
 pub use error::PacketError;
 pub use root::RootLayer;
 pub use sync::SyncFraming;
@@ -173,15 +173,15 @@ impl acn::Pdu for Pdu {
     }
 }
 
+/// Encodes the source name as a null-terminated 64 byte field.
 pub(crate) fn source_name_from_str(source_name: &str) -> Result<[u8; 64], PacketError> {
-    if source_name.len() > 64 {
-        return Err(PacketError::InvalidSourceNameLength(source_name.len()));
+    let bytes = source_name.as_bytes();
+    if bytes.len() > 63 {
+        return Err(PacketError::InvalidSourceNameLength(bytes.len()));
     }
 
-    let bytes = source_name.as_bytes();
     let mut source_name = [0u8; 64];
-    let len = bytes.len().min(64);
-    source_name[..len].copy_from_slice(&bytes[..len]);
+    source_name[..bytes.len()].copy_from_slice(bytes);
     Ok(source_name)
 }
 
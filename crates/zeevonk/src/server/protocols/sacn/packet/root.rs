@@ -1,8 +1,5 @@
-use super::{
-    ComponentIdentifier, PacketError, Pdu, Postamble, Preamble,
-    acn::{self, Postamble as _, Preamble as _},
-    flags_and_length,
-};
+use super::acn::{self, Postamble as _, Preamble as _};
+use super::{ComponentIdentifier, PacketError, Pdu, Postamble, Preamble, flags_and_length};
 
 /// An E1.31 Root Layer.
 #[derive(Debug, Clone, PartialEq, Eq)]
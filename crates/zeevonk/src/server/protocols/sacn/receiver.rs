@@ -3,7 +3,7 @@
 //! Responsible for receiving and processing sACN packets.
 
 use super::packet::{DataFraming, DiscoveryFraming, Packet, PacketError, Pdu, SyncFraming};
-use super::{DEFAULT_PORT, Universe};
+use super::{DEFAULT_PORT, Universe, UniverseNumber, multicast_addr};
 use socket2::{Domain, Socket, Type};
 use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr};
 use std::sync::{Arc, Mutex, mpsc};
@@ -26,6 +26,10 @@ pub enum ReceiverError {
     /// The connection was closed.
     #[error("Connection closed")]
     NoData,
+
+    /// The universe has no multicast address, because it can't carry data.
+    #[error("Invalid universe: {0}")]
+    InvalidUniverse(UniverseNumber),
 }
 
 /// A sACN receiver.
@@ -108,6 +112,21 @@ impl Receiver {
         self.rx.try_recv()
     }
 
+    /// Starts receiving the packets of the given universe that are sent to its
+    /// multicast address (see [multicast_addr]).
+    pub fn join_universe(&self, universe: UniverseNumber) -> Result<(), ReceiverError> {
+        let group = multicast_addr(universe).ok_or(ReceiverError::InvalidUniverse(universe))?;
+        self.inner.socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?;
+        Ok(())
+    }
+
+    /// Stops receiving the multicast packets of the given universe.
+    pub fn leave_universe(&self, universe: UniverseNumber) -> Result<(), ReceiverError> {
+        let group = multicast_addr(universe).ok_or(ReceiverError::InvalidUniverse(universe))?;
+        self.inner.socket.leave_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?;
+        Ok(())
+    }
+
     /// Returns the [ReceiverConfig] for this [Receiver].
     pub fn config(&self) -> ReceiverConfig {
         self.inner.config.lock().unwrap().clone()
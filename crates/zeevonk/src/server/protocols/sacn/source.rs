@@ -2,16 +2,24 @@
 //!
 //! Responsible for sending sACN packets.
 
-use super::packet::{DataFraming, Dmp, Packet, PacketError, Pdu};
-use super::{ComponentIdentifier, DEFAULT_PORT, Universe};
+use super::packet::{
+    DataFraming, DiscoveryFraming, Dmp, Packet, PacketError, Pdu, UniverseDiscovery,
+};
+use super::{
+    ComponentIdentifier, DEFAULT_PORT, DISCOVERY_UNIVERSE, MAX_UNIVERSE_SIZE, Universe,
+    UniverseNumber, multicast_addr,
+};
 use socket2::{Domain, SockAddr, Socket, Type};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 const DMX_SEND_INTERVAL: Duration = Duration::from_millis(44);
 const UNIVERSE_DISCOVERY_INTERVAL: Duration = Duration::from_secs(10);
+/// Number of packets with the Stream_Terminated option set that are sent when
+/// a universe stops being sent (E1.31 6.7.1).
+const STREAM_TERMINATION_PACKET_COUNT: usize = 3;
 
 /// Error type returned by a [Source].
 #[derive(Debug, thiserror::Error)]
@@ -23,6 +31,15 @@ pub enum SourceError {
     /// An [PacketError] wrapper.
     #[error(transparent)]
     Packet(#[from] PacketError),
+
+    /// The universe has no multicast address, because it can't carry data.
+    #[error("Invalid universe: {0}")]
+    InvalidUniverse(UniverseNumber),
+
+    /// The interface can't send to the destination, because it is of another
+    /// IP version, or because it is not an address of this machine.
+    #[error("Invalid interface: {0}")]
+    InvalidInterface(IpAddr),
 }
 
 /// An sACN Source.
@@ -34,14 +51,22 @@ pub struct Source {
     socket: Socket,
     addr: SockAddr,
     sequence_numbers: Mutex<HashMap<u16, u8>>,
+    /// Universes that data has been sent for and that have not been terminated.
+    active_universes: Mutex<BTreeSet<UniverseNumber>>,
     last_universe_discovery_time: Mutex<Option<Instant>>,
 }
 
 impl Source {
     /// Creates a new [Source].
     pub fn new(config: SourceConfig) -> Result<Self, SourceError> {
-        let domain = if config.ip.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
+        // Multicast addresses are IPv4 addresses.
+        let multicast = config.multicast
+            || config.universe_destinations.values().any(|d| *d == Destination::Multicast);
+        let domain = if multicast || config.ip.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
         let socket = Socket::new(domain, Type::DGRAM, None)?;
+        if let Some(interface) = config.interface {
+            bind_to_interface(&socket, domain, interface, multicast)?;
+        }
         let addr: SockAddr = SocketAddr::new(config.ip, config.port).into();
 
         Ok(Source {
@@ -49,6 +74,7 @@ impl Source {
             socket,
             addr,
             sequence_numbers: Mutex::new(HashMap::new()),
+            active_universes: Mutex::new(BTreeSet::new()),
             last_universe_discovery_time: Mutex::new(None),
         })
     }
@@ -63,9 +89,11 @@ impl Source {
         &mut self.config
     }
 
-    /// Shut down this [Source].
+    /// Shut down this [Source], terminating the streams of all universes it
+    /// was sending.
     pub fn shutdown(&self) -> Result<(), SourceError> {
         log::info!("shutting down sACN source");
+        self.terminate_universes_except(&[])?;
         self.socket.shutdown(Shutdown::Both)?;
         Ok(())
     }
@@ -78,6 +106,46 @@ impl Source {
     }
 
     pub fn send_universe_data_packet(&self, universe: Universe) -> Result<(), SourceError> {
+        self.active_universes.lock().unwrap().insert(universe.number);
+        self.send_data_packet(&universe, false)
+    }
+
+    /// Tells receivers that this source stopped sending the universe, by
+    /// sending packets with the Stream_Terminated option set (E1.31 6.7.1),
+    /// so they release it immediately instead of waiting for a timeout.
+    pub fn terminate_universe(&self, number: UniverseNumber) -> Result<(), SourceError> {
+        self.active_universes.lock().unwrap().remove(&number);
+
+        let universe = Universe::new(number);
+        for _ in 0..STREAM_TERMINATION_PACKET_COUNT {
+            self.send_data_packet(&universe, true)?;
+        }
+        Ok(())
+    }
+
+    /// Terminates all universes that data has been sent for, except the
+    /// given ones. See [Source::terminate_universe].
+    pub fn terminate_universes_except(&self, keep: &[UniverseNumber]) -> Result<(), SourceError> {
+        let terminated = self
+            .active_universes
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .filter(|number| !keep.contains(number))
+            .collect::<Vec<_>>();
+
+        for number in terminated {
+            self.terminate_universe(number)?;
+        }
+        Ok(())
+    }
+
+    fn send_data_packet(
+        &self,
+        universe: &Universe,
+        stream_terminated: bool,
+    ) -> Result<(), SourceError> {
         let sequence_number = self.next_sequence_number_for_universe(universe.number);
 
         let packet = {
@@ -85,7 +153,7 @@ impl Source {
             let data_framing = DataFraming::from_source_config(
                 &self.config,
                 sequence_number,
-                false,
+                stream_terminated,
                 universe.number,
                 dmp,
             )?;
@@ -94,11 +162,94 @@ impl Source {
         };
 
         let bytes = packet.encode();
-        self.socket.send_to(&bytes, &self.addr)?;
+        self.socket.send_to(&bytes, &self.destination(universe.number)?)?;
 
         Ok(())
     }
 
+    /// Returns the address packets for the given universe are sent to: its
+    /// destination in [SourceConfig::universe_destinations] if it has one, or
+    /// else the multicast address of the universe when multicasting, or the
+    /// configured address otherwise.
+    fn destination(&self, universe: UniverseNumber) -> Result<SockAddr, SourceError> {
+        let ip = match self.config.universe_destinations.get(&universe) {
+            Some(Destination::Unicast(ip)) => *ip,
+            Some(Destination::Multicast) => return self.multicast_destination(universe),
+            None if self.config.multicast => return self.multicast_destination(universe),
+            None => return Ok(self.addr.clone()),
+        };
+        Ok(SocketAddr::new(ip, self.config.port).into())
+    }
+
+    fn multicast_destination(&self, universe: UniverseNumber) -> Result<SockAddr, SourceError> {
+        let ip = multicast_addr(universe).ok_or(SourceError::InvalidUniverse(universe))?;
+        Ok(SocketAddr::new(ip.into(), self.config.port).into())
+    }
+
+    /// Sends Universe Discovery packets listing the given universes, if the
+    /// last ones were sent at least [UNIVERSE_DISCOVERY_INTERVAL] ago
+    /// (E1.31 4.3).
+    pub fn send_universe_discovery_if_due(
+        &self,
+        universes: &[UniverseNumber],
+    ) -> Result<(), SourceError> {
+        let now = Instant::now();
+        {
+            let mut last_time = self.last_universe_discovery_time.lock().unwrap();
+            if last_time.is_some_and(|last| now - last < UNIVERSE_DISCOVERY_INTERVAL) {
+                return Ok(());
+            }
+            *last_time = Some(now);
+        }
+
+        self.send_universe_discovery_packets(universes)
+    }
+
+    /// Sends Universe Discovery packets listing the given universes.
+    ///
+    /// Discovery packets belong to the [DISCOVERY_UNIVERSE] and are sent to
+    /// its multicast address when multicasting, or to the same destination as
+    /// the data packets otherwise.
+    pub fn send_universe_discovery_packets(
+        &self,
+        universes: &[UniverseNumber],
+    ) -> Result<(), SourceError> {
+        let destination = self.destination(DISCOVERY_UNIVERSE)?;
+        for packet in self.universe_discovery_packets(universes)? {
+            self.socket.send_to(&packet.encode(), &destination)?;
+        }
+        Ok(())
+    }
+
+    /// Creates the Universe Discovery packets listing the given universes,
+    /// split into pages of at most 512 universes (E1.31 8.5).
+    fn universe_discovery_packets(
+        &self,
+        universes: &[UniverseNumber],
+    ) -> Result<Vec<Packet>, SourceError> {
+        let mut universes = universes.to_vec();
+        universes.sort();
+        universes.dedup();
+
+        // A source without universes still sends a single, empty page.
+        let pages = universes.chunks(MAX_UNIVERSE_SIZE).collect::<Vec<_>>();
+        let pages = if pages.is_empty() { vec![&[][..]] } else { pages };
+        // There are at most 65536 / 512 = 128 pages, so page numbers fit in a `u8`.
+        let last = (pages.len() - 1) as u8;
+
+        pages
+            .into_iter()
+            .enumerate()
+            .map(|(page, universes)| {
+                let universe_discovery =
+                    UniverseDiscovery::new(page as u8, last, universes.to_vec());
+                let discovery_framing =
+                    DiscoveryFraming::from_source_config(&self.config, universe_discovery)?;
+                Ok(Packet::new(self.config.cid, Pdu::DiscoveryFraming(discovery_framing)))
+            })
+            .collect()
+    }
+
     fn next_sequence_number_for_universe(&self, universe_number: u16) -> u8 {
         let mut seq_nums = self.sequence_numbers.lock().unwrap();
         let current = seq_nums.get(&universe_number).copied().unwrap_or_default();
@@ -108,6 +259,30 @@ impl Source {
     }
 }
 
+/// Binds the socket to the interface with the given local address, so packets
+/// are sent from it instead of from the interface of the default route.
+fn bind_to_interface(
+    socket: &Socket,
+    domain: Domain,
+    interface: IpAddr,
+    multicast: bool,
+) -> Result<(), SourceError> {
+    if (domain == Domain::IPV4) != interface.is_ipv4() {
+        return Err(SourceError::InvalidInterface(interface));
+    }
+
+    socket
+        .bind(&SocketAddr::new(interface, 0).into())
+        .map_err(|_| SourceError::InvalidInterface(interface))?;
+
+    // Multicast packets are sent from the interface of the multicast route,
+    // regardless of the address the socket is bound to.
+    if let (true, IpAddr::V4(interface)) = (multicast, interface) {
+        socket.set_multicast_if_v4(&interface)?;
+    }
+    Ok(())
+}
+
 impl Drop for Source {
     fn drop(&mut self) {
         self.shutdown().ok();
@@ -122,10 +297,18 @@ pub struct SourceConfig {
     /// Name of the source.
     pub name: String,
 
-    /// IP address the source should send to.
+    /// IP address the source should send to, if not multicasting.
     pub ip: IpAddr,
     /// Port number the source should send to.
     pub port: u16,
+    /// Local address of the network interface the source should send from,
+    /// or `None` to send from the interface of the route to the destination.
+    pub interface: Option<IpAddr>,
+    /// Whether to send the packets of each universe to its multicast address
+    /// (see [multicast_addr]) instead of to `ip`.
+    pub multicast: bool,
+    /// Universes that are sent to another destination than the one above.
+    pub universe_destinations: BTreeMap<UniverseNumber, Destination>,
 
     /// The priority of the data packets sent by the source.
     pub priority: u8,
@@ -150,6 +333,15 @@ pub struct SourceConfig {
     pub force_synchronization: bool,
 }
 
+/// Where the packets of a universe are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    /// The multicast address of the universe (see [multicast_addr]).
+    Multicast,
+    /// A single receiver.
+    Unicast(IpAddr),
+}
+
 impl Default for SourceConfig {
     fn default() -> Self {
         Self {
@@ -158,6 +350,9 @@ impl Default for SourceConfig {
 
             ip: Ipv4Addr::UNSPECIFIED.into(),
             port: DEFAULT_PORT,
+            interface: None,
+            multicast: false,
+            universe_destinations: BTreeMap::new(),
 
             priority: 100,
             preview_data: false,
@@ -166,3 +361,170 @@ impl Default for SourceConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery_pages(universes: &[UniverseNumber]) -> Vec<UniverseDiscovery> {
+        let source = Source::new(SourceConfig::default()).unwrap();
+        source
+            .universe_discovery_packets(universes)
+            .unwrap()
+            .into_iter()
+            .map(|packet| match packet.block.pdus()[0].pdu() {
+                Pdu::DiscoveryFraming(framing) => framing.universe_discovery().clone(),
+                pdu => panic!("expected discovery framing, got {pdu:?}"),
+            })
+            .collect()
+    }
+
+    /// Creates a source that sends to a socket on the loopback interface.
+    fn loopback_source() -> (Source, std::net::UdpSocket) {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let source = Source::new(SourceConfig {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        })
+        .unwrap();
+        (source, receiver)
+    }
+
+    /// Receives a data packet, returning its universe and whether the stream
+    /// was terminated.
+    fn recv_data(receiver: &std::net::UdpSocket) -> (UniverseNumber, bool) {
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        let packet = Packet::decode(&buf[..len]).unwrap();
+        match packet.block.pdus()[0].pdu() {
+            Pdu::DataFraming(framing) => (framing.universe(), framing.stream_terminated()),
+            pdu => panic!("expected data framing, got {pdu:?}"),
+        }
+    }
+
+    #[test]
+    fn terminates_removed_universes() {
+        let (source, receiver) = loopback_source();
+        source.send_universe_data_packet(Universe::new(1)).unwrap();
+        source.send_universe_data_packet(Universe::new(2)).unwrap();
+        assert_eq!(recv_data(&receiver), (1, false));
+        assert_eq!(recv_data(&receiver), (2, false));
+
+        source.terminate_universes_except(&[2]).unwrap();
+        for _ in 0..STREAM_TERMINATION_PACKET_COUNT {
+            assert_eq!(recv_data(&receiver), (1, true));
+        }
+
+        // Dropping the source shuts it down. Universes that were terminated
+        // are not terminated again.
+        drop(source);
+        for _ in 0..STREAM_TERMINATION_PACKET_COUNT {
+            assert_eq!(recv_data(&receiver), (2, true));
+        }
+        receiver.set_nonblocking(true).unwrap();
+        assert!(receiver.recv(&mut [0; 1024]).is_err());
+    }
+
+    #[test]
+    fn universe_discovery_lists_sorted_universes() {
+        let pages = discovery_pages(&[3, 1, 2, 1]);
+        assert_eq!(pages.len(), 1);
+        assert_eq!((pages[0].page(), pages[0].last()), (0, 0));
+        assert_eq!(pages[0].list_of_universes(), &[1, 2, 3]);
+
+        let pages = discovery_pages(&[]);
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].list_of_universes().is_empty());
+    }
+
+    #[test]
+    fn universe_discovery_paginates_universes() {
+        let universes = (1..=1100).collect::<Vec<_>>();
+        let pages = discovery_pages(&universes);
+
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().enumerate().all(|(ix, page)| page.page() == ix as u8));
+        assert!(pages.iter().all(|page| page.last() == 2));
+        assert_eq!(pages[0].list_of_universes().len(), 512);
+        assert_eq!(pages[2].list_of_universes(), &(1025..=1100).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn multicasts_to_universe_addresses() {
+        let source = Source::new(SourceConfig { multicast: true, ..Default::default() }).unwrap();
+        let destination = |universe| source.destination(universe).map(|addr| addr.as_socket());
+
+        let expected = SocketAddr::new(Ipv4Addr::new(239, 255, 1, 2).into(), DEFAULT_PORT);
+        assert_eq!(destination(258).unwrap(), Some(expected));
+        let expected = SocketAddr::new(Ipv4Addr::new(239, 255, 250, 214).into(), DEFAULT_PORT);
+        assert_eq!(destination(DISCOVERY_UNIVERSE).unwrap(), Some(expected));
+        assert!(matches!(destination(0), Err(SourceError::InvalidUniverse(0))));
+    }
+
+    #[test]
+    fn sends_from_interface() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let source = Source::new(SourceConfig {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            port: receiver.local_addr().unwrap().port(),
+            interface: Some(Ipv4Addr::LOCALHOST.into()),
+            ..Default::default()
+        })
+        .unwrap();
+        let local_addr = source.socket.local_addr().unwrap().as_socket().unwrap();
+        assert_eq!(local_addr.ip(), IpAddr::from(Ipv4Addr::LOCALHOST));
+
+        source.send_universe_data_packet(Universe::new(1)).unwrap();
+        let (_, from) = receiver.recv_from(&mut [0; 1024]).unwrap();
+        assert_eq!(from, local_addr);
+    }
+
+    #[test]
+    fn rejects_invalid_interfaces() {
+        let source = |interface: IpAddr, multicast| {
+            Source::new(SourceConfig {
+                interface: Some(interface),
+                multicast,
+                ..Default::default()
+            })
+        };
+
+        // Not an address of this machine (TEST-NET-1).
+        let unknown = Ipv4Addr::new(192, 0, 2, 1).into();
+        assert!(
+            matches!(source(unknown, false), Err(SourceError::InvalidInterface(ip)) if ip == unknown)
+        );
+        // Multicast is sent over IPv4.
+        let ipv6 = std::net::Ipv6Addr::LOCALHOST.into();
+        assert!(matches!(source(ipv6, true), Err(SourceError::InvalidInterface(ip)) if ip == ipv6));
+        assert!(source(Ipv4Addr::LOCALHOST.into(), true).is_ok());
+    }
+
+    #[test]
+    fn sends_universes_to_their_destination() {
+        let node = Ipv4Addr::new(10, 0, 0, 5);
+        let source = Source::new(SourceConfig {
+            ip: Ipv4Addr::new(10, 0, 0, 1).into(),
+            universe_destinations: BTreeMap::from([
+                (2, Destination::Unicast(node.into())),
+                (3, Destination::Multicast),
+            ]),
+            ..Default::default()
+        })
+        .unwrap();
+        let destination = |universe| source.destination(universe).unwrap().as_socket().unwrap();
+
+        assert_eq!(
+            destination(1),
+            SocketAddr::new(Ipv4Addr::new(10, 0, 0, 1).into(), DEFAULT_PORT)
+        );
+        assert_eq!(destination(2), SocketAddr::new(node.into(), DEFAULT_PORT));
+        assert_eq!(
+            destination(3),
+            SocketAddr::new(Ipv4Addr::new(239, 255, 0, 3).into(), DEFAULT_PORT)
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use super::{ServerState, show_data_builder};
+use crate::Error;
+use crate::show::ShowData;
+use crate::showfile::{self, Protocols, Showfile};
+
+/// Interval at which the showfile is checked for changes.
+pub(super) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+impl ServerState {
+    /// Replaces the show data and notifies all clients, keeping the
+    /// attribute values of fixtures and attributes that still exist.
+    pub(super) async fn replace_show_data(&self, show_data: ShowData) {
+        let exists = |path, attribute| {
+            show_data
+                .patch()
+                .fixture(&path)
+                .is_some_and(|fixture| fixture.has_attribute(&attribute))
+        };
+
+        self.fades.write().await.retain(|(path, attribute), _| exists(*path, *attribute));
+        let mut pending_attribute_values = self.pending_attribute_values.write().await;
+        let removed = pending_attribute_values
+            .values()
+            .map(|(key, _)| *key)
+            .filter(|(path, attribute)| !exists(*path, *attribute))
+            .collect::<Vec<_>>();
+        for (path, attribute) in removed {
+            pending_attribute_values.remove(path, attribute);
+        }
+        drop(pending_attribute_values);
+
+        *self.show_data.write().await = show_data;
+        self.mark_all_dirty().await;
+        self.resolve_values().await;
+
+        // Sending only fails if no clients are connected.
+        let _ = self.show_data_changes.send(());
+    }
+}
+
+/// Reloads the showfile at the path every time its description changes on
+/// disk. A showfile that fails to load is logged and the current show data
+/// is kept.
+///
+/// The patch, fixture types and protocols are reloaded, see
+/// [ServerState::reload_protocols]. The config and triggers are read once,
+/// when the server starts.
+pub(super) async fn watch(state: Arc<ServerState>, path: PathBuf, interval: Duration) {
+    let description_path = Showfile::description_path(&path);
+    let mut last_modified = modified(&description_path);
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let modified = modified(&description_path);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        log::info!("showfile changed, reloading {}", path.display());
+        let loaded = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || load_show_data(&path)
+        })
+        .await;
+        match loaded {
+            Ok(Ok((show_data, protocols))) => {
+                state.replace_show_data(show_data).await;
+                for issue in state.reload_protocols(protocols).await {
+                    log::error!("keeping the current protocols: {issue}");
+                }
+                log::info!("showfile reloaded");
+            }
+            Ok(Err(err)) => {
+                log::error!("failed to reload showfile, keeping the current one: {err}")
+            }
+            Err(err) => log::error!("failed to reload showfile: {err}"),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn load_show_data(path: &Path) -> Result<(ShowData, Protocols), Error> {
+    let showfile = Showfile::load(path).map_err(|err| Error::server(err.to_string()))?;
+    match showfile.check_valid() {
+        Ok(()) => {}
+        Err(showfile::Error::Invalid(issues)) => {
+            for issue in &issues {
+                log::error!("{issue}");
+            }
+            return Err(Error::server(format!(
+                "showfile has {} validation issue(s)",
+                issues.len()
+            )));
+        }
+        Err(err) => return Err(Error::server(err.to_string())),
+    }
+    let show_data = show_data_builder::build_from_showfile(&showfile)?;
+    Ok((show_data, showfile.protocols().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attr::Attribute;
+    use crate::fpath;
+    use crate::server::test_util::{copy_example, example_showfile, example_state};
+    use crate::show::fixture::FixtureId;
+    use crate::value::ClampedValue;
+
+    /// Returns the example showfile without fixture 601.
+    fn showfile_without_601() -> Showfile {
+        let mut showfile = example_showfile();
+        showfile.patch_mut().remove_fixture(FixtureId::new(601).unwrap()).unwrap();
+        showfile
+    }
+
+    fn has_fixture(show_data: &ShowData, id: u32) -> bool {
+        show_data.patch().fixture(&FixtureId::new(id).unwrap().into()).is_some()
+    }
+
+    #[tokio::test]
+    async fn replacing_show_data_drops_removed_fixtures() {
+        let state = example_state();
+        let mut show_data_changes = state.show_data_changes.subscribe();
+        let kept = (fpath![101, 1, 1], Attribute::Dimmer);
+        let removed = (fpath![601], Attribute::Dimmer);
+        state.set_attribute_value(kept.0, kept.1, ClampedValue::new(1.0)).await;
+        state.set_attribute_value(removed.0, removed.1, ClampedValue::new(1.0)).await;
+
+        let show_data = show_data_builder::build_from_showfile(&showfile_without_601()).unwrap();
+        state.replace_show_data(show_data).await;
+
+        show_data_changes.try_recv().unwrap();
+        assert!(!has_fixture(&*state.show_data.read().await, 601));
+        let pending_attribute_values = state.pending_attribute_values.read().await;
+        assert_eq!(pending_attribute_values.get(kept.0, kept.1), Some(ClampedValue::new(1.0)));
+        assert_eq!(pending_attribute_values.get(removed.0, removed.1), None);
+    }
+
+    #[test]
+    fn invalid_showfile_is_not_loaded() {
+        let path = copy_example("load-invalid-showfile");
+        fs::write(path.join("showfile.json"), "{ invalid").unwrap();
+        assert!(load_show_data(&path).is_err());
+
+        showfile_without_601().save_to_folder(&path).unwrap();
+        let (show_data, _) = load_show_data(&path).unwrap();
+        assert!(!has_fixture(&show_data, 601));
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn watch_keeps_show_data_of_invalid_showfile() {
+        let path = copy_example("watch-showfile");
+        let state =
+            Arc::new(ServerState::new(&Showfile::load_from_folder(&path).unwrap()).unwrap());
+        let mut show_data_changes = state.show_data_changes.subscribe();
+        let watcher = tokio::spawn(watch(Arc::clone(&state), path.clone(), POLL_INTERVAL));
+        // Let the watcher read the modification time of the showfile.
+        tokio::task::yield_now().await;
+
+        fs::write(path.join("showfile.json"), "{ invalid").unwrap();
+        tokio::time::sleep(POLL_INTERVAL * 2).await;
+        assert!(show_data_changes.try_recv().is_err());
+        assert!(has_fixture(&*state.show_data.read().await, 601));
+
+        showfile_without_601().save_to_folder(&path).unwrap();
+        tokio::time::timeout(POLL_INTERVAL * 2, show_data_changes.recv()).await.unwrap().unwrap();
+        assert!(!has_fixture(&*state.show_data.read().await, 601));
+
+        watcher.abort();
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -1,27 +1,217 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::Instant;
+
 use tokio::sync::RwLock;
 
-use crate::attr::Attribute;
-use crate::dmx::Multiverse;
+use crate::attr::{Attribute, AttributeCategory};
+use crate::dmx::{self, Address, Multiverse};
 use crate::packet::AttributeValues;
 use crate::server::ServerState;
 use crate::show::ShowData;
 use crate::show::fixture::{
     FixtureChannelFunction, FixtureChannelFunctionKind, FixturePath, Relation, RelationKind,
 };
-use crate::value::ClampedValue;
+use crate::show::patch::Patch;
+use crate::value::{ClampedValue, DimmerCurve};
 
 impl ServerState {
+    /// Resolves all attribute values into the output multiverse.
     pub async fn resolve_values(&self) {
-        // Use the defaulted multiverse as the new output multiverse.
-        *self.output_multiverse.write().await =
-            self.show_data.read().await.patch().default_multiverse().clone();
+        let start = Instant::now();
+
+        let mut output_multiverse = self.output_multiverse.write().await;
+        // Fixtures that become dirty while resolving stay dirty for the next
+        // resolve.
+        self.dirty_fixtures.write().await.clear();
+        self.resolve_into(&mut output_multiverse).await;
+
+        let related = related_fixtures(self.show_data.read().await.patch());
+        self.dirty_fixtures.write().await.related = related;
+
+        self.stats.resolved(start.elapsed());
+    }
+
+    /// Resolves only the fixtures whose attribute values changed since the
+    /// last resolve, updating their channels in the output multiverse in
+    /// place. Fixtures that are connected to them by virtual channel
+    /// relations or shared addresses are resolved with them.
+    ///
+    /// Falls back to [ServerState::resolve_values] if the patch changed, or
+    /// nothing was resolved yet.
+    pub async fn resolve_dirty(&self) {
+        let start = Instant::now();
+
+        let mut output_multiverse = self.output_multiverse.write().await;
+        let fixture_paths = {
+            let mut dirty_fixtures = self.dirty_fixtures.write().await;
+            if dirty_fixtures.all {
+                drop(dirty_fixtures);
+                drop(output_multiverse);
+                self.resolve_values().await;
+                return;
+            }
+            dirty_fixtures.take()
+        };
+        if fixture_paths.is_empty() {
+            return;
+        }
+
+        // Reset the channels of the fixtures, like a full resolve starts from
+        // the defaulted multiverse.
+        {
+            let show_data = self.show_data.read().await;
+            let patch = show_data.patch();
+            let addresses = fixture_paths
+                .iter()
+                .filter_map(|path| patch.fixture(path))
+                .flat_map(|fixture| fixture.physical_addresses());
+            for address in addresses {
+                let value = match self.output_defaults_when_idle {
+                    true => patch.default_multiverse().get_value(address),
+                    false => dmx::Value::default(),
+                };
+                output_multiverse.set_value(address, value);
+            }
+        }
 
-        Resolver::new(&self.pending_attribute_values, &self.show_data, &self.output_multiverse)
-            .resolve()
+        Resolver::new(&self.pending_attribute_values, &self.show_data, &mut output_multiverse)
+            .resolve_fixtures(fixture_paths.iter().copied())
             .await;
+
+        self.masters.read().await.apply_to_fixtures(
+            self.show_data.read().await.patch(),
+            &fixture_paths,
+            &mut output_multiverse,
+        );
+        self.apply_overrides(&mut output_multiverse).await;
+
+        self.stats.resolved(start.elapsed());
+    }
+
+    /// Resolves all attribute values into `target`, replacing its contents.
+    ///
+    /// Universes that are already in `target` are overwritten in place, so
+    /// resolving into the same multiverse every frame does not allocate.
+    pub async fn resolve_into(&self, target: &mut Multiverse) {
+        // Start from the defaulted multiverse, or the same universes at zero
+        // if defaults are not output.
+        target.copy_from(self.show_data.read().await.patch().default_multiverse());
+        if !self.output_defaults_when_idle {
+            target.clear();
+        }
+
+        Resolver::new(&self.pending_attribute_values, &self.show_data, target).resolve().await;
+
+        // Masters scale the resolved values, but not the raw DMX overrides
+        // and parked addresses that are applied on top of them.
+        self.masters.read().await.apply(self.show_data.read().await.patch(), target);
+        self.apply_overrides(target).await;
+    }
+
+    /// Applies the raw DMX overrides and parked addresses, which always win
+    /// over resolved values. Parked addresses win over everything.
+    async fn apply_overrides(&self, target: &mut Multiverse) {
+        let raw_dmx_overrides = self.raw_dmx_overrides.read().await;
+        let parked_addresses = self.parked_addresses.read().await;
+        for (address, value) in raw_dmx_overrides.iter().chain(parked_addresses.iter()) {
+            target.set_value(address, *value);
+        }
+    }
+
+    /// Marks the fixture as dirty, so [ServerState::resolve_dirty] resolves
+    /// it.
+    pub(super) async fn mark_dirty(&self, fixture_path: FixturePath) {
+        self.dirty_fixtures.write().await.paths.insert(fixture_path);
+    }
+
+    /// Makes the next [ServerState::resolve_dirty] resolve all fixtures, e.g.
+    /// because the patch changed.
+    pub(super) async fn mark_all_dirty(&self) {
+        self.dirty_fixtures.write().await.all = true;
+    }
+}
+
+/// The fixtures whose attribute values changed since the last resolve.
+#[derive(Debug)]
+pub(super) struct DirtyFixtures {
+    paths: HashSet<FixturePath>,
+    /// Whether all fixtures have to be resolved.
+    all: bool,
+    /// The fixtures each fixture is connected to by virtual channel
+    /// relations or shared addresses, as of the last full resolve.
+    related: HashMap<FixturePath, Vec<FixturePath>>,
+}
+
+impl Default for DirtyFixtures {
+    fn default() -> Self {
+        Self { paths: HashSet::new(), all: true, related: HashMap::new() }
+    }
+}
+
+impl DirtyFixtures {
+    /// Marks all fixtures as clean.
+    fn clear(&mut self) {
+        self.all = false;
+        self.paths.clear();
+    }
+
+    /// Returns the dirty fixtures, together with all fixtures connected to
+    /// them, in the order of a full resolve, and marks all fixtures as clean.
+    fn take(&mut self) -> BTreeSet<FixturePath> {
+        self.all = false;
+
+        let mut affected = BTreeSet::new();
+        let mut pending = self.paths.drain().collect::<Vec<_>>();
+        while let Some(path) = pending.pop() {
+            if affected.insert(path) {
+                pending.extend(self.related.get(&path).into_iter().flatten().copied());
+            }
+        }
+        affected
+    }
+}
+
+/// Returns the fixtures each fixture is connected to, in both directions, by
+/// virtual channel relations or addresses they share. Resolving a fixture
+/// can change the output of the fixtures it is connected to.
+fn related_fixtures(patch: &Patch) -> HashMap<FixturePath, Vec<FixturePath>> {
+    let mut related = HashMap::<FixturePath, Vec<FixturePath>>::new();
+    let mut connect = |a: FixturePath, b: FixturePath| {
+        if a != b {
+            related.entry(a).or_default().push(b);
+            related.entry(b).or_default().push(a);
+        }
+    };
+
+    let mut occupants = HashMap::<Address, FixturePath>::new();
+    for fixture in patch.fixtures().values() {
+        for (_, channel_function) in fixture.channel_functions() {
+            match channel_function.kind() {
+                FixtureChannelFunctionKind::Physical { addresses } => {
+                    for address in addresses {
+                        match occupants.get(address) {
+                            Some(occupant) => connect(*occupant, fixture.path()),
+                            None => {
+                                occupants.insert(*address, fixture.path());
+                            }
+                        }
+                    }
+                }
+                FixtureChannelFunctionKind::Virtual { relations } => {
+                    for relation in relations {
+                        connect(fixture.path(), relation.fixture_path());
+                    }
+                }
+            }
+        }
     }
+
+    related
 }
 
+/// A channel function, identified by its fixture and attribute.
+type Node = (FixturePath, Attribute);
+
 /// Resolver for translating GDCS state into a physical DMX multiverse.
 ///
 /// The resolver walks the fixtures, computes the effective value for
@@ -33,12 +223,24 @@ impl ServerState {
 struct Resolver<'a> {
     attribute_values: &'a RwLock<AttributeValues>,
     show_data: &'a RwLock<ShowData>,
-    multiverse: &'a RwLock<Multiverse>,
+    multiverse: &'a mut Multiverse,
 
     /// Relations whose writes are deferred until after the initial fixture
-    /// pass. Each entry contains the relation and the resolved value to apply.
-    /// This is needed for resolving virtual channels.
-    deferred_relations: Vec<(Relation, ClampedValue)>,
+    /// pass. This is needed for resolving virtual channels.
+    deferred_relations: Vec<DeferredRelation>,
+    /// Values of followers that have been written by a relation, so that
+    /// multiple masters of the same follower are combined.
+    follower_values: HashMap<Node, ClampedValue>,
+}
+
+/// A relation write that is applied after the initial fixture pass.
+struct DeferredRelation {
+    relation: Relation,
+    /// The resolved value of the master.
+    value: ClampedValue,
+    /// The virtual channel functions that led to this write, used to detect
+    /// cycles.
+    chain: Vec<Node>,
 }
 
 impl<'a> Resolver<'a> {
@@ -46,65 +248,77 @@ impl<'a> Resolver<'a> {
     pub fn new(
         attribute_values: &'a RwLock<AttributeValues>,
         show_data: &'a RwLock<ShowData>,
-        multiverse: &'a RwLock<Multiverse>,
+        multiverse: &'a mut Multiverse,
     ) -> Self {
-        Self { attribute_values, show_data, multiverse, deferred_relations: Vec::new() }
+        Self {
+            attribute_values,
+            show_data,
+            multiverse,
+            deferred_relations: Vec::new(),
+            follower_values: HashMap::new(),
+        }
     }
 
-    /// Perform resolution and return the populated multiverse.
-    pub async fn resolve(mut self) {
+    /// Perform resolution, writing into the multiverse.
+    pub async fn resolve(self) {
         // Collect fixture paths.
         let fixture_paths: Vec<FixturePath> = {
             let show_data = self.show_data.read().await;
             show_data.patch.fixtures.keys().cloned().collect()
         };
 
+        self.resolve_fixtures(fixture_paths).await;
+    }
+
+    /// Perform resolution of only the given fixtures, writing into the
+    /// multiverse. The channels of other fixtures are left untouched.
+    pub async fn resolve_fixtures(mut self, fixture_paths: impl IntoIterator<Item = FixturePath>) {
         // Resolve each fixture independently.
         for fixture_path in fixture_paths {
             self.resolve_fixture(fixture_path).await;
         }
 
-        // FIXME: This goes only one layer of deferring deep. It might be possible to have two or more
-        // FIXME: layers of virtual channel chaining, but only the first layer gets deferred.
-        // Apply deferred relation writes. Each relation is looked up in the
-        // current show data before applying so that channel functions are resolved
-        // against the latest fixture definitions.
-        let deferred_writes = std::mem::take(&mut self.deferred_relations);
-        for (relation, value) in deferred_writes {
-            // Look up the target channel function from show data.
-            let channel_function_opt = {
-                let show_data = self.show_data.read().await;
-                show_data
-                    .patch
-                    .fixtures
-                    .get(&relation.fixture_path())
-                    .and_then(|f| f.channel_function(&relation.attribute()))
-                    .cloned()
-            };
-
-            if let Some(channel_function) = channel_function_opt {
-                self.set_channel_function_value(&channel_function, value).await;
-            }
+        // Apply deferred relation writes. Writing to a virtual channel function can
+        // defer new relation writes, so keep going until no more writes are pending.
+        // Each relation is looked up in the current show data before applying so that
+        // channel functions are resolved against the latest fixture definitions.
+        while let Some(deferred) = self.deferred_relations.pop() {
+            self.apply_relation(deferred).await;
         }
     }
 
     /// Resolve all channel functions of a single fixture.
     async fn resolve_fixture(&mut self, fixture_path: FixturePath) {
         // Snapshot the fixture's channel functions.
-        let channel_functions: Vec<(Attribute, FixtureChannelFunction)> = {
+        let (channel_functions, dimmer_curve): (Vec<(Attribute, FixtureChannelFunction)>, _) = {
             let show_data = self.show_data.read().await;
             if let Some(fixture) = show_data.patch.fixtures.get(&fixture_path) {
-                fixture.channel_functions.iter().map(|(a, cf)| (*a, cf.clone())).collect()
+                let channel_functions =
+                    fixture.channel_functions.iter().map(|(a, cf)| (*a, cf.clone())).collect();
+                (channel_functions, fixture.dimmer_curve)
             } else {
-                Vec::new()
+                (Vec::new(), DimmerCurve::Linear)
             }
         };
 
         // For each channel function, get its explicit value (if any) and apply it.
+        // Virtual channel functions are always applied, as their followers
+        // depend on them even when they are at their default value.
         for (attribute, channel_function) in channel_functions {
-            if let Some(value) = self.get_channel_function_value(fixture_path, attribute).await {
-                self.set_channel_function_value(&channel_function, value).await;
-            }
+            let node = (fixture_path, attribute);
+            let value = match self.get_channel_function_value(fixture_path, attribute).await {
+                Some(value) => value,
+                None if channel_function.is_virtual() => channel_function.relative_default(),
+                None => continue,
+            };
+            self.set_channel_function_value(
+                node,
+                &channel_function,
+                dimmer_curve,
+                value,
+                Vec::new(),
+            )
+            .await;
         }
     }
 
@@ -120,55 +334,647 @@ impl<'a> Resolver<'a> {
 
     /// Apply a computed value to a channel function.
     ///
-    /// For physical channel functions, converts the `ClampedValue` to the
-    /// appropriate byte sequence and writes it into the multiverse at the
-    /// configured addresses.
+    /// For physical channel functions, scales the `ClampedValue` into the DMX
+    /// range of the channel function, converts it to the appropriate byte
+    /// sequence and writes it into the multiverse at the configured addresses.
+    ///
+    /// For virtual channel functions, defers the writes of its relations so
+    /// that they can be applied after the initial pass. `chain` contains the
+    /// virtual channel functions whose relations led to this write.
     ///
-    /// For virtual channel functions, evaluates relations and defers the
-    /// actual writes so that they can be applied after the initial pass.
+    /// Values of intensity attributes are mapped through the dimmer curve of
+    /// the fixture first, unless an intensity attribute earlier in the chain
+    /// already was, so e.g. the colors multiplied by a virtual dimmer follow
+    /// the curve of the dimmer.
     async fn set_channel_function_value(
         &mut self,
+        node: Node,
         channel_function: &FixtureChannelFunction,
+        dimmer_curve: DimmerCurve,
         value: ClampedValue,
+        mut chain: Vec<Node>,
     ) {
+        let is_intensity =
+            |(_, attribute): &Node| attribute.category() == AttributeCategory::Intensity;
+        let curved_value = if is_intensity(&node) && !chain.iter().any(is_intensity) {
+            value.apply_curve(dimmer_curve)
+        } else {
+            value
+        };
+
         match channel_function.kind() {
             FixtureChannelFunctionKind::Physical { addresses } => {
-                let values = value.to_address_values(addresses);
-                let mut multiverse = self.multiverse.write().await;
+                let values = channel_function.to_address_values(curved_value, addresses);
                 for (address, value) in values {
-                    multiverse.set_value(&address, value);
+                    self.multiverse.set_value(&address, value);
                 }
             }
             FixtureChannelFunctionKind::Virtual { relations } => {
+                chain.push(node);
+                let is_default = value == channel_function.relative_default();
                 for relation in relations {
-                    match *relation.kind() {
-                        RelationKind::Multiply => {
-                            if let Some(follower_value) = self
-                                .get_channel_function_value(
-                                    relation.fixture_path(),
-                                    relation.attribute(),
-                                )
-                                .await
-                            {
-                                let new_value =
-                                    ClampedValue::new(follower_value.as_f32() * value.as_f32());
-                                self.defer_relation_resolution(relation.clone(), new_value);
-                            }
-                        }
-                        RelationKind::Override => {
-                            self.defer_relation_resolution(relation.clone(), value);
-                        }
+                    // An override only takes effect when the master is actually used.
+                    if matches!(relation.kind(), RelationKind::Override) && is_default {
+                        continue;
                     }
+
+                    self.deferred_relations.push(DeferredRelation {
+                        relation: relation.clone(),
+                        value: curved_value,
+                        chain: chain.clone(),
+                    });
                 }
             }
         }
     }
 
-    /// Queue a relation write to be applied after the initial resolution pass.
+    /// Write the value of a master to the follower of a relation.
     ///
-    /// Deferring relation resolutions ensures that master values are computed
-    /// before followers are written.
-    fn defer_relation_resolution(&mut self, relation: Relation, value: ClampedValue) {
-        self.deferred_relations.push((relation, value));
+    /// A [RelationKind::Multiply] relation scales the follower's value (its
+    /// explicit value, or its default if none is set) by the master's value.
+    /// A [RelationKind::Override] relation replaces it.
+    async fn apply_relation(&mut self, deferred: DeferredRelation) {
+        let DeferredRelation { relation, value, chain } = deferred;
+        let node = (relation.fixture_path(), relation.attribute());
+
+        // Relation cycles are rejected when building the show data, but we guard
+        // against them anyway so a bad patch can never hang the resolver.
+        if chain.contains(&node) {
+            log::warn!(
+                "virtual channel relations of {} {} form a cycle, skipping write",
+                node.0,
+                node.1
+            );
+            return;
+        }
+
+        // Look up the target channel function from show data.
+        let Some((channel_function, dimmer_curve)) = ({
+            let show_data = self.show_data.read().await;
+            show_data.patch.fixtures.get(&node.0).and_then(|fixture| {
+                Some((fixture.channel_function(&node.1)?.clone(), fixture.dimmer_curve))
+            })
+        }) else {
+            return;
+        };
+
+        let follower_value = match relation.kind() {
+            RelationKind::Multiply => {
+                let current = match self.follower_values.get(&node) {
+                    Some(value) => *value,
+                    None => self
+                        .get_channel_function_value(node.0, node.1)
+                        .await
+                        .unwrap_or_else(|| channel_function.relative_default()),
+                };
+                current.scale(value.as_f32())
+            }
+            RelationKind::Override => value,
+        };
+        self.follower_values.insert(node, follower_value);
+
+        self.set_channel_function_value(
+            node,
+            &channel_function,
+            dimmer_curve,
+            follower_value,
+            chain,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+impl ServerState {
+    /// Creates a state with an empty showfile and the given fixtures, which
+    /// are built by hand instead of from GDTF files, so the resolver can be
+    /// tested in isolation. The default output of the fixtures is the default
+    /// of each of their physical channel functions.
+    pub(super) fn with_fixtures_for_test(
+        fixtures: impl IntoIterator<Item = crate::show::fixture::Fixture>,
+    ) -> Self {
+        let mut state = Self::new(&crate::showfile::Showfile::default()).unwrap();
+
+        let mut default_multiverse = Multiverse::new();
+        let fixtures = fixtures
+            .into_iter()
+            .map(|fixture| {
+                for channel_function in fixture.channel_functions.values() {
+                    if let FixtureChannelFunctionKind::Physical { addresses } =
+                        channel_function.kind()
+                    {
+                        for (address, value) in
+                            channel_function.default().to_address_values(addresses)
+                        {
+                            default_multiverse.set_value(&address, value);
+                        }
+                    }
+                }
+                (fixture.path(), fixture)
+            })
+            .collect();
+
+        state.show_data.get_mut().patch =
+            Patch { fixtures, default_multiverse, groups: std::collections::BTreeMap::new() };
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::dmx::{self, Address};
+    use crate::fpath;
+    use crate::server::test_util::example_state;
+    use crate::show::fixture::{Fixture, FixtureId};
+    use crate::show::patch::Patch;
+    use crate::showfile::{self, FixtureKind, Showfile};
+    use crate::value::DimmerCurve;
+
+    /// Channel 2 of the first Sharpy is shared by its shutter and strobe functions.
+    const SHUTTER_ADDRESS: &str = "1.2";
+
+    async fn resolve(values: &[(Attribute, f32)]) -> Multiverse {
+        let state = example_state();
+
+        for (attribute, value) in values {
+            state
+                .set_attribute_value(fpath![101, 1, 1], *attribute, ClampedValue::new(*value))
+                .await;
+        }
+        state.resolve_values().await;
+
+        state.output_multiverse.read().await.clone()
+    }
+
+    fn shutter_value(multiverse: &Multiverse) -> dmx::Value {
+        multiverse.get_value(&SHUTTER_ADDRESS.parse::<Address>().unwrap())
+    }
+
+    /// Returns the first DMX address of the attribute on the first Sharpy
+    /// or one of its sub-fixtures.
+    async fn sharpy_address(state: &ServerState, attribute: Attribute) -> Address {
+        let show_data = state.show_data.read().await;
+        show_data
+            .patch()
+            .fixtures()
+            .values()
+            .filter(|fixture| fixture.path().contains(&fpath![101]))
+            .find_map(|fixture| match fixture.channel_function(&attribute)?.kind() {
+                FixtureChannelFunctionKind::Physical { addresses } => addresses.first().copied(),
+                FixtureChannelFunctionKind::Virtual { .. } => None,
+            })
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn idle_output_uses_defaults() {
+        let state = example_state();
+        let pan = sharpy_address(&state, Attribute::Pan).await;
+        let dimmer = sharpy_address(&state, Attribute::Dimmer).await;
+
+        state.resolve_values().await;
+        let multiverse = state.output_multiverse.read().await.clone();
+        assert_eq!(multiverse.get_value(&pan), dmx::Value(128));
+        assert_eq!(multiverse.get_value(&dimmer), dmx::Value(0));
+        assert_eq!(&multiverse, state.show_data.read().await.patch().default_multiverse());
+    }
+
+    #[tokio::test]
+    async fn idle_output_without_defaults_is_zero() {
+        let mut state = example_state();
+        state.output_defaults_when_idle = false;
+        let pan = sharpy_address(&state, Attribute::Pan).await;
+        let dimmer = sharpy_address(&state, Attribute::Dimmer).await;
+
+        state.resolve_values().await;
+        {
+            let multiverse = state.output_multiverse.read().await;
+            assert_eq!(multiverse.get_value(&pan), dmx::Value(0));
+            assert!(multiverse.has_universe(&pan.universe));
+        }
+
+        // Set attribute values are still output.
+        state
+            .set_attribute_value(fpath![101, 1, 1], Attribute::Dimmer, ClampedValue::new(1.0))
+            .await;
+        state.resolve_values().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&dimmer), dmx::Value(255));
+    }
+
+    #[tokio::test]
+    async fn resolving_reuses_the_output_multiverse() {
+        let state = example_state();
+        let dimmer = sharpy_address(&state, Attribute::Dimmer).await;
+
+        state.resolve_values().await;
+        let universe_ptr = |multiverse: &Multiverse| -> *const dmx::Universe {
+            multiverse.universe(&dimmer.universe).unwrap()
+        };
+        let before = universe_ptr(&*state.output_multiverse.read().await);
+
+        for frame in 0..100 {
+            let value = ClampedValue::new(frame as f32 / 100.0);
+            state.set_attribute_value(fpath![101, 1, 1], Attribute::Dimmer, value).await;
+            state.resolve_values().await;
+        }
+
+        // The universes were overwritten in place instead of replaced by a
+        // clone of the defaults.
+        let multiverse = state.output_multiverse.read().await;
+        assert_eq!(universe_ptr(&multiverse), before);
+        assert_eq!(multiverse.get_value(&dimmer), dmx::Value(252));
+    }
+
+    #[tokio::test]
+    async fn resolve_into_other_multiverse() {
+        let state = example_state();
+        state
+            .set_attribute_value(fpath![101, 1, 1], Attribute::Dimmer, ClampedValue::new(1.0))
+            .await;
+
+        let mut target = Multiverse::new();
+        target.create_universe(dmx::UniverseId::new(9).unwrap(), dmx::Universe::new());
+        state.resolve_into(&mut target).await;
+
+        state.resolve_values().await;
+        assert_eq!(target, *state.output_multiverse.read().await);
+    }
+
+    #[tokio::test]
+    async fn strobe_stays_within_its_range() {
+        let multiverse = resolve(&[(Attribute::ShutterStrobe(1), 0.0)]).await;
+        assert_eq!(shutter_value(&multiverse), dmx::Value(4));
+
+        let multiverse = resolve(&[(Attribute::ShutterStrobe(1), 1.0)]).await;
+        assert_eq!(shutter_value(&multiverse), dmx::Value(103));
+
+        let multiverse = resolve(&[(Attribute::ShutterStrobe(1), 0.5)]).await;
+        assert_eq!(shutter_value(&multiverse), dmx::Value(54));
+    }
+
+    #[tokio::test]
+    async fn shutter_stays_within_its_range() {
+        let multiverse = resolve(&[(Attribute::Shutter(1), 0.0)]).await;
+        assert_eq!(shutter_value(&multiverse), dmx::Value(252));
+
+        let multiverse = resolve(&[(Attribute::Shutter(1), 1.0)]).await;
+        assert_eq!(shutter_value(&multiverse), dmx::Value(255));
+    }
+
+    #[tokio::test]
+    async fn full_range_16_bit_function() {
+        let multiverse = resolve(&[(Attribute::Tilt, 1.0)]).await;
+        assert_eq!(multiverse.get_value(&"1.12".parse().unwrap()), dmx::Value(255));
+        assert_eq!(multiverse.get_value(&"1.13".parse().unwrap()), dmx::Value(255));
+
+        let multiverse = resolve(&[(Attribute::Tilt, 0.5)]).await;
+        assert_eq!(multiverse.get_value(&"1.12".parse().unwrap()), dmx::Value(128));
+        assert_eq!(multiverse.get_value(&"1.13".parse().unwrap()), dmx::Value(0));
+    }
+
+    #[tokio::test]
+    async fn raw_dmx_overrides_win_and_persist_until_cleared() {
+        let state = example_state();
+        let shutter = SHUTTER_ADDRESS.parse::<Address>().unwrap();
+        let relay = "2.1".parse::<Address>().unwrap();
+
+        state.set_raw_dmx(vec![(shutter, dmx::Value(7)), (relay, dmx::Value(255))]).await;
+        state
+            .set_attribute_value(fpath![101, 1, 1], Attribute::Shutter(1), ClampedValue::new(1.0))
+            .await;
+        state.resolve_values().await;
+        state.resolve_values().await;
+        assert_eq!(shutter_value(&*state.output_multiverse.read().await), dmx::Value(7));
+        assert_eq!(state.output_multiverse.read().await.get_value(&relay), dmx::Value(255));
+
+        state.clear_raw_dmx(Some(vec![shutter])).await;
+        state.resolve_values().await;
+        assert_eq!(shutter_value(&*state.output_multiverse.read().await), dmx::Value(255));
+        assert_eq!(state.output_multiverse.read().await.get_value(&relay), dmx::Value(255));
+
+        state.clear_raw_dmx(None).await;
+        state.resolve_values().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&relay), dmx::Value(0));
+    }
+
+    /// Resolves the state from scratch into a new multiverse.
+    async fn full_output(state: &ServerState) -> Multiverse {
+        let mut multiverse = Multiverse::new();
+        state.resolve_into(&mut multiverse).await;
+        multiverse
+    }
+
+    #[tokio::test]
+    async fn resolve_dirty_matches_full_resolve() {
+        let state = example_state();
+        state.set_grand_master(ClampedValue::new(0.5)).await;
+        state.resolve_values().await;
+
+        let pokes = [
+            (fpath![101, 1, 1], Attribute::Dimmer, 1.0),
+            (fpath![101], Attribute::Pan, 0.25),
+            (fpath![102, 1, 1], Attribute::Dimmer, 0.5),
+            (fpath![101, 1, 1], Attribute::Dimmer, 0.0),
+        ];
+        for (path, attribute, value) in pokes {
+            state.set_attribute_value(path, attribute, ClampedValue::new(value)).await;
+            state.resolve_dirty().await;
+            assert_eq!(*state.output_multiverse.read().await, full_output(&state).await);
+        }
+
+        state.clear_attribute_values(fpath![101]).await;
+        state.resolve_dirty().await;
+        assert_eq!(*state.output_multiverse.read().await, full_output(&state).await);
+    }
+
+    #[tokio::test]
+    async fn resolve_dirty_only_touches_dirty_fixtures() {
+        let state = example_state();
+        let dimmer = sharpy_address(&state, Attribute::Dimmer).await;
+        let other = "1.17".parse::<Address>().unwrap();
+        assert!(state.show_data.read().await.patch().fixture_at_address(&other).is_some());
+
+        state.resolve_values().await;
+        state.output_multiverse.write().await.set_value(&other, dmx::Value(42));
+
+        // Nothing is dirty, so nothing is resolved.
+        state.resolve_dirty().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&other), dmx::Value(42));
+
+        state
+            .set_attribute_value(fpath![101, 1, 1], Attribute::Dimmer, ClampedValue::new(1.0))
+            .await;
+        state.resolve_dirty().await;
+        let multiverse = state.output_multiverse.read().await.clone();
+        assert_eq!(multiverse.get_value(&dimmer), dmx::Value(255));
+        assert_eq!(multiverse.get_value(&other), dmx::Value(42));
+
+        // Patch changes resolve everything.
+        state.mark_all_dirty().await;
+        state.resolve_dirty().await;
+        assert_eq!(*state.output_multiverse.read().await, full_output(&state).await);
+    }
+
+    #[tokio::test]
+    async fn resolve_dirty_includes_related_fixtures() {
+        // Fixture 1 has a virtual dimmer that multiplies the colors of
+        // fixture 2.
+        let relations = COLORS
+            .iter()
+            .map(|attribute| Relation::new(RelationKind::Multiply, fpath![2], *attribute))
+            .collect();
+        let master = Fixture {
+            channel_functions: HashMap::from([(Attribute::Dimmer, virtual_(relations))]),
+            ..fixture_with(fpath![1], HashMap::new())
+        };
+        let colors = COLORS
+            .iter()
+            .enumerate()
+            .map(|(ix, attribute)| (*attribute, physical(ix as u32 + 1, 1.0)))
+            .collect();
+        let follower = fixture_with(fpath![2], colors);
+
+        let state = example_state();
+        state.show_data.write().await.patch = Patch {
+            default_multiverse: Multiverse::new(),
+            fixtures: BTreeMap::from([(master.path(), master), (follower.path(), follower)]),
+            groups: BTreeMap::new(),
+        };
+        state.resolve_values().await;
+
+        let red = Address::from_absolute(1).unwrap();
+        state.set_attribute_value(fpath![1], Attribute::Dimmer, ClampedValue::new(0.5)).await;
+        state.resolve_dirty().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&red), dmx::Value(128));
+
+        // Resolving the follower also applies its master.
+        state.set_attribute_value(fpath![2], Attribute::ColorAddR, ClampedValue::new(0.5)).await;
+        state.resolve_dirty().await;
+        assert_eq!(state.output_multiverse.read().await.get_value(&red), dmx::Value(64));
+        assert_eq!(*state.output_multiverse.read().await, full_output(&state).await);
+    }
+
+    const COLORS: [Attribute; 3] =
+        [Attribute::ColorAddR, Attribute::ColorAddG, Attribute::ColorAddB];
+
+    fn physical(absolute_address: u32, default: f32) -> FixtureChannelFunction {
+        let address = Address::from_absolute(absolute_address).unwrap();
+        FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Physical { addresses: vec![address] },
+            min: ClampedValue::new(0.0),
+            max: ClampedValue::new(1.0),
+            default: ClampedValue::new(default),
+        }
+    }
+
+    /// A 16-bit channel function with its coarse channel at the first and
+    /// its fine channel at the second address.
+    fn physical_16_bit(absolute_address: u32, default: f32) -> FixtureChannelFunction {
+        let addresses = [absolute_address, absolute_address + 1]
+            .map(|absolute| Address::from_absolute(absolute).unwrap());
+        FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Physical { addresses: addresses.to_vec() },
+            ..physical(absolute_address, default)
+        }
+    }
+
+    fn virtual_(relations: Vec<Relation>) -> FixtureChannelFunction {
+        FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Virtual { relations },
+            min: ClampedValue::new(0.0),
+            max: ClampedValue::new(1.0),
+            default: ClampedValue::new(1.0),
+        }
+    }
+
+    /// A state with a single fixture at path 1 with the channel functions.
+    fn state_with_channel_functions(
+        channel_functions: HashMap<Attribute, FixtureChannelFunction>,
+    ) -> ServerState {
+        ServerState::with_fixtures_for_test([fixture_with(fpath![1], channel_functions)])
+    }
+
+    fn fixture_with(
+        path: FixturePath,
+        channel_functions: HashMap<Attribute, FixtureChannelFunction>,
+    ) -> Fixture {
+        Fixture {
+            path,
+            root_base_address: Address::from_absolute(1).unwrap(),
+            name: "Virtual Dimmer".to_string(),
+            gdtf_fixture_type_id: uuid::Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions,
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        }
+    }
+
+    /// A state with the fixture in `tests/fixtures/virtual_dimmer`, built from
+    /// a crafted GDTF with a virtual dimmer mastering its color channels at
+    /// addresses 1.1 to 1.3, which default to full. The DMX modes of the
+    /// fixture type are named after the kind of their relations.
+    fn virtual_dimmer_state(kind: RelationKind) -> ServerState {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/virtual_dimmer");
+        let mut showfile = Showfile::load_from_folder(&path).unwrap();
+
+        let fixture = showfile.patch_mut().remove_fixture(FixtureId::new(1).unwrap()).unwrap();
+        let dmx_mode = match kind {
+            RelationKind::Multiply => "Multiply",
+            RelationKind::Override => "Override",
+        };
+        let fixture_type_id = fixture.kind().gdtf_fixture_type_id();
+        showfile.patch_mut().add_fixture(showfile::Fixture::new(
+            fixture.id(),
+            fixture.label(),
+            fixture.address(),
+            FixtureKind::new(fixture_type_id, dmx_mode),
+        ));
+
+        ServerState::new(&showfile).unwrap()
+    }
+
+    /// Sets the values of the fixture at path 1 and returns the resolved
+    /// output at addresses 1.1 to 1.3.
+    async fn output_values(state: &ServerState, values: &[(Attribute, f32)]) -> [u8; 3] {
+        for (attribute, value) in values {
+            state.set_attribute_value(fpath![1], *attribute, ClampedValue::new(*value)).await;
+        }
+        state.resolve_values().await;
+
+        let multiverse = state.output_multiverse.read().await;
+        [1, 2, 3].map(|a| multiverse.get_value(&Address::from_absolute(a).unwrap()).0)
+    }
+
+    #[test]
+    fn virtual_dimmer_multiplies_colors() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let state = virtual_dimmer_state(RelationKind::Multiply);
+        assert_eq!(runtime.block_on(output_values(&state, &[])), [255, 255, 255]);
+
+        let state = virtual_dimmer_state(RelationKind::Multiply);
+        let values = [(Attribute::Dimmer, 0.0)];
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [0, 0, 0]);
+
+        let state = virtual_dimmer_state(RelationKind::Multiply);
+        let values = [(Attribute::Dimmer, 0.5), (Attribute::ColorAddG, 0.5)];
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [128, 64, 128]);
+    }
+
+    #[test]
+    fn virtual_dimmer_overrides_colors_when_not_default() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let state = virtual_dimmer_state(RelationKind::Override);
+        let values = [(Attribute::ColorAddR, 0.5)];
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [128, 255, 255]);
+
+        let state = virtual_dimmer_state(RelationKind::Override);
+        let values = [(Attribute::ColorAddR, 0.5), (Attribute::Dimmer, 0.0)];
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [0, 0, 0]);
+    }
+
+    #[test]
+    fn relation_cycle_is_broken() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        // Dimmer and Shutter master each other, and Dimmer also masters red.
+        let state = state_with_channel_functions(HashMap::from([
+            (
+                Attribute::Dimmer,
+                virtual_(vec![
+                    Relation::new(RelationKind::Multiply, fpath![1], Attribute::Shutter(1)),
+                    Relation::new(RelationKind::Multiply, fpath![1], Attribute::ColorAddR),
+                ]),
+            ),
+            (
+                Attribute::Shutter(1),
+                virtual_(vec![Relation::new(RelationKind::Multiply, fpath![1], Attribute::Dimmer)]),
+            ),
+            (Attribute::ColorAddR, physical(1, 1.0)),
+        ]));
+
+        // The result of a cycle is not well-defined, but resolving should finish
+        // and still apply the dimmer.
+        let values = [(Attribute::Dimmer, 0.5)];
+        assert!(runtime.block_on(output_values(&state, &values))[0] <= 128);
+    }
+
+    fn with_dimmer_curve(state: ServerState, dimmer_curve: DimmerCurve) -> ServerState {
+        for fixture in state.show_data.blocking_write().patch.fixtures.values_mut() {
+            fixture.dimmer_curve = dimmer_curve;
+        }
+        state
+    }
+
+    #[test]
+    fn dimmer_curve_applies_to_intensity_only() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let channel_functions = || {
+            HashMap::from([
+                (Attribute::Dimmer, physical(1, 0.0)),
+                (Attribute::ColorAddR, physical(2, 0.0)),
+            ])
+        };
+        let values = [(Attribute::Dimmer, 0.5), (Attribute::ColorAddR, 0.5)];
+
+        let state = state_with_channel_functions(channel_functions());
+        assert_eq!(runtime.block_on(output_values(&state, &values))[..2], [128, 128]);
+
+        let state = with_dimmer_curve(
+            state_with_channel_functions(channel_functions()),
+            DimmerCurve::SquareLaw,
+        );
+        assert_eq!(runtime.block_on(output_values(&state, &values))[..2], [64, 128]);
+    }
+
+    #[test]
+    fn dimmer_curve_applies_to_virtual_dimmer_once() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let state =
+            with_dimmer_curve(virtual_dimmer_state(RelationKind::Multiply), DimmerCurve::SquareLaw);
+        let values = [(Attribute::Dimmer, 0.5), (Attribute::ColorAddG, 0.5)];
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [64, 32, 64]);
+
+        let state =
+            with_dimmer_curve(virtual_dimmer_state(RelationKind::Multiply), DimmerCurve::Linear);
+        assert_eq!(runtime.block_on(output_values(&state, &values)), [128, 64, 128]);
+    }
+
+    #[test]
+    fn physical_dimmer() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let state =
+            || state_with_channel_functions(HashMap::from([(Attribute::Dimmer, physical(1, 0.0))]));
+
+        assert_eq!(runtime.block_on(output_values(&state(), &[]))[0], 0);
+        let values = [(Attribute::Dimmer, 0.5)];
+        assert_eq!(runtime.block_on(output_values(&state(), &values))[0], 128);
+        let values = [(Attribute::Dimmer, 1.0)];
+        assert_eq!(runtime.block_on(output_values(&state(), &values))[0], 255);
+    }
+
+    #[test]
+    fn physical_16_bit_pan() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let state = || {
+            state_with_channel_functions(HashMap::from([(Attribute::Pan, physical_16_bit(1, 0.5))]))
+        };
+
+        assert_eq!(runtime.block_on(output_values(&state(), &[]))[..2], [128, 0]);
+        let values = [(Attribute::Pan, 0.0)];
+        assert_eq!(runtime.block_on(output_values(&state(), &values))[..2], [0, 0]);
+        let values = [(Attribute::Pan, 0.25)];
+        assert_eq!(runtime.block_on(output_values(&state(), &values))[..2], [64, 0]);
+        let values = [(Attribute::Pan, 1.0)];
+        assert_eq!(runtime.block_on(output_values(&state(), &values))[..2], [255, 255]);
     }
 }
@@ -1,69 +1,101 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fs;
 use std::str::FromStr;
+use std::{fs, io};
 
 use gdtf::dmx_mode::{ChannelFunction, DmxChannel, DmxMode, RelationType};
 use gdtf::fixture_type::FixtureType;
 use gdtf::geometry::{AnyGeometry, Geometry, ReferenceGeometry};
 use gdtf::values::Name;
+use uuid::Uuid;
 
 use crate::Error;
 use crate::attr::Attribute;
 use crate::dmx::{self, Address, Multiverse};
 use crate::show::ShowData;
 use crate::show::fixture::{
-    Fixture, FixtureChannelFunction, FixtureChannelFunctionKind, FixtureId, FixturePath, Relation,
-    RelationKind,
+    Fixture, FixtureChannelFunction, FixtureChannelFunctionKind, FixtureId, FixturePath,
+    FixtureTypeInfo, Relation, RelationKind,
 };
 use crate::show::patch::Patch;
-use crate::showfile::Showfile;
-use crate::value::ClampedValue;
+use crate::showfile::{self, Showfile};
+use crate::value::{ClampedValue, DimmerCurve};
 
-pub(crate) fn build_from_showfile(showfile: &Showfile) -> Result<ShowData, Error> {
-    let mut patch = Patch { fixtures: BTreeMap::new(), default_multiverse: Multiverse::new() };
-
-    // Get all fixture types used in the showfile patch.
-    let mut fixture_types = HashMap::new();
+/// Loads all fixture types from the GDTF files in the showfile.
+pub(crate) fn load_fixture_types(showfile: &Showfile) -> Result<HashMap<Uuid, FixtureType>, Error> {
+    let mut fixture_types = FixtureTypes::default();
     for gdtf_file_path in showfile.gdtf_file_paths() {
         let file = fs::File::open(gdtf_file_path)?;
-        let gdtf_file = gdtf::GdtfFile::new(file)
+        fixture_types.add(&gdtf_file_path.display().to_string(), file)?;
+    }
+
+    for (name, data) in showfile.embedded_gdtf_files() {
+        fixture_types
+            .add(name, io::Cursor::new(data.to_vec()))
+            .map_err(|err| Error::server(format!("{name}: {err}")))?;
+    }
+
+    // Sources are read last, so their fixture types replace the ones in the
+    // GDTF files directory.
+    for gdtf_source_path in showfile.gdtf_source_paths() {
+        let file = fs::File::open(gdtf_source_path)?;
+        fixture_types.add(&gdtf_source_path.display().to_string(), file)?;
+    }
+
+    Ok(fixture_types.fixture_types)
+}
+
+/// Fixture types by id, with the name of the GDTF file they were read from.
+#[derive(Default)]
+struct FixtureTypes {
+    fixture_types: HashMap<Uuid, FixtureType>,
+    file_names: HashMap<Uuid, String>,
+}
+
+impl FixtureTypes {
+    /// Reads a GDTF file and adds its fixture types, replacing fixture types
+    /// with the same id.
+    fn add(
+        &mut self,
+        file_name: &str,
+        reader: impl io::Read + io::Seek + 'static,
+    ) -> Result<(), Error> {
+        let gdtf_file = gdtf::GdtfFile::new(reader)
             .map_err(|err| Error::server(format!("failed to read GDTF file: {err}")))?;
 
         for fixture_type in gdtf_file.description.fixture_types {
             let fixture_type_id = fixture_type.fixture_type_id;
-            fixture_types.insert(fixture_type_id, fixture_type);
+            if let Some(other) = self.file_names.insert(fixture_type_id, file_name.to_string()) {
+                log::warn!(
+                    "fixture type {fixture_type_id} is in both '{other}' and '{file_name}', using the one in '{file_name}'"
+                );
+            }
+            self.fixture_types.insert(fixture_type_id, fixture_type);
         }
+
+        Ok(())
     }
+}
 
-    // Build all fixtures in in the showfile.
-    for fixture in showfile.patch().fixtures() {
-        let fixture_type =
-            fixture_types.get(&fixture.kind().gdtf_fixture_type_id()).ok_or_else(|| {
-                Error::server(format!(
-                    "fixture type with id {:?} not found in loaded GDTF files",
-                    fixture.kind().gdtf_fixture_type_id()
-                ))
-            })?;
-
-        let dmx_mode = fixture_type.dmx_mode(fixture.kind().gdtf_dmx_mode()).ok_or_else(|| {
-            Error::server(format!(
-                "dmx mode {:?} not found for fixture type {:?}",
-                fixture.kind().gdtf_dmx_mode(),
-                fixture.kind().gdtf_fixture_type_id()
-            ))
-        })?;
+pub(crate) fn build_from_showfile(showfile: &Showfile) -> Result<ShowData, Error> {
+    // Get all fixture types used in the showfile patch.
+    let fixture_types = load_fixture_types(showfile)?;
+    build_with_fixture_types(showfile, &fixture_types)
+}
 
-        let builder = FixtureBuilder::new(
-            fixture.id(),
-            fixture.label().to_owned(),
-            fixture.address(),
-            fixture_type,
-            dmx_mode,
-        );
+/// Builds the show data of the showfile from already loaded fixture types.
+pub(crate) fn build_with_fixture_types(
+    showfile: &Showfile,
+    fixture_types: &HashMap<Uuid, FixtureType>,
+) -> Result<ShowData, Error> {
+    let mut patch = Patch {
+        fixtures: BTreeMap::new(),
+        default_multiverse: Multiverse::new(),
+        groups: showfile.patch().groups().iter().map(|g| (g.id(), g.clone())).collect(),
+    };
 
-        let (built_fixtures, defaults) = builder
-            .build_fixture_tree()
-            .map_err(|err| Error::server(format!("failed to build fixture tree: {err}")))?;
+    // Build all fixtures in in the showfile.
+    for fixture in showfile.patch().fixtures() {
+        let (built_fixtures, defaults) = build_fixture(fixture, fixture_types)?;
         for built_fixture in built_fixtures {
             patch.fixtures.insert(built_fixture.path(), built_fixture);
         }
@@ -72,7 +104,73 @@ pub(crate) fn build_from_showfile(showfile: &Showfile) -> Result<ShowData, Error
         }
     }
 
-    Ok(ShowData { patch })
+    patch.check_relation_cycles()?;
+
+    let fixture_types =
+        fixture_types.values().map(|ft| (ft.fixture_type_id, fixture_type_info(ft))).collect();
+
+    Ok(ShowData { patch, fixture_types })
+}
+
+pub(crate) fn fixture_type_info(fixture_type: &FixtureType) -> FixtureTypeInfo {
+    FixtureTypeInfo {
+        id: fixture_type.fixture_type_id,
+        manufacturer: fixture_type.manufacturer.clone(),
+        name: fixture_type.name.as_deref().unwrap_or_default().to_string(),
+        short_name: fixture_type.short_name.clone(),
+        long_name: fixture_type.long_name.clone(),
+        dmx_modes: fixture_type
+            .dmx_modes
+            .iter()
+            .filter_map(|dmx_mode| dmx_mode.name.as_deref().map(str::to_string))
+            .collect(),
+    }
+}
+
+/// All fixtures built for a single showfile fixture, together with the
+/// default values for their physical addresses.
+pub(crate) type FixtureTree = (Vec<Fixture>, HashSet<(Address, dmx::Value)>);
+
+/// Builds the fixture tree for a single showfile fixture, together with the
+/// default values for its physical addresses.
+pub(crate) fn build_fixture(
+    fixture: &showfile::Fixture,
+    fixture_types: &HashMap<Uuid, FixtureType>,
+) -> Result<FixtureTree, Error> {
+    let fixture_type =
+        fixture_types.get(&fixture.kind().gdtf_fixture_type_id()).ok_or_else(|| {
+            Error::server(format!(
+                "fixture type with id {:?} not found in loaded GDTF files",
+                fixture.kind().gdtf_fixture_type_id()
+            ))
+        })?;
+
+    let dmx_mode = fixture_type.dmx_mode(fixture.kind().gdtf_dmx_mode()).ok_or_else(|| {
+        Error::server(format!(
+            "dmx mode {:?} not found for fixture type {:?}",
+            fixture.kind().gdtf_dmx_mode(),
+            fixture.kind().gdtf_fixture_type_id()
+        ))
+    })?;
+
+    let builder = FixtureBuilder::new(
+        fixture.id(),
+        fixture.label().to_owned(),
+        fixture.address(),
+        fixture_type,
+        dmx_mode,
+    );
+
+    let (mut fixtures, defaults) = builder
+        .build_fixture_tree()
+        .map_err(|err| Error::server(format!("failed to build fixture tree: {err}")))?;
+
+    // Sub-fixtures share the dimmer curve of their root fixture.
+    for built in &mut fixtures {
+        built.dimmer_curve = fixture.dimmer_curve();
+    }
+
+    Ok((fixtures, defaults))
 }
 
 /// Helper for building the fixture tree from a GDTF fixture type + DMX mode.
@@ -130,9 +228,7 @@ impl<'a> FixtureBuilder<'a> {
         }
     }
 
-    pub(crate) fn build_fixture_tree(
-        mut self,
-    ) -> Result<(Vec<Fixture>, HashSet<(Address, dmx::Value)>), Error> {
+    pub(crate) fn build_fixture_tree(mut self) -> Result<FixtureTree, Error> {
         // Find the root geometry for the chosen DMX mode and start the recursive building.
         let root_geometry = self.get_root_geometry()?.clone();
         let root_path = FixturePath::new(self.root_id);
@@ -260,6 +356,7 @@ impl<'a> FixtureBuilder<'a> {
             gdtf_dmx_mode: gdtf_dmx_mode_name,
             channel_functions,
             sub_fixture_paths,
+            dimmer_curve: DimmerCurve::Linear,
         }];
 
         fixtures.extend(sub_fixtures);
@@ -355,12 +452,12 @@ impl<'a> FixtureBuilder<'a> {
                     .collect::<Vec<_>>();
 
                 for (cf_ix, channel_function) in &filtered_channel_functions {
-                    // Compute the DMX range for this logical function: from current `dmx_from`
-                    // up to (but not including) the next function's `dmx_from`, or to max.
-                    let from = channel_function.dmx_from.into();
+                    // Compute the inclusive DMX range for this logical function: from current
+                    // `dmx_from` up to the value just below the next function's `dmx_from`, or to max.
+                    let from: ClampedValue = channel_function.dmx_from.into();
                     let to = filtered_channel_functions
                         .get(cf_ix + 1)
-                        .map(|(_, cf)| ClampedValue::from(cf.dmx_from))
+                        .map(|(_, cf)| dmx_value_before(cf.dmx_from))
                         .unwrap_or_else(|| ClampedValue::new(ClampedValue::MAX));
 
                     let Some(attribute) = self.attribute_from_cf(channel_function) else {
@@ -567,9 +664,18 @@ struct ChannelFunctionId {
 impl From<gdtf::values::DmxValue> for ClampedValue {
     fn from(value: gdtf::values::DmxValue) -> Self {
         let len: u8 = value.bytes().into();
-        let raw = value.to(len);
-        let max_value = 2_u64.saturating_pow(len as u32 * 8) - 1;
-        let floating_value = raw as f32 / max_value as f32;
-        ClampedValue::new(floating_value)
+        normalized_dmx_value(value.to(len), len)
     }
 }
+
+/// Returns the DMX value one step below `value`, at the resolution of `value`.
+fn dmx_value_before(value: gdtf::values::DmxValue) -> ClampedValue {
+    let len: u8 = value.bytes().into();
+    normalized_dmx_value(value.to(len).saturating_sub(1), len)
+}
+
+fn normalized_dmx_value(raw: u64, len: u8) -> ClampedValue {
+    let max_value = 2_u64.saturating_pow(len as u32 * 8) - 1;
+    let floating_value = raw as f32 / max_value as f32;
+    ClampedValue::new(floating_value)
+}
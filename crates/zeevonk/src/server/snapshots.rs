@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use tokio::sync::Mutex;
+
+use crate::Error;
+use crate::attr::Attribute;
+use crate::dmx::{self, Address};
+use crate::packet::{AttributeValues, SnapshotInfo};
+use crate::server::ServerState;
+use crate::show::fixture::FixturePath;
+use crate::showfile::Identifier;
+use crate::value::ClampedValue;
+
+/// The name of the file snapshots are persisted to, next to the showfile
+/// description. For a showfile archive, the file is named after the archive
+/// instead (`<archive>.snapshots.json`), so archives in the same folder don't
+/// share their snapshots.
+pub(super) const SNAPSHOTS_FILE_NAME: &str = "snapshots.json";
+
+/// Saved attribute values and raw DMX overrides, which can be restored later.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Snapshot {
+    label: Identifier,
+    created_at: SystemTime,
+    values: Vec<(FixturePath, Attribute, ClampedValue)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    raw_dmx_overrides: Vec<(Address, dmx::Value)>,
+}
+
+impl Snapshot {
+    fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            label: self.label.clone(),
+            created_at: self.created_at,
+            value_count: self.values.len(),
+        }
+    }
+
+    fn attribute_values(&self) -> AttributeValues {
+        self.values.iter().map(|(path, attribute, value)| ((*path, *attribute), *value)).collect()
+    }
+}
+
+/// The snapshots saved on the server, by label.
+#[derive(Debug, Default)]
+pub(super) struct Snapshots {
+    snapshots: BTreeMap<Identifier, Snapshot>,
+    /// The file the snapshots are saved to after every change, if they are
+    /// persisted.
+    file: Option<Arc<SnapshotsFile>>,
+    /// Incremented on every change, so older versions of the snapshots are
+    /// never written over newer ones.
+    generation: u64,
+}
+
+/// The file snapshots are persisted to.
+#[derive(Debug)]
+struct SnapshotsFile {
+    path: PathBuf,
+    /// The generation of the snapshots last written to the file. Held while
+    /// writing, so writes don't interleave.
+    written_generation: Mutex<u64>,
+}
+
+/// Serialized snapshots that still have to be written to their file.
+///
+/// Writing is done after releasing the lock on the snapshots, so a slow
+/// disk doesn't block other snapshot requests.
+#[must_use]
+struct PendingWrite {
+    file: Arc<SnapshotsFile>,
+    generation: u64,
+    data: Vec<u8>,
+}
+
+impl PendingWrite {
+    /// Writes the snapshots to their file, unless newer snapshots were
+    /// written already. Failing to save does not lose the snapshots in
+    /// memory, so it is only logged.
+    async fn write(self) {
+        let mut written_generation = self.file.written_generation.lock().await;
+        if *written_generation >= self.generation {
+            return;
+        }
+
+        let path = self.file.path.clone();
+        let result = tokio::task::spawn_blocking(move || fs::write(path, self.data))
+            .await
+            .map_err(|err| Error::other(err.to_string()))
+            .and_then(|result| Ok(result?));
+        match result {
+            Ok(()) => *written_generation = self.generation,
+            Err(err) => {
+                log::error!("failed to save snapshots to {}: {err}", self.file.path.display())
+            }
+        }
+    }
+}
+
+impl Snapshots {
+    /// Loads the snapshots from the file, if it exists, and persists them to
+    /// it from now on.
+    pub fn load(path: PathBuf) -> Result<Self, Error> {
+        let snapshots = match fs::read(&path) {
+            Ok(data) => serde_json::from_slice::<Vec<Snapshot>>(&data).map_err(|err| {
+                Error::other(format!("failed to load snapshots from {}: {err}", path.display()))
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let snapshots = snapshots.into_iter().map(|s| (s.label.clone(), s)).collect();
+        let file = SnapshotsFile { path, written_generation: Mutex::new(0) };
+        Ok(Self { snapshots, file: Some(Arc::new(file)), generation: 0 })
+    }
+
+    /// Returns the path of the snapshots file for the showfile at the path.
+    pub fn path_for_showfile(showfile_path: &Path) -> PathBuf {
+        if showfile_path.is_file() {
+            let mut file_name = showfile_path.file_name().unwrap_or_default().to_os_string();
+            file_name.push(".");
+            file_name.push(SNAPSHOTS_FILE_NAME);
+            showfile_path.with_file_name(file_name)
+        } else {
+            crate::showfile::Showfile::description_path(showfile_path)
+                .with_file_name(SNAPSHOTS_FILE_NAME)
+        }
+    }
+
+    /// Inserts the snapshot, returning `true` if it replaced one with the same
+    /// label, together with the write that persists the change.
+    fn insert(&mut self, snapshot: Snapshot) -> (bool, Option<PendingWrite>) {
+        let overwritten = self.snapshots.insert(snapshot.label.clone(), snapshot).is_some();
+        (overwritten, self.serialize())
+    }
+
+    /// Removes the snapshot, returning `true` if it existed, together with the
+    /// write that persists the change.
+    fn remove(&mut self, label: &Identifier) -> (bool, Option<PendingWrite>) {
+        let removed = self.snapshots.remove(label).is_some();
+        let pending_write = if removed { self.serialize() } else { None };
+        (removed, pending_write)
+    }
+
+    /// Serializes the snapshots to be written to their file, if they are
+    /// persisted. Failing to serialize is only logged, like failing to write.
+    fn serialize(&mut self) -> Option<PendingWrite> {
+        let file = self.file.as_ref()?;
+        self.generation += 1;
+        let snapshots = self.snapshots.values().collect::<Vec<_>>();
+        match serde_json::to_vec_pretty(&snapshots) {
+            Ok(data) => {
+                Some(PendingWrite { file: Arc::clone(file), generation: self.generation, data })
+            }
+            Err(err) => {
+                log::error!("failed to save snapshots to {}: {err}", file.path.display());
+                None
+            }
+        }
+    }
+}
+
+impl ServerState {
+    /// Replaces the snapshots with the ones persisted in the file.
+    pub(super) async fn load_snapshots(&self, path: PathBuf) -> Result<(), Error> {
+        let snapshots = Snapshots::load(path)?;
+        *self.snapshots.write().await = snapshots;
+        Ok(())
+    }
+
+    /// Saves the current attribute values and raw DMX overrides under the
+    /// label. If a snapshot with the label exists, it is replaced and `true`
+    /// is returned.
+    pub(super) async fn save_snapshot(&self, label: Identifier) -> bool {
+        let values = self
+            .pending_attribute_values
+            .read()
+            .await
+            .values()
+            .map(|((path, attribute), value)| (*path, *attribute, *value))
+            .collect();
+        let mut raw_dmx_overrides = self
+            .raw_dmx_overrides
+            .read()
+            .await
+            .iter()
+            .map(|(address, value)| (*address, *value))
+            .collect::<Vec<_>>();
+        raw_dmx_overrides.sort_by_key(|(address, _)| *address);
+
+        let snapshot = Snapshot { label, created_at: SystemTime::now(), values, raw_dmx_overrides };
+        let (overwritten, pending_write) = self.snapshots.write().await.insert(snapshot);
+        if let Some(pending_write) = pending_write {
+            pending_write.write().await;
+        }
+        overwritten
+    }
+
+    /// Replaces the attribute values and raw DMX overrides with the ones in
+    /// the snapshot, cancelling all fades, and resolves the output.
+    ///
+    /// Returns `false` if no snapshot with the label exists.
+    pub(super) async fn restore_snapshot(&self, label: &Identifier, peer: SocketAddr) -> bool {
+        let Some(snapshot) = self.snapshots.read().await.snapshots.get(label).cloned() else {
+            return false;
+        };
+
+        let values = snapshot.attribute_values();
+        self.cancel_fades_where(|_| true).await;
+        *self.pending_attribute_values.write().await = values.clone();
+        *self.raw_dmx_overrides.write().await =
+            snapshot.raw_dmx_overrides.into_iter().collect::<HashMap<_, _>>();
+        self.mark_all_dirty().await;
+        self.resolve_values().await;
+
+        self.notify_attribute_changes(&values, peer).await;
+        true
+    }
+
+    /// Deletes the snapshot with the label, returning `false` if it does not
+    /// exist.
+    pub(super) async fn delete_snapshot(&self, label: &Identifier) -> bool {
+        let (deleted, pending_write) = self.snapshots.write().await.remove(label);
+        if let Some(pending_write) = pending_write {
+            pending_write.write().await;
+        }
+        deleted
+    }
+
+    /// Returns information about all snapshots, sorted by label.
+    pub(super) async fn snapshot_infos(&self) -> Vec<SnapshotInfo> {
+        self.snapshots.read().await.snapshots.values().map(Snapshot::info).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fpath;
+    use crate::server::test_util::{example_path, example_state};
+
+    const PEER: SocketAddr =
+        SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+
+    fn label(label: &str) -> Identifier {
+        label.parse().unwrap()
+    }
+
+    async fn set_dimmer(state: &ServerState, value: f32) {
+        let mut values = AttributeValues::new();
+        values.set(fpath![101, 1, 1], Attribute::Dimmer, value);
+        state.set_attribute_values(&values, PEER).await;
+    }
+
+    async fn output(state: &ServerState, address: &str) -> dmx::Value {
+        state.output_multiverse.read().await.get_value(&address.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn restore_replaces_values_and_overrides() {
+        let state = example_state();
+        set_dimmer(&state, 1.0).await;
+        state.set_raw_dmx(vec![("2.1".parse().unwrap(), dmx::Value(42))]).await;
+        assert!(!state.save_snapshot(label("look")).await);
+
+        set_dimmer(&state, 0.0).await;
+        let mut values = AttributeValues::new();
+        values.set(fpath![102, 1, 1], Attribute::Dimmer, 1.0);
+        state.set_attribute_values(&values, PEER).await;
+        state.clear_raw_dmx(None).await;
+        state.resolve_values().await;
+        assert_eq!(output(&state, "1.3").await, dmx::Value(0));
+        assert_eq!(output(&state, "2.1").await, dmx::Value(0));
+
+        assert!(state.restore_snapshot(&label("look"), PEER).await);
+        assert_eq!(output(&state, "1.3").await, dmx::Value(255));
+        assert_eq!(output(&state, "2.1").await, dmx::Value(42));
+        let pending = state.pending_attribute_values.read().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending.get(fpath![101, 1, 1], Attribute::Dimmer), Some(ClampedValue::new(1.0)));
+        drop(pending);
+
+        assert!(!state.restore_snapshot(&label("unknown"), PEER).await);
+    }
+
+    #[tokio::test]
+    async fn saving_under_the_same_label_overwrites() {
+        let state = example_state();
+        set_dimmer(&state, 1.0).await;
+        assert!(!state.save_snapshot(label("b")).await);
+        assert!(!state.save_snapshot(label("a")).await);
+        state.clear_all_attribute_values().await;
+        assert!(state.save_snapshot(label("b")).await);
+
+        let infos = state.snapshot_infos().await;
+        let summary = infos.iter().map(|i| (i.label.as_str(), i.value_count)).collect::<Vec<_>>();
+        assert_eq!(summary, [("a", 1), ("b", 0)]);
+
+        assert!(state.delete_snapshot(&label("a")).await);
+        assert!(!state.delete_snapshot(&label("a")).await);
+        assert_eq!(state.snapshot_infos().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn snapshots_persist_to_file() {
+        let dir = std::env::temp_dir().join(format!("zeevonk-snapshots-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SNAPSHOTS_FILE_NAME);
+        let _ = fs::remove_file(&path);
+
+        let saved = example_state();
+        saved.load_snapshots(path.clone()).await.unwrap();
+        assert!(saved.snapshot_infos().await.is_empty());
+        set_dimmer(&saved, 1.0).await;
+        saved.set_raw_dmx(vec![("2.1".parse().unwrap(), dmx::Value(42))]).await;
+        saved.save_snapshot(label("look")).await;
+        saved.save_snapshot(label("other")).await;
+        saved.delete_snapshot(&label("other")).await;
+
+        let loaded = example_state();
+        loaded.load_snapshots(path.clone()).await.unwrap();
+        assert_eq!(loaded.snapshot_infos().await, saved.snapshot_infos().await);
+        assert!(loaded.restore_snapshot(&label("look"), PEER).await);
+        assert_eq!(output(&loaded, "1.3").await, dmx::Value(255));
+        assert_eq!(output(&loaded, "2.1").await, dmx::Value(42));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(saved.load_snapshots(path).await.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn older_writes_do_not_overwrite_newer_snapshots() {
+        let dir =
+            std::env::temp_dir().join(format!("zeevonk-snapshot-writes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SNAPSHOTS_FILE_NAME);
+        let _ = fs::remove_file(&path);
+
+        let snapshot = |name| Snapshot {
+            label: label(name),
+            created_at: SystemTime::UNIX_EPOCH,
+            values: Vec::new(),
+            raw_dmx_overrides: Vec::new(),
+        };
+        let mut snapshots = Snapshots::load(path.clone()).unwrap();
+        let (_, first) = snapshots.insert(snapshot("a"));
+        let (_, second) = snapshots.insert(snapshot("b"));
+
+        // Writes can finish in any order once the lock is released.
+        second.unwrap().write().await;
+        first.unwrap().write().await;
+
+        let loaded = Snapshots::load(path).unwrap();
+        assert_eq!(loaded.snapshots.keys().collect::<Vec<_>>(), [&label("a"), &label("b")]);
+        assert!(Snapshots::default().insert(snapshot("a")).1.is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshots_file_is_next_to_the_description() {
+        let path = example_path();
+        assert_eq!(Snapshots::path_for_showfile(&path), path.join(SNAPSHOTS_FILE_NAME));
+
+        let dir = std::env::temp_dir();
+        let archive = dir.join(format!("zeevonk-snapshots-{}.zvk", std::process::id()));
+        fs::write(&archive, []).unwrap();
+        let expected =
+            dir.join(format!("zeevonk-snapshots-{}.zvk.snapshots.json", std::process::id()));
+        assert_eq!(Snapshots::path_for_showfile(&archive), expected);
+        fs::remove_file(&archive).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::dmx::UniverseId;
+use crate::packet::{DurationHistogram, ServerStats};
+
+/// The upper bounds of the buckets resolve durations are counted in.
+const RESOLVE_DURATION_BUCKETS: [Duration; 12] = [
+    Duration::from_micros(50),
+    Duration::from_micros(100),
+    Duration::from_micros(250),
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_micros(2500),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(25),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+];
+
+/// Counters that are updated while the server is running, from which
+/// [ServerStats] are taken.
+#[derive(Debug)]
+pub(super) struct StatsCounters {
+    packets_received: Mutex<BTreeMap<&'static str, u64>>,
+    bytes_sent: AtomicU64,
+    active_clients: AtomicU32,
+    last_resolve_duration: Mutex<Duration>,
+    resolve_durations: Mutex<DurationHistogram>,
+    frames_sent: Mutex<BTreeMap<u16, u64>>,
+}
+
+impl Default for StatsCounters {
+    fn default() -> Self {
+        Self {
+            packets_received: Mutex::default(),
+            bytes_sent: AtomicU64::default(),
+            active_clients: AtomicU32::default(),
+            last_resolve_duration: Mutex::default(),
+            resolve_durations: Mutex::new(DurationHistogram::new(RESOLVE_DURATION_BUCKETS.into())),
+            frames_sent: Mutex::default(),
+        }
+    }
+}
+
+impl StatsCounters {
+    pub fn packet_received(&self, packet_type: &'static str) {
+        *self.packets_received.lock().unwrap().entry(packet_type).or_default() += 1;
+    }
+
+    pub fn bytes_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn client_connected(&self) {
+        self.active_clients.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn client_disconnected(&self) {
+        self.active_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn resolved(&self, duration: Duration) {
+        *self.last_resolve_duration.lock().unwrap() = duration;
+        self.resolve_durations.lock().unwrap().record(duration);
+    }
+
+    pub fn frame_sent(&self, universe: UniverseId) {
+        *self.frames_sent.lock().unwrap().entry(universe.into()).or_default() += 1;
+    }
+
+    /// Returns the current values of all counters.
+    pub fn snapshot(&self) -> ServerStats {
+        ServerStats {
+            packets_received: self
+                .packets_received
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(packet_type, count)| (packet_type.to_string(), *count))
+                .collect(),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            active_clients: self.active_clients.load(Ordering::Relaxed),
+            last_resolve_duration: *self.last_resolve_duration.lock().unwrap(),
+            resolve_durations: self.resolve_durations.lock().unwrap().clone(),
+            frames_sent: self.frames_sent.lock().unwrap().clone(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Fixtures shared by the tests of the server.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::ServerState;
+use crate::showfile::Showfile;
+
+/// Returns the path of the example showfile.
+pub(crate) fn example_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example_showfile")
+}
+
+/// Loads the example showfile.
+pub(crate) fn example_showfile() -> Showfile {
+    Showfile::load_from_folder(&example_path()).unwrap()
+}
+
+/// Returns a state with the show data of the example showfile.
+pub(crate) fn example_state() -> ServerState {
+    ServerState::new(&example_showfile()).unwrap()
+}
+
+/// Copies the example showfile to a new directory named after the test, so
+/// the test can change it.
+pub(crate) fn copy_example(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("zeevonk-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(path.join("gdtf_files")).unwrap();
+    for entry in fs::read_dir(example_path().join("gdtf_files")).unwrap() {
+        let entry = entry.unwrap();
+        fs::copy(entry.path(), path.join("gdtf_files").join(entry.file_name())).unwrap();
+    }
+    fs::copy(example_path().join("showfile.json"), path.join("showfile.json")).unwrap();
+    path
+}
@@ -0,0 +1,306 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+use crate::Error;
+use crate::dmx::{Address, SACN_UNIVERSES, UniverseId};
+use crate::server::show_data_builder;
+use crate::show::fixture::{FixtureId, FixturePath};
+use crate::showfile::{
+    Protocols, SacnMode, SacnUniverseMapping, SacnUniverses, Showfile, ValidationIssue,
+};
+
+/// Checks the showfile for problems that would prevent it from being loaded,
+/// collecting every issue instead of stopping at the first one.
+pub(crate) fn validate_showfile(showfile: &Showfile) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let fixture_types = match show_data_builder::load_fixture_types(showfile) {
+        Ok(fixture_types) => Some(fixture_types),
+        Err(err) => {
+            issues.push(ValidationIssue::new(None, format!("failed to load GDTF files: {err}")));
+            None
+        }
+    };
+
+    let mut seen_ids = HashSet::new();
+    let mut occupied_addresses: HashMap<Address, FixtureId> = HashMap::new();
+    // The label and first and last address of every patched fixture.
+    let mut footprints: HashMap<FixtureId, (&str, Address, Address)> = HashMap::new();
+    let mut patched_universes = BTreeSet::new();
+    let sacn_universes = showfile.protocols().sacn().universes();
+
+    for fixture in showfile.patch().fixtures() {
+        let id = fixture.id();
+
+        if !seen_ids.insert(id) {
+            issues.push(ValidationIssue::new(Some(id), "duplicate fixture id"));
+        }
+
+        if fixture.label().trim().is_empty() {
+            issues.push(ValidationIssue::new(Some(id), "label is empty"));
+        }
+
+        issues.extend(crate::showfile::dimmer_curve_issue(fixture));
+
+        let Some(fixture_types) = &fixture_types else { continue };
+
+        let fixture_type_id = fixture.kind().gdtf_fixture_type_id();
+        let Some(fixture_type) = fixture_types.get(&fixture_type_id) else {
+            issues.push(ValidationIssue::new(
+                Some(id),
+                format!("unknown fixture type {fixture_type_id}"),
+            ));
+            continue;
+        };
+
+        let dmx_mode = fixture.kind().gdtf_dmx_mode();
+        if fixture_type.dmx_mode(dmx_mode).is_none() {
+            issues.push(ValidationIssue::new(
+                Some(id),
+                format!("unknown dmx mode '{dmx_mode}' for fixture type {fixture_type_id}"),
+            ));
+            continue;
+        }
+
+        let built_fixtures = match show_data_builder::build_fixture(fixture, fixture_types) {
+            Ok((built_fixtures, _)) => built_fixtures,
+            Err(err) => {
+                issues.push(ValidationIssue::new(Some(id), err.to_string()));
+                continue;
+            }
+        };
+
+        let mut addresses =
+            built_fixtures.iter().flat_map(|f| f.physical_addresses().copied()).collect::<Vec<_>>();
+        addresses.sort();
+        addresses.dedup();
+        patched_universes.extend(addresses.iter().map(|address| address.universe));
+
+        // Explicitly mapped universes can be outside of the sACN range.
+        if *sacn_universes == SacnUniverses::Auto
+            && let Some(address) =
+                addresses.iter().find(|address| !address.universe.is_sacn_routable())
+        {
+            issues.push(ValidationIssue::new(
+                Some(id),
+                format!(
+                    "patched in universe {}, which is outside of the sACN universe range {}..={}",
+                    address.universe,
+                    SACN_UNIVERSES.start(),
+                    SACN_UNIVERSES.end()
+                ),
+            ));
+        }
+
+        let (Some(first), Some(last)) = (addresses.first().copied(), addresses.last().copied())
+        else {
+            continue;
+        };
+
+        let mut overlapping = Vec::new();
+        for address in addresses {
+            match occupied_addresses.get(&address) {
+                Some(other_id) => overlapping.push(*other_id),
+                None => {
+                    occupied_addresses.insert(address, id);
+                }
+            }
+        }
+
+        if let Some(other_id) = overlapping.first()
+            && let Some((existing_label, existing_first, existing_last)) = footprints.get(other_id)
+        {
+            let collision = Error::AddressCollision {
+                requested: fixture.address(),
+                requested_footprint: last.to_absolute() - first.to_absolute() + 1,
+                existing_fixture: FixturePath::new(*other_id),
+                existing_label: existing_label.to_string(),
+                existing_range: (*existing_first, *existing_last),
+            };
+            issues.push(ValidationIssue::new(
+                Some(id),
+                format!("{collision} ({} overlapping addresses)", overlapping.len()),
+            ));
+        }
+
+        footprints.entry(id).or_insert((fixture.label(), first, last));
+    }
+
+    for group in showfile.patch().groups() {
+        for member in group.members() {
+            if !seen_ids.contains(member) {
+                issues.push(ValidationIssue::new(
+                    Some(*member),
+                    format!("group {} references unknown fixture", group.id()),
+                ));
+            }
+        }
+    }
+
+    // Without fixture types, the patched universes are unknown.
+    let patched_universes = fixture_types.is_some().then_some(&patched_universes);
+    issues.extend(protocol_issues(showfile.protocols(), patched_universes));
+
+    issues
+}
+
+/// Checks that the protocols can send the patched universes, if known.
+pub(crate) fn protocol_issues(
+    protocols: &Protocols,
+    patched_universes: Option<&BTreeSet<UniverseId>>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    for output in protocols.sacn().outputs() {
+        if !output.mode().is_routable() {
+            issues.push(ValidationIssue::new(
+                None,
+                format!(
+                    "sACN output '{}' has destination {:?}, which can't be sent to",
+                    output.label(),
+                    output.mode()
+                ),
+            ));
+        }
+
+        if let Some(interface) = output.interface()
+            && let Some(problem) = interface_problem(interface, output.mode())
+        {
+            issues.push(ValidationIssue::new(
+                None,
+                format!(
+                    "sACN output '{}' has interface {interface}, which {problem}",
+                    output.label()
+                ),
+            ));
+        }
+
+        for (kind, universe) in
+            [("local", output.local_universe()), ("destination", output.destination_universe())]
+        {
+            if !SACN_UNIVERSES.contains(&universe) {
+                issues.push(ValidationIssue::new(
+                    None,
+                    format!(
+                        "sACN output '{}' has {kind} universe {universe}, which is outside of the sACN universe range {}..={}",
+                        output.label(),
+                        SACN_UNIVERSES.start(),
+                        SACN_UNIVERSES.end()
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let SacnUniverses::Mapped(mappings) = protocols.sacn().universes() {
+        issues.extend(sacn_universe_mapping_issues(mappings, patched_universes));
+    }
+
+    issues
+}
+
+/// Returns why packets can't be sent from the interface with the given local
+/// address to the destination, if they can't.
+///
+/// Whether the address belongs to this machine is not checked, so showfiles
+/// can be edited on another machine, see [interface_binding_issues].
+fn interface_problem(interface: IpAddr, mode: SacnMode) -> Option<&'static str> {
+    // Multicast addresses are IPv4 addresses.
+    let ipv4 = match mode {
+        SacnMode::Unicast { destination_ip } => destination_ip.is_ipv4(),
+        SacnMode::Multicast => true,
+    };
+    if interface.is_ipv4() != ipv4 {
+        return Some("can't send to the destination, because it is of another IP version");
+    }
+
+    if interface.is_unspecified() || interface.is_multicast() {
+        return Some("is not the address of a network interface");
+    }
+    None
+}
+
+/// Checks that the interfaces of the sACN outputs are addresses of network
+/// interfaces of this machine, by binding to them.
+///
+/// Only useful on the machine that sends the output, like when the protocols
+/// of a running server are reloaded.
+pub(crate) fn interface_binding_issues(protocols: &Protocols) -> Vec<ValidationIssue> {
+    protocols
+        .sacn()
+        .outputs()
+        .iter()
+        .filter_map(|output| {
+            let interface = output.interface()?;
+            UdpSocket::bind(SocketAddr::new(interface, 0)).is_err().then(|| {
+                ValidationIssue::new(
+                    None,
+                    format!(
+                        "sACN output '{}' has interface {interface}, which is not an address of a network interface of this machine",
+                        output.label()
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Checks that every universe is mapped to a valid sACN universe number at
+/// most once, and that no two universes are mapped to the same number.
+fn sacn_universe_mapping_issues(
+    mappings: &[SacnUniverseMapping],
+    patched_universes: Option<&BTreeSet<UniverseId>>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut seen_internal = HashSet::new();
+    let mut seen_sacn = HashSet::new();
+
+    for mapping in mappings {
+        let (internal, sacn) = (mapping.internal(), mapping.sacn());
+
+        if !SACN_UNIVERSES.contains(&sacn) {
+            issues.push(ValidationIssue::new(
+                None,
+                format!(
+                    "universe {internal} is mapped to sACN universe {sacn}, which is outside of the sACN universe range {}..={}",
+                    SACN_UNIVERSES.start(),
+                    SACN_UNIVERSES.end()
+                ),
+            ));
+        }
+
+        if !seen_internal.insert(internal) {
+            issues.push(ValidationIssue::new(
+                None,
+                format!("universe {internal} is mapped to sACN more than once"),
+            ));
+        }
+
+        if !seen_sacn.insert(sacn) {
+            issues.push(ValidationIssue::new(
+                None,
+                format!("multiple universes are mapped to sACN universe {sacn}"),
+            ));
+        }
+
+        if let Some(destination) = mapping.destination()
+            && !destination.is_routable()
+        {
+            issues.push(ValidationIssue::new(
+                None,
+                format!(
+                    "universe {internal} has destination {destination:?}, which can't be sent to"
+                ),
+            ));
+        }
+
+        if patched_universes.is_some_and(|patched| !patched.contains(&internal)) {
+            issues.push(ValidationIssue::new(
+                None,
+                format!("universe {internal} is mapped to sACN, but has no fixtures patched"),
+            ));
+        }
+    }
+
+    issues
+}
@@ -7,12 +7,16 @@ use std::{cmp, fmt, str};
 use uuid::Uuid;
 
 use crate::Error;
-use crate::attr::Attribute;
-use crate::dmx::Address;
-use crate::value::ClampedValue;
+use crate::attr::{Attribute, AttributeCategory, FeatureGroup};
+use crate::dmx::{self, Address};
+use crate::value::{ClampedValue, DimmerCurve};
 
 /// A configured fixture instance.
-#[derive(Debug, Clone)]
+///
+/// Fixtures are part of the [ShowData](crate::show::ShowData) sent to
+/// clients, so everything exposed through the accessors survives a
+/// serialization round-trip.
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Fixture {
     pub(crate) path: FixturePath,
@@ -24,6 +28,8 @@ pub struct Fixture {
     pub(crate) channel_functions: HashMap<Attribute, FixtureChannelFunction>,
 
     pub(crate) sub_fixture_paths: Vec<FixturePath>,
+
+    pub(crate) dimmer_curve: DimmerCurve,
 }
 
 impl Fixture {
@@ -50,6 +56,12 @@ impl Fixture {
         &self.sub_fixture_paths
     }
 
+    /// Returns the curve the values of intensity attributes are mapped
+    /// through before they are output, as configured for the root fixture.
+    pub fn dimmer_curve(&self) -> DimmerCurve {
+        self.dimmer_curve
+    }
+
     /// Returns the GDTF fixture type this instance is based on.
     pub fn gdtf_fixture_type_id(&self) -> Uuid {
         self.gdtf_fixture_type_id
@@ -67,10 +79,87 @@ impl Fixture {
         self.channel_functions.get(attribute)
     }
 
+    /// Returns `true` if the fixture has a channel function for the given
+    /// attribute.
+    pub fn has_attribute(&self, attribute: &Attribute) -> bool {
+        self.channel_functions.contains_key(attribute)
+    }
+
+    /// Returns `true` if the fixture has a channel function for any attribute
+    /// in the given feature group.
+    pub fn has_feature_group(&self, group: FeatureGroup) -> bool {
+        self.channel_functions.keys().any(|attribute| attribute.feature_group() == group)
+    }
+
+    /// Returns the attributes of this fixture in the given category, sorted.
+    pub fn attributes_in_category(&self, category: AttributeCategory) -> Vec<&Attribute> {
+        let mut attributes = self
+            .channel_functions
+            .keys()
+            .filter(|attribute| attribute.category() == category)
+            .collect::<Vec<_>>();
+        attributes.sort();
+        attributes
+    }
+
     /// Get all channel functions for this fixture.
     pub fn channel_functions(&self) -> impl Iterator<Item = (&Attribute, &FixtureChannelFunction)> {
         self.channel_functions.iter()
     }
+
+    /// Returns all DMX addresses occupied by the physical channel functions
+    /// of this fixture.
+    pub fn physical_addresses(&self) -> impl Iterator<Item = &Address> {
+        self.channel_functions.values().flat_map(|cf| match cf.kind() {
+            FixtureChannelFunctionKind::Physical { addresses } => addresses.as_slice(),
+            FixtureChannelFunctionKind::Virtual { .. } => &[],
+        })
+    }
+}
+
+/// Information about a GDTF fixture type, for example to show or render
+/// fixtures of that type.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FixtureTypeInfo {
+    pub(crate) id: Uuid,
+    pub(crate) manufacturer: String,
+    pub(crate) name: String,
+    pub(crate) short_name: String,
+    pub(crate) long_name: String,
+    pub(crate) dmx_modes: Vec<String>,
+}
+
+impl FixtureTypeInfo {
+    /// Returns the GDTF fixture type id.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the manufacturer of the fixture type.
+    pub fn manufacturer(&self) -> &str {
+        &self.manufacturer
+    }
+
+    /// Returns the name of the fixture type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the short name of the fixture type.
+    pub fn short_name(&self) -> &str {
+        &self.short_name
+    }
+
+    /// Returns the detailed, complete name of the fixture type.
+    pub fn long_name(&self) -> &str {
+        &self.long_name
+    }
+
+    /// Returns the names of all DMX modes of the fixture type.
+    pub fn dmx_modes(&self) -> &[String] {
+        &self.dmx_modes
+    }
 }
 
 /// Describes how a fixture attribute maps to DMX channel values.
@@ -78,7 +167,11 @@ impl Fixture {
 /// A channel function defines whether the attribute is controlled by
 /// physical DMX addresses or derived virtually from other attributes,
 /// and the range of values it accepts (min/max) and its default value.
-#[derive(Debug, Clone)]
+///
+/// The min, max and default values are absolute values of the DMX channel(s)
+/// the function is part of. Attribute values set for the function are
+/// relative to its range: `0.0` maps to `min` and `1.0` maps to `max`.
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct FixtureChannelFunction {
     pub(crate) kind: FixtureChannelFunctionKind,
@@ -93,6 +186,12 @@ impl FixtureChannelFunction {
         &self.kind
     }
 
+    /// Returns `true` if this channel function is computed from relations
+    /// instead of being mapped to DMX channels.
+    pub fn is_virtual(&self) -> bool {
+        matches!(self.kind, FixtureChannelFunctionKind::Virtual { .. })
+    }
+
     /// The minimum value (inclusive) supported by this channel function.
     pub fn min(&self) -> ClampedValue {
         self.min
@@ -107,11 +206,37 @@ impl FixtureChannelFunction {
     pub fn default(&self) -> ClampedValue {
         self.default
     }
+
+    /// The default value relative to the range of this channel function, as
+    /// used for attribute values.
+    pub fn relative_default(&self) -> ClampedValue {
+        let range = self.max.as_f32() - self.min.as_f32();
+        if range <= 0.0 {
+            return ClampedValue::new(0.0);
+        }
+        ClampedValue::new((self.default.as_f32() - self.min.as_f32()) / range)
+    }
+
+    /// Converts a value, relative to the range of this channel function, to
+    /// the DMX values for the given addresses.
+    pub fn to_address_values(
+        &self,
+        value: ClampedValue,
+        addresses: &[Address],
+    ) -> Vec<(Address, dmx::Value)> {
+        value.to_address_values_in_range(self.min, self.max, addresses)
+    }
 }
 
 /// Specifies whether an attribute is mapped to physical DMX channels or is
 /// computed virtually from other attributes.
-#[derive(Debug, Clone)]
+///
+/// Serialized externally tagged, as a map from the variant name to its
+/// fields (e.g. `{"Physical":{"addresses":[...]}}`). Internal tagging is
+/// avoided on purpose: it has to buffer the whole value to find the tag,
+/// which does not play well with the compact MessagePack encoding used for
+/// packets, where structs are written as arrays.
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub enum FixtureChannelFunctionKind {
     /// A physical channel mapping addresses to a channel functions.
@@ -130,7 +255,7 @@ pub enum FixtureChannelFunctionKind {
 
 /// A relation describes how a virtual attribute is derived from another
 /// attribute.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Relation {
     pub(crate) kind: RelationKind,
@@ -161,7 +286,9 @@ impl Relation {
 }
 
 /// The operation used when combining a source attribute into a virtual attribute.
-#[derive(Debug, Clone, Copy)]
+///
+/// Serialized as the bare variant name (e.g. `"Multiply"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub enum RelationKind {
     /// Multiply the source attribute value with the target.
@@ -218,8 +345,10 @@ impl str::FromStr for FixtureId {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let id = s.parse::<u32>().map_err(|_| Error::other(format!("non-zero fixture id: 0")))?;
-        FixtureId::new(id)
+        let id = s
+            .parse::<u32>()
+            .map_err(|err| Error::other(format!("invalid fixture id '{s}': {err}")))?;
+        FixtureId::new(id).map_err(|_| Error::other("fixture ids start at 1, found 0"))
     }
 }
 
@@ -228,6 +357,11 @@ impl str::FromStr for FixtureId {
 ///
 /// The first element is considered the "root" fixture and additional
 /// elements are sub-fixtures. The maximum number of elements is [FixturePath::MAX_LEN].
+///
+/// As a string, a path is written as dot-separated [FixtureId]s, e.g. `12.3.1`
+/// refers to sub-fixture 1 of sub-fixture 3 of root fixture 12. This is also
+/// the serialized form. For compatibility, deserializing also accepts the
+/// older form of a sequence of integers, e.g. `[12, 3, 1]`.
 pub struct FixturePath {
     ids: [FixtureId; Self::MAX_LEN],
     len: u8,
@@ -277,6 +411,20 @@ impl FixturePath {
         self.ids[0]
     }
 
+    /// Returns the path of the fixture this is a sub-fixture of, or `None`
+    /// for a root fixture.
+    pub fn parent(&self) -> Option<FixturePath> {
+        if self.is_root_fixture() {
+            return None;
+        }
+        Some(FixturePath::from(&self.as_slice()[..self.len() - 1]))
+    }
+
+    /// Returns the [FixtureId]s in the path, starting at the root.
+    pub fn parts(&self) -> &[FixtureId] {
+        self.as_slice()
+    }
+
     /// Returns the last [FixtureId] in the path.
     pub fn last(&self) -> FixtureId {
         let l = self.len();
@@ -408,21 +556,25 @@ impl str::FromStr for FixturePath {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-
-        if parts.is_empty() {
+        if s.is_empty() {
             return Err(Error::other("empty fixture path"));
         }
 
+        let parts: Vec<&str> = s.split('.').collect();
         if parts.len() > FixturePath::MAX_LEN {
             return Err(Error::other(format!(
-                "fixture path has too many parts (max {})",
+                "fixture path '{s}' has too many parts (max {})",
                 FixturePath::MAX_LEN,
             )));
         }
+
         let mut ids = [FixtureId::new(1).unwrap(); FixturePath::MAX_LEN];
         for (i, part) in parts.iter().enumerate() {
-            ids[i] = FixtureId::from_str(part)?;
+            if part.is_empty() {
+                return Err(Error::other(format!("fixture path '{s}' contains an empty part")));
+            }
+            ids[i] = FixtureId::from_str(part)
+                .map_err(|err| Error::other(format!("invalid fixture path '{s}': {err}")))?;
         }
         Ok(FixturePath { ids, len: parts.len() as u8 })
     }
@@ -451,7 +603,8 @@ impl<'de> serde::Deserialize<'de> for FixturePath {
             type Value = FixturePath;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a string representing a FixturePath")
+                formatter
+                    .write_str("a dot-separated fixture path string or a sequence of fixture ids")
             }
 
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
@@ -461,15 +614,40 @@ impl<'de> serde::Deserialize<'de> for FixturePath {
                 use std::str::FromStr;
                 FixturePath::from_str(v).map_err(E::custom)
             }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                use serde::de::Error as _;
+
+                let mut ids = Vec::new();
+                while let Some(id) = seq.next_element::<u32>()? {
+                    ids.push(FixtureId::new(id).map_err(A::Error::custom)?);
+                }
+
+                if ids.is_empty() {
+                    return Err(A::Error::custom("empty fixture path"));
+                }
+                if ids.len() > FixturePath::MAX_LEN {
+                    return Err(A::Error::custom(format!(
+                        "fixture path has too many parts (max {})",
+                        FixturePath::MAX_LEN
+                    )));
+                }
+
+                Ok(FixturePath::from(ids.as_slice()))
+            }
         }
 
-        deserializer.deserialize_str(FixturePathVisitor)
+        deserializer.deserialize_any(FixturePathVisitor)
     }
 }
 
 #[macro_export]
 macro_rules! fpath {
     ( $first:literal $(, $rest:literal )* $(,)? ) => {{
+        #[allow(unused_mut)]
         let mut p = $crate::show::fixture::FixturePath::new(
             $crate::show::fixture::FixtureId::new($first).unwrap()
         );
@@ -477,8 +655,167 @@ macro_rules! fpath {
         p
     }};
     ( $first:expr $(, $rest:expr )* $(,)? ) => {{
+        #[allow(unused_mut)]
         let mut p = $crate::fixture::FixturePath::new($first);
         $( p.push($rest); )*
         p
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn fixture_path_display_and_parse() {
+        let path = fpath![12, 3, 1];
+        assert_eq!(path.to_string(), "12.3.1");
+        assert_eq!(FixturePath::from_str("12.3.1").unwrap(), path);
+        assert_eq!(FixturePath::from_str("7").unwrap(), fpath![7]);
+    }
+
+    #[test]
+    fn fixture_path_parent() {
+        let path = fpath![12, 3, 1];
+        assert_eq!(path.parent(), Some(fpath![12, 3]));
+        assert_eq!(path.parent().and_then(|p| p.parent()), Some(fpath![12]));
+        assert_eq!(fpath![12].parent(), None);
+        assert_eq!(path.root(), FixtureId::new(12).unwrap());
+        assert_eq!(path.parts(), fpath![12, 3, 1].as_slice());
+
+        // The parent of an extended path is the original path again.
+        let sub = fpath![12].extended_with(FixtureId::new(4).unwrap());
+        assert_eq!(sub.parent(), Some(fpath![12]));
+        assert_eq!(sub, FixturePath::from_str("12.4").unwrap());
+    }
+
+    #[test]
+    fn fixture_path_parse_rejects_invalid() {
+        assert!(FixturePath::from_str("").is_err());
+        assert!(FixturePath::from_str("1..2").is_err());
+        assert!(FixturePath::from_str("1.").is_err());
+        assert!(FixturePath::from_str("1.0").is_err());
+        assert!(FixturePath::from_str("1.a").is_err());
+        assert!(FixturePath::from_str(&["1"; FixturePath::MAX_LEN + 1].join(".")).is_err());
+    }
+
+    #[test]
+    fn fixture_path_serde_string_form() {
+        let path = fpath![12, 3, 1];
+        assert_eq!(serde_json::to_string(&path).unwrap(), "\"12.3.1\"");
+        assert_eq!(serde_json::from_str::<FixturePath>("\"12.3.1\"").unwrap(), path);
+
+        let bytes = rmp_serde::to_vec(&path).unwrap();
+        assert_eq!(rmp_serde::from_slice::<FixturePath>(&bytes).unwrap(), path);
+    }
+
+    #[test]
+    fn fixture_tree_serde_round_trip() {
+        let physical = |address| FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Physical {
+                addresses: vec![Address::from_absolute(address).unwrap()],
+            },
+            min: ClampedValue::new(0.0),
+            max: ClampedValue::new(1.0),
+            default: ClampedValue::new(0.0),
+        };
+        let dimmer = FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Virtual {
+                relations: vec![
+                    Relation::new(RelationKind::Multiply, fpath![1, 1], Attribute::ColorAddR),
+                    Relation::new(RelationKind::Override, fpath![1, 1], Attribute::ColorAddG),
+                ],
+            },
+            min: ClampedValue::new(0.0),
+            max: ClampedValue::new(1.0),
+            default: ClampedValue::new(1.0),
+        };
+
+        let fixture = |path, channel_functions, sub_fixture_paths| Fixture {
+            path,
+            root_base_address: Address::from_absolute(1).unwrap(),
+            name: "LED Bar".to_string(),
+            gdtf_fixture_type_id: Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions,
+            sub_fixture_paths,
+            dimmer_curve: DimmerCurve::Linear,
+        };
+        let tree = vec![
+            fixture(fpath![1], HashMap::from([(Attribute::Dimmer, dimmer)]), vec![fpath![1, 1]]),
+            fixture(
+                fpath![1, 1],
+                HashMap::from([
+                    (Attribute::ColorAddR, physical(1)),
+                    (Attribute::ColorAddG, physical(2)),
+                ]),
+                Vec::new(),
+            ),
+        ];
+
+        let bytes = rmp_serde::to_vec(&tree).unwrap();
+        let decoded: Vec<Fixture> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, tree);
+
+        let dimmer = decoded[0].channel_function(&Attribute::Dimmer).unwrap();
+        let FixtureChannelFunctionKind::Virtual { relations } = dimmer.kind() else {
+            panic!("dimmer should be virtual");
+        };
+        assert_eq!(*relations[1].kind(), RelationKind::Override);
+        assert_eq!(relations[1].fixture_path(), fpath![1, 1]);
+        assert_eq!(relations[1].attribute(), Attribute::ColorAddG);
+        assert_eq!(decoded[1].physical_addresses().count(), 2);
+    }
+
+    #[test]
+    fn attributes_in_category() {
+        let fixture = Fixture {
+            path: fpath![1],
+            root_base_address: Address::from_absolute(1).unwrap(),
+            name: "Wash".to_string(),
+            gdtf_fixture_type_id: Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions: [
+                Attribute::Dimmer,
+                Attribute::Pan,
+                Attribute::Tilt,
+                Attribute::ColorAddB,
+                Attribute::ColorAddR,
+            ]
+            .into_iter()
+            .map(|attribute| {
+                let channel_function = FixtureChannelFunction {
+                    kind: FixtureChannelFunctionKind::Physical { addresses: Vec::new() },
+                    min: ClampedValue::new(0.0),
+                    max: ClampedValue::new(1.0),
+                    default: ClampedValue::new(0.0),
+                };
+                (attribute, channel_function)
+            })
+            .collect(),
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        };
+
+        assert!(fixture.has_attribute(&Attribute::Pan));
+        assert!(!fixture.has_attribute(&Attribute::Zoom));
+        assert_eq!(
+            fixture.attributes_in_category(AttributeCategory::ColorAdditive),
+            vec![&Attribute::ColorAddR, &Attribute::ColorAddB]
+        );
+        assert_eq!(
+            fixture.attributes_in_category(AttributeCategory::Position),
+            vec![&Attribute::Pan, &Attribute::Tilt]
+        );
+        assert!(fixture.attributes_in_category(AttributeCategory::Gobo).is_empty());
+    }
+
+    #[test]
+    fn fixture_path_deserializes_legacy_array_form() {
+        assert_eq!(serde_json::from_str::<FixturePath>("[12, 3, 1]").unwrap(), fpath![12, 3, 1]);
+        assert!(serde_json::from_str::<FixturePath>("[]").is_err());
+        assert!(serde_json::from_str::<FixturePath>("[1, 0]").is_err());
+    }
+}
@@ -1,16 +1,69 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::show::fixture::{Fixture, FixtureTypeInfo};
 use crate::show::patch::Patch;
 
 pub mod fixture;
 pub mod patch;
+pub mod selection;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct ShowData {
     pub(crate) patch: Patch,
+    /// Information about the GDTF fixture types, keyed by fixture type id.
+    ///
+    /// Defaults to empty so show data from servers without fixture type
+    /// information can still be read.
+    #[serde(default)]
+    pub(crate) fixture_types: HashMap<Uuid, FixtureTypeInfo>,
 }
 
 impl ShowData {
     pub fn patch(&self) -> &Patch {
         &self.patch
     }
+
+    /// Returns information about all GDTF fixture types in the show.
+    pub fn fixture_types(&self) -> impl Iterator<Item = &FixtureTypeInfo> {
+        self.fixture_types.values()
+    }
+
+    /// Returns information about the GDTF fixture type with the given id.
+    pub fn fixture_type(&self, fixture_type_id: Uuid) -> Option<&FixtureTypeInfo> {
+        self.fixture_types.get(&fixture_type_id)
+    }
+
+    /// Returns information about the GDTF fixture type of a fixture.
+    pub fn fixture_type_of(&self, fixture: &Fixture) -> Option<&FixtureTypeInfo> {
+        self.fixture_type(fixture.gdtf_fixture_type_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::dmx::Multiverse;
+
+    #[test]
+    fn deserialize_without_fixture_types() {
+        #[derive(serde::Serialize)]
+        struct OldShowData {
+            patch: Patch,
+        }
+
+        let patch = Patch {
+            fixtures: BTreeMap::new(),
+            default_multiverse: Multiverse::new(),
+            groups: BTreeMap::new(),
+        };
+        let bytes = rmp_serde::to_vec(&OldShowData { patch }).unwrap();
+
+        let show_data: ShowData = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(show_data.fixture_types().count(), 0);
+    }
 }
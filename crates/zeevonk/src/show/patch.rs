@@ -1,13 +1,18 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::{Range, RangeInclusive};
 
-use crate::dmx::Multiverse;
-use crate::show::fixture::{Fixture, FixturePath};
+use crate::Error;
+use crate::attr::{Attribute, FeatureGroup};
+use crate::dmx::{self, Address, Channel, Multiverse, UniverseId};
+use crate::show::fixture::{Fixture, FixtureChannelFunctionKind, FixtureId, FixturePath};
+use crate::showfile::{Group, GroupId};
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Patch {
     pub(crate) fixtures: BTreeMap<FixturePath, Fixture>,
     pub(crate) default_multiverse: Multiverse,
+    pub(crate) groups: BTreeMap<GroupId, Group>,
 }
 
 impl Patch {
@@ -18,4 +23,857 @@ impl Patch {
     pub fn default_multiverse(&self) -> &Multiverse {
         &self.default_multiverse
     }
+
+    pub fn groups(&self) -> &BTreeMap<GroupId, Group> {
+        &self.groups
+    }
+
+    /// Returns the fixture at the given path.
+    pub fn fixture(&self, path: &FixturePath) -> Option<&Fixture> {
+        self.fixtures.get(path)
+    }
+
+    /// Returns all root fixtures, sorted by id.
+    pub fn root_fixtures(&self) -> impl Iterator<Item = &Fixture> {
+        self.fixtures.values().filter(|fixture| fixture.path().is_root_fixture())
+    }
+
+    /// Returns the direct sub-fixtures of the fixture at the given path, in
+    /// the order of [Fixture::sub_fixtures]. Returns nothing if there is no
+    /// fixture at the path.
+    pub fn children_of(&self, path: &FixturePath) -> impl Iterator<Item = &Fixture> {
+        self.fixtures
+            .get(path)
+            .into_iter()
+            .flat_map(|fixture| fixture.sub_fixtures())
+            .filter_map(|path| self.fixtures.get(path))
+    }
+
+    /// Returns all sub-fixtures of the fixture at the given path, walking the
+    /// tree depth-first, so every fixture comes before its own sub-fixtures.
+    /// The fixture itself is not included.
+    pub fn descendants_of(&self, path: &FixturePath) -> Vec<&Fixture> {
+        let mut descendants = Vec::new();
+        self.collect_descendants(path, &mut descendants);
+        descendants
+    }
+
+    fn collect_descendants<'a>(&'a self, path: &FixturePath, descendants: &mut Vec<&'a Fixture>) {
+        for child in self.children_of(path) {
+            descendants.push(child);
+            self.collect_descendants(&child.path(), descendants);
+        }
+    }
+
+    /// Returns all fixtures that have the given attribute, sorted by path.
+    pub fn fixtures_with_attribute(&self, attribute: &Attribute) -> impl Iterator<Item = &Fixture> {
+        self.fixtures.values().filter(|fixture| fixture.has_attribute(attribute))
+    }
+
+    /// Returns all fixtures that have an attribute in the given feature group,
+    /// sorted by path.
+    pub fn fixtures_in_feature_group(&self, group: FeatureGroup) -> impl Iterator<Item = &Fixture> {
+        self.fixtures.values().filter(move |fixture| fixture.has_feature_group(group))
+    }
+
+    /// Returns the first fixture, by path, with the given name, ignoring case.
+    ///
+    /// Names don't have to be unique, so use [Patch::fixtures_by_label] to
+    /// find all fixtures with the name.
+    pub fn fixture_by_label(&self, label: &str) -> Option<&Fixture> {
+        self.fixtures.values().find(|fixture| fixture.name().eq_ignore_ascii_case(label))
+    }
+
+    /// Returns all fixtures with the given name, ignoring case, sorted by path.
+    pub fn fixtures_by_label<'a>(&'a self, label: &'a str) -> impl Iterator<Item = &'a Fixture> {
+        self.fixtures.values().filter(|fixture| fixture.name().eq_ignore_ascii_case(label))
+    }
+
+    /// Renames the root fixture with the given id. Sub-fixtures are named
+    /// after their geometry, so they keep their names.
+    ///
+    /// Returns [Error::FixtureNotFound] if no root fixture with that id exists.
+    pub fn rename_fixture(
+        &mut self,
+        root_id: FixtureId,
+        name: impl Into<String>,
+    ) -> Result<(), Error> {
+        let fixture = self
+            .fixtures
+            .get_mut(&FixturePath::new(root_id))
+            .ok_or(Error::FixtureNotFound(root_id.into()))?;
+        fixture.name = name.into();
+        Ok(())
+    }
+
+    /// Returns the paths of all fixtures in the given group that have the
+    /// given attribute, including sub-fixtures of the group members.
+    ///
+    /// Members lacking the attribute are skipped.
+    pub fn group_fixture_paths(
+        &self,
+        group_id: GroupId,
+        attribute: Attribute,
+    ) -> Result<Vec<FixturePath>, Error> {
+        let group = self.groups.get(&group_id).ok_or(Error::GroupNotFound(group_id))?;
+
+        let paths = group
+            .members()
+            .iter()
+            .flat_map(|member| {
+                let root_path = FixturePath::new(*member);
+                self.fixtures.values().filter(move |fixture| fixture.path().contains(&root_path))
+            })
+            .filter(|fixture| fixture.channel_function(&attribute).is_some())
+            .map(Fixture::path)
+            .collect();
+
+        Ok(paths)
+    }
+
+    /// Returns `true` if no fixture in the patch occupies the given [Address].
+    pub fn address_available(&self, address: &Address) -> bool {
+        self.fixture_at_address(address).is_none()
+    }
+
+    /// Returns the fixture occupying the given [Address], together with the
+    /// label and absolute address range of its root fixture, if any.
+    pub fn address_owner(
+        &self,
+        address: &Address,
+    ) -> Option<(FixturePath, &str, RangeInclusive<u32>)> {
+        let path = self.fixture_at_address(address)?;
+        let root_id = path.root();
+        let root_fixture = self.fixtures.get(&FixturePath::new(root_id))?;
+        let footprint = self.fixture_footprint(root_id)?;
+        Some((path, root_fixture.name(), footprint.start..=footprint.end - 1))
+    }
+
+    /// Checks that none of the given addresses are occupied by fixtures
+    /// outside the tree of the root fixture `ignore`.
+    ///
+    /// Returns [Error::AddressCollision] for the lowest occupied address, with
+    /// `requested` and `requested_footprint` describing the fixture that is
+    /// being patched.
+    pub fn check_address_collision(
+        &self,
+        requested: Address,
+        requested_footprint: u32,
+        addresses: impl IntoIterator<Item = Address>,
+        ignore: Option<FixtureId>,
+    ) -> Result<(), Error> {
+        let mut addresses = addresses.into_iter().collect::<Vec<_>>();
+        addresses.sort();
+        for address in addresses {
+            let Some((existing_fixture, existing_label, existing_range)) =
+                self.address_owner(&address)
+            else {
+                continue;
+            };
+            if ignore.is_some_and(|ignore| existing_fixture.root() == ignore) {
+                continue;
+            }
+
+            return Err(Error::AddressCollision {
+                requested,
+                requested_footprint,
+                existing_fixture,
+                existing_label: existing_label.to_string(),
+                existing_range: (
+                    Address::from_absolute(*existing_range.start())?,
+                    Address::from_absolute(*existing_range.end())?,
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `count` consecutive addresses starting at `start` fit
+    /// within the valid address range and are not occupied by any fixture.
+    pub fn range_available(&self, start: Address, count: u32) -> bool {
+        let addresses = Address::range(start, count).collect::<Vec<_>>();
+        addresses.len() == count as usize
+            && addresses.iter().all(|address| self.address_available(address))
+    }
+
+    /// Returns the spans of consecutive channels occupied by fixtures in the
+    /// given universe, ordered by start channel.
+    ///
+    /// A fixture with non-consecutive channels is reported as multiple spans.
+    pub fn universe_occupancy(&self, universe: UniverseId) -> Vec<AddressSpan> {
+        let mut spans = Vec::new();
+        for fixture in self.fixtures.values() {
+            let mut channels = fixture
+                .physical_addresses()
+                .filter(|address| address.universe == universe)
+                .map(|address| address.channel)
+                .collect::<Vec<_>>();
+            channels.sort();
+            channels.dedup();
+
+            let mut channels = channels.into_iter();
+            let Some(first) = channels.next() else { continue };
+            let mut span = AddressSpan::new(first, fixture);
+            for channel in channels {
+                if *channel == *span.end + 1 {
+                    span.end = channel;
+                } else {
+                    spans.push(span);
+                    span = AddressSpan::new(channel, fixture);
+                }
+            }
+            spans.push(span);
+        }
+
+        spans.sort_by_key(|span| (span.start, span.fixture));
+        spans
+    }
+
+    /// Finds the first range of `channel_count` consecutive free addresses
+    /// after the given [Address], or from the start of the first universe.
+    ///
+    /// The range never crosses a universe boundary, as a fixture is connected
+    /// to a single universe. Returns `None` if `channel_count` is zero or
+    /// larger than a universe, or if no such range exists.
+    pub fn next_free_address(&self, channel_count: u32, after: Option<Address>) -> Option<Address> {
+        if channel_count == 0 || channel_count > *Channel::MAX as u32 {
+            return None;
+        }
+
+        let mut occupied = BTreeMap::<UniverseId, HashSet<u16>>::new();
+        for address in self.fixtures.values().flat_map(Fixture::physical_addresses) {
+            occupied.entry(address.universe).or_default().insert(*address.channel);
+        }
+
+        let start = match after {
+            Some(after) => after.with_channel_offset(1).ok()?,
+            None => Address::new(UniverseId::MIN, Channel::MIN),
+        };
+
+        let mut universe = *start.universe;
+        let mut first_channel = *start.channel;
+        loop {
+            let universe_id = UniverseId::new(universe).ok()?;
+            let occupied = occupied.get(&universe_id);
+            let is_free = |channel: u16| occupied.is_none_or(|o| !o.contains(&channel));
+
+            let mut run_start = first_channel;
+            for channel in first_channel..=*Channel::MAX {
+                if !is_free(channel) {
+                    run_start = channel + 1;
+                } else if (channel - run_start + 1) as u32 == channel_count {
+                    return Some(Address::new(universe_id, Channel::new(run_start).ok()?));
+                }
+            }
+
+            universe = universe.checked_add(1)?;
+            first_channel = *Channel::MIN;
+        }
+    }
+
+    /// Returns the absolute address range spanned by the root fixture with
+    /// the given id and all of its sub-fixtures.
+    ///
+    /// The range runs from the lowest to the highest occupied address, so it
+    /// may include unused addresses in between. A fixture without physical
+    /// channels has an empty range at its base address. Returns `None` if no
+    /// root fixture with that id exists.
+    pub fn fixture_footprint(&self, root_id: FixtureId) -> Option<Range<u32>> {
+        let root_path = FixturePath::new(root_id);
+        let root_fixture = self.fixtures.get(&root_path)?;
+
+        let absolute_addresses = self
+            .fixtures
+            .values()
+            .filter(|fixture| fixture.path().contains(&root_path))
+            .flat_map(Fixture::physical_addresses)
+            .map(Address::to_absolute)
+            .collect::<Vec<_>>();
+
+        match (absolute_addresses.iter().min(), absolute_addresses.iter().max()) {
+            (Some(min), Some(max)) => Some(*min..*max + 1),
+            _ => {
+                let base = root_fixture.base_address().to_absolute();
+                Some(base..base)
+            }
+        }
+    }
+
+    /// Returns the path of the fixture occupying the given [Address], if any.
+    ///
+    /// Use [FixturePath::root] to get the id of the root fixture.
+    pub fn fixture_at_address(&self, address: &Address) -> Option<FixturePath> {
+        self.fixtures
+            .values()
+            .find(|fixture| fixture.physical_addresses().any(|a| a == address))
+            .map(Fixture::path)
+    }
+
+    /// Removes the root fixture with the given id and all of its
+    /// sub-fixtures from the patch.
+    ///
+    /// The default values of the freed addresses are reset to zero.
+    ///
+    /// Returns the paths of all removed fixtures, or
+    /// [Error::FixtureNotFound] if no root fixture with that id exists.
+    pub fn remove_fixture(&mut self, root_id: FixtureId) -> Result<Vec<FixturePath>, Error> {
+        let root_path = FixturePath::new(root_id);
+        if !self.fixtures.contains_key(&root_path) {
+            return Err(Error::FixtureNotFound(root_path));
+        }
+
+        let paths = self
+            .fixtures
+            .keys()
+            .filter(|path| path.contains(&root_path))
+            .copied()
+            .collect::<Vec<_>>();
+
+        for path in &paths {
+            let Some(fixture) = self.fixtures.remove(path) else { continue };
+            for address in fixture.physical_addresses() {
+                self.default_multiverse.set_value(address, dmx::Value::default());
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Checks that the relations between virtual channel functions do not
+    /// form a cycle, as resolving those would never terminate.
+    ///
+    /// Returns [Error::RelationCycle] with the first channel function found
+    /// to be part of a cycle.
+    pub fn check_relation_cycles(&self) -> Result<(), Error> {
+        let mut visited = HashSet::new();
+        for (path, fixture) in &self.fixtures {
+            for attribute in fixture.channel_functions.keys() {
+                let mut stack = Vec::new();
+                self.visit_relations((*path, *attribute), &mut visited, &mut stack)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_relations(
+        &self,
+        node: (FixturePath, Attribute),
+        visited: &mut HashSet<(FixturePath, Attribute)>,
+        stack: &mut Vec<(FixturePath, Attribute)>,
+    ) -> Result<(), Error> {
+        if stack.contains(&node) {
+            return Err(Error::RelationCycle { fixture_path: node.0, attribute: node.1 });
+        }
+
+        if !visited.insert(node) {
+            return Ok(());
+        }
+
+        let Some(channel_function) =
+            self.fixtures.get(&node.0).and_then(|fixture| fixture.channel_function(&node.1))
+        else {
+            return Ok(());
+        };
+
+        if let FixtureChannelFunctionKind::Virtual { relations } = channel_function.kind() {
+            stack.push(node);
+            for relation in relations {
+                self.visit_relations(
+                    (relation.fixture_path(), relation.attribute()),
+                    visited,
+                    stack,
+                )?;
+            }
+            stack.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Moves the root fixture with the given id, together with all of its
+    /// sub-fixtures, to a new base address.
+    ///
+    /// The physical addresses of every channel function in the fixture tree
+    /// are shifted by the same offset, and the default values move along.
+    /// Fixture paths are left untouched, so any attribute values set for
+    /// them are preserved.
+    ///
+    /// Returns [Error::AddressCollision] with the first fixture in the way if
+    /// the new footprint overlaps with another fixture.
+    pub fn set_fixture_address(
+        &mut self,
+        root_id: FixtureId,
+        new_address: Address,
+    ) -> Result<(), Error> {
+        let root_path = FixturePath::new(root_id);
+        let Some(root_fixture) = self.fixtures.get(&root_path) else {
+            return Err(Error::FixtureNotFound(root_path));
+        };
+
+        let offset =
+            new_address.to_absolute() as i32 - root_fixture.base_address().to_absolute() as i32;
+        let paths = self
+            .fixtures
+            .keys()
+            .filter(|path| path.contains(&root_path))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let mut moved_defaults = Vec::new();
+        for path in &paths {
+            for address in self.fixtures[path].physical_addresses() {
+                let moved_address = address.with_channel_offset(offset)?;
+                moved_defaults.push((
+                    *address,
+                    moved_address,
+                    self.default_multiverse.get_value(address),
+                ));
+            }
+        }
+
+        // Check the new footprint against all other fixtures before changing anything.
+        let footprint = self.fixture_footprint(root_id).map_or(0, |range| range.len() as u32);
+        self.check_address_collision(
+            new_address,
+            footprint,
+            moved_defaults.iter().map(|(_, moved_address, _)| *moved_address),
+            Some(root_id),
+        )?;
+
+        for path in &paths {
+            let fixture = self.fixtures.get_mut(path).expect("fixture path should exist");
+            fixture.root_base_address = new_address;
+            for channel_function in fixture.channel_functions.values_mut() {
+                if let FixtureChannelFunctionKind::Physical { addresses } =
+                    &mut channel_function.kind
+                {
+                    for address in addresses {
+                        *address = address.with_channel_offset(offset)?;
+                    }
+                }
+            }
+        }
+
+        for (old_address, _, _) in &moved_defaults {
+            self.default_multiverse.set_value(old_address, dmx::Value::default());
+        }
+        for (_, new_address, value) in moved_defaults {
+            self.default_multiverse.set_value(&new_address, value);
+        }
+
+        Ok(())
+    }
+}
+
+/// A span of consecutive channels in a universe occupied by a fixture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressSpan {
+    start: Channel,
+    end: Channel,
+    fixture: FixturePath,
+    label: String,
+}
+
+impl AddressSpan {
+    fn new(channel: Channel, fixture: &Fixture) -> Self {
+        Self {
+            start: channel,
+            end: channel,
+            fixture: fixture.path(),
+            label: fixture.name().to_string(),
+        }
+    }
+
+    /// The first channel of the span.
+    pub fn start(&self) -> Channel {
+        self.start
+    }
+
+    /// The last channel of the span, inclusive.
+    pub fn end(&self) -> Channel {
+        self.end
+    }
+
+    /// The path of the fixture occupying the span.
+    pub fn fixture(&self) -> FixturePath {
+        self.fixture
+    }
+
+    /// The name of the fixture occupying the span.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::attr::Attribute;
+    use crate::fpath;
+    use crate::show::fixture::{FixtureChannelFunction, Relation, RelationKind};
+    use crate::value::{ClampedValue, DimmerCurve};
+
+    fn fixture(path: FixturePath, absolute_addresses: &[u32]) -> Fixture {
+        let addresses =
+            absolute_addresses.iter().map(|a| Address::from_absolute(*a).unwrap()).collect();
+        let channel_function = FixtureChannelFunction {
+            kind: FixtureChannelFunctionKind::Physical { addresses },
+            min: ClampedValue::new(0.0),
+            max: ClampedValue::new(1.0),
+            default: ClampedValue::new(0.0),
+        };
+
+        Fixture {
+            path,
+            root_base_address: Address::from_absolute(absolute_addresses[0]).unwrap(),
+            name: path.to_string(),
+            gdtf_fixture_type_id: uuid::Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions: HashMap::from([(Attribute::Dimmer, channel_function)]),
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        }
+    }
+
+    fn patch(fixtures: Vec<Fixture>) -> Patch {
+        let mut default_multiverse = Multiverse::new();
+        for address in fixtures.iter().flat_map(Fixture::physical_addresses) {
+            default_multiverse.set_value(address, dmx::Value(255));
+        }
+
+        Patch {
+            fixtures: fixtures.into_iter().map(|f| (f.path(), f)).collect(),
+            default_multiverse,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn remove_fixture_removes_sub_fixtures() {
+        let mut patch = patch(vec![
+            fixture(fpath![1], &[1]),
+            fixture(fpath![1, 1], &[2]),
+            fixture(fpath![1, 1, 1], &[3]),
+            fixture(fpath![2], &[4]),
+        ]);
+
+        let removed = patch.remove_fixture(FixtureId::new(1).unwrap()).unwrap();
+        assert_eq!(removed, vec![fpath![1], fpath![1, 1], fpath![1, 1, 1]]);
+        assert_eq!(patch.fixtures().keys().copied().collect::<Vec<_>>(), vec![fpath![2]]);
+    }
+
+    #[test]
+    fn remove_fixture_frees_addresses() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1, 2]), fixture(fpath![2], &[3])]);
+        let address = Address::from_absolute(2).unwrap();
+        assert!(!patch.address_available(&address));
+
+        patch.remove_fixture(FixtureId::new(1).unwrap()).unwrap();
+        assert!(patch.address_available(&address));
+        assert!(!patch.address_available(&Address::from_absolute(3).unwrap()));
+        assert_eq!(patch.default_multiverse().get_value(&address), dmx::Value(0));
+    }
+
+    #[test]
+    fn range_available() {
+        let patch = patch(vec![fixture(fpath![1], &[3])]);
+        assert!(patch.range_available(Address::from_absolute(1).unwrap(), 2));
+        assert!(!patch.range_available(Address::from_absolute(1).unwrap(), 3));
+        assert!(patch.range_available(Address::from_absolute(4).unwrap(), 600));
+
+        let last_universe = Address::new(dmx::UniverseId::MAX, dmx::Channel::new(511).unwrap());
+        assert!(patch.range_available(last_universe, 2));
+        assert!(!patch.range_available(last_universe, 3));
+    }
+
+    #[test]
+    fn universe_occupancy() {
+        let patch = patch(vec![
+            fixture(fpath![2], &[10, 11, 12]),
+            fixture(fpath![1], &[1, 2, 5]),
+            fixture(fpath![3], &[512, 513]),
+        ]);
+
+        let spans = patch
+            .universe_occupancy(UniverseId::new(1).unwrap())
+            .into_iter()
+            .map(|span| (*span.start(), *span.end(), span.fixture()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [(1, 2, fpath![1]), (5, 5, fpath![1]), (10, 12, fpath![2]), (512, 512, fpath![3])]
+        );
+
+        let spans = patch.universe_occupancy(UniverseId::new(2).unwrap());
+        assert_eq!(spans.len(), 1);
+        assert_eq!((*spans[0].start(), *spans[0].end()), (1, 1));
+        assert_eq!(spans[0].label(), "3");
+
+        assert!(patch.universe_occupancy(UniverseId::new(3).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn next_free_address() {
+        let patch = patch(vec![fixture(fpath![1], &[1, 2]), fixture(fpath![2], &[6, 7])]);
+        let address = |absolute| Some(Address::from_absolute(absolute).unwrap());
+
+        assert_eq!(patch.next_free_address(3, None), address(3));
+        assert_eq!(patch.next_free_address(4, None), address(8));
+        assert_eq!(patch.next_free_address(1, address(6)), address(8));
+        assert_eq!(patch.next_free_address(0, None), None);
+        assert_eq!(patch.next_free_address(513, None), None);
+    }
+
+    #[test]
+    fn next_free_address_does_not_cross_universes() {
+        let patch = patch(vec![fixture(fpath![1], &[1, 2])]);
+        let after = Address::from_absolute(508);
+        assert_eq!(
+            patch.next_free_address(8, after.ok()),
+            Some(Address::new(UniverseId::new(2).unwrap(), Channel::MIN))
+        );
+
+        let last = Address::new(UniverseId::MAX, Channel::new(510).unwrap());
+        assert_eq!(patch.next_free_address(8, Some(last)), None);
+    }
+
+    #[test]
+    fn fixture_footprint() {
+        let patch = patch(vec![
+            fixture(fpath![1], &[3, 4]),
+            fixture(fpath![1, 1], &[10]),
+            fixture(fpath![2], &[512, 513]),
+        ]);
+
+        assert_eq!(patch.fixture_footprint(FixtureId::new(1).unwrap()), Some(3..11));
+        assert_eq!(patch.fixture_footprint(FixtureId::new(2).unwrap()), Some(512..514));
+        assert_eq!(patch.fixture_footprint(FixtureId::new(3).unwrap()), None);
+
+        let address = Address::from_absolute(10).unwrap();
+        assert_eq!(
+            patch.fixture_at_address(&address).map(|path| path.root()),
+            FixtureId::new(1).ok()
+        );
+        assert_eq!(patch.fixture_at_address(&Address::from_absolute(5).unwrap()), None);
+    }
+
+    #[test]
+    fn set_fixture_address_moves_fixture_tree() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1, 2]), fixture(fpath![1, 1], &[3])]);
+
+        let new_address = Address::from_absolute(101).unwrap();
+        patch.set_fixture_address(FixtureId::new(1).unwrap(), new_address).unwrap();
+
+        for absolute in [1, 2, 3] {
+            let address = Address::from_absolute(absolute).unwrap();
+            assert!(patch.address_available(&address));
+            assert_eq!(patch.default_multiverse().get_value(&address), dmx::Value(0));
+        }
+        for absolute in [101, 102, 103] {
+            let address = Address::from_absolute(absolute).unwrap();
+            assert!(!patch.address_available(&address));
+            assert_eq!(patch.default_multiverse().get_value(&address), dmx::Value(255));
+        }
+        assert_eq!(patch.fixtures()[&fpath![1, 1]].base_address(), new_address);
+    }
+
+    #[test]
+    fn set_fixture_address_overlapping_itself() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1, 2, 3])]);
+        patch
+            .set_fixture_address(FixtureId::new(1).unwrap(), Address::from_absolute(2).unwrap())
+            .unwrap();
+        assert!(patch.address_available(&Address::from_absolute(1).unwrap()));
+        assert!(!patch.address_available(&Address::from_absolute(4).unwrap()));
+    }
+
+    #[test]
+    fn set_fixture_address_collision() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1, 2]), fixture(fpath![2], &[11])]);
+        let result = patch
+            .set_fixture_address(FixtureId::new(1).unwrap(), Address::from_absolute(10).unwrap());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "address 1.10 collides with '2' (id 2) occupying 1.11..=1.11"
+        );
+        assert!(!patch.address_available(&Address::from_absolute(1).unwrap()));
+    }
+
+    #[test]
+    fn address_owner_reports_root_fixture() {
+        let patch = patch(vec![fixture(fpath![4], &[97, 98]), fixture(fpath![4, 1], &[112])]);
+
+        let (path, label, range) = patch.address_owner(&"1.112".parse().unwrap()).unwrap();
+        assert_eq!((path, label, range), (fpath![4, 1], "4", 97..=112));
+        assert_eq!(patch.address_owner(&"1.100".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn address_collision_at_start_middle_and_end_of_footprint() {
+        let occupied = (97..=112).collect::<Vec<_>>();
+        let mut patch = patch(vec![fixture(fpath![4], &occupied)]);
+        patch.rename_fixture(FixtureId::new(4).unwrap(), "Spot 2").unwrap();
+        let addresses = |start: u32, count: u32| {
+            Address::range(Address::from_absolute(start).unwrap(), count).collect::<Vec<_>>()
+        };
+
+        // Overlapping the start, lying in the middle and overlapping the end.
+        for (start, count) in [(90, 8), (100, 4), (110, 8)] {
+            let requested = Address::from_absolute(start).unwrap();
+            let err = patch
+                .check_address_collision(requested, count, addresses(start, count), None)
+                .unwrap_err();
+
+            let Error::AddressCollision { requested_footprint, existing_fixture, .. } = &err else {
+                panic!("unexpected error: {err}");
+            };
+            assert_eq!((*requested_footprint, *existing_fixture), (count, fpath![4]));
+            assert_eq!(
+                err.to_string(),
+                format!("address {requested} collides with 'Spot 2' (id 4) occupying 1.97..=1.112")
+            );
+        }
+
+        // Adjacent ranges and the fixture itself, e.g. when it is moved, are fine.
+        let ignore = FixtureId::new(4).ok();
+        let requested = Address::from_absolute(96).unwrap();
+        assert!(patch.check_address_collision(requested, 1, addresses(96, 1), None).is_ok());
+        assert!(patch.check_address_collision(requested, 2, addresses(96, 2), ignore).is_ok());
+        let requested = Address::from_absolute(113).unwrap();
+        assert!(patch.check_address_collision(requested, 4, addresses(113, 4), None).is_ok());
+    }
+
+    #[test]
+    fn group_fixture_paths_skips_members_without_attribute() {
+        let mut patch = patch(vec![
+            fixture(fpath![1], &[1]),
+            fixture(fpath![1, 1], &[2]),
+            fixture(fpath![2], &[3]),
+            fixture(fpath![3], &[4]),
+        ]);
+        patch.fixtures.get_mut(&fpath![2]).unwrap().channel_functions.clear();
+
+        let id = GroupId(1);
+        let members = [1, 2].map(|id| FixtureId::new(id).unwrap()).to_vec();
+        patch.groups.insert(id, Group::new(id, "Washes", members));
+
+        let paths = patch.group_fixture_paths(id, Attribute::Dimmer).unwrap();
+        assert_eq!(paths, vec![fpath![1], fpath![1, 1]]);
+        assert!(patch.group_fixture_paths(id, Attribute::Pan).unwrap().is_empty());
+        assert!(matches!(
+            patch.group_fixture_paths(GroupId(2), Attribute::Dimmer),
+            Err(Error::GroupNotFound(GroupId(2)))
+        ));
+    }
+
+    fn set_relations(patch: &mut Patch, path: FixturePath, relations: Vec<Relation>) {
+        let fixture = patch.fixtures.get_mut(&path).unwrap();
+        let channel_function = fixture.channel_functions.get_mut(&Attribute::Dimmer).unwrap();
+        channel_function.kind = FixtureChannelFunctionKind::Virtual { relations };
+    }
+
+    #[test]
+    fn check_relation_cycles_accepts_chains() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1]), fixture(fpath![1, 1], &[2])]);
+        set_relations(
+            &mut patch,
+            fpath![1],
+            vec![Relation::new(RelationKind::Multiply, fpath![1, 1], Attribute::Dimmer)],
+        );
+        assert!(patch.check_relation_cycles().is_ok());
+    }
+
+    #[test]
+    fn check_relation_cycles_detects_cycle() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1]), fixture(fpath![1, 1], &[2])]);
+        set_relations(
+            &mut patch,
+            fpath![1],
+            vec![Relation::new(RelationKind::Override, fpath![1, 1], Attribute::Dimmer)],
+        );
+        set_relations(
+            &mut patch,
+            fpath![1, 1],
+            vec![Relation::new(RelationKind::Multiply, fpath![1], Attribute::Dimmer)],
+        );
+        assert!(matches!(
+            patch.check_relation_cycles(),
+            Err(Error::RelationCycle { attribute: Attribute::Dimmer, .. })
+        ));
+    }
+
+    #[test]
+    fn remove_unknown_fixture() {
+        let mut patch = patch(vec![fixture(fpath![1], &[1])]);
+        let id = FixtureId::new(2).unwrap();
+        assert!(
+            matches!(patch.remove_fixture(id), Err(Error::FixtureNotFound(path)) if path == id.into())
+        );
+    }
+
+    fn led_bar() -> Patch {
+        let mut bar = fixture(fpath![1], &[1]);
+        bar.name = "LED Bar".to_string();
+        bar.sub_fixture_paths = vec![fpath![1, 1], fpath![1, 2]];
+        let mut pixel = fixture(fpath![1, 1], &[2]);
+        pixel.sub_fixture_paths = vec![fpath![1, 1, 1]];
+        let mut pixel_segment = fixture(fpath![1, 1, 1], &[3]);
+        pixel_segment.channel_functions.clear();
+        let mut spot = fixture(fpath![2], &[11]);
+        spot.name = "Spot".to_string();
+
+        patch(vec![bar, pixel, pixel_segment, fixture(fpath![1, 2], &[4]), spot])
+    }
+
+    #[test]
+    fn fixture_tree_lookups() {
+        let patch = led_bar();
+        let paths = |fixtures: Vec<&Fixture>| fixtures.into_iter().map(Fixture::path).collect();
+
+        assert_eq!(patch.fixture(&fpath![1, 2]).unwrap().path(), fpath![1, 2]);
+        assert!(patch.fixture(&fpath![1, 3]).is_none());
+        let roots: Vec<_> = paths(patch.root_fixtures().collect());
+        assert_eq!(roots, vec![fpath![1], fpath![2]]);
+
+        let children: Vec<_> = paths(patch.children_of(&fpath![1]).collect());
+        assert_eq!(children, vec![fpath![1, 1], fpath![1, 2]]);
+        assert_eq!(patch.children_of(&fpath![3]).count(), 0);
+        let descendants: Vec<_> = paths(patch.descendants_of(&fpath![1]));
+        assert_eq!(descendants, vec![fpath![1, 1], fpath![1, 1, 1], fpath![1, 2]]);
+        assert!(patch.descendants_of(&fpath![2]).is_empty());
+    }
+
+    #[test]
+    fn fixture_attribute_and_label_lookups() {
+        let patch = led_bar();
+
+        let dimmers: Vec<_> =
+            patch.fixtures_with_attribute(&Attribute::Dimmer).map(Fixture::path).collect();
+        assert_eq!(dimmers, vec![fpath![1], fpath![1, 1], fpath![1, 2], fpath![2]]);
+        assert_eq!(patch.fixtures_with_attribute(&Attribute::Pan).count(), 0);
+        let intensity: Vec<_> =
+            patch.fixtures_in_feature_group(FeatureGroup::Intensity).map(Fixture::path).collect();
+        assert_eq!(intensity, dimmers);
+        assert_eq!(patch.fixtures_in_feature_group(FeatureGroup::Position).count(), 0);
+
+        assert_eq!(patch.fixture_by_label("led bar").unwrap().path(), fpath![1]);
+        assert_eq!(patch.fixture_by_label("SPOT").unwrap().path(), fpath![2]);
+        assert!(patch.fixture_by_label("Wash").is_none());
+    }
+
+    #[test]
+    fn rename_fixture() {
+        let mut patch = led_bar();
+
+        patch.rename_fixture(FixtureId::new(2).unwrap(), "LED Bar").unwrap();
+        let named: Vec<_> = patch.fixtures_by_label("led bar").map(Fixture::path).collect();
+        assert_eq!(named, vec![fpath![1], fpath![2]]);
+        assert_eq!(patch.fixture_by_label("LED BAR").unwrap().path(), fpath![1]);
+        assert!(patch.fixture_by_label("Spot").is_none());
+
+        let result = patch.rename_fixture(FixtureId::new(3).unwrap(), "Wash");
+        assert!(matches!(result, Err(Error::FixtureNotFound(_))));
+    }
 }
@@ -0,0 +1,460 @@
+use std::{fmt, str};
+
+use crate::show::fixture::{FixtureId, FixturePath};
+use crate::show::patch::Patch;
+
+/// An ordered set of root [FixtureId]s, as typed on a lighting console.
+///
+/// A selection is written as a list of terms combined with `+` (add) and `-`
+/// (remove), evaluated from left to right. A term is a single id, or a range
+/// `a thru b` which may be descending. A term can be followed by `even` or
+/// `odd` to only keep the even or odd ids in it. For example,
+/// `1 thru 10 odd + 21 - 5` selects 1, 3, 7, 9 and 21.
+///
+/// Ids keep the order in which they were first added and are never selected
+/// twice. Keywords are case-insensitive and whitespace between tokens is
+/// optional.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FixtureSelection {
+    ids: Vec<FixtureId>,
+}
+
+impl FixtureSelection {
+    /// Parses a selection, see [FixtureSelection] for the syntax.
+    pub fn parse(s: &str) -> Result<Self, SelectionError> {
+        Parser::new(s)?.parse()
+    }
+
+    /// Returns the selected ids, in order.
+    pub fn ids(&self) -> &[FixtureId] {
+        &self.ids
+    }
+
+    /// Returns `true` if nothing is selected.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns whether the id is selected.
+    pub fn contains(&self, id: FixtureId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Returns the paths of the selected root fixtures in the patch, in
+    /// order. Ids that are not in the patch are skipped, see
+    /// [FixtureSelection::missing].
+    pub fn resolve(&self, patch: &Patch) -> Vec<FixturePath> {
+        self.ids
+            .iter()
+            .map(|id| FixturePath::new(*id))
+            .filter(|path| patch.fixture(path).is_some())
+            .collect()
+    }
+
+    /// Returns the selected ids that are not a root fixture in the patch.
+    pub fn missing(&self, patch: &Patch) -> Vec<FixtureId> {
+        self.ids
+            .iter()
+            .copied()
+            .filter(|id| patch.fixture(&FixturePath::new(*id)).is_none())
+            .collect()
+    }
+
+    fn add(&mut self, ids: impl IntoIterator<Item = FixtureId>) {
+        for id in ids {
+            if !self.ids.contains(&id) {
+                self.ids.push(id);
+            }
+        }
+    }
+
+    fn remove(&mut self, ids: &[FixtureId]) {
+        self.ids.retain(|id| !ids.contains(id));
+    }
+}
+
+impl str::FromStr for FixtureSelection {
+    type Err = SelectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for FixtureSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids = self.ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "{}", ids.join(" + "))
+    }
+}
+
+/// Error returned when parsing an invalid [FixtureSelection].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid fixture selection at byte {offset}: {kind}")]
+pub struct SelectionError {
+    offset: usize,
+    kind: SelectionErrorKind,
+}
+
+impl SelectionError {
+    /// Returns the byte offset in the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns what went wrong.
+    pub fn kind(&self) -> &SelectionErrorKind {
+        &self.kind
+    }
+}
+
+/// The reason a [FixtureSelection] could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SelectionErrorKind {
+    #[error("selection is empty")]
+    Empty,
+
+    #[error("unexpected character '{0}'")]
+    UnexpectedChar(char),
+
+    #[error("unknown keyword '{0}'")]
+    UnknownKeyword(String),
+
+    #[error("expected {expected}, found {found}")]
+    Unexpected { expected: &'static str, found: String },
+
+    #[error("expected {0}, found end of selection")]
+    UnexpectedEnd(&'static str),
+
+    #[error("invalid fixture id '{0}'")]
+    InvalidId(String),
+
+    #[error("range of {0} ids is too large")]
+    RangeTooLarge(u32),
+}
+
+/// Ranges spanning more ids than this are rejected, so a typo like
+/// `1 thru 4000000000` doesn't allocate gigabytes.
+const MAX_RANGE_LEN: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Id(&'a str),
+    Thru,
+    Even,
+    Odd,
+    Plus,
+    Minus,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Id(id) => write!(f, "'{id}'"),
+            Token::Thru => write!(f, "'thru'"),
+            Token::Even => write!(f, "'even'"),
+            Token::Odd => write!(f, "'odd'"),
+            Token::Plus => write!(f, "'+'"),
+            Token::Minus => write!(f, "'-'"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>, SelectionError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut take_while = |pred: fn(char) -> bool| {
+            let mut end = start + c.len_utf8();
+            while let Some((i, c)) = chars.next_if(|(_, c)| pred(*c)) {
+                end = i + c.len_utf8();
+            }
+            &s[start..end]
+        };
+
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            c if c.is_ascii_digit() => Token::Id(take_while(|c| c.is_ascii_digit())),
+            c if c.is_alphabetic() => {
+                let word = take_while(char::is_alphabetic);
+                match word.to_ascii_lowercase().as_str() {
+                    "thru" => Token::Thru,
+                    "even" => Token::Even,
+                    "odd" => Token::Odd,
+                    _ => {
+                        return Err(SelectionError {
+                            offset: start,
+                            kind: SelectionErrorKind::UnknownKeyword(word.to_string()),
+                        });
+                    }
+                }
+            }
+            c => {
+                return Err(SelectionError {
+                    offset: start,
+                    kind: SelectionErrorKind::UnexpectedChar(c),
+                });
+            }
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+    len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Result<Self, SelectionError> {
+        Ok(Self { tokens: tokenize(s)?, pos: 0, len: s.len() })
+    }
+
+    fn parse(mut self) -> Result<FixtureSelection, SelectionError> {
+        if self.tokens.is_empty() {
+            return Err(SelectionError { offset: 0, kind: SelectionErrorKind::Empty });
+        }
+
+        let mut selection = FixtureSelection::default();
+        selection.add(self.term()?);
+        while let Some((offset, token)) = self.next() {
+            match token {
+                Token::Plus => selection.add(self.term()?),
+                Token::Minus => selection.remove(&self.term()?),
+                token => return Err(self.unexpected(offset, "'+' or '-'", token)),
+            }
+        }
+        Ok(selection)
+    }
+
+    fn term(&mut self) -> Result<Vec<FixtureId>, SelectionError> {
+        let start = self.id()?;
+        let mut ids = match self.peek() {
+            Some(Token::Thru) => {
+                let offset = self.tokens[self.pos].0;
+                self.pos += 1;
+                let end = self.id()?;
+                range(start, end, offset)?
+            }
+            _ => vec![start],
+        };
+
+        while let Some(modifier @ (Token::Even | Token::Odd)) = self.peek() {
+            self.next();
+            let remainder = if modifier == Token::Even { 0 } else { 1 };
+            ids.retain(|id| id.as_u32() % 2 == remainder);
+        }
+
+        Ok(ids)
+    }
+
+    fn id(&mut self) -> Result<FixtureId, SelectionError> {
+        match self.next() {
+            Some((offset, Token::Id(id))) => {
+                id.parse::<u32>().ok().and_then(|id| FixtureId::new(id).ok()).ok_or_else(|| {
+                    SelectionError { offset, kind: SelectionErrorKind::InvalidId(id.to_string()) }
+                })
+            }
+            Some((offset, token)) => Err(self.unexpected(offset, "a fixture id", token)),
+            None => Err(SelectionError {
+                offset: self.len,
+                kind: SelectionErrorKind::UnexpectedEnd("a fixture id"),
+            }),
+        }
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|(_, token)| *token)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token<'a>)> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn unexpected(&self, offset: usize, expected: &'static str, found: Token) -> SelectionError {
+        SelectionError {
+            offset,
+            kind: SelectionErrorKind::Unexpected { expected, found: found.to_string() },
+        }
+    }
+}
+
+fn range(
+    start: FixtureId,
+    end: FixtureId,
+    offset: usize,
+) -> Result<Vec<FixtureId>, SelectionError> {
+    let (low, high) = (start.min(end).as_u32(), start.max(end).as_u32());
+    let len = high - low + 1;
+    if len > MAX_RANGE_LEN {
+        return Err(SelectionError { offset, kind: SelectionErrorKind::RangeTooLarge(len) });
+    }
+
+    // Neither bound is zero, so every id in between is valid.
+    let ids = (low..=high).filter_map(|id| FixtureId::new(id).ok());
+    Ok(if start <= end { ids.collect() } else { ids.rev().collect() })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::dmx::{Address, Multiverse};
+    use crate::fpath;
+    use crate::show::fixture::Fixture;
+    use crate::value::DimmerCurve;
+
+    fn ids(s: &str) -> Vec<u32> {
+        let selection = FixtureSelection::parse(s).unwrap();
+        selection.ids().iter().map(FixtureId::as_u32).collect()
+    }
+
+    fn error(s: &str) -> (usize, SelectionErrorKind) {
+        let err = FixtureSelection::parse(s).unwrap_err();
+        (err.offset(), err.kind().clone())
+    }
+
+    #[test]
+    fn single_ids() {
+        assert_eq!(ids("1"), [1]);
+        assert_eq!(ids("101"), [101]);
+        assert_eq!(ids("  42  "), [42]);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(ids("1 thru 5"), [1, 2, 3, 4, 5]);
+        assert_eq!(ids("3 thru 3"), [3]);
+        assert_eq!(ids("5 thru 1"), [5, 4, 3, 2, 1]);
+        assert_eq!(ids("1 THRU 3"), [1, 2, 3]);
+        assert_eq!(ids("1 Thru 3"), [1, 2, 3]);
+    }
+
+    #[test]
+    fn add_and_remove_left_to_right() {
+        assert_eq!(ids("1 thru 10 + 21 - 5"), [1, 2, 3, 4, 6, 7, 8, 9, 10, 21]);
+        assert_eq!(ids("1 + 2 + 3"), [1, 2, 3]);
+        assert_eq!(ids("3 + 1 + 2"), [3, 1, 2]);
+        assert_eq!(ids("1 thru 5 - 2 thru 4"), [1, 5]);
+        // Removing and then adding again puts the id at the end.
+        assert_eq!(ids("1 thru 3 - 2 + 2"), [1, 3, 2]);
+        // Adding after removing is not the same as removing after adding.
+        assert_eq!(ids("1 + 2 - 2"), [1]);
+        assert_eq!(ids("1 - 2 + 2"), [1, 2]);
+        assert_eq!(ids("1 - 1"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn duplicates_keep_first_position() {
+        assert_eq!(ids("1 thru 3 + 2 + 1"), [1, 2, 3]);
+        assert_eq!(ids("5 + 1 thru 6"), [5, 1, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn even_and_odd_bind_to_term() {
+        assert_eq!(ids("1 thru 10 even"), [2, 4, 6, 8, 10]);
+        assert_eq!(ids("1 thru 10 odd"), [1, 3, 5, 7, 9]);
+        assert_eq!(ids("10 thru 1 odd"), [9, 7, 5, 3, 1]);
+        assert_eq!(ids("1 thru 10 odd + 21 - 5"), [1, 3, 7, 9, 21]);
+        // The modifier only applies to the term it follows.
+        assert_eq!(ids("1 thru 4 + 5 thru 8 even"), [1, 2, 3, 4, 6, 8]);
+        assert_eq!(ids("1 thru 6 - 1 thru 6 even"), [1, 3, 5]);
+        assert_eq!(ids("3 even"), Vec::<u32>::new());
+        assert_eq!(ids("1 thru 10 even odd"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn whitespace_is_optional() {
+        assert_eq!(ids("1thru3"), [1, 2, 3]);
+        assert_eq!(ids("1+2-1"), [2]);
+        assert_eq!(ids("1thru10odd-5"), [1, 3, 7, 9]);
+        assert_eq!(ids("\t1 \n thru\t3  "), [1, 2, 3]);
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let selection: FixtureSelection = "3 thru 1 + 7".parse().unwrap();
+        assert_eq!(selection.to_string(), "3 + 2 + 1 + 7");
+        assert_eq!(selection.to_string().parse::<FixtureSelection>().unwrap(), selection);
+    }
+
+    #[test]
+    fn errors_report_byte_offsets() {
+        assert_eq!(error(""), (0, SelectionErrorKind::Empty));
+        assert_eq!(error("   "), (0, SelectionErrorKind::Empty));
+        assert_eq!(error("1 thru"), (6, SelectionErrorKind::UnexpectedEnd("a fixture id")));
+        assert_eq!(error("1 +"), (3, SelectionErrorKind::UnexpectedEnd("a fixture id")));
+        assert_eq!(
+            error("1 2"),
+            (2, SelectionErrorKind::Unexpected { expected: "'+' or '-'", found: "'2'".into() })
+        );
+        assert_eq!(
+            error("+ 1"),
+            (0, SelectionErrorKind::Unexpected { expected: "a fixture id", found: "'+'".into() })
+        );
+        assert_eq!(
+            error("1 thru thru 2"),
+            (
+                7,
+                SelectionErrorKind::Unexpected { expected: "a fixture id", found: "'thru'".into() }
+            )
+        );
+        assert_eq!(
+            error("1 thru 2 thru 3"),
+            (9, SelectionErrorKind::Unexpected { expected: "'+' or '-'", found: "'thru'".into() })
+        );
+        assert_eq!(
+            error("even"),
+            (
+                0,
+                SelectionErrorKind::Unexpected { expected: "a fixture id", found: "'even'".into() }
+            )
+        );
+        assert_eq!(error("1 + 0"), (4, SelectionErrorKind::InvalidId("0".into())));
+        assert_eq!(error("99999999999"), (0, SelectionErrorKind::InvalidId("99999999999".into())));
+        assert_eq!(error("1, 2"), (1, SelectionErrorKind::UnexpectedChar(',')));
+        // Offsets are in bytes, not characters.
+        assert_eq!(error("1 + é"), (4, SelectionErrorKind::UnknownKeyword("é".into())));
+        assert_eq!(error("1 through 3"), (2, SelectionErrorKind::UnknownKeyword("through".into())));
+        assert_eq!(error("1 thru 1000000"), (2, SelectionErrorKind::RangeTooLarge(1_000_000)));
+    }
+
+    #[test]
+    fn error_message_includes_offset() {
+        let err = FixtureSelection::parse("1 + x").unwrap_err();
+        assert_eq!(err.to_string(), "invalid fixture selection at byte 4: unknown keyword 'x'");
+    }
+
+    #[test]
+    fn resolve_skips_missing_fixtures() {
+        let fixture = |path: FixturePath| Fixture {
+            path,
+            root_base_address: Address::default(),
+            name: path.to_string(),
+            gdtf_fixture_type_id: uuid::Uuid::nil(),
+            gdtf_dmx_mode: "Default".to_string(),
+            channel_functions: HashMap::new(),
+            sub_fixture_paths: Vec::new(),
+            dimmer_curve: DimmerCurve::Linear,
+        };
+        let patch = Patch {
+            fixtures: [fpath![1], fpath![1, 1], fpath![2], fpath![3]]
+                .into_iter()
+                .map(|path| (path, fixture(path)))
+                .collect(),
+            default_multiverse: Multiverse::new(),
+            groups: BTreeMap::new(),
+        };
+
+        let selection = FixtureSelection::parse("3 + 4 + 1").unwrap();
+        assert_eq!(selection.resolve(&patch), [fpath![3], fpath![1]]);
+        assert_eq!(selection.missing(&patch), [FixtureId::new(4).unwrap()]);
+    }
+}
@@ -1,24 +1,474 @@
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use std::{fmt, str};
+
+/// The valid range for [Config::output_refresh_rate_hz].
+pub const OUTPUT_REFRESH_RATES_HZ: RangeInclusive<f32> = 1.0..=120.0;
+
+/// The valid range for [Config::max_frame_size_bytes]. The length prefix of a
+/// frame has 31 bits for the size of its payload.
+pub const MAX_FRAME_SIZES_BYTES: RangeInclusive<usize> = 64 * 1024..=i32::MAX as usize;
 
 /// General configuration for the server.
 #[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct Config {
-    address: SocketAddr,
+    /// The name the server announces itself with.
+    name: String,
+    /// The address the server accepts clients on, e.g. `127.0.0.1:7334`,
+    /// `[::]:7334` or `lighting-server.local:7334`.
+    address: ServerAddress,
+    /// Whether the server binds to an IPv4 address when the host of
+    /// `address` resolves to both IPv4 and IPv6 addresses.
+    prefer_ipv4: bool,
+    /// Seconds without any packet from a client before the server pings it.
+    /// A value of `0` disables pings.
+    ping_interval_secs: u64,
+    /// Seconds without any packet from a client, including pongs, before its
+    /// connection is closed. A value of `0` disables the timeout.
+    idle_timeout_secs: u64,
+    /// Number of DMX frames sent per second by the output protocols.
+    #[serde(deserialize_with = "deserialize_refresh_rate")]
+    output_refresh_rate_hz: f32,
+    /// Whether to send a final frame with every channel at zero when the
+    /// server stops, instead of leaving receivers at the last frame.
+    blackout_on_exit: bool,
+    /// Whether channels without an attribute value output the default value
+    /// of their channel function, instead of zero.
+    output_defaults_when_idle: bool,
+    /// Whether the server answers discovery probes of clients on the network.
+    enable_discovery: bool,
+    /// The UDP port the server listens for discovery probes on.
+    discovery_port: u16,
+    /// Which clients are allowed to change the output.
+    permissions: Permissions,
+    /// Whether the server reloads the showfile when it changes on disk.
+    hot_reload: bool,
+    /// The TCP port the server serves Prometheus metrics on, if any.
+    metrics_port: Option<u16>,
+    /// Whether snapshots of the attribute values are saved next to the
+    /// showfile, so they are kept across runs.
+    persist_snapshots: bool,
+    /// The maximum size of a packet from a client, in bytes. Clients that send
+    /// larger packets are disconnected.
+    #[serde(deserialize_with = "deserialize_max_frame_size")]
+    max_frame_size_bytes: usize,
 }
 
 impl Config {
-    /// Returns the socket address configured for the server.
-    pub fn address(&self) -> SocketAddr {
-        self.address
+    /// Returns the name the server announces itself with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the address configured for the server, which may have to be
+    /// resolved before binding to it.
+    pub fn address(&self) -> &ServerAddress {
+        &self.address
+    }
+
+    /// Returns whether the server prefers an IPv4 address when the host of
+    /// [Config::address] resolves to multiple addresses, instead of taking
+    /// the first one.
+    pub fn prefer_ipv4(&self) -> bool {
+        self.prefer_ipv4
+    }
+
+    /// Returns how long a client can be idle before the server pings it, or
+    /// `None` if the server never pings clients.
+    ///
+    /// Clients that miss [MAX_MISSED_PONGS](crate::packet::MAX_MISSED_PONGS)
+    /// consecutive pings are disconnected.
+    pub fn ping_interval(&self) -> Option<Duration> {
+        match self.ping_interval_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Returns how long a client can be idle before its connection is
+    /// closed, or `None` if idle connections are never closed.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        match self.idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Returns the number of DMX frames sent per second by the output protocols.
+    pub fn output_refresh_rate_hz(&self) -> f32 {
+        self.output_refresh_rate_hz
+    }
+
+    /// Returns whether a final blackout frame is sent when the server stops.
+    pub fn blackout_on_exit(&self) -> bool {
+        self.blackout_on_exit
+    }
+
+    /// Returns whether channels without an attribute value output the default
+    /// value of their channel function (e.g. shutter open, pan and tilt
+    /// centered), instead of zero.
+    pub fn output_defaults_when_idle(&self) -> bool {
+        self.output_defaults_when_idle
+    }
+
+    /// Returns whether the server answers discovery probes, so clients can
+    /// find it with `Client::discover`.
+    pub fn enable_discovery(&self) -> bool {
+        self.enable_discovery
+    }
+
+    /// Returns the UDP port the server listens for discovery probes on.
+    pub fn discovery_port(&self) -> u16 {
+        self.discovery_port
+    }
+
+    /// Returns which clients are allowed to change the output.
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Returns whether the server reloads the patch and protocols of the
+    /// showfile when it changes on disk, without disconnecting clients.
+    pub fn hot_reload(&self) -> bool {
+        self.hot_reload
+    }
+
+    /// Returns the TCP port the server serves its stats on as Prometheus
+    /// metrics at `/metrics`, on the same IP address it accepts clients on.
+    /// Returns `None` if metrics are not served.
+    pub fn metrics_port(&self) -> Option<u16> {
+        self.metrics_port
+    }
+
+    /// Returns whether the server saves snapshots to a `snapshots.json` file
+    /// next to the showfile (`<archive>.snapshots.json` for an archive), and
+    /// loads them from it when it starts.
+    pub fn persist_snapshots(&self) -> bool {
+        self.persist_snapshots
+    }
+
+    /// Returns the maximum size in bytes of the payload of a packet from a
+    /// client, both as received and after decompression. Clients that send a
+    /// larger packet are disconnected, so they can't make the server run out
+    /// of memory.
+    pub fn max_frame_size_bytes(&self) -> usize {
+        self.max_frame_size_bytes
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            address: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, crate::DEFAULT_PORT)),
+            name: "Zeevonk".to_string(),
+            address: ServerAddress::new(Ipv4Addr::LOCALHOST.to_string(), crate::DEFAULT_PORT),
+            prefer_ipv4: false,
+            ping_interval_secs: 10,
+            idle_timeout_secs: 30,
+            output_refresh_rate_hz: 40.0,
+            blackout_on_exit: true,
+            output_defaults_when_idle: true,
+            enable_discovery: false,
+            discovery_port: crate::DEFAULT_PORT,
+            permissions: Permissions::default(),
+            hot_reload: false,
+            metrics_port: None,
+            persist_snapshots: false,
+            max_frame_size_bytes: crate::packet::DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+/// A host and port the server accepts clients on. The host is an IP address
+/// or a hostname that is resolved when the server starts.
+///
+/// As a string, this is written as `host:port`, with IPv6 addresses in
+/// brackets, e.g. `127.0.0.1:7334`, `[::1]:7334` or `localhost:7334`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ServerAddress {
+    host: String,
+    port: u16,
+}
+
+impl ServerAddress {
+    /// Creates a new [ServerAddress]. IPv6 addresses are given without
+    /// brackets.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self { host: host.into(), port }
+    }
+
+    /// Returns the IP address or hostname, without brackets.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the port.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the host as an IP address, or `None` if it is a hostname.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host.parse().ok()
+    }
+}
+
+impl From<SocketAddr> for ServerAddress {
+    fn from(address: SocketAddr) -> Self {
+        Self::new(address.ip().to_string(), address.port())
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl str::FromStr for ServerAddress {
+    type Err = InvalidServerAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(address) = s.parse::<SocketAddr>() {
+            return Ok(address.into());
+        }
+
+        let invalid = |reason| InvalidServerAddress { address: s.to_string(), reason };
+        let (host, port) = s.rsplit_once(':').ok_or_else(|| invalid("missing port"))?;
+        let port = port.parse().map_err(|_| invalid("invalid port"))?;
+        let host = match host.strip_prefix('[').and_then(|host| host.strip_suffix(']')) {
+            Some(ip) => {
+                ip.parse::<Ipv6Addr>().map_err(|_| invalid("invalid IPv6 address"))?.to_string()
+            }
+            None if host.contains(':') => {
+                return Err(invalid("IPv6 addresses should be in brackets"));
+            }
+            None if host.is_empty() => return Err(invalid("missing host")),
+            None => host.to_string(),
+        };
+        Ok(Self::new(host, port))
+    }
+}
+
+impl TryFrom<String> for ServerAddress {
+    type Error = InvalidServerAddress;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ServerAddress> for String {
+    fn from(address: ServerAddress) -> Self {
+        address.to_string()
+    }
+}
+
+/// Error returned when parsing an invalid [ServerAddress].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid address '{address}': {reason}, expected host:port")]
+pub struct InvalidServerAddress {
+    address: String,
+    reason: &'static str,
+}
+
+/// The permissions of clients, by the name they send when connecting.
+///
+/// ```json
+/// {
+///     "default_read_only": false,
+///     "clients": {
+///         "visualizer": { "read_only": true }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+    /// Whether clients without an entry in `clients` are read-only.
+    default_read_only: bool,
+    clients: BTreeMap<String, ClientPermissions>,
+}
+
+impl Permissions {
+    /// Returns whether clients without their own permissions are read-only.
+    pub fn default_read_only(&self) -> bool {
+        self.default_read_only
+    }
+
+    /// Returns the permissions of the client with the given name, if it has
+    /// its own.
+    pub fn client(&self, client_name: &str) -> Option<&ClientPermissions> {
+        self.clients.get(client_name)
+    }
+
+    /// Returns whether the client with the given name is read-only, falling
+    /// back to [Permissions::default_read_only] for unknown clients.
+    pub fn is_read_only(&self, client_name: &str) -> bool {
+        self.client(client_name).map_or(self.default_read_only, ClientPermissions::read_only)
+    }
+}
+
+/// The permissions of a single client.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ClientPermissions {
+    /// Whether the client is only allowed to make requests that don't change
+    /// the output, like requesting the DMX output.
+    read_only: bool,
+}
+
+impl ClientPermissions {
+    /// Returns whether the client is read-only.
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+fn deserialize_refresh_rate<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f32, D::Error> {
+    let rate = <f32 as serde::Deserialize>::deserialize(deserializer)?;
+    if !OUTPUT_REFRESH_RATES_HZ.contains(&rate) {
+        return Err(serde::de::Error::custom(format!(
+            "output refresh rate of {rate} Hz is out of range, should be in the range {}..={} Hz",
+            OUTPUT_REFRESH_RATES_HZ.start(),
+            OUTPUT_REFRESH_RATES_HZ.end()
+        )));
+    }
+    Ok(rate)
+}
+
+fn deserialize_max_frame_size<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<usize, D::Error> {
+    let size = <usize as serde::Deserialize>::deserialize(deserializer)?;
+    if !MAX_FRAME_SIZES_BYTES.contains(&size) {
+        return Err(serde::de::Error::custom(format!(
+            "maximum frame size of {size} bytes is out of range, should be in the range {}..={} bytes",
+            MAX_FRAME_SIZES_BYTES.start(),
+            MAX_FRAME_SIZES_BYTES.end()
+        )));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let config = serde_json::from_str::<Config>("{}").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.output_refresh_rate_hz(), 40.0);
+        assert!(config.blackout_on_exit());
+        assert!(config.output_defaults_when_idle());
+        assert!(!config.enable_discovery());
+        assert_eq!(config.discovery_port(), crate::DEFAULT_PORT);
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let json = r#"{ "max_frame_size_bytes": 1048576 }"#;
+        let config = serde_json::from_str::<Config>(json).unwrap();
+        assert_eq!(config.max_frame_size_bytes(), 1024 * 1024);
+        assert_eq!(Config::default().max_frame_size_bytes(), crate::packet::DEFAULT_MAX_FRAME_SIZE);
+
+        for size in [0, 1024, 1u64 << 31] {
+            let json = format!(r#"{{ "max_frame_size_bytes": {size} }}"#);
+            let err = serde_json::from_str::<Config>(&json).unwrap_err();
+            assert!(err.to_string().contains("out of range"), "{err}");
+        }
+    }
+
+    #[test]
+    fn server_address_from_str() {
+        for (s, host, port) in [
+            ("127.0.0.1:7334", "127.0.0.1", 7334),
+            ("localhost:7334", "localhost", 7334),
+            ("lighting-server.local:80", "lighting-server.local", 80),
+            ("[::1]:7334", "::1", 7334),
+            ("[::]:0", "::", 0),
+        ] {
+            let address = s.parse::<ServerAddress>().unwrap();
+            assert_eq!((address.host(), address.port()), (host, port), "{s}");
+            assert_eq!(address.to_string(), s);
+        }
+
+        assert_eq!("localhost:1".parse::<ServerAddress>().unwrap().ip(), None);
+        assert_eq!(
+            "[::1]:1".parse::<ServerAddress>().unwrap().ip(),
+            Some(Ipv6Addr::LOCALHOST.into())
+        );
+
+        for (s, reason) in [
+            ("localhost", "missing port"),
+            ("localhost:", "invalid port"),
+            ("localhost:65536", "invalid port"),
+            (":7334", "missing host"),
+            ("::1:7334", "IPv6 addresses should be in brackets"),
+            ("[::g]:7334", "invalid IPv6 address"),
+        ] {
+            let err = s.parse::<ServerAddress>().unwrap_err();
+            assert_eq!(err.reason, reason, "{s}");
+        }
+    }
+
+    #[test]
+    fn address_from_json() {
+        let config = serde_json::from_str::<Config>(r#"{ "address": "[::]:7334" }"#).unwrap();
+        assert_eq!(config.address(), &ServerAddress::new("::", 7334));
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["address"], "[::]:7334");
+
+        let config = serde_json::from_str::<Config>(r#"{ "address": "zeevonk.local:1" }"#).unwrap();
+        assert_eq!(config.address().host(), "zeevonk.local");
+
+        let err = serde_json::from_str::<Config>(r#"{ "address": "zeevonk.local" }"#).unwrap_err();
+        assert!(err.to_string().contains("invalid address 'zeevonk.local'"), "{err}");
+    }
+
+    #[test]
+    fn permissions() {
+        let json = r#"{
+            "permissions": {
+                "default_read_only": true,
+                "clients": { "console": { "read_only": false }, "visualizer": { "read_only": true } }
+            }
+        }"#;
+        let config = serde_json::from_str::<Config>(json).unwrap();
+        let permissions = config.permissions();
+        assert!(!permissions.is_read_only("console"));
+        assert!(permissions.is_read_only("visualizer"));
+        assert!(permissions.is_read_only("unknown"));
+
+        assert!(!Config::default().permissions().is_read_only("unknown"));
+    }
+
+    #[test]
+    fn output_refresh_rate_range() {
+        for rate in ["1", "44", "120", "60.5"] {
+            let json = format!(r#"{{ "output_refresh_rate_hz": {rate} }}"#);
+            let config = serde_json::from_str::<Config>(&json).unwrap();
+            assert_eq!(config.output_refresh_rate_hz(), rate.parse::<f32>().unwrap());
+        }
+
+        for rate in ["0", "0.5", "120.1", "1000", "-40"] {
+            let json = format!(r#"{{ "output_refresh_rate_hz": {rate} }}"#);
+            let err = serde_json::from_str::<Config>(&json).unwrap_err();
+            assert!(err.to_string().contains("out of range"), "{rate}: {err}");
         }
     }
 }
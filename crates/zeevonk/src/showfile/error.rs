@@ -1,13 +1,36 @@
 use thiserror::Error;
 
+use crate::show::fixture::FixtureId;
+use crate::showfile::{GroupId, Identifier, ValidationIssue};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("i/o error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("invalid showfile archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error("GDTF file '{0}' not found")]
+    MissingGdtfFile(String),
     #[error("failed to serialize showfile: {message}")]
     SerializationError { message: String },
     #[error("failed to deserialize showfile: {message}")]
     DeserializationError { message: String },
     #[error("missing or invalid directory: {0}")]
     InvalidDirectory(String),
+    #[error("group {group} references unknown fixture {fixture}")]
+    UnknownGroupMember { group: GroupId, fixture: FixtureId },
+    #[error("multiple triggers with identifier '{0}'")]
+    DuplicateTrigger(Identifier),
+    #[error("fixture id {0} is already used")]
+    DuplicateFixtureId(FixtureId),
+    #[error("invalid fixture array: {message}")]
+    InvalidFixtureArray { message: String },
+    #[error("dmx error: {0}")]
+    Dmx(#[from] crate::dmx::Error),
+    #[error("unsupported showfile version {version}, the latest supported version is {supported}")]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[error("sACN source name '{name}' is {} bytes long, but may be at most {max} bytes", .name.len())]
+    SacnSourceNameTooLong { name: String, max: usize },
+    #[error("showfile has {} validation issue(s)", .0.len())]
+    Invalid(Vec<ValidationIssue>),
 }
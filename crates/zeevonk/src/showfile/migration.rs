@@ -0,0 +1,69 @@
+use serde_json::Value;
+
+use crate::showfile::Error;
+
+/// The version of the showfile format written by this version of Zeevonk.
+///
+/// Bump this when the format changes in a way that older showfiles no longer
+/// deserialize, and add a migration step from the previous version to
+/// [migrate].
+pub const CURRENT_SHOWFILE_VERSION: u32 = 1;
+
+/// Upgrades a showfile description of any older version to the shape of
+/// [CURRENT_SHOWFILE_VERSION], so it can be deserialized.
+///
+/// Showfiles without a version are version 1, the first versioned format.
+pub(super) fn migrate(description: &mut Value) -> Result<(), Error> {
+    let version = match description.get("version") {
+        None => 1,
+        Some(version) => version.as_u64().and_then(|v| u32::try_from(v).ok()).ok_or_else(|| {
+            Error::DeserializationError { message: format!("invalid showfile version: {version}") }
+        })?,
+    };
+
+    if version == 0 || version > CURRENT_SHOWFILE_VERSION {
+        return Err(Error::UnsupportedVersion { version, supported: CURRENT_SHOWFILE_VERSION });
+    }
+
+    // Every format change adds a step here that upgrades the description by
+    // one version, so old showfiles pass through all steps in order, e.g.:
+    //
+    // if version < 2 {
+    //     migrate_v1_to_v2(description);
+    // }
+
+    if let Some(description) = description.as_object_mut() {
+        description.insert("version".to_string(), CURRENT_SHOWFILE_VERSION.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn unversioned_showfile_is_current() {
+        let mut description = json!({ "config": {} });
+        migrate(&mut description).unwrap();
+        assert_eq!(description["version"], json!(CURRENT_SHOWFILE_VERSION));
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let mut description = json!({ "version": CURRENT_SHOWFILE_VERSION + 1 });
+        assert!(matches!(
+            migrate(&mut description),
+            Err(Error::UnsupportedVersion { supported: CURRENT_SHOWFILE_VERSION, .. })
+        ));
+
+        let mut description = json!({ "version": 0 });
+        assert!(matches!(migrate(&mut description), Err(Error::UnsupportedVersion { .. })));
+
+        let mut description = json!({ "version": "one" });
+        assert!(matches!(migrate(&mut description), Err(Error::DeserializationError { .. })));
+    }
+}
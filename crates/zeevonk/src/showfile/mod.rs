@@ -1,14 +1,22 @@
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub use config::*;
 pub use error::*;
+pub use migration::CURRENT_SHOWFILE_VERSION;
 pub use patch::*;
 pub use protocols::*;
+pub use triggers::*;
+pub use validation::*;
 
 mod config;
+mod migration;
 mod patch;
 mod protocols;
+mod triggers;
+mod validation;
 
 mod error;
 
@@ -16,43 +24,204 @@ const RELATIVE_DESCRIPTION_FILE_PATH: &str = "showfile.json";
 const RELATIVE_GDTF_FILES_PATH: &str = "gdtf_files";
 
 // A showfile is the main configuration for Zeevonk.
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct Showfile {
     #[serde(skip)]
     gdtf_file_paths: Vec<PathBuf>,
+    /// The resolved paths of the files in `gdtf_sources`.
+    #[serde(skip)]
+    gdtf_source_paths: Vec<PathBuf>,
+    /// GDTF files read from a showfile archive, by file name.
+    #[serde(skip)]
+    embedded_gdtf_files: Vec<(String, Arc<[u8]>)>,
+
+    version: u32,
+
+    /// File names of the GDTF files to use, relative to the GDTF files
+    /// directory. If empty, all GDTF files in the directory are used.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    gdtf_files: Vec<String>,
+
+    /// GDTF files outside of the GDTF files directory, e.g. in a fixture
+    /// library shared by multiple showfiles.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    gdtf_sources: Vec<GdtfSource>,
 
     config: Config,
     patch: Patch,
     protocols: Protocols,
+    triggers: Vec<Trigger>,
+}
+
+impl Default for Showfile {
+    fn default() -> Self {
+        Self {
+            gdtf_file_paths: Vec::new(),
+            gdtf_source_paths: Vec::new(),
+            embedded_gdtf_files: Vec::new(),
+            version: CURRENT_SHOWFILE_VERSION,
+            gdtf_files: Vec::new(),
+            gdtf_sources: Vec::new(),
+            config: Config::default(),
+            patch: Patch::default(),
+            protocols: Protocols::default(),
+            triggers: Vec::new(),
+        }
+    }
 }
 
 impl Showfile {
+    /// Loads the showfile from a showfile folder, or from a showfile archive
+    /// if the path is a file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if path.is_file() { Self::load_from_archive(path) } else { Self::load_from_folder(path) }
+    }
+
+    /// Loads the showfile from the given folder, migrating it from older
+    /// versions of the format if needed.
     pub fn load_from_folder(showfile_path: &Path) -> Result<Self, Error> {
         // Load showfile from description file.
         let showfile_file = fs::File::open(showfile_path.join(RELATIVE_DESCRIPTION_FILE_PATH))?;
-        let mut showfile: Showfile = serde_json::from_reader(showfile_file)
-            .map_err(|e| Error::DeserializationError { message: e.to_string() })?;
+        let mut showfile = Self::from_description(showfile_file)?;
+        showfile.resolve_gdtf_sources(showfile_path)?;
 
         // Get GDTF file paths.
         let gdtf_dir_path = showfile_path.join(RELATIVE_GDTF_FILES_PATH);
-        let gdtf_file_dir = fs::read_dir(&gdtf_dir_path)?;
-        for entry in gdtf_file_dir {
-            let Ok(entry) = entry else { continue };
+        if showfile.gdtf_files.is_empty() {
+            let gdtf_file_dir = match fs::read_dir(&gdtf_dir_path) {
+                Ok(gdtf_file_dir) => Some(gdtf_file_dir),
+                // Showfiles that only use GDTF sources don't need the directory.
+                Err(err)
+                    if err.kind() == io::ErrorKind::NotFound
+                        && !showfile.gdtf_sources.is_empty() =>
+                {
+                    None
+                }
+                Err(err) => return Err(err.into()),
+            };
+            for entry in gdtf_file_dir.into_iter().flatten() {
+                let Ok(entry) = entry else { continue };
+
+                let file_path = entry.path();
+
+                if !file_path.extension().is_some_and(|ext| ext == "gdtf") {
+                    continue;
+                }
+
+                if showfile.is_gdtf_source(&file_path) {
+                    continue;
+                }
+
+                showfile.gdtf_file_paths.push(file_path);
+            }
+        } else {
+            for name in &showfile.gdtf_files {
+                let file_path = gdtf_dir_path.join(name);
+                if !file_path.is_file() {
+                    return Err(Error::MissingGdtfFile(name.clone()));
+                }
+                if showfile.is_gdtf_source(&file_path) {
+                    continue;
+                }
+                showfile.gdtf_file_paths.push(file_path);
+            }
+        }
+
+        showfile.check_loaded()?;
 
-            let file_path = entry.path();
+        Ok(showfile)
+    }
+
+    /// Loads the showfile from a single `.zvk` archive: a zip file containing
+    /// the `showfile.json` description and the GDTF files, either next to the
+    /// description or in a `gdtf_files` directory.
+    pub fn load_from_archive(archive_path: &Path) -> Result<Self, Error> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)?;
+        let mut showfile =
+            Self::from_description(archive.by_name(RELATIVE_DESCRIPTION_FILE_PATH)?)?;
+        showfile.resolve_gdtf_sources(archive_path.parent().unwrap_or(Path::new("")))?;
+
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            let Some(name) = file
+                .enclosed_name()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "gdtf"))
+                .and_then(|path| path.file_name()?.to_str().map(str::to_string))
+            else {
+                continue;
+            };
 
-            if !file_path.extension().is_some_and(|ext| ext == "gdtf") {
+            if !showfile.gdtf_files.is_empty() && !showfile.gdtf_files.contains(&name) {
                 continue;
             }
 
-            showfile.gdtf_file_paths.push(file_path);
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            showfile.embedded_gdtf_files.push((name, data.into()));
         }
 
+        if let Some(missing) = showfile.gdtf_files.iter().find(|name| {
+            !showfile.embedded_gdtf_files.iter().any(|(embedded, _)| embedded == *name)
+        }) {
+            return Err(Error::MissingGdtfFile(missing.clone()));
+        }
+
+        showfile.check_loaded()?;
+
         Ok(showfile)
     }
 
+    /// Returns the path of the file that describes the showfile at the path:
+    /// the description in a showfile folder, or the archive itself.
+    pub fn description_path(path: &Path) -> PathBuf {
+        if path.is_file() { path.to_path_buf() } else { path.join(RELATIVE_DESCRIPTION_FILE_PATH) }
+    }
+
+    /// Reads and migrates the showfile description.
+    fn from_description(reader: impl Read) -> Result<Self, Error> {
+        let mut description: serde_json::Value = serde_json::from_reader(reader)
+            .map_err(|e| Error::DeserializationError { message: e.to_string() })?;
+        migration::migrate(&mut description)?;
+        serde_json::from_value(description)
+            .map_err(|e| Error::DeserializationError { message: e.to_string() })
+    }
+
+    /// Returns `true` if the file is also listed as a GDTF source, so it is
+    /// only read once.
+    fn is_gdtf_source(&self, file_path: &Path) -> bool {
+        fs::canonicalize(file_path).is_ok_and(|path| self.gdtf_source_paths.contains(&path))
+    }
+
+    /// Resolves the paths of the GDTF sources, which are relative to the
+    /// given folder, checking that they exist.
+    fn resolve_gdtf_sources(&mut self, base_path: &Path) -> Result<(), Error> {
+        self.gdtf_source_paths = self
+            .gdtf_sources
+            .iter()
+            .map(|source| {
+                let path = base_path.join(&source.path);
+                if !path.is_file() {
+                    return Err(Error::MissingGdtfFile(source.path.display().to_string()));
+                }
+                Ok(fs::canonicalize(path)?)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Checks a loaded showfile for structural problems.
+    ///
+    /// The patch is not validated, so a showfile with an invalid patch can
+    /// still be loaded to repair it. See [Showfile::check_valid].
+    fn check_loaded(&self) -> Result<(), Error> {
+        self.validate_groups()?;
+        self.validate_triggers()?;
+        self.validate_protocols()?;
+        Ok(())
+    }
+
     pub fn save_to_folder(&self, showfile_path: &Path) -> Result<(), Error> {
         // Ensure the gdtf_files directory exists.
         let gdtf_dir = showfile_path.join(RELATIVE_GDTF_FILES_PATH);
@@ -60,13 +229,24 @@ impl Showfile {
 
         // Save the showfile description.
         let description_path = showfile_path.join(RELATIVE_DESCRIPTION_FILE_PATH);
-        let showfile_to_save = self.clone();
+        let mut showfile_to_save = self.clone();
+
+        // GDTF sources are referenced instead of copied, so sources that are
+        // relative to another folder are saved with their full path.
+        for (source, resolved_path) in
+            showfile_to_save.gdtf_sources.iter_mut().zip(&self.gdtf_source_paths)
+        {
+            let path = fs::canonicalize(showfile_path.join(&source.path)).ok();
+            if path.as_ref() != Some(resolved_path) {
+                source.path = resolved_path.clone();
+            }
+        }
 
         let file = fs::File::create(&description_path)?;
         serde_json::to_writer_pretty(file, &showfile_to_save)
             .map_err(|e| Error::SerializationError { message: e.to_string() })?;
 
-        // Copy GDTF files into the gdtf_files directory.
+        // Copy GDTF files into the gdtf_files directory, except the sources.
         for path in &self.gdtf_file_paths {
             if let Some(filename) = path.file_name() {
                 let dest = gdtf_dir.join(filename);
@@ -77,13 +257,43 @@ impl Showfile {
             }
         }
 
+        for (name, data) in &self.embedded_gdtf_files {
+            fs::write(gdtf_dir.join(name), data)?;
+        }
+
         Ok(())
     }
 
+    /// Returns the version of the showfile format. Loaded showfiles are
+    /// always migrated to [CURRENT_SHOWFILE_VERSION].
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the paths of the GDTF files in the GDTF files directory.
     pub fn gdtf_file_paths(&self) -> &[PathBuf] {
         &self.gdtf_file_paths
     }
 
+    /// Returns the GDTF files the showfile references outside of its GDTF
+    /// files directory.
+    pub fn gdtf_sources(&self) -> &[GdtfSource] {
+        &self.gdtf_sources
+    }
+
+    /// Returns the resolved paths of the [GDTF sources](Self::gdtf_sources).
+    /// Their fixture types take precedence over fixture types with the same
+    /// id in the GDTF files directory.
+    pub fn gdtf_source_paths(&self) -> &[PathBuf] {
+        &self.gdtf_source_paths
+    }
+
+    /// Returns the names and contents of the GDTF files that were loaded from
+    /// a showfile archive.
+    pub fn embedded_gdtf_files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.embedded_gdtf_files.iter().map(|(name, data)| (name.as_str(), &**data))
+    }
+
     pub fn config(&self) -> &Config {
         &self.config
     }
@@ -92,7 +302,391 @@ impl Showfile {
         &self.patch
     }
 
+    pub fn patch_mut(&mut self) -> &mut Patch {
+        &mut self.patch
+    }
+
     pub fn protocols(&self) -> &Protocols {
         &self.protocols
     }
+
+    pub fn protocols_mut(&mut self) -> &mut Protocols {
+        &mut self.protocols
+    }
+
+    /// Generates an sACN component identifier if the showfile has sACN
+    /// outputs but no identifier yet. Returns `true` if one was generated,
+    /// in which case the showfile should be saved to keep it across runs.
+    pub fn ensure_sacn_cid(&mut self) -> bool {
+        let sacn = self.protocols.sacn_mut();
+        if sacn.cid().is_some() || sacn.outputs().is_empty() {
+            return false;
+        }
+        sacn.set_cid(uuid::Uuid::new_v4());
+        true
+    }
+
+    pub fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
+
+    /// Checks every fixture in the patch for problems, like unknown fixture
+    /// types or DMX modes, overlapping addresses, duplicate ids, empty labels
+    /// and invalid dimmer curves.
+    ///
+    /// All issues are collected, so they can be reported at once.
+    #[cfg(feature = "server")]
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        crate::server::validate_showfile(self)
+    }
+
+    /// Checks that the showfile can be used to run a show, returning
+    /// [Error::Invalid] with all issues that prevent it.
+    pub fn check_valid(&self) -> Result<(), Error> {
+        let issues = self.issues();
+        if !issues.is_empty() {
+            return Err(Error::Invalid(issues));
+        }
+
+        Ok(())
+    }
+
+    /// Returns all issues that prevent the showfile from being used.
+    #[cfg(feature = "server")]
+    fn issues(&self) -> Vec<ValidationIssue> {
+        self.validate()
+    }
+
+    /// Returns all issues that prevent the showfile from being used.
+    ///
+    /// GDTF files can only be checked with the `server` feature, so only the
+    /// fixture ids and labels are checked.
+    #[cfg(not(feature = "server"))]
+    fn issues(&self) -> Vec<ValidationIssue> {
+        validation::fixture_issues(self)
+    }
+
+    /// Checks that every group member references a fixture in the patch.
+    fn validate_groups(&self) -> Result<(), Error> {
+        for group in self.patch.groups() {
+            for member in group.members() {
+                if self.patch.fixture(*member).is_none() {
+                    return Err(Error::UnknownGroupMember { group: group.id(), fixture: *member });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the protocol configuration can be used to send output.
+    fn validate_protocols(&self) -> Result<(), Error> {
+        let source_name = self.protocols.sacn().source_name();
+        if source_name.len() > SACN_SOURCE_NAME_MAX_LEN {
+            return Err(Error::SacnSourceNameTooLong {
+                name: source_name.to_string(),
+                max: SACN_SOURCE_NAME_MAX_LEN,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no two triggers have the same identifier.
+    fn validate_triggers(&self) -> Result<(), Error> {
+        let mut ids = std::collections::HashSet::new();
+        for trigger in &self.triggers {
+            if !ids.insert(trigger.id()) {
+                return Err(Error::DuplicateTrigger(trigger.id().clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A GDTF file outside of the GDTF files directory of a showfile.
+///
+/// In a showfile, this is written as `{ "path": "../library/Dimmer.gdtf" }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GdtfSource {
+    path: PathBuf,
+}
+
+impl GdtfSource {
+    /// Creates a new [GdtfSource] for the file at the path, which is either
+    /// absolute or relative to the showfile folder.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path of the file, as written in the showfile.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    fn example_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example_showfile")
+    }
+
+    /// Returns an empty directory for the test to write to.
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("zeevonk-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// Copies the example showfile, with the given GDTF file references.
+    fn example_with_gdtf_files(name: &str, gdtf_files: &[&str]) -> PathBuf {
+        let path = temp_dir(name);
+        let example = example_path();
+        fs::create_dir(path.join(RELATIVE_GDTF_FILES_PATH)).unwrap();
+        for entry in fs::read_dir(example.join(RELATIVE_GDTF_FILES_PATH)).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(entry.path(), path.join(RELATIVE_GDTF_FILES_PATH).join(entry.file_name()))
+                .unwrap();
+        }
+
+        let description = fs::read_to_string(example.join(RELATIVE_DESCRIPTION_FILE_PATH)).unwrap();
+        let mut description: serde_json::Value = serde_json::from_str(&description).unwrap();
+        description["gdtf_files"] = gdtf_files.iter().copied().collect();
+        fs::write(path.join(RELATIVE_DESCRIPTION_FILE_PATH), description.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn gdtf_file_references() {
+        let all = fs::read_dir(example_path().join(RELATIVE_GDTF_FILES_PATH))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        let all = all.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let dir = example_with_gdtf_files("gdtf-references", &all);
+        let showfile = Showfile::load_from_folder(&dir).unwrap();
+        assert_eq!(showfile.gdtf_file_paths().len(), all.len());
+        assert!(showfile.gdtf_file_paths().iter().all(|path| path.starts_with(&dir)));
+
+        // Referenced files that are also listed as a source are only read once.
+        let description_path = dir.join(RELATIVE_DESCRIPTION_FILE_PATH);
+        let description = fs::read_to_string(&description_path).unwrap();
+        let mut description: serde_json::Value = serde_json::from_str(&description).unwrap();
+        description["gdtf_sources"] = serde_json::json!([
+            { "path": format!("{RELATIVE_GDTF_FILES_PATH}/{DIMMER_GDTF_FILE_NAME}") }
+        ]);
+        fs::write(&description_path, description.to_string()).unwrap();
+        let showfile = Showfile::load_from_folder(&dir).unwrap();
+        assert_eq!(showfile.gdtf_file_paths().len(), all.len() - 1);
+        assert_eq!(showfile.gdtf_source_paths().len(), 1);
+
+        let path = example_with_gdtf_files("missing-gdtf-reference", &["Missing.gdtf"]);
+        let result = Showfile::load_from_folder(&path);
+        assert!(matches!(result, Err(Error::MissingGdtfFile(name)) if name == "Missing.gdtf"));
+    }
+
+    const DIMMER_GDTF_FILE_NAME: &str = "Generic@Dimmer@Generic.gdtf";
+
+    /// Writes the example showfile description to the folder, referencing
+    /// the given GDTF sources.
+    fn write_description_with_gdtf_sources(path: &Path, sources: &[&str]) {
+        let description =
+            fs::read_to_string(example_path().join(RELATIVE_DESCRIPTION_FILE_PATH)).unwrap();
+        let mut description: serde_json::Value = serde_json::from_str(&description).unwrap();
+        description["gdtf_sources"] =
+            sources.iter().map(|path| serde_json::json!({ "path": path })).collect();
+        fs::create_dir_all(path).unwrap();
+        fs::write(path.join(RELATIVE_DESCRIPTION_FILE_PATH), description.to_string()).unwrap();
+    }
+
+    /// Copies the GDTF files of the example showfile for which `filter`
+    /// returns `true` to the folder.
+    fn copy_example_gdtf_files(path: &Path, filter: impl Fn(&str) -> bool) -> Vec<String> {
+        fs::create_dir_all(path).unwrap();
+        let mut names = Vec::new();
+        for entry in fs::read_dir(example_path().join(RELATIVE_GDTF_FILES_PATH)).unwrap() {
+            let entry = entry.unwrap();
+            let name = entry.file_name().into_string().unwrap();
+            if filter(&name) {
+                fs::copy(entry.path(), path.join(&name)).unwrap();
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn gdtf_sources_and_directory() {
+        let root = temp_dir("gdtf-sources");
+        let library = root.join("library");
+        let show = root.join("show");
+        copy_example_gdtf_files(&library, |name| name == DIMMER_GDTF_FILE_NAME);
+        copy_example_gdtf_files(&show.join(RELATIVE_GDTF_FILES_PATH), |name| {
+            name != DIMMER_GDTF_FILE_NAME
+        });
+        let source = format!("../library/{DIMMER_GDTF_FILE_NAME}");
+        write_description_with_gdtf_sources(&show, &[&source]);
+
+        let showfile = Showfile::load_from_folder(&show).unwrap();
+        assert_eq!(showfile.gdtf_sources(), [GdtfSource::new(&source)]);
+        assert_eq!(showfile.gdtf_file_paths().len(), 5);
+        assert_eq!(
+            showfile.gdtf_source_paths(),
+            [fs::canonicalize(library.join(DIMMER_GDTF_FILE_NAME)).unwrap()]
+        );
+
+        // Sources are not copied, and keep pointing to the same file.
+        let copy = root.join("nested").join("copy");
+        showfile.save_to_folder(&copy).unwrap();
+        assert!(!copy.join(RELATIVE_GDTF_FILES_PATH).join(DIMMER_GDTF_FILE_NAME).exists());
+        let copied = Showfile::load_from_folder(&copy).unwrap();
+        assert!(copied.gdtf_sources()[0].path().is_absolute());
+        assert_eq!(copied.gdtf_source_paths(), showfile.gdtf_source_paths());
+
+        showfile.save_to_folder(&show).unwrap();
+        let saved = Showfile::load_from_folder(&show).unwrap();
+        assert_eq!(saved.gdtf_sources(), [GdtfSource::new(&source)]);
+
+        // A fixture type in both the directory and a source is read from the
+        // source.
+        copy_example_gdtf_files(&show.join(RELATIVE_GDTF_FILES_PATH), |name| {
+            name == DIMMER_GDTF_FILE_NAME
+        });
+        let showfile = Showfile::load_from_folder(&show).unwrap();
+        assert_eq!(showfile.gdtf_file_paths().len(), 6);
+        assert_eq!(showfile.gdtf_source_paths().len(), 1);
+    }
+
+    #[test]
+    fn gdtf_sources_without_directory() {
+        let root = temp_dir("gdtf-sources-only");
+        let names = copy_example_gdtf_files(&root.join("library"), |_| true);
+        let sources = names.iter().map(|name| format!("../library/{name}")).collect::<Vec<_>>();
+        let show = root.join("show");
+        write_description_with_gdtf_sources(
+            &show,
+            &sources.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+
+        let showfile = Showfile::load_from_folder(&show).unwrap();
+        assert!(showfile.gdtf_file_paths().is_empty());
+        assert_eq!(showfile.gdtf_source_paths().len(), names.len());
+
+        write_description_with_gdtf_sources(&show, &["../library/Missing.gdtf"]);
+        let result = Showfile::load_from_folder(&show);
+        assert!(
+            matches!(result, Err(Error::MissingGdtfFile(name)) if name == "../library/Missing.gdtf")
+        );
+
+        let description =
+            fs::read_to_string(example_path().join(RELATIVE_DESCRIPTION_FILE_PATH)).unwrap();
+        let mut description: serde_json::Value = serde_json::from_str(&description).unwrap();
+        description["gdtf_sources"] =
+            serde_json::json!([{ "url": "https://example.com/Dimmer.gdtf" }]);
+        fs::write(show.join(RELATIVE_DESCRIPTION_FILE_PATH), description.to_string()).unwrap();
+        let result = Showfile::load_from_folder(&show);
+        assert!(matches!(result, Err(Error::DeserializationError { .. })));
+    }
+
+    #[test]
+    fn load_from_archive() {
+        let example = example_path();
+        let dir = temp_dir("archive");
+        let archive_path = dir.join("example.zvk");
+
+        let mut archive = zip::ZipWriter::new(fs::File::create(&archive_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        archive.start_file(RELATIVE_DESCRIPTION_FILE_PATH, options).unwrap();
+        archive
+            .write_all(&fs::read(example.join(RELATIVE_DESCRIPTION_FILE_PATH)).unwrap())
+            .unwrap();
+        for entry in fs::read_dir(example.join(RELATIVE_GDTF_FILES_PATH)).unwrap() {
+            let entry = entry.unwrap();
+            let name = format!("gdtf_files/{}", entry.file_name().to_str().unwrap());
+            archive.start_file(name, options).unwrap();
+            archive.write_all(&fs::read(entry.path()).unwrap()).unwrap();
+        }
+        archive.finish().unwrap();
+
+        let showfile = Showfile::load_from_archive(&archive_path).unwrap();
+        let from_folder = Showfile::load_from_folder(&example).unwrap();
+        assert_eq!(showfile.patch(), from_folder.patch());
+        assert_eq!(showfile.embedded_gdtf_files().count(), from_folder.gdtf_file_paths().len());
+
+        // Saving an archived showfile writes the embedded GDTF files.
+        let folder = dir.join("extracted");
+        showfile.save_to_folder(&folder).unwrap();
+        let extracted = Showfile::load_from_folder(&folder).unwrap();
+        assert_eq!(extracted.gdtf_file_paths().len(), from_folder.gdtf_file_paths().len());
+    }
+
+    #[test]
+    fn sacn_source_name() {
+        let showfile = Showfile::load_from_folder(&example_path()).unwrap();
+        assert_eq!(showfile.protocols().sacn().source_name(), "Zeevonk");
+
+        let path = example_with_gdtf_files("sacn-source-name", &[]);
+        let description_path = path.join(RELATIVE_DESCRIPTION_FILE_PATH);
+        let description = fs::read_to_string(&description_path).unwrap();
+        let mut description: serde_json::Value = serde_json::from_str(&description).unwrap();
+
+        description["protocols"]["sacn"]["source_name"] = "a".repeat(63).into();
+        fs::write(&description_path, description.to_string()).unwrap();
+        let showfile = Showfile::load_from_folder(&path).unwrap();
+        assert_eq!(showfile.protocols().sacn().source_name().len(), 63);
+
+        description["protocols"]["sacn"]["source_name"] = "a".repeat(64).into();
+        fs::write(&description_path, description.to_string()).unwrap();
+        let result = Showfile::load_from_folder(&path);
+        assert!(matches!(result, Err(Error::SacnSourceNameTooLong { max: 63, .. })));
+    }
+
+    #[test]
+    fn sacn_cid_is_kept_across_runs() {
+        let path = example_with_gdtf_files("sacn-cid", &[]);
+        let mut showfile = Showfile::load_from_folder(&path).unwrap();
+        assert_eq!(showfile.protocols().sacn().cid(), None);
+        assert!(showfile.ensure_sacn_cid());
+        let cid = showfile.protocols().sacn().cid().unwrap();
+        assert!(!showfile.ensure_sacn_cid());
+        showfile.save_to_folder(&path).unwrap();
+
+        let mut reloaded = Showfile::load_from_folder(&path).unwrap();
+        assert!(!reloaded.ensure_sacn_cid());
+        assert_eq!(reloaded.protocols().sacn().cid(), Some(cid));
+
+        // Without sACN outputs, there is nothing to identify.
+        assert!(!Showfile::default().ensure_sacn_cid());
+    }
+
+    #[test]
+    fn sacn_refresh_rate_and_keep_alive() {
+        let sacn = serde_json::from_str::<Sacn>("{}").unwrap();
+        assert_eq!(sacn.max_refresh_rate_hz(), 44.0);
+        assert_eq!(sacn.keep_alive_interval(), std::time::Duration::from_secs(1));
+
+        let json = r#"{ "max_refresh_rate_hz": 20, "keep_alive_interval_ms": 500 }"#;
+        let sacn = serde_json::from_str::<Sacn>(json).unwrap();
+        assert_eq!(sacn.max_refresh_rate_hz(), 20.0);
+        assert_eq!(sacn.keep_alive_interval(), std::time::Duration::from_millis(500));
+
+        for json in [
+            r#"{ "max_refresh_rate_hz": 45 }"#,
+            r#"{ "max_refresh_rate_hz": 0 }"#,
+            r#"{ "keep_alive_interval_ms": 0 }"#,
+            r#"{ "keep_alive_interval_ms": 1001 }"#,
+        ] {
+            let err = serde_json::from_str::<Sacn>(json).unwrap_err();
+            assert!(err.to_string().contains("out of range"), "{json}: {err}");
+        }
+    }
 }
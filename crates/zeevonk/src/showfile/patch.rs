@@ -1,8 +1,10 @@
-use std::str;
+use std::{fmt, str};
 use uuid::Uuid;
 
-use crate::dmx::Address;
+use crate::dmx::{self, Address, Channel};
 use crate::show::fixture::FixtureId;
+use crate::showfile::Error;
+use crate::value::DimmerCurve;
 
 /// A patch containing a list of [`Fixture`]s.
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -10,6 +12,7 @@ use crate::show::fixture::FixtureId;
 #[serde(default)]
 pub struct Patch {
     fixtures: Vec<Fixture>,
+    groups: Vec<Group>,
 }
 
 impl Patch {
@@ -17,6 +20,125 @@ impl Patch {
     pub fn fixtures(&self) -> &[Fixture] {
         &self.fixtures
     }
+
+    /// Returns the fixture with the given [`FixtureId`], if it exists.
+    pub fn fixture(&self, id: FixtureId) -> Option<&Fixture> {
+        self.fixtures.iter().find(|fixture| fixture.id == id)
+    }
+
+    /// Appends a [`Fixture`] to the [`Patch`].
+    pub fn add_fixture(&mut self, fixture: Fixture) {
+        self.fixtures.push(fixture);
+    }
+
+    /// Appends `count` fixtures of the same kind, labeled `base_label 1` to
+    /// `base_label N`, and returns their ids.
+    ///
+    /// Each fixture id is `id_step` after the previous one and each fixture is
+    /// patched right after the previous one, occupying `channel_count`
+    /// channels. A fixture that would not fit in the rest of a universe is
+    /// patched at the start of the next universe instead.
+    ///
+    /// If any of the fixtures can't be added, for example because its id is
+    /// already used, none of them are added.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_fixture_array(
+        &mut self,
+        first_id: FixtureId,
+        base_label: &str,
+        start_address: Address,
+        count: u32,
+        kind: FixtureKind,
+        channel_count: u32,
+        id_step: i32,
+    ) -> Result<Vec<FixtureId>, Error> {
+        let len = self.fixtures.len();
+        if let Err(err) = self.push_fixture_array(
+            first_id,
+            base_label,
+            start_address,
+            count,
+            kind,
+            channel_count,
+            id_step,
+        ) {
+            // Roll back the fixtures of the array that were already added.
+            self.fixtures.truncate(len);
+            return Err(err);
+        }
+
+        Ok(self.fixtures[len..].iter().map(Fixture::id).collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_fixture_array(
+        &mut self,
+        first_id: FixtureId,
+        base_label: &str,
+        start_address: Address,
+        count: u32,
+        kind: FixtureKind,
+        channel_count: u32,
+        id_step: i32,
+    ) -> Result<(), Error> {
+        let max_channel = *Channel::MAX as u32;
+        if channel_count == 0 || channel_count > max_channel {
+            return Err(Error::InvalidFixtureArray {
+                message: format!("invalid channel count: {channel_count}"),
+            });
+        }
+
+        let mut id = first_id;
+        let mut address = start_address;
+        for n in 1..=count {
+            if n > 1 {
+                id = id
+                    .offset(id_step)
+                    .map_err(|err| Error::InvalidFixtureArray { message: err.to_string() })?;
+                address = address.with_channel_offset(channel_count as i32)?;
+            }
+
+            // Fixtures can't span multiple universes.
+            let channel = *address.channel as u32;
+            if channel + channel_count - 1 > max_channel {
+                address = address.with_channel_offset((max_channel - channel + 1) as i32)?;
+            }
+
+            if self.fixture(id).is_some() {
+                return Err(Error::DuplicateFixtureId(id));
+            }
+
+            let fixture = Fixture::new(id, format!("{base_label} {n}"), address, kind.clone());
+            self.fixtures.push(fixture);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the fixture with the given [`FixtureId`] from the [`Patch`] and
+    /// from all groups, and returns it if it existed.
+    pub fn remove_fixture(&mut self, id: FixtureId) -> Option<Fixture> {
+        let index = self.fixtures.iter().position(|fixture| fixture.id == id)?;
+        for group in &mut self.groups {
+            group.members.retain(|member| *member != id);
+        }
+        Some(self.fixtures.remove(index))
+    }
+
+    /// Returns all groups in the [`Patch`].
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// Returns the group with the given [`GroupId`], if it exists.
+    pub fn group(&self, id: GroupId) -> Option<&Group> {
+        self.groups.iter().find(|group| group.id == id)
+    }
+
+    /// Appends a [`Group`] to the [`Patch`].
+    pub fn add_group(&mut self, group: Group) {
+        self.groups.push(group);
+    }
 }
 
 /// A single fixture in the [`Patch`].
@@ -25,11 +147,27 @@ impl Patch {
 pub struct Fixture {
     id: FixtureId,
     label: String,
+    /// Written as a struct, but can also be given as a string like `"1.100"`
+    /// when editing a patch by hand.
+    #[serde(deserialize_with = "dmx::address_as_string::deserialize")]
     address: Address,
     kind: FixtureKind,
+    /// The curve intensity attributes of the fixture are mapped through.
+    #[serde(default, skip_serializing_if = "DimmerCurve::is_linear")]
+    dimmer_curve: DimmerCurve,
 }
 
 impl Fixture {
+    /// Creates a new [`Fixture`].
+    pub fn new(
+        id: FixtureId,
+        label: impl Into<String>,
+        address: Address,
+        kind: FixtureKind,
+    ) -> Self {
+        Self { id, label: label.into(), address, kind, dimmer_curve: DimmerCurve::Linear }
+    }
+
     /// Returns the unique [`FixtureId`] of the fixture.
     pub fn id(&self) -> FixtureId {
         self.id
@@ -49,6 +187,18 @@ impl Fixture {
     pub fn kind(&self) -> &FixtureKind {
         &self.kind
     }
+
+    /// Returns the [`DimmerCurve`] the intensity attributes of the fixture
+    /// and its sub-fixtures are mapped through before output.
+    pub fn dimmer_curve(&self) -> DimmerCurve {
+        self.dimmer_curve
+    }
+
+    /// Sets the [`DimmerCurve`] of the fixture, e.g. to keep LED fixtures
+    /// from jumping from off to visible at the bottom end.
+    pub fn set_dimmer_curve(&mut self, dimmer_curve: DimmerCurve) {
+        self.dimmer_curve = dimmer_curve;
+    }
 }
 
 /// Describes the GDTF fixture type and DMX mode of a [`Fixture`].
@@ -60,6 +210,11 @@ pub struct FixtureKind {
 }
 
 impl FixtureKind {
+    /// Creates a new [`FixtureKind`].
+    pub fn new(gdtf_fixture_type_id: Uuid, gdtf_dmx_mode: impl Into<String>) -> Self {
+        Self { gdtf_fixture_type_id, gdtf_dmx_mode: gdtf_dmx_mode.into() }
+    }
+
     /// Returns the [`Uuid`] of the GDTF fixture type.
     pub fn gdtf_fixture_type_id(&self) -> Uuid {
         self.gdtf_fixture_type_id
@@ -70,3 +225,149 @@ impl FixtureKind {
         &self.gdtf_dmx_mode
     }
 }
+
+/// A named set of fixtures that can be addressed as one unit.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Group {
+    id: GroupId,
+    label: String,
+    members: Vec<FixtureId>,
+}
+
+impl Group {
+    /// Creates a new [`Group`].
+    pub fn new(id: GroupId, label: impl Into<String>, members: Vec<FixtureId>) -> Self {
+        Self { id, label: label.into(), members }
+    }
+
+    /// Returns the unique [`GroupId`] of the group.
+    pub fn id(&self) -> GroupId {
+        self.id
+    }
+
+    /// Returns the label of the group.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the ids of the root fixtures in this group.
+    pub fn members(&self) -> &[FixtureId] {
+        &self.members
+    }
+}
+
+/// An identifier for a [`Group`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct GroupId(pub u32);
+
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl str::FromStr for GroupId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+impl From<u32> for GroupId {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(id: u32) -> FixtureId {
+        FixtureId::new(id).unwrap()
+    }
+
+    fn kind() -> FixtureKind {
+        FixtureKind::new(Uuid::nil(), "Standard")
+    }
+
+    #[test]
+    fn fixture_address_in_string_form() {
+        let json = r#"{
+            "id": 1,
+            "label": "Spot",
+            "address": "2.100",
+            "kind": { "gdtf_fixture_type_id": "00000000-0000-0000-0000-000000000000", "gdtf_dmx_mode": "Standard" }
+        }"#;
+        let fixture: Fixture = serde_json::from_str(json).unwrap();
+        assert_eq!(fixture.address(), "2.100".parse().unwrap());
+
+        let json = serde_json::to_value(&fixture).unwrap();
+        assert_eq!(json["address"], serde_json::json!({ "universe": 2, "channel": 100 }));
+        assert_eq!(serde_json::from_value::<Fixture>(json).unwrap(), fixture);
+    }
+
+    #[test]
+    fn fixture_dimmer_curve() {
+        let json = r#"{
+            "id": 1,
+            "label": "Spot",
+            "address": "1.1",
+            "kind": { "gdtf_fixture_type_id": "00000000-0000-0000-0000-000000000000", "gdtf_dmx_mode": "Standard" },
+            "dimmer_curve": { "gamma": 2.2 }
+        }"#;
+        let mut fixture: Fixture = serde_json::from_str(json).unwrap();
+        assert_eq!(fixture.dimmer_curve(), DimmerCurve::Gamma(2.2));
+
+        fixture.set_dimmer_curve(DimmerCurve::Linear);
+        let json = serde_json::to_value(&fixture).unwrap();
+        assert!(json.get("dimmer_curve").is_none());
+        assert_eq!(serde_json::from_value::<Fixture>(json).unwrap(), fixture);
+    }
+
+    #[test]
+    fn fixture_array_crosses_universe_boundary() {
+        let mut patch = Patch::default();
+        let start = "1.481".parse().unwrap();
+        let ids = patch.add_fixture_array(id(10), "Bar", start, 3, kind(), 16, 2).unwrap();
+        assert_eq!(ids, [id(10), id(12), id(14)]);
+
+        let addresses =
+            patch.fixtures().iter().map(|f| f.address().to_string()).collect::<Vec<_>>();
+        assert_eq!(addresses, ["1.481", "1.497", "2.1"]);
+        assert_eq!(patch.fixture(id(14)).unwrap().label(), "Bar 3");
+    }
+
+    #[test]
+    fn fixture_array_rolls_back_on_duplicate_id() {
+        let mut patch = Patch::default();
+        patch.add_fixture(Fixture::new(id(3), "Existing", "2.1".parse().unwrap(), kind()));
+        let before = patch.clone();
+
+        let result = patch.add_fixture_array(id(1), "Bar", "1.1".parse().unwrap(), 5, kind(), 8, 1);
+        assert!(matches!(result, Err(Error::DuplicateFixtureId(duplicate)) if duplicate == id(3)));
+        assert_eq!(patch, before);
+
+        let result = patch.add_fixture_array(id(5), "Bar", "1.1".parse().unwrap(), 2, kind(), 0, 1);
+        assert!(matches!(result, Err(Error::InvalidFixtureArray { .. })));
+        assert_eq!(patch, before);
+    }
+
+    #[test]
+    fn remove_fixture_removes_group_members() {
+        let mut patch = Patch::default();
+        patch.add_fixture(Fixture::new(id(1), "A", "1.1".parse().unwrap(), kind()));
+        patch.add_fixture(Fixture::new(id(2), "B", "1.2".parse().unwrap(), kind()));
+        let group_id = "1".parse::<GroupId>().unwrap();
+        patch.add_group(Group::new(group_id, "All", vec![id(1), id(2)]));
+
+        assert_eq!(patch.remove_fixture(id(1)).unwrap().label(), "A");
+        assert!(patch.remove_fixture(id(1)).is_none());
+        assert_eq!(patch.fixtures().len(), 1);
+        assert_eq!(patch.group(group_id).unwrap().members(), [id(2)]);
+    }
+}
@@ -1,4 +1,10 @@
 use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::dmx::UniverseId;
 
 /// Contains all DMX IO protocol configurations.
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -13,23 +19,234 @@ impl Protocols {
     pub fn sacn(&self) -> &Sacn {
         &self.sacn
     }
+
+    pub fn sacn_mut(&mut self) -> &mut Sacn {
+        &mut self.sacn
+    }
 }
 
+/// The maximum length of an sACN source name in bytes. The name is sent in a
+/// 64 byte field, which must be null-terminated.
+pub const SACN_SOURCE_NAME_MAX_LEN: usize = 63;
+
+/// The valid range for [Sacn::max_refresh_rate_hz]. DMX512 can't refresh a
+/// universe more than 44 times per second, so receivers that output DMX512
+/// can't keep up with faster sources.
+pub const SACN_REFRESH_RATES_HZ: RangeInclusive<f32> = 1.0..=44.0;
+
+/// The valid range for [Sacn::keep_alive_interval]. E1.31 6.6.1 requires
+/// unchanged universes to be resent at least once per second.
+pub const SACN_KEEP_ALIVE_INTERVALS_MS: RangeInclusive<u64> = 1..=1000;
+
 /// Inputs and outputs for the sACN protocol.
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct Sacn {
+    source_name: String,
+    universes: SacnUniverses,
+    /// The highest number of frames sent per second, regardless of the
+    /// output refresh rate of the server.
+    #[serde(deserialize_with = "deserialize_max_refresh_rate")]
+    max_refresh_rate_hz: f32,
+    /// Milliseconds after which a universe is resent, even if it did not
+    /// change.
+    #[serde(deserialize_with = "deserialize_keep_alive_interval")]
+    keep_alive_interval_ms: u64,
     outputs: Vec<SacnOutput>,
+    /// The component identifier (CID) all sACN outputs send. Receivers use
+    /// it to recognize the source, so it should not change between runs
+    /// (E1.31 5.6).
+    ///
+    /// This is the last field, as skipping a field is only possible at the
+    /// end in formats that serialize structs as sequences, like packets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cid: Option<Uuid>,
+}
+
+impl Default for Sacn {
+    fn default() -> Self {
+        Self {
+            source_name: "Zeevonk".to_string(),
+            universes: SacnUniverses::default(),
+            max_refresh_rate_hz: *SACN_REFRESH_RATES_HZ.end(),
+            keep_alive_interval_ms: *SACN_KEEP_ALIVE_INTERVALS_MS.end(),
+            outputs: Vec::new(),
+            cid: None,
+        }
+    }
 }
 
 impl Sacn {
+    /// Returns the name all sACN outputs identify themselves with, e.g. in
+    /// monitoring tools.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// Returns the component identifier all sACN outputs send, if one is
+    /// configured.
+    pub fn cid(&self) -> Option<Uuid> {
+        self.cid
+    }
+
+    pub fn set_cid(&mut self, cid: Uuid) {
+        self.cid = Some(cid);
+    }
+
+    /// Returns how universes are mapped to sACN universe numbers.
+    pub fn universes(&self) -> &SacnUniverses {
+        &self.universes
+    }
+
+    /// Returns the highest number of frames sent per second. Frames of the
+    /// server are skipped if its output refresh rate is higher.
+    pub fn max_refresh_rate_hz(&self) -> f32 {
+        self.max_refresh_rate_hz
+    }
+
+    /// Returns the time after which a universe is resent, even if it did not
+    /// change. Changed universes are sent right away.
+    pub fn keep_alive_interval(&self) -> Duration {
+        Duration::from_millis(self.keep_alive_interval_ms)
+    }
+
     /// Returns all sACN output configurations.
     pub fn outputs(&self) -> &[SacnOutput] {
         &self.outputs
     }
 }
 
+fn deserialize_max_refresh_rate<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f32, D::Error> {
+    let rate = <f32 as serde::Deserialize>::deserialize(deserializer)?;
+    if !SACN_REFRESH_RATES_HZ.contains(&rate) {
+        return Err(serde::de::Error::custom(format!(
+            "sACN refresh rate of {rate} Hz is out of range, should be in the range {}..={} Hz",
+            SACN_REFRESH_RATES_HZ.start(),
+            SACN_REFRESH_RATES_HZ.end()
+        )));
+    }
+    Ok(rate)
+}
+
+fn deserialize_keep_alive_interval<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    let interval = <u64 as serde::Deserialize>::deserialize(deserializer)?;
+    if !SACN_KEEP_ALIVE_INTERVALS_MS.contains(&interval) {
+        return Err(serde::de::Error::custom(format!(
+            "sACN keep-alive interval of {interval} ms is out of range, should be in the range {}..={} ms",
+            SACN_KEEP_ALIVE_INTERVALS_MS.start(),
+            SACN_KEEP_ALIVE_INTERVALS_MS.end()
+        )));
+    }
+    Ok(interval)
+}
+
+/// Maps universes to the sACN universe numbers they are sent as.
+///
+/// In a showfile, this is either `"auto"` or a list of mappings like
+/// `[{ "internal": 7, "sacn": 100 }]`.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "SacnUniversesRepr", into = "SacnUniversesRepr")]
+pub enum SacnUniverses {
+    /// Every universe is sent as the sACN universe with the same number.
+    /// Universes outside of [crate::dmx::SACN_UNIVERSES] can't be sent.
+    #[default]
+    Auto,
+    /// Only the listed universes are sent.
+    Mapped(Vec<SacnUniverseMapping>),
+}
+
+impl SacnUniverses {
+    /// Returns the sACN universe number the universe is sent as, or `None` if
+    /// it is not sent.
+    pub fn sacn_universe(&self, universe: UniverseId) -> Option<u16> {
+        match self {
+            Self::Auto => universe.is_sacn_routable().then_some(*universe),
+            Self::Mapped(mappings) => mappings
+                .iter()
+                .find(|mapping| mapping.internal == universe)
+                .map(|mapping| mapping.sacn),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum SacnUniversesRepr {
+    Mode(String),
+    Mapped(Vec<SacnUniverseMapping>),
+}
+
+impl TryFrom<SacnUniversesRepr> for SacnUniverses {
+    type Error = String;
+
+    fn try_from(repr: SacnUniversesRepr) -> Result<Self, Self::Error> {
+        match repr {
+            SacnUniversesRepr::Mode(mode) if mode == "auto" => Ok(Self::Auto),
+            SacnUniversesRepr::Mode(mode) => Err(format!(
+                "invalid sACN universe mapping '{mode}', expected \"auto\" or a list of mappings"
+            )),
+            SacnUniversesRepr::Mapped(mappings) => Ok(Self::Mapped(mappings)),
+        }
+    }
+}
+
+impl From<SacnUniverses> for SacnUniversesRepr {
+    fn from(universes: SacnUniverses) -> Self {
+        match universes {
+            SacnUniverses::Auto => Self::Mode("auto".to_string()),
+            SacnUniverses::Mapped(mappings) => Self::Mapped(mappings),
+        }
+    }
+}
+
+/// Sends a universe as the given sACN universe number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SacnUniverseMapping {
+    internal: UniverseId,
+    sacn: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    destination: Option<SacnMode>,
+}
+
+impl SacnUniverseMapping {
+    /// Creates a new [SacnUniverseMapping], sent to the destination of the
+    /// output.
+    pub fn new(internal: UniverseId, sacn: u16) -> Self {
+        Self { internal, sacn, destination: None }
+    }
+
+    /// Sends the universe to the given destination, instead of to the
+    /// destination of the output.
+    pub fn with_destination(mut self, destination: SacnMode) -> Self {
+        self.destination = Some(destination);
+        self
+    }
+
+    /// Returns the universe that is sent.
+    pub fn internal(&self) -> UniverseId {
+        self.internal
+    }
+
+    /// Returns the sACN universe number it is sent as.
+    pub fn sacn(&self) -> u16 {
+        self.sacn
+    }
+
+    /// Returns where the universe is sent to, if it is sent somewhere else
+    /// than the destination of the output. E.g. to send the universe to a
+    /// node that drops multicast packets.
+    pub fn destination(&self) -> Option<SacnMode> {
+        self.destination
+    }
+}
+
 /// Configuration for a single sACN output.
 #[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -40,6 +257,12 @@ pub struct SacnOutput {
     destination_universe: u16,
     priority: u8,
     preview_data: bool,
+    /// The local address of the network interface packets are sent from.
+    ///
+    /// This is the last field, as skipping a field is only possible at the
+    /// end in formats that serialize structs as sequences, like packets.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "ip_addr_string::option")]
+    interface: Option<IpAddr>,
 }
 
 impl SacnOutput {
@@ -77,18 +300,90 @@ impl SacnOutput {
     pub fn preview_data(&self) -> bool {
         self.preview_data
     }
+
+    /// Returns the local address of the network interface this output sends
+    /// from, or `None` to let the operating system choose one, e.g. by the
+    /// default route.
+    pub fn interface(&self) -> Option<IpAddr> {
+        self.interface
+    }
+
+    /// Sends from the network interface with the given local address, e.g. to
+    /// keep output on the lighting network of a machine with multiple
+    /// network interfaces.
+    pub fn set_interface(&mut self, interface: Option<IpAddr>) {
+        self.interface = interface;
+    }
 }
 
-/// Mode for sACN output.
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Mode for sACN output, which is where the packets of a universe are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SacnMode {
     /// Unicast mode with a specific destination IP address.
     Unicast {
         /// The ip address of the targeted sACN endpoint.
+        #[serde(with = "ip_addr_string")]
         destination_ip: IpAddr,
     },
     /// Multicast mode.
     Multicast,
 }
+
+impl SacnMode {
+    /// Returns whether packets can be sent to the destination: multicast, or
+    /// a unicast address that is not unspecified, multicast or broadcast.
+    pub fn is_routable(&self) -> bool {
+        match self {
+            Self::Unicast { destination_ip } => {
+                !destination_ip.is_unspecified()
+                    && !destination_ip.is_multicast()
+                    && !matches!(destination_ip, IpAddr::V4(ip) if ip.is_broadcast())
+            }
+            Self::Multicast => true,
+        }
+    }
+}
+
+/// Serializes an [IpAddr] as a string in every format. `IpAddr` is compact
+/// in binary formats, which can't be deserialized from the buffered payload of
+/// a packet.
+mod ip_addr_string {
+    use std::net::IpAddr;
+
+    use serde::{Deserialize as _, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(ip: &IpAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(ip)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+
+    /// Serializes an optional [IpAddr] as an optional string.
+    pub mod option {
+        use std::net::IpAddr;
+
+        use serde::{Deserialize as _, Deserializer, Serializer, de};
+
+        pub fn serialize<S: Serializer>(
+            ip: &Option<IpAddr>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match ip {
+                Some(ip) => serializer.collect_str(ip),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<IpAddr>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|ip| ip.parse().map_err(de::Error::custom))
+                .transpose()
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fmt, str};
+
+use crate::attr::Attribute;
+use crate::packet::AttributeValues;
+use crate::show::fixture::FixturePath;
+use crate::value::ClampedValue;
+
+/// A named set of attribute values that can be applied at once, for example
+/// to recall a look from a MIDI controller.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Trigger {
+    id: Identifier,
+    #[serde(default)]
+    label: String,
+    values: Vec<TriggerValue>,
+}
+
+impl Trigger {
+    /// Creates a new [`Trigger`].
+    pub fn new(id: Identifier, label: impl Into<String>, values: Vec<TriggerValue>) -> Self {
+        Self { id, label: label.into(), values }
+    }
+
+    /// Returns the unique [`Identifier`] of the trigger.
+    pub fn id(&self) -> &Identifier {
+        &self.id
+    }
+
+    /// Returns the label of the trigger.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the values that are set when the trigger fires.
+    pub fn values(&self) -> &[TriggerValue] {
+        &self.values
+    }
+
+    /// Returns the values that are set when the trigger fires as
+    /// [AttributeValues]. If a value is set multiple times, the last one wins.
+    pub fn attribute_values(&self) -> AttributeValues {
+        self.values.iter().map(|value| ((value.fixture, value.attribute), value.value)).collect()
+    }
+}
+
+/// A single attribute value set by a [`Trigger`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct TriggerValue {
+    pub fixture: FixturePath,
+    pub attribute: Attribute,
+    pub value: ClampedValue,
+}
+
+/// A name that identifies a [`Trigger`].
+///
+/// Identifiers are non-empty and consist of ASCII letters, digits, `-`, `_`
+/// and `.`.
+///
+/// # Examples
+///
+/// ```
+/// # use zeevonk::showfile::Identifier;
+/// assert!("red-look".parse::<Identifier>().is_ok());
+/// assert!("red look".parse::<Identifier>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Identifier(String);
+
+impl Identifier {
+    /// Returns the identifier as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl str::FromStr for Identifier {
+    type Err = InvalidIdentifier;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_string())
+    }
+}
+
+impl TryFrom<String> for Identifier {
+    type Error = InvalidIdentifier;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let is_valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+        if s.is_empty() || !s.chars().all(is_valid_char) {
+            return Err(InvalidIdentifier(s));
+        }
+        Ok(Self(s))
+    }
+}
+
+impl From<Identifier> for String {
+    fn from(identifier: Identifier) -> Self {
+        identifier.0
+    }
+}
+
+/// Error returned when parsing an invalid [`Identifier`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "invalid identifier: '{0}', should be non-empty and only contain ASCII letters, digits, '-', '_' and '.'"
+)]
+pub struct InvalidIdentifier(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fpath;
+
+    #[test]
+    fn identifier_from_str() {
+        for valid in ["a", "red-look", "Look_2", "scene.1"] {
+            assert_eq!(valid.parse::<Identifier>().unwrap().as_str(), valid);
+        }
+        for invalid in ["", "red look", "löök", "a/b"] {
+            assert!(invalid.parse::<Identifier>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn trigger_from_json() {
+        let json = r#"{
+            "id": "red",
+            "values": [
+                { "fixture": "101", "attribute": "Dimmer", "value": 1.0 },
+                { "fixture": "101.1", "attribute": "ColorAdd_R", "value": 0.5 },
+                { "fixture": "101", "attribute": "Dimmer", "value": 0.75 }
+            ]
+        }"#;
+        let trigger: Trigger = serde_json::from_str(json).unwrap();
+        assert_eq!(trigger.id().as_str(), "red");
+        assert_eq!(trigger.label(), "");
+
+        let values = trigger.attribute_values();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get(fpath![101], Attribute::Dimmer), Some(ClampedValue::new(0.75)));
+        assert_eq!(values.get(fpath![101, 1], Attribute::ColorAddR), Some(ClampedValue::new(0.5)));
+
+        let invalid = r#"{ "id": "red look", "values": [] }"#;
+        assert!(serde_json::from_str::<Trigger>(invalid).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use crate::show::fixture::FixtureId;
+use crate::value::DimmerCurve;
+
+/// A problem found while validating a [`Showfile`](super::Showfile).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ValidationIssue {
+    fixture_id: Option<FixtureId>,
+    message: String,
+}
+
+impl ValidationIssue {
+    /// Creates a new [`ValidationIssue`].
+    pub fn new(fixture_id: Option<FixtureId>, message: impl Into<String>) -> Self {
+        Self { fixture_id, message: message.into() }
+    }
+
+    /// Returns the id of the fixture this issue relates to, if any.
+    pub fn fixture_id(&self) -> Option<FixtureId> {
+        self.fixture_id
+    }
+
+    /// Returns a human-readable description of the issue.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.fixture_id {
+            Some(fixture_id) => write!(f, "fixture {fixture_id}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Checks the fixtures in the patch for duplicate ids, empty labels and
+/// invalid dimmer curves.
+#[cfg(not(feature = "server"))]
+pub(super) fn fixture_issues(showfile: &super::Showfile) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut seen_ids = std::collections::HashSet::new();
+    for fixture in showfile.patch().fixtures() {
+        if !seen_ids.insert(fixture.id()) {
+            issues.push(ValidationIssue::new(Some(fixture.id()), "duplicate fixture id"));
+        }
+        if fixture.label().trim().is_empty() {
+            issues.push(ValidationIssue::new(Some(fixture.id()), "label is empty"));
+        }
+        issues.extend(dimmer_curve_issue(fixture));
+    }
+    issues
+}
+
+/// Checks that the gamma of the dimmer curve of the fixture is a finite,
+/// positive number.
+pub(crate) fn dimmer_curve_issue(fixture: &super::Fixture) -> Option<ValidationIssue> {
+    match fixture.dimmer_curve() {
+        DimmerCurve::Gamma(gamma) if !fixture.dimmer_curve().is_valid() => {
+            Some(ValidationIssue::new(
+                Some(fixture.id()),
+                format!("dimmer curve gamma {gamma} is not a positive number"),
+            ))
+        }
+        _ => None,
+    }
+}
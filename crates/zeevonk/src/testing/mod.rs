@@ -0,0 +1,196 @@
+//! Helpers for testing clients and processors against a real server, without
+//! a network connection.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use zeevonk::testing::TestHarness;
+//!
+//! let harness = TestHarness::new(4)?;
+//! let client = harness.connect().await?;
+//! let output = client.request_dmx_output().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use uuid::Uuid;
+
+use crate::Error;
+use crate::client::{self, Client};
+use crate::dmx::{Address, Channel, UniverseId};
+use crate::server::Server;
+use crate::show::fixture::FixtureId;
+use crate::showfile::{self, FixtureKind, Showfile};
+
+/// The GDTF fixture type of the dimmers patched by a [TestHarness]: a single
+/// 8-bit `Dimmer` channel.
+pub const DIMMER_FIXTURE_TYPE_ID: Uuid = uuid::uuid!("B4DAFF6B-3E52-451B-AFDB-E6C94C64F85D");
+
+/// The DMX mode of the dimmers patched by a [TestHarness].
+pub const DIMMER_DMX_MODE: &str = "Default";
+
+const DIMMER_GDTF_FILE_NAME: &str = "Generic@Dimmer@Generic.gdtf";
+const DIMMER_GDTF: &[u8] = include_bytes!("generic_dimmer.gdtf");
+
+/// A server with a generated showfile, to which in-memory clients can be
+/// connected.
+///
+/// The showfile is written to a temporary directory, which is removed when
+/// the harness is dropped. The server is not started: clients are served over
+/// in-memory pipes and no output protocols are running.
+///
+/// The showfile is leaked to give the server a `'static` reference to it, so
+/// a harness should only be created a limited number of times, like in tests.
+pub struct TestHarness {
+    path: PathBuf,
+    showfile: &'static Showfile,
+    server: Server<'static>,
+}
+
+impl TestHarness {
+    /// Creates a harness with `dimmer_count` single channel dimmers, with ids
+    /// starting at 1, patched consecutively from address 1.1.
+    pub fn new(dimmer_count: u32) -> Result<Self, Error> {
+        let mut showfile = Showfile::default();
+        for id in 1..=dimmer_count {
+            let address = Address::new(UniverseId::new(1)?, Channel::new(id as u16)?);
+            showfile.patch_mut().add_fixture(showfile::Fixture::new(
+                FixtureId::new(id)?,
+                format!("Dimmer {id}"),
+                address,
+                FixtureKind::new(DIMMER_FIXTURE_TYPE_ID, DIMMER_DMX_MODE),
+            ));
+        }
+
+        Self::with_showfile(showfile)
+    }
+
+    /// Creates a harness for the given showfile, which may use the bundled
+    /// dimmer fixture type ([DIMMER_FIXTURE_TYPE_ID]).
+    pub fn with_showfile(showfile: Showfile) -> Result<Self, Error> {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("zeevonk-test-harness-{}-{id}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+
+        let showfile = save_and_load(&showfile, &path).inspect_err(|_| {
+            let _ = fs::remove_dir_all(&path);
+        })?;
+        let showfile: &'static Showfile = Box::leak(Box::new(showfile));
+        let server = Server::new(showfile)?;
+
+        Ok(Self { path, showfile, server })
+    }
+
+    /// Returns the path to the showfile folder.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the loaded showfile.
+    pub fn showfile(&self) -> &Showfile {
+        self.showfile
+    }
+
+    /// Returns the server, e.g. to inspect its DMX output.
+    pub fn server(&self) -> &Server<'static> {
+        &self.server
+    }
+
+    /// Connects a new client to the server over an in-memory pipe.
+    pub async fn connect(&self) -> Result<Client, client::Error> {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        self.server.handle_connection(server_io);
+        Client::connect_duplex(client_io).await
+    }
+}
+
+impl Drop for TestHarness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Saves the showfile with the bundled GDTF file to the given folder, and loads
+/// it back in.
+fn save_and_load(showfile: &Showfile, path: &Path) -> Result<Showfile, Error> {
+    let to_error = |err: showfile::Error| Error::other(err.to_string());
+
+    showfile.save_to_folder(path).map_err(to_error)?;
+    fs::write(path.join("gdtf_files").join(DIMMER_GDTF_FILE_NAME), DIMMER_GDTF)?;
+    Showfile::load_from_folder(path).map_err(to_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attr::Attribute;
+    use crate::packet::AttributeValues;
+    use crate::value::ClampedValue;
+
+    #[tokio::test]
+    async fn clients_share_server() {
+        let harness = TestHarness::new(3).unwrap();
+        let a = harness.connect().await.unwrap();
+        let b = harness.connect().await.unwrap();
+
+        let address =
+            |channel| Address::new(UniverseId::new(1).unwrap(), Channel::new(channel).unwrap());
+        let show_data = a.request_show_data().await.unwrap();
+        let roots = show_data.patch().fixtures().keys().filter(|path| path.is_root_fixture());
+        assert_eq!(roots.count(), 3);
+
+        let dimmer = show_data.patch().fixture_at_address(&address(2)).unwrap();
+        assert_eq!(dimmer.root(), FixtureId::new(2).unwrap());
+        let mut values = AttributeValues::new();
+        values.set(dimmer, Attribute::Dimmer, ClampedValue::new(1.0));
+        a.request_set_attribute_values(values).await.unwrap();
+
+        let output = b.request_dmx_output().await.unwrap();
+        assert_eq!(output.get_value(&address(1)), crate::dmx::Value(0));
+        assert_eq!(output.get_value(&address(2)), crate::dmx::Value(255));
+    }
+
+    #[tokio::test]
+    async fn raw_dmx_overrides_attribute_values() {
+        let harness = TestHarness::new(2).unwrap();
+        let client = harness.connect().await.unwrap();
+        let address =
+            |channel| Address::new(UniverseId::new(1).unwrap(), Channel::new(channel).unwrap());
+
+        let show_data = client.request_show_data().await.unwrap();
+        let dimmer = show_data.patch().fixture_at_address(&address(1)).unwrap();
+        let mut values = AttributeValues::new();
+        values.set(dimmer, Attribute::Dimmer, ClampedValue::new(1.0));
+        client.request_set_attribute_values(values).await.unwrap();
+
+        let raw = vec![(address(1), crate::dmx::Value(10)), (address(2), crate::dmx::Value(20))];
+        client.request_set_raw_dmx(raw).await.unwrap();
+        let output = harness.server().dmx_output().await;
+        assert_eq!(output.get_value(&address(1)), crate::dmx::Value(10));
+        assert_eq!(output.get_value(&address(2)), crate::dmx::Value(20));
+
+        client.request_clear_raw_dmx(Some(vec![address(2)])).await.unwrap();
+        let output = harness.server().dmx_output().await;
+        assert_eq!(output.get_value(&address(1)), crate::dmx::Value(10));
+        assert_eq!(output.get_value(&address(2)), crate::dmx::Value(0));
+
+        harness.server().clear_raw_dmx(None).await;
+        let output = harness.server().dmx_output().await;
+        assert_eq!(output.get_value(&address(1)), crate::dmx::Value(255));
+    }
+
+    #[test]
+    fn removes_showfile_folder_on_drop() {
+        let harness = TestHarness::new(1).unwrap();
+        let path = harness.path().to_path_buf();
+        assert!(path.join("gdtf_files").join(DIMMER_GDTF_FILE_NAME).is_file());
+
+        drop(harness);
+        assert!(!path.exists());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, num, str};
+use std::{fmt, num, ops, str};
 
 use crate::dmx::{self, Address};
 
@@ -6,9 +6,14 @@ use crate::dmx::{self, Address};
 ///
 /// ClampedValue represents a floating-point value constrained to the range
 /// [0.0, 1.0]. All operations automatically clamp values to this valid range.
+///
+/// # Serialization
+///
+/// A value is serialized together with its resolution as `{ "value": n,
+/// "max": m }`, where `max` is always `1.0`. When deserializing, any
+/// resolution is accepted, so `{ "value": 32768, "max": 65535 }` is a 16-bit
+/// value of about `0.5`. A bare number is read as a value in `[0.0, 1.0]`.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(transparent)]
 pub struct ClampedValue(f32);
 
 impl ClampedValue {
@@ -42,6 +47,25 @@ impl ClampedValue {
         self.0
     }
 
+    /// Creates a value from a percentage, clamped to `0.0..=100.0`.
+    #[inline]
+    pub fn from_percent(percent: f32) -> Self {
+        Self::new(percent / 100.0)
+    }
+
+    /// Returns the value as a percentage in `0.0..=100.0`.
+    #[inline]
+    pub fn to_percent(self) -> f32 {
+        self.0 * 100.0
+    }
+
+    /// Multiplies the value by `factor`, saturating at the bounds of the
+    /// range. E.g. a master at `0.5` scales a follower at `0.8` to `0.4`.
+    #[inline]
+    pub fn scale(self, factor: f32) -> Self {
+        Self::new(self.0 * factor)
+    }
+
     /// Performs linear interpolation between this value and another.
     #[inline]
     pub fn lerp(&self, other: &Self, t: f32) -> Self {
@@ -49,6 +73,29 @@ impl ClampedValue {
         Self::new(self.0 * (1.0 - t) + other.0 * t)
     }
 
+    /// Maps the value through the dimmer curve. [DimmerCurve::Linear] returns
+    /// the value unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use zeevonk::value::{ClampedValue, DimmerCurve};
+    ///
+    /// let value = ClampedValue::new(0.5);
+    /// assert_eq!(value.apply_curve(DimmerCurve::Linear), value);
+    /// assert_eq!(value.apply_curve(DimmerCurve::SquareLaw), ClampedValue::new(0.25));
+    /// ```
+    #[inline]
+    pub fn apply_curve(self, curve: DimmerCurve) -> Self {
+        match curve {
+            DimmerCurve::Linear => self,
+            DimmerCurve::SquareLaw => Self::new(self.0 * self.0),
+            DimmerCurve::Gamma(gamma) if curve.is_valid() => Self::new(self.0.powf(gamma)),
+            // An invalid gamma is reported when the showfile is validated.
+            DimmerCurve::Gamma(_) => self,
+        }
+    }
+
     /// Converts the value to a 1-byte representation (u8).
     #[inline]
     pub fn to_u8(&self) -> u8 {
@@ -77,22 +124,91 @@ impl ClampedValue {
     }
 
     /// Converts the value to values directly mappable at addresses.
+    ///
+    /// The number of addresses determines the resolution: one address is
+    /// 8-bit, two are 16-bit (coarse, fine), three are 24-bit and four are
+    /// 32-bit. Like in GDTF, bytes are big-endian, so the first address receives
+    /// the most significant byte. Any other number of addresses results in no
+    /// values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use zeevonk::dmx::{self, Address};
+    /// use zeevonk::value::ClampedValue;
+    ///
+    /// let coarse = Address::from_absolute(1).unwrap();
+    /// let fine = Address::from_absolute(2).unwrap();
+    /// let values = ClampedValue::new(0.5).to_address_values(&[coarse, fine]);
+    /// assert_eq!(values, vec![(coarse, dmx::Value(0x80)), (fine, dmx::Value(0x00))]);
+    /// ```
     pub fn to_address_values(&self, addresses: &[Address]) -> Vec<(Address, dmx::Value)> {
-        let bytes: Vec<u8> = match addresses.len() {
-            1 => vec![self.to_u8()],
-            2 => self.to_u16_bytes().to_vec(),
-            3 => self.to_u24_bytes().to_vec(),
-            4 => self.to_u32_bytes().to_vec(),
-            _ => {
-                log::warn!(
-                    "cannot set DMX channel value for fixture: unsupported address length {}",
-                    addresses.len()
-                );
-                return Vec::new();
-            }
-        };
+        self.to_address_values_in_range(Self::new(Self::MIN), Self::new(Self::MAX), addresses)
+    }
+
+    /// Converts the value to values directly mappable at addresses, scaling it
+    /// into the inclusive `[min, max]` range first.
+    ///
+    /// The range is quantized to the resolution of the addresses (e.g. 16 bits
+    /// for two addresses) before scaling, so the resulting DMX values never
+    /// fall outside of the range.
+    pub fn to_address_values_in_range(
+        &self,
+        min: ClampedValue,
+        max: ClampedValue,
+        addresses: &[Address],
+    ) -> Vec<(Address, dmx::Value)> {
+        let len = addresses.len();
+        if !(1..=4).contains(&len) {
+            log::warn!(
+                "cannot set DMX channel value for fixture: unsupported address length {}",
+                len
+            );
+            return Vec::new();
+        }
+
+        let full_range = ((1_u64 << (len * 8)) - 1) as f64;
+        let min = (min.0 as f64 * full_range).round() as u64;
+        let max = ((max.0 as f64 * full_range).round() as u64).max(min);
+        let raw = min + (self.0 as f64 * (max - min) as f64).round() as u64;
+
+        let bytes = raw.to_be_bytes();
+        addresses.iter().copied().zip(bytes[8 - len..].iter().map(|b| dmx::Value(*b))).collect()
+    }
+}
+
+/// A curve that maps the intensity set for a fixture to the intensity sent to
+/// it, to compensate for dimmers that don't respond linearly.
+///
+/// Written as `"linear"`, `"square_law"` or `{ "gamma": 2.2 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DimmerCurve {
+    /// Sends the intensity unchanged.
+    #[default]
+    Linear,
+    /// Sends the square of the intensity, so low intensities are spread over
+    /// more DMX values.
+    SquareLaw,
+    /// Raises the intensity to the given power, which should be positive.
+    /// Powers above `1.0` spread low intensities over more DMX values.
+    Gamma(f32),
+}
+
+impl DimmerCurve {
+    /// Returns `true` if the curve sends the intensity unchanged.
+    pub fn is_linear(&self) -> bool {
+        matches!(self, Self::Linear)
+    }
 
-        addresses.iter().copied().zip(bytes.into_iter().map(|b| dmx::Value::from(b))).collect()
+    /// Returns `false` if the curve is a [DimmerCurve::Gamma] with a power
+    /// that is not a finite, positive number.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Gamma(gamma) => gamma.is_finite() && *gamma > 0.0,
+            Self::Linear | Self::SquareLaw => true,
+        }
     }
 }
 
@@ -102,6 +218,33 @@ impl fmt::Display for ClampedValue {
     }
 }
 
+/// Adds two values, saturating at [ClampedValue::MAX].
+impl ops::Add for ClampedValue {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.0 + rhs.0)
+    }
+}
+
+/// Subtracts two values, saturating at [ClampedValue::MIN].
+impl ops::Sub for ClampedValue {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.0 - rhs.0)
+    }
+}
+
+/// Scales the value, see [ClampedValue::scale].
+impl ops::Mul<f32> for ClampedValue {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        self.scale(rhs)
+    }
+}
+
 impl From<f32> for ClampedValue {
     fn from(value: f32) -> Self {
         Self::new(value)
@@ -126,6 +269,64 @@ impl From<ClampedValue> for dmx::Value {
     }
 }
 
+impl From<dmx::Value> for ClampedValue {
+    fn from(value: dmx::Value) -> Self {
+        Self::new(value.0 as f32 / u8::MAX as f32)
+    }
+}
+
+impl From<u8> for ClampedValue {
+    /// Creates a value from an 8-bit value, where `u8::MAX` is `1.0`.
+    fn from(value: u8) -> Self {
+        Self::new(value as f32 / u8::MAX as f32)
+    }
+}
+
+impl From<u16> for ClampedValue {
+    /// Creates a value from a 16-bit value, where `u16::MAX` is `1.0`.
+    fn from(value: u16) -> Self {
+        Self::new(value as f32 / u16::MAX as f32)
+    }
+}
+
+/// The serialized form of a [ClampedValue], with an explicit resolution.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ValueWithResolution {
+    value: f64,
+    max: f64,
+}
+
+/// The serialized forms of a [ClampedValue] that can be deserialized.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ClampedValueRepr {
+    WithResolution(ValueWithResolution),
+    /// The form used before values carried their resolution.
+    Normalized(f32),
+}
+
+impl serde::Serialize for ClampedValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ValueWithResolution { value: self.0 as f64, max: Self::MAX as f64 }.serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ClampedValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match ClampedValueRepr::deserialize(deserializer)? {
+            ClampedValueRepr::WithResolution(ValueWithResolution { value, max }) => {
+                if max.is_nan() || max <= 0.0 {
+                    return Err(serde::de::Error::custom(format!(
+                        "maximum of value should be positive, found {max}"
+                    )));
+                }
+                Ok(Self::new((value / max) as f32))
+            }
+            ClampedValueRepr::Normalized(value) => Ok(Self::new(value)),
+        }
+    }
+}
+
 impl str::FromStr for ClampedValue {
     type Err = num::ParseFloatError;
 
@@ -133,3 +334,207 @@ impl str::FromStr for ClampedValue {
         Ok(Self::new(s.parse()?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent() {
+        assert_eq!(ClampedValue::from_percent(50.0), ClampedValue::new(0.5));
+        assert_eq!(ClampedValue::from_percent(150.0), ClampedValue::new(1.0));
+        assert_eq!(ClampedValue::from_percent(-5.0), ClampedValue::new(0.0));
+        assert_eq!(ClampedValue::new(0.25).to_percent(), 25.0);
+        assert_eq!(ClampedValue::from_percent(100.0).to_u8(), 255);
+        assert_eq!(ClampedValue::from_percent(50.0).to_u8(), 128);
+    }
+
+    #[test]
+    fn arithmetic_saturates() {
+        let v = ClampedValue::new;
+        assert_eq!(v(0.25) + v(0.5), v(0.75));
+        assert_eq!(v(0.75) + v(0.75), v(1.0));
+        assert_eq!(v(0.75) - v(0.25), v(0.5));
+        assert_eq!(v(0.25) - v(0.75), v(0.0));
+
+        assert_eq!(v(0.8) * 0.0, v(0.0));
+        assert_eq!(v(0.8) * 1.0, v(0.8));
+        assert_eq!(v(0.8) * 0.5, v(0.4));
+        assert_eq!(v(0.8) * 2.0, v(1.0));
+        assert_eq!(v(0.8) * -1.0, v(0.0));
+        assert_eq!(v(0.8).scale(f32::MAX), v(1.0));
+    }
+
+    #[test]
+    fn from_integers() {
+        assert_eq!(ClampedValue::from(255_u8), ClampedValue::new(1.0));
+        assert_eq!(ClampedValue::from(u16::MAX), ClampedValue::new(1.0));
+        assert_eq!(ClampedValue::from(255_u16).to_u8(), 1);
+    }
+
+    #[test]
+    fn serialize_with_resolution() {
+        let json = serde_json::to_string(&ClampedValue::new(0.5)).unwrap();
+        assert_eq!(json, r#"{"value":0.5,"max":1.0}"#);
+
+        let de = |json: &str| serde_json::from_str::<ClampedValue>(json);
+        assert_eq!(de(&json).unwrap(), ClampedValue::new(0.5));
+        assert_eq!(de(r#"{ "value": 255, "max": 255 }"#).unwrap(), ClampedValue::new(1.0));
+        assert_eq!(de(r#"{ "value": 255, "max": 65535 }"#).unwrap().to_u8(), 1);
+        assert!(de(r#"{ "value": 1, "max": 0 }"#).is_err());
+    }
+
+    #[test]
+    fn deserialize_bare_number() {
+        let de = |json: &str| serde_json::from_str::<ClampedValue>(json).unwrap();
+        assert_eq!(de("0.25"), ClampedValue::new(0.25));
+        assert_eq!(de("1"), ClampedValue::new(1.0));
+        assert_eq!(de("255"), ClampedValue::new(1.0));
+    }
+
+    #[test]
+    fn msgpack_round_trip() {
+        let bytes = rmp_serde::to_vec(&ClampedValue::new(0.75)).unwrap();
+        assert_eq!(rmp_serde::from_slice::<ClampedValue>(&bytes).unwrap(), ClampedValue::new(0.75));
+
+        let bare = rmp_serde::to_vec(&0.75_f32).unwrap();
+        assert_eq!(rmp_serde::from_slice::<ClampedValue>(&bare).unwrap(), ClampedValue::new(0.75));
+    }
+
+    fn addresses(len: u32) -> Vec<Address> {
+        (1..=len).map(|a| Address::from_absolute(a).unwrap()).collect()
+    }
+
+    fn bytes(values: Vec<(Address, dmx::Value)>) -> Vec<u8> {
+        values.into_iter().map(|(_, value)| value.0).collect()
+    }
+
+    #[test]
+    fn to_address_values_in_range_8_bit() {
+        let min = ClampedValue::from(dmx::Value(10));
+        let max = ClampedValue::from(dmx::Value(245));
+        let addresses = addresses(1);
+
+        assert_eq!(
+            bytes(ClampedValue::new(0.0).to_address_values_in_range(min, max, &addresses)),
+            [10]
+        );
+        assert_eq!(
+            bytes(ClampedValue::new(1.0).to_address_values_in_range(min, max, &addresses)),
+            [245]
+        );
+        assert_eq!(
+            bytes(ClampedValue::new(0.5).to_address_values_in_range(min, max, &addresses)),
+            [128]
+        );
+    }
+
+    #[test]
+    fn to_address_values_in_range_16_bit() {
+        let min = ClampedValue::from(dmx::Value(10));
+        let max = ClampedValue::from(dmx::Value(245));
+        let addresses = addresses(2);
+
+        assert_eq!(
+            bytes(ClampedValue::new(0.0).to_address_values_in_range(min, max, &addresses)),
+            [10, 10]
+        );
+        assert_eq!(
+            bytes(ClampedValue::new(1.0).to_address_values_in_range(min, max, &addresses)),
+            [245, 245]
+        );
+        assert_eq!(
+            bytes(ClampedValue::new(0.5).to_address_values_in_range(min, max, &addresses)),
+            [128, 0]
+        );
+    }
+
+    #[test]
+    fn dimmer_curves() {
+        for step in 0..=255 {
+            let value = ClampedValue::from(dmx::Value(step));
+            assert_eq!(value.apply_curve(DimmerCurve::Linear), value);
+            assert_eq!(dmx::Value(step).apply_curve(DimmerCurve::Linear), dmx::Value(step));
+        }
+
+        let value = ClampedValue::new(0.5);
+        assert_eq!(value.apply_curve(DimmerCurve::Gamma(1.0)), value);
+        assert_eq!(value.apply_curve(DimmerCurve::Gamma(2.0)), ClampedValue::new(0.25));
+        assert_eq!(value.apply_curve(DimmerCurve::Gamma(0.0)), value);
+        assert_eq!(value.apply_curve(DimmerCurve::Gamma(f32::NAN)), value);
+        for curve in [DimmerCurve::SquareLaw, DimmerCurve::Gamma(2.2)] {
+            assert_eq!(ClampedValue::new(0.0).apply_curve(curve), ClampedValue::new(0.0));
+            assert_eq!(ClampedValue::new(1.0).apply_curve(curve), ClampedValue::new(1.0));
+        }
+
+        // The bottom end is spread over more DMX values.
+        assert_eq!(dmx::Value(16).apply_curve(DimmerCurve::SquareLaw), dmx::Value(1));
+        assert_eq!(dmx::Value(128).apply_curve(DimmerCurve::SquareLaw), dmx::Value(64));
+    }
+
+    #[test]
+    fn dimmer_curve_serde() {
+        let curves: Vec<DimmerCurve> =
+            serde_json::from_str(r#"["linear", "square_law", { "gamma": 2.2 }]"#).unwrap();
+        assert_eq!(curves, [DimmerCurve::Linear, DimmerCurve::SquareLaw, DimmerCurve::Gamma(2.2)]);
+        assert_eq!(serde_json::to_string(&curves[2]).unwrap(), r#"{"gamma":2.2}"#);
+    }
+
+    #[test]
+    fn to_address_values_8_bit() {
+        let addresses = addresses(1);
+        assert_eq!(bytes(ClampedValue::new(0.0).to_address_values(&addresses)), [0x00]);
+        assert_eq!(bytes(ClampedValue::new(0.5).to_address_values(&addresses)), [0x80]);
+        assert_eq!(bytes(ClampedValue::new(1.0).to_address_values(&addresses)), [0xFF]);
+    }
+
+    #[test]
+    fn to_address_values_16_bit() {
+        let addresses = addresses(2);
+        assert_eq!(bytes(ClampedValue::new(0.0).to_address_values(&addresses)), [0x00, 0x00]);
+        assert_eq!(bytes(ClampedValue::new(0.5).to_address_values(&addresses)), [0x80, 0x00]);
+        assert_eq!(bytes(ClampedValue::new(1.0).to_address_values(&addresses)), [0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn to_address_values_24_bit() {
+        let addresses = addresses(3);
+        assert_eq!(bytes(ClampedValue::new(0.0).to_address_values(&addresses)), [0x00, 0x00, 0x00]);
+        assert_eq!(bytes(ClampedValue::new(0.5).to_address_values(&addresses)), [0x80, 0x00, 0x00]);
+        assert_eq!(bytes(ClampedValue::new(1.0).to_address_values(&addresses)), [0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn to_address_values_keeps_address_order() {
+        let addresses = addresses(2);
+        let values = ClampedValue::new(1.0).to_address_values(&addresses);
+        assert_eq!(values.iter().map(|(address, _)| *address).collect::<Vec<_>>(), addresses);
+    }
+
+    #[test]
+    fn to_address_values_unsupported_length() {
+        assert!(ClampedValue::new(0.5).to_address_values(&addresses(0)).is_empty());
+        assert!(ClampedValue::new(0.5).to_address_values(&addresses(5)).is_empty());
+    }
+
+    #[test]
+    fn to_address_values_recombines_to_original() {
+        for len in 1..=3 {
+            let addresses = addresses(len);
+            let full_range = ((1_u64 << (len * 8)) - 1) as f64;
+
+            for step in 0..=1000 {
+                let value = ClampedValue::new(step as f32 / 1000.0);
+                let raw = bytes(value.to_address_values(&addresses))
+                    .into_iter()
+                    .fold(0_u64, |raw, byte| (raw << 8) | byte as u64);
+
+                let expected = value.as_f32() as f64 * full_range;
+                assert!(
+                    (raw as f64 - expected).abs() <= 0.5,
+                    "{value} with {len} addresses recombined to {raw}, expected {expected}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use zeevonk::dmx::UniverseId;
+use zeevonk::show::fixture::FixtureId;
+use zeevonk::showfile::{self, Protocols, Sacn, SacnUniverses, Showfile, ValidationIssue};
+use zeevonk::value::DimmerCurve;
+
+/// Loads a showfile that is expected to be invalid and returns its issues.
+///
+/// Invalid showfiles can still be loaded, so they can be repaired.
+fn load_issues(name: &str) -> Vec<ValidationIssue> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    let showfile = Showfile::load_from_folder(&path).unwrap();
+    match showfile.check_valid() {
+        Err(showfile::Error::Invalid(issues)) => issues,
+        result => panic!("expected validation issues, got {result:?}"),
+    }
+}
+
+fn id(id: u32) -> Option<FixtureId> {
+    Some(FixtureId::new(id).unwrap())
+}
+
+#[test]
+fn valid_showfile_has_no_issues() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example_showfile");
+    let showfile = Showfile::load_from_folder(&path).unwrap();
+    assert!(showfile.validate().is_empty());
+    assert!(showfile.check_valid().is_ok());
+}
+
+#[test]
+fn interfaces_of_other_machines_are_valid() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example_showfile");
+    let mut showfile = Showfile::load_from_folder(&path).unwrap();
+    let protocols = |interface: &str| -> Protocols {
+        serde_json::from_value(serde_json::json!({ "sacn": { "outputs": [{
+            "label": "Node",
+            "mode": { "unicast": { "destination_ip": "127.0.0.1" } },
+            "local_universe": 1,
+            "destination_universe": 1,
+            "priority": 100,
+            "preview_data": false,
+            "interface": interface,
+        }] } }))
+        .unwrap()
+    };
+
+    // Not an address of this machine (TEST-NET-1), but it may be of the
+    // machine the show runs on.
+    *showfile.protocols_mut() = protocols("192.0.2.1");
+    assert!(showfile.validate().is_empty(), "{:?}", showfile.validate());
+
+    *showfile.protocols_mut() = protocols("0.0.0.0");
+    let issues = showfile.validate();
+    assert_eq!(issues.len(), 1, "{issues:?}");
+    assert!(issues[0].message().contains("interface 0.0.0.0"));
+}
+
+#[test]
+fn broken_showfile_reports_all_issues() {
+    let issues = load_issues("broken_showfile");
+    let issues =
+        issues.iter().map(|issue| (issue.fixture_id(), issue.message())).collect::<Vec<_>>();
+
+    assert_eq!(issues.len(), 7, "{issues:#?}");
+    assert!(issues.iter().any(|(f, m)| *f == id(2)
+        && m.contains("address 1.1 collides with 'Dimmer 1' (id 1) occupying 1.1..=1.1")));
+    assert!(issues.iter().any(|(f, m)| *f == id(2) && m.contains("duplicate fixture id")));
+    assert!(issues.iter().any(|(f, m)| *f == id(2) && m.contains("label is empty")));
+    assert!(issues.iter().any(|(f, m)| *f == id(3) && m.contains("unknown fixture type")));
+    assert!(issues.iter().any(|(f, m)| *f == id(4) && m.contains("unknown dmx mode")));
+    assert!(issues.iter().any(|(f, m)| *f == id(5) && m.contains("universe 64000")));
+    assert!(issues.iter().any(|(f, m)| f.is_none() && m.contains("local universe 0")));
+}
+
+#[test]
+fn validation_issue_serde_round_trip() {
+    let issues = load_issues("broken_showfile");
+    let json = serde_json::to_string(&issues).unwrap();
+    let deserialized: Vec<ValidationIssue> = serde_json::from_str(&json).unwrap();
+    assert_eq!(issues, deserialized);
+}
+
+#[test]
+fn sacn_universe_mapping_reports_all_conflicts() {
+    let issues = load_issues("sacn_universe_mapping");
+    let messages = issues.iter().map(|issue| issue.message()).collect::<Vec<_>>();
+
+    // Universe 64000 is outside of the sACN range, but may be mapped.
+    assert_eq!(messages.len(), 6, "{messages:#?}");
+    assert!(messages.iter().any(|m| m.contains("universe 2 is mapped to sACN universe 64000")));
+    assert!(
+        messages.iter().any(|m| m.contains("multiple universes are mapped to sACN universe 63999"))
+    );
+    assert!(messages.iter().any(|m| m.contains("universe 1 is mapped to sACN more than once")));
+    assert!(
+        messages.iter().any(|m| m.contains("universe 3 is mapped to sACN, but has no fixtures"))
+    );
+}
+
+#[test]
+fn sacn_universe_mapping_modes() {
+    let universe = |id| UniverseId::new(id).unwrap();
+
+    let sacn: Sacn = serde_json::from_str(r#"{ "universes": "auto" }"#).unwrap();
+    assert_eq!(*sacn.universes(), SacnUniverses::Auto);
+    assert_eq!(sacn.universes().sacn_universe(universe(63999)), Some(63999));
+    assert_eq!(sacn.universes().sacn_universe(universe(64000)), None);
+    assert_eq!(*serde_json::from_str::<Sacn>("{}").unwrap().universes(), SacnUniverses::Auto);
+
+    let json = r#"{ "universes": [{ "internal": 7, "sacn": 100 }] }"#;
+    let sacn: Sacn = serde_json::from_str(json).unwrap();
+    assert_eq!(sacn.universes().sacn_universe(universe(7)), Some(100));
+    assert_eq!(sacn.universes().sacn_universe(universe(1)), None);
+    let round_trip: Sacn = serde_json::from_str(&serde_json::to_string(&sacn).unwrap()).unwrap();
+    assert_eq!(round_trip, sacn);
+
+    assert!(serde_json::from_str::<Sacn>(r#"{ "universes": "identity" }"#).is_err());
+}
+
+#[test]
+fn gamma_of_dimmer_curve_must_be_positive() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example_showfile");
+    let mut showfile = Showfile::load_from_folder(&path).unwrap();
+    let fixture_id = showfile.patch().fixtures()[0].id();
+
+    for gamma in [2.2, 0.5] {
+        let mut fixture = showfile.patch_mut().remove_fixture(fixture_id).unwrap();
+        fixture.set_dimmer_curve(DimmerCurve::Gamma(gamma));
+        showfile.patch_mut().add_fixture(fixture);
+        assert!(showfile.validate().is_empty(), "{:?}", showfile.validate());
+    }
+
+    for gamma in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        let mut fixture = showfile.patch_mut().remove_fixture(fixture_id).unwrap();
+        fixture.set_dimmer_curve(DimmerCurve::Gamma(gamma));
+        showfile.patch_mut().add_fixture(fixture);
+        let issues = showfile.validate();
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert_eq!(issues[0].fixture_id(), Some(fixture_id));
+        assert!(issues[0].message().contains("dimmer curve gamma"), "{issues:?}");
+    }
+}